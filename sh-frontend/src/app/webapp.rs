@@ -7,11 +7,13 @@ use crate::common::alert_boxes::AlertBoxToShow;
 use crate::components::alerts::error::ShAlertErrorBox;
 use crate::components::alerts::AlertPropsTrait;
 use crate::components::alerts::{AlertType, ShAlert};
+use crate::components::layout::authenticated::Authenticated;
 use crate::components::layout::footer::Footer;
-use crate::components::layout::live::Live;
 use crate::components::layout::nav::Nav;
+use crate::services::saved_state::WebAppState;
 use crate::services::temp_state::WebAppStateTemp;
 use anyhow::Error;
+use sh_common::auth::ShAuthStatus;
 use sh_common::{
     MessageData, ServerMessageTypes, ServerWssMessage, UserMessageTypes, UserWssMessage,
 };
@@ -21,6 +23,25 @@ use yewdux::Dispatch;
 
 // https://github.com/security-union/yew-websocket/
 
+// the Tauri app loads the page from its own origin, and runs the hub on the default port
+const FALLBACK_WEBSOCKET_URL: &str = "ws://127.0.0.1:3000/sdre-hub";
+
+/// The hub serving this page is the one to talk to, over wss if the page came over https
+fn websocket_url() -> String {
+    let location = gloo_utils::window().location();
+    let (Ok(protocol), Ok(host)) = (location.protocol(), location.host()) else {
+        return FALLBACK_WEBSOCKET_URL.to_string();
+    };
+
+    let scheme = match protocol.as_str() {
+        "https:" => "wss",
+        "http:" if host != "tauri.localhost" => "ws",
+        _ => return FALLBACK_WEBSOCKET_URL.to_string(),
+    };
+
+    format!("{scheme}://{host}/sdre-hub")
+}
+
 #[derive(Debug)]
 pub enum WsAction {
     Connect,
@@ -48,22 +69,20 @@ pub struct App {
     pub ws: Option<WebSocketTask>,
     pub dispatch: Dispatch<WebAppStateTemp>,
     pub alert_box_type: AlertBoxToShow,
+    // The saved session has been offered to the server on this connection
+    pub resuming: bool,
 }
 
 impl App {
     fn handle_wsaction_connect(&mut self, ctx: &Context<Self>) {
         let callback = ctx.link().callback(Msg::WsReady);
+        // nothing is asked for until the server has said who we are
         let notification = ctx.link().batch_callback(|status| match status {
-            WebSocketStatus::Opened => {
-                let initial_message =
-                    UserWssMessage::new(UserMessageTypes::UserRequestConfig, MessageData::NoData);
-                Some(WsAction::SendData(initial_message).into())
-            }
-            WebSocketStatus::Closed | WebSocketStatus::Error => Some(WsAction::Lost.into()),
+            WebSocketStatus::Opened => vec![],
+            WebSocketStatus::Closed | WebSocketStatus::Error => vec![WsAction::Lost.into()],
         });
         let task =
-            WebSocketService::connect_text("ws://127.0.0.1:3000/sdre-hub", callback, notification)
-                .unwrap();
+            WebSocketService::connect_text(&websocket_url(), callback, notification).unwrap();
         self.ws = Some(task);
     }
 
@@ -97,15 +116,73 @@ impl App {
         self.ws = None;
         log::error!("WebSocket connection lost. Reconnecting");
         self.fetching = false;
+        self.resuming = false;
         // reconnect
         ctx.link().send_message(WsAction::Connect);
         self.dispatch.reduce_mut(|state| {
             state.config = None;
             state.websocket_connected = false;
+            state.auth = None;
+        });
+    }
+
+    fn request_initial_data(ctx: &Context<Self>) {
+        for message_type in [
+            UserMessageTypes::UserRequestConfig,
+            UserMessageTypes::UserRequestRecentMessages,
+            UserMessageTypes::UserRequestGroundStations,
+            UserMessageTypes::UserRequestEmergencies,
+            UserMessageTypes::UserRequestSourceStatus,
+            UserMessageTypes::UserRequestConsumerStatus,
+        ] {
+            ctx.link()
+                .send_message(WsAction::SendData(UserWssMessage::new(
+                    message_type,
+                    MessageData::NoData,
+                )));
+        }
+    }
+
+    /// Start asking for data once we are somebody, or try the saved session, or leave the user
+    /// to log in
+    fn handle_auth_status(&mut self, ctx: &Context<Self>, status: &ShAuthStatus) {
+        let saved = Dispatch::<WebAppState>::global();
+        if let Some(token) = &status.token {
+            saved.reduce_mut(|state| state.session_token = Some(token.clone()));
+        }
+
+        if status.user.is_some() {
+            Self::request_initial_data(ctx);
+        } else if let Some(token) = saved.get().session_token.clone() {
+            if self.resuming {
+                // the server didn't take it, so it has ended
+                saved.reduce_mut(|state| state.session_token = None);
+            } else {
+                self.resuming = true;
+                ctx.link()
+                    .send_message(WsAction::SendData(UserWssMessage::new(
+                        UserMessageTypes::UserResumeSession,
+                        MessageData::ShSessionToken(token),
+                    )));
+                // the login page would only flash up while the server looks at the session
+                return;
+            }
+        }
+
+        let status = status.clone();
+        self.dispatch.reduce_mut(|state| {
+            state.websocket_connected = true;
+            if status.user.is_some() {
+                state.auth_error = None;
+            } else {
+                state.config = None;
+            }
+            state.auth = Some(status);
         });
     }
 
-    fn handle_wsaction_ready(&self, ctx: &Context<Self>, response: Result<String, Error>) {
+    #[allow(clippy::too_many_lines)]
+    fn handle_wsaction_ready(&mut self, ctx: &Context<Self>, response: Result<String, Error>) {
         log::debug!("Received data: {response:?}");
 
         if response.is_err() {
@@ -138,10 +215,353 @@ impl App {
                     });
             }
 
+            ServerMessageTypes::ServerResponseFlightInfo => {
+                log::debug!("Received flight info message");
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShFlightInfo(flight_info) => {
+                            state
+                                .flight_info
+                                .insert(flight_info.flight.clone(), flight_info.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseRecentMessages => {
+                log::debug!("Received recent messages");
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShAcarsMessages(messages) => {
+                            state.set_messages(messages.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerNewAcarsMessage => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShAcarsMessage(message) => {
+                            state.add_message(*message.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseGroundStations => {
+                log::debug!("Received ground stations");
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShGroundStations(stations) => {
+                            state.ground_stations.clone_from(stations);
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerGroundStationUpdate => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShGroundStation(station) => {
+                            state.update_ground_station(station.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseFlightTimeline => {
+                log::debug!("Received flight timeline");
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShFlightTimeline(timeline) => {
+                            state.flight_timeline = Some(*timeline.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseWeather => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShWeatherProducts(products) => {
+                            state.weather.clone_from(products);
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerNewWeather => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShWeatherProduct(product) => {
+                            state.add_weather(product.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseFlightSessions => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShFlightSessions(sessions) => {
+                            state.flight_sessions.clone_from(sessions);
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseFlightSession => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShFlightSessionDetail(detail) => {
+                            // the user may have moved on while we were waiting
+                            if state.selected_session_id == Some(detail.session.id) {
+                                state.selected_session = Some(*detail.clone());
+                            }
+                        }
+                        MessageData::NoData => {
+                            state.selected_session_id = None;
+                            state.selected_session = None;
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerFlightSessionUpdate => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShFlightSessionChange(change) => {
+                            state.update_flight_sessions(change.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerAlert => match data_deserialized.get_data() {
+                MessageData::ShAlert(alert) => {
+                    log::info!("Alert {} raised: {}", alert.rule, alert.description);
+                    self.dispatch
+                        .reduce_mut(|state| state.add_alert(*alert.clone()));
+                    ctx.link()
+                        .send_message(Msg::ShowAlert(AlertBoxToShow::NewAlert));
+                }
+                _ => {
+                    log::error!("Received invalid data type");
+                }
+            },
+
+            ServerMessageTypes::ServerEmergency => match data_deserialized.get_data() {
+                MessageData::ShEmergency(emergency) => {
+                    let raised = emergency.is_active()
+                        && !self
+                            .dispatch
+                            .get()
+                            .emergencies
+                            .iter()
+                            .any(|known| known.id == emergency.id);
+                    if raised {
+                        log::warn!(
+                            "Emergency: {} from {}",
+                            emergency.kind,
+                            emergency.aircraft()
+                        );
+                    }
+
+                    self.dispatch
+                        .reduce_mut(|state| state.update_emergency(*emergency.clone()));
+                    if raised {
+                        ctx.link()
+                            .send_message(Msg::ShowAlert(AlertBoxToShow::Emergency));
+                    }
+                }
+                _ => {
+                    log::error!("Received invalid data type");
+                }
+            },
+
+            ServerMessageTypes::ServerResponseEmergencies => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShEmergencies(emergencies) => {
+                            state.emergencies.clone_from(emergencies);
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseAlertHistory => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShAlerts(alerts) => {
+                            state.alerts = alerts.iter().cloned().collect();
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerNotificationDelivery => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShNotificationDelivery(delivery) => {
+                            state.add_notification_delivery(delivery.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseNotificationLog => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShNotificationDeliveries(deliveries) => {
+                            state.notification_deliveries = deliveries.iter().cloned().collect();
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerSourceStatus => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShSourceStatus(source) => {
+                            state.update_source(source.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseSourceStatus => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShSourceStatuses(sources) => {
+                            state.sources.clone_from(sources);
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerConsumerStatus => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShConsumerStatus(consumer) => {
+                            state.update_consumer(consumer.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseConsumerStatus => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShConsumerStatuses(consumers) => {
+                            state.consumers.clone_from(consumers);
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseSourceTest => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShSourceTestResult(result) => {
+                            state.source_test = Some(result.as_ref().clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseStats => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        // a reply for a range the user has since moved off is no use
+                        MessageData::ShStats(stats) if stats.range == state.stats_range => {
+                            state.stats = Some((**stats).clone());
+                        }
+                        MessageData::ShStats(_) => (),
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerAuthStatus => match data_deserialized.get_data() {
+                MessageData::ShAuthStatus(status) => self.handle_auth_status(ctx, status),
+                _ => {
+                    log::error!("Received invalid data type");
+                }
+            },
+
+            ServerMessageTypes::ServerAuthFailure => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShAuthFailure(error) => {
+                            log::error!("Refused by the server: {error}");
+                            state.auth_error = Some(error.clone());
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
+            ServerMessageTypes::ServerResponseUsers => {
+                self.dispatch
+                    .reduce_mut(|state| match data_deserialized.get_data() {
+                        MessageData::ShUsers(users) => {
+                            state.users.clone_from(users);
+                        }
+                        _ => {
+                            log::error!("Received invalid data type");
+                        }
+                    });
+            }
+
             ServerMessageTypes::ServerWriteConfigFailure => {
                 match data_deserialized.get_data() {
                     MessageData::ShConfigFailure(data) => {
                         log::error!("Failed to write config: {data}");
+                        self.dispatch
+                            .reduce_mut(|state| state.config_error = Some(data.clone()));
                     }
                     _ => {
                         log::error!("Invalid response type");
@@ -166,6 +586,7 @@ impl App {
                 match data_deserialized.get_data() {
                     MessageData::ShConfigSuccess(data) => {
                         log::info!("Config written successfully: {data}");
+                        self.dispatch.reduce_mut(|state| state.config_error = None);
                     }
                     MessageData::ShConfigFailure(_) => {
                         log::error!("Invalid response type");
@@ -200,6 +621,21 @@ impl App {
             AlertBoxToShow::UnsavedChanges => {
                 self.alert_box_type = AlertBoxToShow::UnsavedChanges;
             }
+            AlertBoxToShow::NewAlert => {
+                // don't cover up anything more important with a new alert
+                if matches!(self.alert_box_type, AlertBoxToShow::None) {
+                    self.alert_box_type = AlertBoxToShow::NewAlert;
+                }
+            }
+            AlertBoxToShow::Emergency => {
+                // only unsaved changes and config results are left in place, they need an answer
+                if matches!(
+                    self.alert_box_type,
+                    AlertBoxToShow::None | AlertBoxToShow::NewAlert
+                ) {
+                    self.alert_box_type = AlertBoxToShow::Emergency;
+                }
+            }
             AlertBoxToShow::None => {
                 self.alert_box_type = AlertBoxToShow::None;
             }
@@ -223,6 +659,7 @@ impl Component for App {
             ws: None,
             dispatch,
             alert_box_type: AlertBoxToShow::None,
+            resuming: false,
         }
     }
 
@@ -297,6 +734,16 @@ impl Component for App {
                                 <ShAlert show_alert={true} message={"You have unsaved changes. Please save changes, or reset, before continuing."} title={"Unsaved Changes"} on_confirm={hide_alert_box} alert_type={AlertType::Error(ShAlertErrorBox::new())}/>
                             }
                         }
+                        AlertBoxToShow::NewAlert => {
+                            html! {
+                                <ShAlert show_alert={true} message={"An alert rule has matched. Open the Alerts panel to see what was heard."} title={"Alert Raised"} on_confirm={hide_alert_box} />
+                            }
+                        }
+                        AlertBoxToShow::Emergency => {
+                            html! {
+                                <ShAlert show_alert={true} message={"An aircraft has declared an emergency. It is pinned on the map, and the Alerts panel has what has been heard from it."} title={"Emergency"} on_confirm={hide_alert_box} alert_type={AlertType::Error(ShAlertErrorBox::new())}/>
+                            }
+                        }
                         AlertBoxToShow::None => {
                             html! {}
                         }
@@ -304,9 +751,9 @@ impl Component for App {
                 }
                 <Nav />
                 <section class="container flex text-left p-0 pb-1 mt-1 mb-1 h-full w-full max-h-full max-w-full overflow-hidden">
-                    <Live send_message={send_data_to_wss} request_alert_box={show_alert_box} />
+                    <Authenticated send_message={send_data_to_wss.clone()} request_alert_box={show_alert_box} />
                 </section>
-                <Footer />
+                <Footer send_message={send_data_to_wss} />
             </div>
             </>
         }
@@ -8,6 +8,8 @@ pub enum AlertBoxToShow {
     ConfigWriteSuccess,
     ConfigWriteFailure,
     UnsavedChanges,
+    NewAlert,
+    Emergency,
     #[default]
     None,
 }
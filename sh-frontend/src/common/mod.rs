@@ -5,4 +5,5 @@
 
 pub mod alert_boxes;
 pub mod panels;
+pub mod time;
 pub mod wssprops;
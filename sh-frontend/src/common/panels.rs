@@ -9,7 +9,10 @@ use serde::{Deserialize, Serialize};
 pub enum Panels {
     Messages,
     Map,
+    Flights,
+    Alerts,
     Stats,
+    Weather,
     Settings,
     Help,
     #[default]
@@ -22,7 +25,10 @@ impl std::fmt::Display for Panels {
         match self {
             Self::Messages => write!(f, "Messages"),
             Self::Map => write!(f, "Map"),
+            Self::Flights => write!(f, "Flights"),
+            Self::Alerts => write!(f, "Alerts"),
             Self::Stats => write!(f, "Stats"),
+            Self::Weather => write!(f, "Weather"),
             Self::Settings => write!(f, "Settings"),
             Self::Help => write!(f, "Help"),
             Self::None => write!(f, "None"),
@@ -36,7 +42,10 @@ impl From<&str> for Panels {
         match s {
             "Messages" => Self::Messages,
             "Map" => Self::Map,
+            "Flights" => Self::Flights,
+            "Alerts" => Self::Alerts,
             "Stats" => Self::Stats,
+            "Weather" => Self::Weather,
             "Settings" => Self::Settings,
             "Help" => Self::Help,
             _ => Self::None,
@@ -58,13 +67,34 @@ impl Panels {
                 }
             }
             Self::Map => {
+                if skip == Self::Flights {
+                    Self::Stats
+                } else {
+                    Self::Flights
+                }
+            }
+            Self::Flights => {
+                if skip == Self::Alerts {
+                    Self::Stats
+                } else {
+                    Self::Alerts
+                }
+            }
+            Self::Alerts => {
                 if skip == Self::Stats {
-                    Self::Settings
+                    Self::Weather
                 } else {
-                    Self::Map
+                    Self::Stats
                 }
             }
             Self::Stats => {
+                if skip == Self::Weather {
+                    Self::Settings
+                } else {
+                    Self::Weather
+                }
+            }
+            Self::Weather => {
                 if skip == Self::Settings {
                     Self::Help
                 } else {
@@ -109,23 +139,44 @@ impl Panels {
                     Self::Messages
                 }
             }
-            Self::Stats => {
+            Self::Flights => {
                 if skip == Self::Map {
                     Self::Messages
                 } else {
                     Self::Map
                 }
             }
-            Self::Settings => {
+            Self::Alerts => {
+                if skip == Self::Flights {
+                    Self::Map
+                } else {
+                    Self::Flights
+                }
+            }
+            Self::Stats => {
+                if skip == Self::Alerts {
+                    Self::Flights
+                } else {
+                    Self::Alerts
+                }
+            }
+            Self::Weather => {
                 if skip == Self::Stats {
                     Self::Map
                 } else {
                     Self::Stats
                 }
             }
+            Self::Settings => {
+                if skip == Self::Weather {
+                    Self::Stats
+                } else {
+                    Self::Weather
+                }
+            }
             Self::Help => {
                 if skip == Self::Settings {
-                    Self::Stats
+                    Self::Weather
                 } else {
                     Self::Settings
                 }
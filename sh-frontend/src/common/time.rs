@@ -0,0 +1,22 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use wasm_bindgen::JsValue;
+
+/// Format a Unix timestamp, in seconds, as a time in the browser's locale
+#[must_use]
+pub fn format_time(timestamp: f64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(timestamp * 1000.0))
+        .to_locale_time_string("default")
+        .into()
+}
+
+/// Format a Unix timestamp, in seconds, as a date and time in the browser's locale
+#[must_use]
+pub fn format_date_time(timestamp: f64) -> String {
+    js_sys::Date::new(&JsValue::from_f64(timestamp * 1000.0))
+        .to_locale_string("default", &JsValue::UNDEFINED)
+        .into()
+}
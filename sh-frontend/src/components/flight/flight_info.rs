@@ -0,0 +1,61 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct FlightInfoProps {
+    pub flight: String,
+    pub send_message: Callback<UserWssMessage>,
+}
+
+/// Shows the airline name and route for a flight number. The info is requested from the
+/// server the first time a flight is shown and cached in the temp state after that
+#[function_component(FlightInfo)]
+pub fn flight_info(props: &FlightInfoProps) -> Html {
+    let info = use_selector_with_deps(
+        |state: &WebAppStateTemp, flight| state.flight_info.get(flight).cloned(),
+        props.flight.clone(),
+    );
+
+    {
+        let send_message = props.send_message.clone();
+        let have_info = info.is_some();
+        use_effect_with(props.flight.clone(), move |flight| {
+            if !have_info && !flight.is_empty() {
+                log::debug!("Requesting flight info for {flight}");
+                send_message.emit(UserWssMessage::new(
+                    UserMessageTypes::UserRequestFlightInfo,
+                    MessageData::ShFlightLookup(flight.clone()),
+                ));
+            }
+        });
+    }
+
+    let Some(info) = info.as_ref() else {
+        return html! { <span class="flight-info">{ props.flight.clone() }</span> };
+    };
+
+    let flight = match (&info.flight_iata, &info.flight_icao) {
+        (Some(iata), Some(icao)) => format!("{iata}/{icao}"),
+        (None, Some(icao)) => icao.clone(),
+        _ => info.flight.clone(),
+    };
+
+    html! {
+        <span class="flight-info">
+            <span class="flight-info-flight">{ flight }</span>
+            if let Some(airline_name) = &info.airline_name {
+                <span class="flight-info-airline" title={info.airline_callsign.clone().unwrap_or_default()}>{ airline_name.clone() }</span>
+            }
+            if let (Some(origin), Some(destination)) = (&info.origin, &info.destination) {
+                <span class="flight-info-route">{ format!("{origin} \u{2192} {destination}") }</span>
+            }
+        </span>
+    }
+}
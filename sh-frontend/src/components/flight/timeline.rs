@@ -0,0 +1,121 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::common::time::format_time;
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::timeline::{ShTimelineEntry, ShTimelineEntryKind, ShTimelineFlagKind};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct FlightTimelineProps {
+    pub flight: String,
+    pub send_message: Callback<UserWssMessage>,
+}
+
+/// OOOI events, messages and ADS-B milestones for a flight. The timeline is requested again
+/// whenever a new message for the flight comes in
+#[function_component(FlightTimeline)]
+pub fn flight_timeline(props: &FlightTimelineProps) -> Html {
+    let dispatch = Dispatch::<WebAppStateTemp>::global();
+    let timeline = use_selector(|state: &WebAppStateTemp| state.flight_timeline.clone());
+    let latest_message = use_selector_with_deps(
+        |state: &WebAppStateTemp, flight| {
+            state
+                .messages
+                .iter()
+                .find(|message| message.flight.as_deref() == Some(flight.as_str()))
+                .map(|message| message.timestamp.to_bits())
+        },
+        props.flight.clone(),
+    );
+
+    {
+        let send_message = props.send_message.clone();
+        use_effect_with(
+            (props.flight.clone(), *latest_message),
+            move |(flight, _)| {
+                send_message.emit(UserWssMessage::new(
+                    UserMessageTypes::UserRequestFlightTimeline,
+                    MessageData::ShFlightLookup(flight.clone()),
+                ));
+            },
+        );
+    }
+
+    let close = Callback::from(move |_: MouseEvent| {
+        dispatch.reduce_mut(|state| {
+            state.selected_flight = None;
+            state.flight_timeline = None;
+        });
+    });
+
+    let body = match timeline.as_ref() {
+        None => html! { <p>{"Loading timeline..."}</p> },
+        Some(timeline) if timeline.entries.is_empty() => {
+            html! { <p>{"Nothing has been seen for this flight yet."}</p> }
+        }
+        Some(timeline) => html! {
+            <>
+                if !timeline.flags.is_empty() {
+                    <ul class="flight-timeline-flags">
+                        { for timeline.flags.iter().map(|flag| {
+                            let class = match flag.kind {
+                                ShTimelineFlagKind::Missing => "flight-timeline-flag-missing",
+                                ShTimelineFlagKind::Contradictory => "flight-timeline-flag-contradictory",
+                            };
+                            html! { <li class={class}>{ flag.description.clone() }</li> }
+                        }) }
+                    </ul>
+                }
+                <ol class="flight-timeline-entries">
+                    { for timeline.entries.iter().map(render_entry) }
+                </ol>
+            </>
+        },
+    };
+
+    let title = timeline
+        .as_ref()
+        .as_ref()
+        .and_then(|timeline| timeline.tail.as_ref())
+        .map_or_else(
+            || props.flight.clone(),
+            |tail| format!("{} ({tail})", props.flight),
+        );
+
+    html! {
+        <div class="flight-timeline">
+            <div class="flight-timeline-header">
+                <h2>{ title }</h2>
+                <button class="flight-timeline-close" onclick={close}>{"\u{2715}"}</button>
+            </div>
+            { body }
+        </div>
+    }
+}
+
+fn render_entry(entry: &ShTimelineEntry) -> Html {
+    let (class, description) = match &entry.kind {
+        ShTimelineEntryKind::Oooi(event_type) => ("flight-timeline-oooi", event_type.to_string()),
+        ShTimelineEntryKind::Position(milestone) => {
+            ("flight-timeline-position", milestone.to_string())
+        }
+        ShTimelineEntryKind::Message { label, text } => {
+            let label = label.clone().unwrap_or_default();
+            let text = text.clone().unwrap_or_default();
+            let text = text.lines().next().unwrap_or_default();
+            ("flight-timeline-message", format!("{label} {text}"))
+        }
+    };
+
+    html! {
+        <li class={class}>
+            <span class="flight-timeline-time">{ format_time(entry.timestamp) }</span>
+            <span class="flight-timeline-description">{ description }</span>
+        </li>
+    }
+}
@@ -3,24 +3,174 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use crate::common::time::format_time;
 use crate::services::temp_state::WebAppStateTemp;
+use sh_common::consumer_status::{ShConsumerState, ShConsumerStatus};
+use sh_common::source_status::{ShSourceState, ShSourceStatus};
 use yew::prelude::*;
 use yewdux::prelude::*;
 
+/// The status tables that can be opened from the footer, one at a time
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Panel {
+    Sources,
+    Consumers,
+}
+
+const fn state_class(state: ShSourceState) -> &'static str {
+    match state {
+        ShSourceState::Connected => "text-sdre-green-lighter",
+        ShSourceState::Connecting | ShSourceState::Silent => "text-sdre-yellow",
+        ShSourceState::Disconnected => "text-sdre-red-darker",
+    }
+}
+
+const fn consumer_state_class(state: ShConsumerState) -> &'static str {
+    match state {
+        ShConsumerState::Running | ShConsumerState::Stopped => "text-sdre-green-lighter",
+        ShConsumerState::Restarting => "text-sdre-yellow",
+        ShConsumerState::Failed | ShConsumerState::CrashLooping => "text-sdre-red-darker",
+    }
+}
+
+const fn consumer_severity(state: ShConsumerState) -> u8 {
+    match state {
+        ShConsumerState::Running | ShConsumerState::Stopped => 0,
+        ShConsumerState::Restarting => 1,
+        ShConsumerState::Failed | ShConsumerState::CrashLooping => 2,
+    }
+}
+
+fn render_consumer(consumer: &ShConsumerStatus) -> Html {
+    // a restarting consumer says when it will be back
+    let state = consumer.restart_at.map_or_else(
+        || format!("{} since {}", consumer.state, format_time(consumer.since)),
+        |restart_at| format!("{} at {}", consumer.state, format_time(restart_at)),
+    );
+
+    html! {
+        <tr>
+            <td>{ &consumer.name }</td>
+            <td class={consumer_state_class(consumer.state)}>{ state }</td>
+            <td>{ consumer.policy.to_string() }</td>
+            <td>{ consumer.restarts }</td>
+            <td>{ consumer.last_error.clone().unwrap_or_default() }</td>
+        </tr>
+    }
+}
+
+fn render_source(source: &ShSourceStatus) -> Html {
+    html! {
+        <tr>
+            <td>{ &source.name }</td>
+            <td>{ source.kind.to_string() }</td>
+            <td class={state_class(source.state)}>
+                { format!("{} since {}", source.state, format_time(source.since)) }
+            </td>
+            <td>{ source.last_data.map_or_else(|| "Never".to_string(), format_time) }</td>
+            <td>{ source.reconnects }</td>
+            <td>{ source.last_error.clone().unwrap_or_default() }</td>
+        </tr>
+    }
+}
+
 #[function_component(Connected)]
 pub fn connected() -> Html {
     log::debug!("Connected component rendered");
 
     let connected = use_selector(|state: &WebAppStateTemp| state.websocket_connected);
+    let sources = use_selector(|state: &WebAppStateTemp| state.sources.clone());
+    let consumers = use_selector(|state: &WebAppStateTemp| state.consumers.clone());
+    let expanded = use_state(|| None::<Panel>);
+
+    let toggle = |panel: Panel| {
+        let expanded = expanded.clone();
+        Callback::from(move |_: MouseEvent| {
+            expanded.set(if *expanded == Some(panel) {
+                None
+            } else {
+                Some(panel)
+            });
+        })
+    };
+
+    let healthy = sources.iter().filter(|source| source.is_healthy()).count();
+    // the summary takes the colour of the worst source
+    let summary_class = sources
+        .iter()
+        .map(|source| source.state)
+        .max_by_key(|state| match state {
+            ShSourceState::Connected => 0,
+            ShSourceState::Connecting | ShSourceState::Silent => 1,
+            ShSourceState::Disconnected => 2,
+        })
+        .map_or("text-sdre-green-lighter", state_class);
+
+    let consumers_healthy = consumers
+        .iter()
+        .filter(|consumer| consumer.is_healthy())
+        .count();
+    let consumers_class = consumers
+        .iter()
+        .map(|consumer| consumer.state)
+        .max_by_key(|state| consumer_severity(*state))
+        .map_or("text-sdre-green-lighter", consumer_state_class);
 
     html! {
-        <div> {
+        <div class="connected"> {
             if *connected {
                 html!{<span class="text-sdre-green-lighter">{"Connected"}</span>}
             } else {
                 html!{<span class="text-sdre-red-darker">{"Disconnected"}</span>}
             }
          }
+            if *connected && !sources.is_empty() {
+                <span class={classes!("source-summary", summary_class)} onclick={toggle(Panel::Sources)} title="Show the status of each source">
+                    { format!("Sources: {healthy}/{} healthy", sources.len()) }
+                </span>
+                if *expanded == Some(Panel::Sources) {
+                    <div class="source-status">
+                        <table>
+                            <thead>
+                                <tr>
+                                    <th>{"Source"}</th>
+                                    <th>{"Type"}</th>
+                                    <th>{"Status"}</th>
+                                    <th>{"Last Data"}</th>
+                                    <th>{"Reconnects"}</th>
+                                    <th>{"Last Error"}</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                { for sources.iter().map(render_source) }
+                            </tbody>
+                        </table>
+                    </div>
+                }
+            }
+            if *connected && !consumers.is_empty() {
+                <span class={classes!("source-summary", consumers_class)} onclick={toggle(Panel::Consumers)} title="Show the status of everything the server runs">
+                    { format!("Services: {consumers_healthy}/{} healthy", consumers.len()) }
+                </span>
+                if *expanded == Some(Panel::Consumers) {
+                    <div class="source-status">
+                        <table>
+                            <thead>
+                                <tr>
+                                    <th>{"Service"}</th>
+                                    <th>{"Status"}</th>
+                                    <th>{"Restart"}</th>
+                                    <th>{"Restarts"}</th>
+                                    <th>{"Last Error"}</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                { for consumers.iter().map(render_consumer) }
+                            </tbody>
+                        </table>
+                    </div>
+                }
+            }
          </div>
     }
 }
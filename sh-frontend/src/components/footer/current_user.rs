@@ -0,0 +1,47 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::services::saved_state::WebAppState;
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct CurrentUserProps {
+    pub send_message: Callback<UserWssMessage>,
+}
+
+/// Who we are logged in as. Users the server lets in without logging in have nothing to log
+/// out of
+#[function_component(CurrentUser)]
+pub fn current_user(props: &CurrentUserProps) -> Html {
+    let user = use_selector(|state: &WebAppStateTemp| state.user().cloned());
+    let (saved, saved_dispatch) = use_store::<WebAppState>();
+
+    let logout = {
+        let send_message = props.send_message.clone();
+        Callback::from(move |_: MouseEvent| {
+            saved_dispatch.reduce_mut(|state| state.session_token = None);
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserLogout,
+                MessageData::NoData,
+            ));
+        })
+    };
+
+    let Some(user) = user.as_ref() else {
+        return html! {};
+    };
+
+    html! {
+        <div class="current-user">
+            <span>{ format!("{} ({})", user.name, user.role) }</span>
+            if saved.session_token.is_some() {
+                <span class="logout" onclick={logout}>{"Log Out"}</span>
+            }
+        </div>
+    }
+}
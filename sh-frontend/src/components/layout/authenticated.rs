@@ -0,0 +1,25 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::common::wssprops::WssCommunicationProps;
+use crate::components::layout::live::Live;
+use crate::components::pages::login::ShLoginPage;
+use crate::services::temp_state::WebAppStateTemp;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+/// The panels, or the login page when the server says we have to log in first
+#[function_component(Authenticated)]
+pub fn authenticated(props: &WssCommunicationProps) -> Html {
+    let needs_login = use_selector(|state: &WebAppStateTemp| {
+        state.auth.as_ref().is_some_and(|auth| auth.user.is_none())
+    });
+
+    if *needs_login {
+        html! { <ShLoginPage send_message={props.send_message.clone()} /> }
+    } else {
+        html! { <Live send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} /> }
+    }
+}
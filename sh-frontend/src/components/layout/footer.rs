@@ -4,15 +4,23 @@
 // https://opensource.org/licenses/MIT.
 
 use crate::components::footer::connected::Connected;
+use crate::components::footer::current_user::CurrentUser;
+use sh_common::UserWssMessage;
 use yew::prelude::*;
 
+#[derive(Properties, Clone, PartialEq)]
+pub struct FooterProps {
+    pub send_message: Callback<UserWssMessage>,
+}
+
 #[function_component(Footer)]
-pub fn footer() -> Html {
+pub fn footer(props: &FooterProps) -> Html {
     log::debug!("Rendering footer.");
 
     html! {
         <footer class="footer">
             <Connected />
+            <CurrentUser send_message={props.send_message.clone()} />
         </footer>
     }
 }
@@ -5,9 +5,12 @@
 
 use crate::components::map::map_display::ShMap;
 use crate::components::pages::acars_messages::AcarsMessages;
+use crate::components::pages::alerts::ShAlerts;
+use crate::components::pages::flights::ShFlights;
 use crate::components::pages::help::ShHelp;
 use crate::components::pages::settings::ShSettings;
 use crate::components::pages::stats::ShStatistics;
+use crate::components::pages::weather::ShWeather;
 use crate::services::saved_state::WebAppState;
 use crate::{
     common::panels::Panels, common::wssprops::WssCommunicationProps,
@@ -47,26 +50,36 @@ pub fn live(props: &WssCommunicationProps) -> Html {
         #[allow(clippy::redundant_clone)]
         let right_panel = right_panel.clone();
         match *right_panel {
-            Panels::Messages => html! { <AcarsMessages /> },
+            Panels::Messages => {
+                html! { <AcarsMessages send_message={props.send_message.clone()} /> }
+            }
             Panels::Map => html! { <ShMap /> },
+            Panels::Flights => html! { <ShFlights send_message={props.send_message.clone()} /> },
+            Panels::Alerts => html! { <ShAlerts send_message={props.send_message.clone()} /> },
             Panels::Settings => {
                 html! { <ShSettings send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()}/>}
             }
             Panels::Help => html! { <ShHelp /> },
-            Panels::Stats => html! { <ShStatistics /> },
+            Panels::Stats => html! { <ShStatistics send_message={props.send_message.clone()} /> },
+            Panels::Weather => html! { <ShWeather send_message={props.send_message.clone()} /> },
             Panels::None => panic!("Right Panel is none!!!"),
         }
     };
 
     let left_panel_show = {
         match *left_panel {
-            Panels::Messages => html! { <AcarsMessages /> },
+            Panels::Messages => {
+                html! { <AcarsMessages send_message={props.send_message.clone()} /> }
+            }
             Panels::Map => html! { <ShMap /> },
+            Panels::Flights => html! { <ShFlights send_message={props.send_message.clone()} /> },
+            Panels::Alerts => html! { <ShAlerts send_message={props.send_message.clone()} /> },
             Panels::Settings => {
                 html! { <ShSettings send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} />}
             }
             Panels::Help => html! { <ShHelp /> },
-            Panels::Stats => html! { <ShStatistics /> },
+            Panels::Stats => html! { <ShStatistics send_message={props.send_message.clone()} /> },
+            Panels::Weather => html! { <ShWeather send_message={props.send_message.clone()} /> },
             Panels::None => panic!("Left Panel is none!!!"),
         }
     };
@@ -3,6 +3,7 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+pub mod authenticated;
 pub mod footer;
 pub mod live;
 pub mod nav;
@@ -138,6 +138,22 @@ pub fn nav() -> Html {
         state_local.clone(),
         dispatch_local.clone(),
     );
+    let right_panel_flights = MenuItemState::new(
+        Rc::new(*right_panel_visible),
+        menu_state_right.clone(),
+        PanelSide::Right,
+        Panels::Flights,
+        state_local.clone(),
+        dispatch_local.clone(),
+    );
+    let right_panel_alerts = MenuItemState::new(
+        Rc::new(*right_panel_visible),
+        menu_state_right.clone(),
+        PanelSide::Right,
+        Panels::Alerts,
+        state_local.clone(),
+        dispatch_local.clone(),
+    );
     let right_panel_stats = MenuItemState::new(
         Rc::new(*right_panel_visible),
         menu_state_right.clone(),
@@ -146,6 +162,14 @@ pub fn nav() -> Html {
         state_local.clone(),
         dispatch_local.clone(),
     );
+    let right_panel_weather = MenuItemState::new(
+        Rc::new(*right_panel_visible),
+        menu_state_right.clone(),
+        PanelSide::Right,
+        Panels::Weather,
+        state_local.clone(),
+        dispatch_local.clone(),
+    );
     let right_panel_settings = MenuItemState::new(
         Rc::new(*right_panel_visible),
         menu_state_right.clone(),
@@ -187,6 +211,22 @@ pub fn nav() -> Html {
         state_local.clone(),
         dispatch_local.clone(),
     );
+    let left_panel_flights = MenuItemState::new(
+        Rc::new(*right_panel_visible),
+        menu_state_left.clone(),
+        PanelSide::Left,
+        Panels::Flights,
+        state_local.clone(),
+        dispatch_local.clone(),
+    );
+    let left_panel_alerts = MenuItemState::new(
+        Rc::new(*right_panel_visible),
+        menu_state_left.clone(),
+        PanelSide::Left,
+        Panels::Alerts,
+        state_local.clone(),
+        dispatch_local.clone(),
+    );
     let left_panel_stats = MenuItemState::new(
         Rc::new(*right_panel_visible),
         menu_state_left.clone(),
@@ -195,6 +235,14 @@ pub fn nav() -> Html {
         state_local.clone(),
         dispatch_local.clone(),
     );
+    let left_panel_weather = MenuItemState::new(
+        Rc::new(*right_panel_visible),
+        menu_state_left.clone(),
+        PanelSide::Left,
+        Panels::Weather,
+        state_local.clone(),
+        dispatch_local.clone(),
+    );
     let left_panel_settings = MenuItemState::new(
         Rc::new(*right_panel_visible),
         menu_state_left.clone(),
@@ -263,6 +311,24 @@ pub fn nav() -> Html {
                             html! {}
                         }
                     }
+                    {
+                        if left_panel_flights.show() {
+                            html! {
+                                <li onclick={left_panel_flights.callback()}>{ "Left: Flights" }</li>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        if left_panel_alerts.show() {
+                            html! {
+                                <li onclick={left_panel_alerts.callback()}>{ "Left: Alerts" }</li>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
                     {
                         if left_panel_stats.show()
                         {
@@ -273,6 +339,15 @@ pub fn nav() -> Html {
                             html! {}
                         }
                     }
+                    {
+                        if left_panel_weather.show() {
+                            html! {
+                                <li onclick={left_panel_weather.callback()}>{ "Left: Weather" }</li>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
                     {
                         if left_panel_settings.show() {
                             html! {
@@ -323,6 +398,24 @@ pub fn nav() -> Html {
                                             html! {}
                                         }
                                     }
+                                    {
+                                        if right_panel_flights.show() {
+                                            html! {
+                                                <li onclick={right_panel_flights.callback()}>{ "Right: Flights" }</li>
+                                            }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
+                                    {
+                                        if right_panel_alerts.show() {
+                                            html! {
+                                                <li onclick={right_panel_alerts.callback()}>{ "Right: Alerts" }</li>
+                                            }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
                                     {
                                         if right_panel_stats.show() {
                                             html! {
@@ -332,6 +425,15 @@ pub fn nav() -> Html {
                                             html! {}
                                         }
                                     }
+                                    {
+                                        if right_panel_weather.show() {
+                                            html! {
+                                                <li onclick={right_panel_weather.callback()}>{ "Right: Weather" }</li>
+                                            }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
                                     {
                                         if right_panel_settings.show() {
                                             html! {
@@ -4,6 +4,7 @@
 // https://opensource.org/licenses/MIT.
 
 pub mod alerts;
+pub mod flight;
 pub mod footer;
 pub mod input;
 pub mod layout;
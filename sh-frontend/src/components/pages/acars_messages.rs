@@ -3,16 +3,236 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use crate::common::time::format_time;
+use crate::components::flight::flight_info::FlightInfo;
+use crate::components::flight::timeline::FlightTimeline;
+use crate::services::saved_state::WebAppState;
+use crate::services::temp_state::WebAppStateTemp;
+use reqwasm::http::Request;
+use sh_common::acars_message::{ShAcarsMessage, ShMessageDirection};
+use sh_common::api::ShExportFormat;
+use sh_common::auth::ShDownloadToken;
+use sh_common::UserWssMessage;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
+use yewdux::prelude::*;
+
+// The search fields the export form has, as the REST API names them
+const EXPORT_FILTERS: [(&str, &str); 5] = [
+    ("text", "Text"),
+    ("aircraft", "Aircraft"),
+    ("flight", "Flight"),
+    ("label", "Label"),
+    ("message_type", "Type, like VDLM2"),
+];
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct AcarsMessagesProps {
+    pub send_message: Callback<UserWssMessage>,
+}
 
 #[function_component(AcarsMessages)]
-pub fn acars_messages() -> Html {
+pub fn acars_messages(props: &AcarsMessagesProps) -> Html {
     log::debug!("Rendering ACARS messages page.");
 
+    let messages = use_selector(|state: &WebAppStateTemp| state.messages.clone());
+    let selected_flight = use_selector(|state: &WebAppStateTemp| state.selected_flight.clone());
+
+    html! {
+        <div class="acars-messages">
+            <MessageExport />
+            if let Some(flight) = selected_flight.as_ref() {
+                <FlightTimeline flight={flight.clone()} send_message={props.send_message.clone()} />
+            }
+            if messages.is_empty() {
+                <p>{"No messages received yet."}</p>
+            }
+            { for messages.iter().map(|message| render_message(message, &props.send_message)) }
+        </div>
+    }
+}
+
+fn input_value(node: &NodeRef) -> String {
+    node.cast::<HtmlInputElement>()
+        .map(|input| input.value().trim().to_string())
+        .unwrap_or_default()
+}
+
+/// A `datetime-local` input as a Unix timestamp, in seconds, if it has been filled in
+fn input_timestamp(node: &NodeRef) -> Option<f64> {
+    let milliseconds = js_sys::Date::parse(&input_value(node));
+    (!milliseconds.is_nan()).then_some(milliseconds / 1000.0)
+}
+
+/// Send the browser to the export. A link can't send the session in a header, so it gets a
+/// token that works for this one download instead
+// browser futures are never sent to another thread
+#[allow(clippy::future_not_send)]
+async fn start_export(mut parameters: Vec<(String, String)>) {
+    let mut request = Request::post("/api/v1/messages/export/token");
+    if let Some(token) = Dispatch::<WebAppState>::global()
+        .get()
+        .session_token
+        .clone()
+    {
+        request = request.header("Authorization", &format!("Bearer {token}"));
+    }
+
+    let download = match request.send().await {
+        Ok(response) if response.ok() => response.json::<ShDownloadToken>().await,
+        Ok(response) => {
+            log::error!("Error starting the export: {}", response.status_text());
+            return;
+        }
+        Err(e) => Err(e),
+    };
+    match download {
+        Ok(download) => parameters.push(("token".to_string(), download.token)),
+        Err(e) => {
+            log::error!("Error starting the export: {e}");
+            return;
+        }
+    }
+
+    let query = parameters
+        .iter()
+        .map(|(name, value)| format!("{name}={}", js_sys::encode_uri_component(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    // the file comes back as an attachment, so the page stays where it is
+    if let Err(e) = gloo_utils::window()
+        .location()
+        .set_href(&format!("/api/v1/messages/export?{query}"))
+    {
+        log::error!("Error starting the export: {e:?}");
+    }
+}
+
+/// Download the stored messages matching a search. The browser saves the file as it comes in,
+/// so a big export isn't held in the page
+#[function_component(MessageExport)]
+fn message_export() -> Html {
+    let filter_nodes = use_memo((), |()| EXPORT_FILTERS.map(|_| NodeRef::default()));
+    let since_node = use_node_ref();
+    let until_node = use_node_ref();
+    let format_node = use_node_ref();
+    let aircraft_node = use_node_ref();
+
+    let onsubmit = {
+        let filter_nodes = filter_nodes.clone();
+        let since_node = since_node.clone();
+        let until_node = until_node.clone();
+        let format_node = format_node.clone();
+        let aircraft_node = aircraft_node.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let format = format_node
+                .cast::<HtmlSelectElement>()
+                .map(|select| select.value())
+                .unwrap_or_default();
+            let mut parameters = vec![("format".to_string(), format)];
+            for ((name, _), node) in EXPORT_FILTERS.iter().zip(filter_nodes.iter()) {
+                let value = input_value(node);
+                if !value.is_empty() {
+                    parameters.push(((*name).to_string(), value));
+                }
+            }
+            if let Some(since) = input_timestamp(&since_node) {
+                parameters.push(("since".to_string(), since.to_string()));
+            }
+            if let Some(until) = input_timestamp(&until_node) {
+                parameters.push(("until".to_string(), until.to_string()));
+            }
+            if aircraft_node
+                .cast::<HtmlInputElement>()
+                .is_some_and(|input| input.checked())
+            {
+                parameters.push(("aircraft_info".to_string(), "true".to_string()));
+            }
+
+            wasm_bindgen_futures::spawn_local(start_export(parameters));
+        })
+    };
+
+    html! {
+        <details class="message-export">
+            <summary>{"Export messages"}</summary>
+            <form class="message-export-filters" {onsubmit}>
+                { for EXPORT_FILTERS.iter().zip(filter_nodes.iter()).map(|((name, placeholder), node)| html! {
+                    <input type="text" class="text-black" name={*name} placeholder={*placeholder} ref={node.clone()} />
+                }) }
+                <label>{"From "}<input type="datetime-local" class="text-black" ref={since_node} /></label>
+                <label>{"To "}<input type="datetime-local" class="text-black" ref={until_node} /></label>
+                <select class="text-black" ref={format_node}>
+                    { for ShExportFormat::ALL.iter().map(|format| html! {
+                        <option value={format.to_string()} selected={*format == ShExportFormat::Csv}>{ format.to_string().to_uppercase() }</option>
+                    }) }
+                </select>
+                <label>
+                    <input type="checkbox" ref={aircraft_node} />
+                    {"Aircraft info"}
+                </label>
+                <button type="submit" class="button">{"Download"}</button>
+            </form>
+        </details>
+    }
+}
+
+fn select_flight(flight: String) -> Callback<MouseEvent> {
+    Callback::from(move |_: MouseEvent| {
+        let flight = flight.clone();
+        Dispatch::<WebAppStateTemp>::global().reduce_mut(move |state| {
+            if state.selected_flight.as_ref() != Some(&flight) {
+                state.flight_timeline = None;
+                state.selected_flight = Some(flight);
+            }
+        });
+    })
+}
+
+pub fn render_message(message: &ShAcarsMessage, send_message: &Callback<UserWssMessage>) -> Html {
+    let time = format_time(message.timestamp);
+
+    let direction = match message.direction {
+        ShMessageDirection::Uplink => "\u{2191}",
+        ShMessageDirection::Downlink => "\u{2193}",
+        ShMessageDirection::Unknown => "",
+    };
+
+    // the name if we know it, otherwise the ID is better than nothing
+    let ground_station = message
+        .ground_station_name
+        .clone()
+        .or_else(|| message.ground_station_id.clone());
+
     html! {
-        <div>
-            <h1>{"ACARS Messages"}</h1>
-            <p>{"This is a placeholder for the ACARS messages page."}</p>
+        <div class="acars-message">
+            <div class="acars-message-header">
+                <span class="acars-message-time">{ time }</span>
+                <span class="acars-message-type">{ format!("{} {direction}", message.message_type) }</span>
+                if let Some(frequency) = message.frequency {
+                    <span class="acars-message-frequency">{ format!("{frequency:.3} MHz") }</span>
+                }
+                if let Some(tail) = &message.tail {
+                    <span class="acars-message-tail">{ tail.clone() }</span>
+                }
+                if let Some(flight) = &message.flight {
+                    <span class="acars-message-flight" title="Show the flight timeline" onclick={select_flight(flight.clone())}>
+                        <FlightInfo flight={flight.clone()} send_message={send_message.clone()} />
+                    </span>
+                }
+                if let Some(label) = &message.label {
+                    <span class="acars-message-label">{ format!("Label {label}") }</span>
+                }
+                if let Some(ground_station) = ground_station {
+                    <span class="acars-message-ground-station">{ ground_station }</span>
+                }
+            </div>
+            if let Some(text) = &message.text {
+                <pre class="acars-message-text">{ text.clone() }</pre>
+            }
         </div>
     }
 }
@@ -3,18 +3,39 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use crate::services::temp_state::WebAppStateTemp;
 use gloo_utils::document;
-use leaflet::{LatLng, Map, MapOptions, TileLayer};
-use wasm_bindgen::JsCast;
+use js_sys::Array;
+use leaflet::{
+    CircleMarker, LatLng, LayerGroup, Map, MapOptions, PathOptions, Polyline, PolylineOptions,
+    TileLayer, Tooltip, TooltipOptions,
+};
+use sh_common::emergency::ShEmergency;
+use sh_common::flight::ShFlightSessionDetail;
+use sh_common::ground_station::{ShGroundStation, ShGroundStationNetwork};
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{Element, HtmlElement, Node};
 use yew::{html::ImplicitClone, prelude::*};
+use yewdux::Dispatch;
 
-pub enum Msg {}
+pub enum Msg {
+    StateChanged(Rc<WebAppStateTemp>),
+}
 
 pub struct ShMapComponent {
     map: Map,
     lat: Point,
     container: HtmlElement,
+    ground_station_layer: LayerGroup,
+    ground_stations: Vec<ShGroundStation>,
+    track_layer: LayerGroup,
+    track: Option<ShFlightSessionDetail>,
+    emergency_layer: LayerGroup,
+    // Only the emergencies still going are pinned
+    emergencies: Vec<ShEmergency>,
+    // Held so the subscription lives as long as the map
+    _dispatch: Dispatch<WebAppStateTemp>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -38,6 +59,88 @@ impl ShMapComponent {
         let node: &Node = &self.container.clone().into();
         Html::VRef(node.clone())
     }
+
+    fn draw_ground_stations(&self) {
+        self.ground_station_layer.clear_layers();
+
+        for station in &self.ground_stations {
+            let (Some(latitude), Some(longitude)) = (station.latitude, station.longitude) else {
+                continue;
+            };
+
+            let options = PathOptions::new();
+            options.set_class_name(match station.network {
+                ShGroundStationNetwork::Vdlm2 => "ground-station-vdlm2".to_string(),
+                ShGroundStationNetwork::Hfdl => "ground-station-hfdl".to_string(),
+            });
+
+            let marker =
+                CircleMarker::new_with_options(&LatLng::new(latitude, longitude), &options);
+            marker.bind_tooltip_with_content(
+                &JsValue::from_str(&format!("{} ({})", station.display_name(), station.network)),
+                &JsValue::UNDEFINED,
+            );
+            marker.add_to_layer_group(&self.ground_station_layer);
+        }
+    }
+    /// Pin every aircraft in an emergency we have a position for, labelled so it can't be missed
+    fn draw_emergencies(&self) {
+        self.emergency_layer.clear_layers();
+
+        for emergency in &self.emergencies {
+            let Some((latitude, longitude)) = emergency.location() else {
+                continue;
+            };
+
+            let options = PathOptions::new();
+            options.set_class_name("emergency-aircraft".to_string());
+
+            let marker =
+                CircleMarker::new_with_options(&LatLng::new(latitude, longitude), &options);
+            marker.set_radius(12.0);
+
+            let tooltip_options = TooltipOptions::new();
+            tooltip_options.set_permanent(true);
+            tooltip_options.set_direction("top".to_string());
+            let tooltip = Tooltip::new(&tooltip_options, None);
+            tooltip.set_content(&JsValue::from_str(&format!(
+                "{}: {}",
+                emergency.aircraft(),
+                emergency.kind
+            )));
+            marker.bind_tooltip(&tooltip);
+            marker.add_to_layer_group(&self.emergency_layer);
+        }
+    }
+
+    /// Draw the track of the flight the user has open, and move the map to show it
+    fn draw_track(&self) {
+        self.track_layer.clear_layers();
+
+        let Some(track) = &self.track else {
+            return;
+        };
+
+        let points: Array = track
+            .track
+            .iter()
+            .filter_map(|position| match (position.latitude, position.longitude) {
+                (Some(latitude), Some(longitude)) => Some(LatLng::new(latitude, longitude)),
+                _ => None,
+            })
+            .collect();
+
+        if points.length() == 0 {
+            return;
+        }
+
+        let options = PolylineOptions::new();
+        options.set_class_name("flight-track".to_string());
+
+        let line = Polyline::new_with_options(&points, &options);
+        line.add_to_layer_group(&self.track_layer);
+        self.map.fit_bounds(&line.get_bounds());
+    }
 }
 
 impl Component for ShMapComponent {
@@ -51,10 +154,24 @@ impl Component for ShMapComponent {
         let container: HtmlElement = container.dyn_into().unwrap();
         container.set_class_name("map");
         let leaflet_map = Map::new_with_element(&container, &MapOptions::default());
+
+        let dispatch =
+            Dispatch::<WebAppStateTemp>::global().subscribe(ctx.link().callback(Msg::StateChanged));
+        let ground_stations = dispatch.get().ground_stations.clone();
+        let track = dispatch.get().selected_session.clone();
+        let emergencies = dispatch.get().active_emergencies();
+
         Self {
             map: leaflet_map,
             container,
             lat: props.city.lat,
+            ground_station_layer: LayerGroup::new(),
+            ground_stations,
+            track_layer: LayerGroup::new(),
+            track,
+            emergency_layer: LayerGroup::new(),
+            emergencies,
+            _dispatch: dispatch,
         }
     }
 
@@ -63,10 +180,37 @@ impl Component for ShMapComponent {
             self.map
                 .set_view(&LatLng::new(self.lat.0, self.lat.1), 11.0);
             add_tile_layer(&self.map);
+            self.ground_station_layer.add_to(&self.map);
+            self.draw_ground_stations();
+            self.track_layer.add_to(&self.map);
+            self.draw_track();
+            self.emergency_layer.add_to(&self.map);
+            self.draw_emergencies();
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, _msg: Self::Message) -> bool {
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::StateChanged(state) => {
+                // most state changes are new messages, which don't move anything on the map
+                if state.ground_stations != self.ground_stations {
+                    self.ground_stations.clone_from(&state.ground_stations);
+                    self.draw_ground_stations();
+                }
+
+                if state.selected_session != self.track {
+                    self.track.clone_from(&state.selected_session);
+                    self.draw_track();
+                }
+
+                let emergencies = state.active_emergencies();
+                if emergencies != self.emergencies {
+                    self.emergencies = emergencies;
+                    self.draw_emergencies();
+                }
+            }
+        }
+
         false
     }
 
@@ -0,0 +1,160 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::common::time::format_time;
+use crate::components::pages::acars_messages::render_message;
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::alert::ShAlert;
+use sh_common::emergency::ShEmergency;
+use sh_common::notification::ShNotificationDelivery;
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct AlertsProps {
+    pub send_message: Callback<UserWssMessage>,
+}
+
+/// Emergencies the hub has spotted, then everything the alert rules have matched, newest first.
+/// Rules are set up on the settings page
+#[function_component(ShAlerts)]
+pub fn alerts(props: &AlertsProps) -> Html {
+    log::debug!("Rendering alerts page.");
+
+    let alerts = use_selector(|state: &WebAppStateTemp| state.alerts.clone());
+    let deliveries = use_selector(|state: &WebAppStateTemp| state.notification_deliveries.clone());
+    let emergencies = use_selector(|state: &WebAppStateTemp| state.emergencies.clone());
+
+    {
+        let send_message = props.send_message.clone();
+        use_effect_with((), move |()| {
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserRequestAlertHistory,
+                MessageData::NoData,
+            ));
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserRequestNotificationLog,
+                MessageData::NoData,
+            ));
+        });
+    }
+
+    html! {
+        <div class="alerts">
+            if !emergencies.is_empty() {
+                <h2>{"Emergencies"}</h2>
+                { for emergencies.iter().map(render_emergency) }
+            }
+            <h2>{"Alerts"}</h2>
+            if alerts.is_empty() {
+                <p>{"No alerts have been raised. Alert rules can be added on the settings page."}</p>
+            }
+            { for alerts.iter().map(|alert| render_alert(alert, &props.send_message)) }
+            if !deliveries.is_empty() {
+                <h2>{"Notifications"}</h2>
+                <table class="alert-deliveries">
+                    <thead>
+                        <tr>
+                            <th>{"Time"}</th>
+                            <th>{"Rule"}</th>
+                            <th>{"Target"}</th>
+                            <th>{"Attempts"}</th>
+                            <th>{"Result"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for deliveries.iter().map(render_delivery) }
+                    </tbody>
+                </table>
+            }
+        </div>
+    }
+}
+
+fn render_alert(alert: &ShAlert, send_message: &Callback<UserWssMessage>) -> Html {
+    html! {
+        <div class="alert-item">
+            <div class="alert-item-header">
+                <span class="alert-item-rule">{ &alert.rule }</span>
+                <span class="alert-item-aircraft">{ alert.aircraft() }</span>
+                <span class="alert-item-time">{ format_time(alert.timestamp) }</span>
+            </div>
+            <div class="alert-item-description">{ format!("Matched {}", alert.description) }</div>
+            if let Some(position) = &alert.position {
+                <div class="alert-item-description">
+                    { format!(
+                        "Squawk {} at {} ft",
+                        position.squawk.as_deref().unwrap_or("unknown"),
+                        position.altitude.map_or_else(|| "unknown".to_string(), |altitude| altitude.to_string())
+                    ) }
+                </div>
+            }
+            if let Some(message) = &alert.message {
+                { render_message(message, send_message) }
+            }
+        </div>
+    }
+}
+
+fn render_emergency(emergency: &ShEmergency) -> Html {
+    let (class, status) = emergency.cleared.map_or_else(
+        || ("emergency-item emergency-active", "Ongoing".to_string()),
+        |cleared| {
+            (
+                "emergency-item",
+                format!("Over at {}", format_time(cleared)),
+            )
+        },
+    );
+
+    html! {
+        <div class={class}>
+            <div class="alert-item-header">
+                <span class="alert-item-rule">{ emergency.kind.to_string() }</span>
+                <span class="alert-item-aircraft">{ emergency.aircraft() }</span>
+                <span class="alert-item-time">{ format_time(emergency.started) }</span>
+            </div>
+            <div class="alert-item-description">
+                { status }
+                if let Some(altitude) = emergency.altitude {
+                    { format!(", last seen at {altitude} ft") }
+                }
+            </div>
+            <ul class="emergency-events">
+                { for emergency.events.iter().map(|event| html! {
+                    <li>
+                        <span class="alert-item-time">{ format_time(event.timestamp) }</span>
+                        { &event.description }
+                    </li>
+                }) }
+            </ul>
+        </div>
+    }
+}
+
+fn render_delivery(delivery: &ShNotificationDelivery) -> Html {
+    let (class, result) = if delivery.delivered {
+        ("alert-delivery-sent", "Sent".to_string())
+    } else {
+        (
+            "alert-delivery-failed",
+            delivery
+                .error
+                .clone()
+                .unwrap_or_else(|| "Failed".to_string()),
+        )
+    };
+
+    html! {
+        <tr>
+            <td>{ format_time(delivery.timestamp) }</td>
+            <td>{ &delivery.rule }</td>
+            <td>{ &delivery.target }</td>
+            <td>{ delivery.attempts }</td>
+            <td class={class}>{ result }</td>
+        </tr>
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::common::time::format_time;
+use crate::components::pages::acars_messages::render_message;
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::flight::{ShFlightSession, ShFlightSessionDetail};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct FlightsProps {
+    pub send_message: Callback<UserWssMessage>,
+}
+
+/// Recent flights, and everything we have heard from the one the user opens. The track is
+/// drawn on the map
+#[function_component(ShFlights)]
+pub fn flights(props: &FlightsProps) -> Html {
+    log::debug!("Rendering flights page.");
+
+    let sessions = use_selector(|state: &WebAppStateTemp| state.flight_sessions.clone());
+    let selected_id = use_selector(|state: &WebAppStateTemp| state.selected_session_id);
+    let selected = use_selector(|state: &WebAppStateTemp| state.selected_session.clone());
+
+    // the open flight as it is in the list, which changes when the server updates it
+    let selected_summary = use_selector_with_deps(
+        |state: &WebAppStateTemp, id| {
+            state
+                .flight_sessions
+                .iter()
+                .find(|session| Some(session.id) == *id)
+                .cloned()
+        },
+        *selected_id,
+    );
+
+    // and the latest message from the aircraft, so new messages show up while it is open
+    let latest_message = use_selector_with_deps(
+        |state: &WebAppStateTemp, session: &Option<ShFlightSession>| {
+            let session = session.as_ref()?;
+            state
+                .messages
+                .iter()
+                .find(|message| {
+                    message
+                        .icao_hex
+                        .as_deref()
+                        .is_some_and(|hex| hex.eq_ignore_ascii_case(&session.aircraft))
+                        || message.tail.as_deref().is_some_and(|tail| {
+                            session.tail.as_deref() == Some(tail.trim_start_matches('.'))
+                        })
+                })
+                .map(|message| message.timestamp.to_bits())
+        },
+        (*selected_summary).clone(),
+    );
+
+    {
+        let send_message = props.send_message.clone();
+        use_effect_with((), move |()| {
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserRequestFlightSessions,
+                MessageData::NoData,
+            ));
+        });
+    }
+
+    {
+        let send_message = props.send_message.clone();
+        use_effect_with(
+            (*selected_id, (*selected_summary).clone(), *latest_message),
+            move |(id, _, _)| {
+                if let Some(id) = id {
+                    send_message.emit(UserWssMessage::new(
+                        UserMessageTypes::UserRequestFlightSession,
+                        MessageData::ShFlightSessionId(*id),
+                    ));
+                }
+            },
+        );
+    }
+
+    let refresh = {
+        let send_message = props.send_message.clone();
+        Callback::from(move |_: MouseEvent| {
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserRequestFlightSessions,
+                MessageData::NoData,
+            ));
+        })
+    };
+
+    if selected_id.is_some() {
+        let body = selected.as_ref().as_ref().map_or_else(
+            || html! { <p>{"Loading flight..."}</p> },
+            |detail| render_detail(detail, &props.send_message),
+        );
+
+        return html! { <div class="flights">{ body }</div> };
+    }
+
+    html! {
+        <div class="flights">
+            <div class="flights-header">
+                <h2>{"Recent Flights"}</h2>
+                <button onclick={refresh}>{"Refresh"}</button>
+            </div>
+            if sessions.is_empty() {
+                <p>{"No flights have been seen yet."}</p>
+            } else {
+                <table class="flights-table">
+                    <thead>
+                        <tr>
+                            <th>{"Flight"}</th>
+                            <th>{"Aircraft"}</th>
+                            <th>{"First Seen"}</th>
+                            <th>{"Last Seen"}</th>
+                            <th>{"Takeoff"}</th>
+                            <th>{"Landing"}</th>
+                            <th>{"Messages"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        { for sessions.iter().map(render_session) }
+                    </tbody>
+                </table>
+            }
+        </div>
+    }
+}
+
+fn select_session(id: Option<i64>) -> Callback<MouseEvent> {
+    Callback::from(move |_: MouseEvent| {
+        Dispatch::<WebAppStateTemp>::global().reduce_mut(|state| {
+            state.selected_session_id = id;
+            state.selected_session = None;
+        });
+    })
+}
+
+fn aircraft_name(session: &ShFlightSession) -> String {
+    session.tail.as_ref().map_or_else(
+        || session.aircraft.clone(),
+        |tail| format!("{tail} ({})", session.aircraft),
+    )
+}
+
+fn optional_time(timestamp: Option<f64>) -> String {
+    timestamp.map(format_time).unwrap_or_default()
+}
+
+fn render_session(session: &ShFlightSession) -> Html {
+    html! {
+        <tr class="flights-row" onclick={select_session(Some(session.id))}>
+            <td>{ session.callsign.clone().unwrap_or_default() }</td>
+            <td>{ aircraft_name(session) }</td>
+            <td>{ format_time(session.start) }</td>
+            <td>{ format_time(session.end) }</td>
+            <td>{ optional_time(session.takeoff) }</td>
+            <td>{ optional_time(session.landing) }</td>
+            <td>{ session.message_count }</td>
+        </tr>
+    }
+}
+
+fn render_detail(detail: &ShFlightSessionDetail, send_message: &Callback<UserWssMessage>) -> Html {
+    let session = &detail.session;
+    let title = session.callsign.as_ref().map_or_else(
+        || aircraft_name(session),
+        |callsign| format!("{callsign} - {}", aircraft_name(session)),
+    );
+
+    html! {
+        <>
+            <div class="flights-header">
+                <h2>{ title }</h2>
+                <button class="flights-close" onclick={select_session(None)}>{"\u{2715}"}</button>
+            </div>
+            <ul class="flights-summary">
+                <li>{ format!("First seen {}", format_time(session.start)) }</li>
+                if let Some(takeoff) = session.takeoff {
+                    <li>{ format!("Takeoff {}", format_time(takeoff)) }</li>
+                }
+                if let Some(landing) = session.landing {
+                    <li>{ format!("Landing {}", format_time(landing)) }</li>
+                }
+                <li>{ format!("Last seen {}", format_time(session.end)) }</li>
+                <li>{ format!("{} positions", detail.track.len()) }</li>
+            </ul>
+            if detail.messages.is_empty() {
+                <p>{"No messages were heard from this flight."}</p>
+            }
+            // newest first, like the messages page
+            { for detail.messages.iter().rev().map(|message| render_message(message, send_message)) }
+        </>
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::auth::ShLogin;
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct LoginProps {
+    pub send_message: Callback<UserWssMessage>,
+}
+
+fn input_value(node: &NodeRef) -> String {
+    node.cast::<HtmlInputElement>()
+        .map(|input| input.value())
+        .unwrap_or_default()
+}
+
+/// Logging in. With no accounts yet, this creates the admin account instead
+#[function_component(ShLoginPage)]
+pub fn login(props: &LoginProps) -> Html {
+    log::debug!("Rendering login page.");
+
+    let setup_needed = use_selector(|state: &WebAppStateTemp| {
+        state.auth.as_ref().is_some_and(|auth| auth.setup_needed)
+    });
+    let (state, dispatch) = use_store::<WebAppStateTemp>();
+
+    let username_node = use_node_ref();
+    let password_node = use_node_ref();
+    let confirm_node = use_node_ref();
+
+    let onsubmit = {
+        let send_message = props.send_message.clone();
+        let setup_needed = *setup_needed;
+        let username_node = username_node.clone();
+        let password_node = password_node.clone();
+        let confirm_node = confirm_node.clone();
+
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let login = ShLogin {
+                username: input_value(&username_node).trim().to_string(),
+                password: input_value(&password_node),
+            };
+
+            if setup_needed && login.password != input_value(&confirm_node) {
+                dispatch.reduce_mut(|state| {
+                    state.auth_error = Some("The passwords don't match".to_string());
+                });
+                return;
+            }
+
+            let message_type = if setup_needed {
+                UserMessageTypes::UserSetupAdmin
+            } else {
+                UserMessageTypes::UserLogin
+            };
+            send_message.emit(UserWssMessage::new(
+                message_type,
+                MessageData::ShLogin(login),
+            ));
+        })
+    };
+
+    html! {
+        <div class="login">
+            <form {onsubmit}>
+                if *setup_needed {
+                    <h2>{"Create the Admin Account"}</h2>
+                    <p>{"There are no accounts yet. This one will be able to change the settings and add everyone else."}</p>
+                } else {
+                    <h2>{"Log In"}</h2>
+                }
+                if let Some(error) = state.auth_error.as_ref() {
+                    <p class="login-error">{ error }</p>
+                }
+                <div class="settings-item">
+                    <label for="login-username">{"Username"}</label>
+                    <input id="login-username" class="text-black" type="text" autocomplete="username" ref={username_node} />
+                </div>
+                <div class="settings-item">
+                    <label for="login-password">{"Password"}</label>
+                    <input id="login-password" class="text-black" type="password"
+                        autocomplete={if *setup_needed { "new-password" } else { "current-password" }} ref={password_node} />
+                </div>
+                if *setup_needed {
+                    <div class="settings-item">
+                        <label for="login-confirm">{"Confirm Password"}</label>
+                        <input id="login-confirm" class="text-black" type="password" autocomplete="new-password" ref={confirm_node} />
+                    </div>
+                }
+                <div class="settings-item buttons">
+                    <div><button type="submit" class="button">{ if *setup_needed { "Create Account" } else { "Log In" } }</button></div>
+                </div>
+            </form>
+        </div>
+    }
+}
@@ -5,6 +5,10 @@
 
 pub mod acars_messages;
 pub mod adsb;
+pub mod alerts;
+pub mod flights;
 pub mod help;
+pub mod login;
 pub mod settings;
 pub mod stats;
+pub mod weather;
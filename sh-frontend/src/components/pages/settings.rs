@@ -6,24 +6,95 @@
 use crate::{
     common::wssprops::WssCommunicationProps,
     components::setting::{
-        sh_app_config::ShAppConfig, sh_data_sources::ShDataSourcesConfig,
-        sh_enabled_data_sources::ShEnabledDataSourcesConfig, sh_map::ShMapConfig,
+        sh_alerts::ShAlertRulesConfig, sh_app_config::ShAppConfig,
+        sh_data_sources::ShDataSourcesConfig, sh_enabled_data_sources::ShEnabledDataSourcesConfig,
+        sh_map::ShMapConfig, sh_users::ShUsersConfig,
     },
+    services::temp_state::WebAppStateTemp,
 };
 use yew::prelude::*;
+use yewdux::prelude::*;
+
+/// What the server found wrong with the config. Nothing is shown when there isn't anything
+#[function_component(ShConfigIssues)]
+fn config_issues() -> Html {
+    let issues = use_selector(|state: &WebAppStateTemp| {
+        state
+            .config
+            .as_ref()
+            .map(|config| config.issues.clone())
+            .unwrap_or_default()
+    });
+
+    if issues.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class="config-issues">
+            <p>{"There are problems with the config:"}</p>
+            <ul>
+                { for issues.iter().map(|issue| html! {
+                    <li class={if issue.is_error() { "field-error" } else { "config-warning" }}>
+                        { issue.to_string() }
+                    </li>
+                }) }
+            </ul>
+        </div>
+    }
+}
+
+/// Settings set by `SH_` variables or the command line. The server turns away changes to them,
+/// so they are locked here too
+#[function_component(ShOverriddenSettings)]
+fn overridden_settings() -> Html {
+    let overridden = use_selector(|state: &WebAppStateTemp| {
+        state
+            .config
+            .as_ref()
+            .map(|config| config.overridden.clone())
+            .unwrap_or_default()
+    });
+
+    if overridden.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class="config-issues">
+            <p>{"These are set by SH_ environment variables or the command line, and can't be changed here:"}</p>
+            <ul>
+                { for overridden.iter().map(|path| html! { <li class="config-warning">{ path }</li> }) }
+            </ul>
+        </div>
+    }
+}
 
 /// Home page
 #[function_component(ShSettings)]
 pub fn settings(props: &WssCommunicationProps) -> Html {
     log::debug!("Rendering settings page.");
 
+    let is_admin = use_selector(WebAppStateTemp::is_admin);
+    if !*is_admin {
+        return html! {
+            <div class="settings">
+                <p>{"Only admins can change the settings."}</p>
+            </div>
+        };
+    }
+
     html! {
         <>
             <div class="settings">
+                <ShConfigIssues />
+                <ShOverriddenSettings />
                 <ShAppConfig send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} />
-                <ShEnabledDataSourcesConfig />
-                <ShDataSourcesConfig />
+                <ShEnabledDataSourcesConfig send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} />
+                <ShDataSourcesConfig send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} />
                 <ShMapConfig send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} />
+                <ShAlertRulesConfig send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} />
+                <ShUsersConfig send_message={props.send_message.clone()} request_alert_box={props.request_alert_box.clone()} />
             </div>
         </>
     }
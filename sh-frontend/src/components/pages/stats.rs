@@ -3,16 +3,193 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use crate::common::time::{format_date_time, format_time};
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::stats::{ShStats, ShStatsCount, ShStatsPoint, ShStatsRange};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
 use yew::prelude::*;
+use yew_hooks::use_interval;
+use yewdux::prelude::*;
 
-/// Home page
+// How often the page asks for fresh numbers, in milliseconds
+const REFRESH_INTERVAL: u32 = 60 * 1000;
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct StatisticsProps {
+    pub send_message: Callback<UserWssMessage>,
+}
+
+fn request_stats(send_message: &Callback<UserWssMessage>, range: ShStatsRange) {
+    send_message.emit(UserWssMessage::new(
+        UserMessageTypes::UserRequestStats,
+        MessageData::ShStatsRange(range),
+    ));
+}
+
+/// Width of a bar, as a percentage of the largest
+#[allow(clippy::cast_precision_loss)]
+fn bar_percent(count: i64, largest: i64) -> f64 {
+    if largest <= 0 {
+        0.0
+    } else {
+        count as f64 / largest as f64 * 100.0
+    }
+}
+
+/// Bar chart of one value over the range. Drawn into a 100 high box and stretched to fit
+#[allow(clippy::cast_precision_loss)]
+fn render_series(
+    title: &str,
+    range: ShStatsRange,
+    series: &[ShStatsPoint],
+    value: fn(&ShStatsPoint) -> i64,
+) -> Html {
+    let largest = series.iter().map(value).max().unwrap_or_default();
+    let view_box = format!("0 0 {} 100", series.len().max(1));
+    let label = |timestamp: i64| {
+        // anything longer than a day needs the date as well
+        if range.seconds() <= ShStatsRange::Day.seconds() {
+            format_time(timestamp as f64)
+        } else {
+            format_date_time(timestamp as f64)
+        }
+    };
+
+    html! {
+        <div class="stats-chart">
+            <h3>{ format!("{title} (most: {largest})") }</h3>
+            <svg viewBox={view_box} preserveAspectRatio="none">
+                { for series.iter().enumerate().map(|(index, point)| {
+                    let height = bar_percent(value(point), largest);
+                    html! {
+                        <rect
+                            x={ format!("{}", index as f64 + 0.1) }
+                            y={ format!("{}", 100.0 - height) }
+                            width="0.8"
+                            height={ format!("{height}") }
+                        >
+                            <title>{ format!("{}: {}", label(point.timestamp), value(point)) }</title>
+                        </rect>
+                    }
+                }) }
+            </svg>
+            if let (Some(first), Some(last)) = (series.first(), series.last()) {
+                <div class="stats-chart-axis">
+                    <span>{ label(first.timestamp) }</span>
+                    <span>{ label(last.timestamp) }</span>
+                </div>
+            }
+        </div>
+    }
+}
+
+fn render_breakdown(title: &str, counts: &[ShStatsCount]) -> Html {
+    let largest = counts
+        .iter()
+        .map(|count| count.count)
+        .max()
+        .unwrap_or_default();
+
+    html! {
+        <div class="stats-breakdown">
+            <h3>{ title }</h3>
+            if counts.iter().all(|count| count.count == 0) {
+                <p>{"Nothing yet."}</p>
+            } else {
+                <table class="stats-table">
+                    <tbody>
+                        { for counts.iter().map(|count| html! {
+                            <tr>
+                                <td class="stats-key">{ &count.key }</td>
+                                <td class="stats-bar-cell">
+                                    <div
+                                        class="stats-bar"
+                                        style={ format!("width: {:.1}%", bar_percent(count.count, largest)) }
+                                    />
+                                </td>
+                                <td>{ count.count }</td>
+                            </tr>
+                        }) }
+                    </tbody>
+                </table>
+            }
+        </div>
+    }
+}
+
+fn render_stats(stats: &ShStats) -> Html {
+    html! {
+        <>
+            <p>{ format!("{} messages in the last {}", stats.total_messages, stats.range) }</p>
+            <div class="stats-charts">
+                { render_series("Messages", stats.range, &stats.series, |point| point.messages) }
+                { render_series("Aircraft Tracked", stats.range, &stats.series, |point| point.aircraft) }
+            </div>
+            <div class="stats-breakdowns">
+                { render_breakdown("Sources", &stats.sources) }
+                { render_breakdown("Message Types", &stats.message_types) }
+                { render_breakdown("Frequencies", &stats.frequencies) }
+                { render_breakdown("Labels", &stats.labels) }
+                { render_breakdown("Ground Stations", &stats.ground_stations) }
+                { render_breakdown("Hour of Day (UTC)", &stats.hours) }
+            </div>
+        </>
+    }
+}
+
+/// Message and aircraft counts over time, kept by the server so they last across restarts
 #[function_component(ShStatistics)]
-pub fn statistics() -> Html {
+pub fn statistics(props: &StatisticsProps) -> Html {
     log::debug!("Rendering statistics page.");
 
-    html! {
-        {
-            "Stats"
+    let dispatch = Dispatch::<WebAppStateTemp>::global();
+    let range = use_selector(|state: &WebAppStateTemp| state.stats_range);
+    let stats = use_selector(|state: &WebAppStateTemp| state.stats.clone());
+
+    {
+        let send_message = props.send_message.clone();
+        use_effect_with(*range, move |range| request_stats(&send_message, *range));
+    }
+
+    {
+        let send_message = props.send_message.clone();
+        let range = *range;
+        use_interval(
+            move || request_stats(&send_message, range),
+            REFRESH_INTERVAL,
+        );
+    }
+
+    let range_buttons = ShStatsRange::ALL.into_iter().map(|option| {
+        let dispatch = dispatch.clone();
+        let onclick = Callback::from(move |_: MouseEvent| {
+            dispatch.reduce_mut(|state| {
+                if state.stats_range != option {
+                    state.stats_range = option;
+                    state.stats = None;
+                }
+            });
+        });
+        let class = if option == *range {
+            "button stats-range-selected"
+        } else {
+            "button"
+        };
+
+        html! {
+            <button class={class} {onclick}>{ option.to_string() }</button>
         }
+    });
+
+    html! {
+        <div class="stats">
+            <div class="stats-ranges">
+                { for range_buttons }
+            </div>
+            { stats.as_ref().as_ref().map_or_else(
+                || html! { <p>{"Loading statistics..."}</p> },
+                render_stats,
+            ) }
+        </div>
     }
 }
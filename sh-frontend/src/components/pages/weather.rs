@@ -0,0 +1,137 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::common::time::format_time;
+use crate::services::temp_state::WebAppStateTemp;
+use sh_common::weather::{ShWeatherProduct, ShWeatherProductType, ShWeatherQuery};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+const PRODUCT_TYPES: [ShWeatherProductType; 4] = [
+    ShWeatherProductType::Metar,
+    ShWeatherProductType::Speci,
+    ShWeatherProductType::Taf,
+    ShWeatherProductType::Atis,
+];
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct WeatherProps {
+    pub send_message: Callback<UserWssMessage>,
+}
+
+/// METARs, TAFs and ATIS heard in uplinks, filtered by airport and type
+#[function_component(ShWeather)]
+pub fn weather(props: &WeatherProps) -> Html {
+    log::debug!("Rendering weather page.");
+
+    let dispatch = Dispatch::<WebAppStateTemp>::global();
+    let query = use_selector(|state: &WebAppStateTemp| state.weather_query.clone());
+    let products = use_selector(|state: &WebAppStateTemp| state.weather.clone());
+
+    // ask again whenever the filters change. New products come in on their own after that
+    {
+        let send_message = props.send_message.clone();
+        use_effect_with((*query).clone(), move |query| {
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserRequestWeather,
+                MessageData::ShWeatherQuery(query.clone()),
+            ));
+        });
+    }
+
+    let update_query = move |update: Box<dyn Fn(&mut ShWeatherQuery)>| {
+        dispatch.reduce_mut(|state| update(&mut state.weather_query));
+    };
+
+    let on_airport = {
+        let update_query = update_query.clone();
+        Callback::from(move |event: InputEvent| {
+            let airport = event
+                .target_unchecked_into::<HtmlInputElement>()
+                .value()
+                .trim()
+                .to_uppercase();
+            // wait for a whole code before asking for anything
+            if airport.is_empty() || airport.len() >= 3 {
+                update_query(Box::new(move |query| {
+                    query.airport = (!airport.is_empty()).then(|| airport.clone());
+                }));
+            }
+        })
+    };
+
+    let on_type = {
+        let update_query = update_query.clone();
+        Callback::from(move |event: Event| {
+            let selected = event.target_unchecked_into::<HtmlSelectElement>().value();
+            let product_type = PRODUCT_TYPES
+                .into_iter()
+                .find(|product_type| product_type.to_string() == selected);
+            update_query(Box::new(move |query| query.product_type = product_type));
+        })
+    };
+
+    let on_latest = Callback::from(move |event: Event| {
+        let latest_only = event.target_unchecked_into::<HtmlInputElement>().checked();
+        update_query(Box::new(move |query| query.latest_only = latest_only));
+    });
+
+    let selected_type = query
+        .product_type
+        .map(|product_type| product_type.to_string())
+        .unwrap_or_default();
+
+    html! {
+        <div class="weather">
+            <div class="weather-filters">
+                <input
+                    type="text"
+                    placeholder="Airport"
+                    maxlength="4"
+                    value={query.airport.clone().unwrap_or_default()}
+                    oninput={on_airport}
+                />
+                <select onchange={on_type}>
+                    <option value="" selected={selected_type.is_empty()}>{"All products"}</option>
+                    { for PRODUCT_TYPES.iter().map(|product_type| {
+                        let name = product_type.to_string();
+                        html! {
+                            <option value={name.clone()} selected={name == selected_type}>{ name.clone() }</option>
+                        }
+                    }) }
+                </select>
+                <label>
+                    <input type="checkbox" checked={query.latest_only} onchange={on_latest} />
+                    {"Latest only"}
+                </label>
+            </div>
+            if products.is_empty() {
+                <p>{"No weather has been heard yet."}</p>
+            }
+            { for products.iter().map(render_product) }
+        </div>
+    }
+}
+
+fn render_product(product: &ShWeatherProduct) -> Html {
+    html! {
+        <div class="weather-product">
+            <div class="weather-product-header">
+                <span class="weather-product-type">{ product.product_type.to_string() }</span>
+                <span class="weather-product-airport">{ product.airport.clone() }</span>
+                if let Some(code) = &product.atis_code {
+                    <span class="weather-product-atis-code">{ format!("Information {code}") }</span>
+                }
+                if let Some(issued) = product.issued {
+                    <span class="weather-product-issued">{ format!("Issued {}", format_time(issued)) }</span>
+                }
+                <span class="weather-product-received">{ format!("Heard {}", format_time(product.received)) }</span>
+            </div>
+            <pre class="weather-product-text">{ product.raw.clone() }</pre>
+        </div>
+    }
+}
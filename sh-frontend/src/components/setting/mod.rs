@@ -3,10 +3,24 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+pub mod sh_alerts;
 pub mod sh_app_config;
 pub mod sh_data_sources;
 pub mod sh_enabled_data_sources;
 pub mod sh_map;
+pub mod sh_users;
+
+use sh_config::source::ShEnabledDataSources;
+
+/// Every kind of data, and what it is called on screen
+pub const SOURCE_TYPES: [(ShEnabledDataSources, &str); 6] = [
+    (ShEnabledDataSources::Acars, "ACARS"),
+    (ShEnabledDataSources::Vdlm2, "VDLM2"),
+    (ShEnabledDataSources::Hfdl, "HFDL"),
+    (ShEnabledDataSources::Inmarsat, "Inmarsat"),
+    (ShEnabledDataSources::Iridium, "Iridium"),
+    (ShEnabledDataSources::Adsb, "ADS-B"),
+];
 
 pub enum ButtonAction {
     Update,
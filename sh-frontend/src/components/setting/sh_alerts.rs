@@ -0,0 +1,319 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::common::wssprops::WssCommunicationProps;
+use crate::components::setting::SOURCE_TYPES;
+use crate::services::temp_state::WebAppStateTemp;
+use serde::{Deserialize, Serialize};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use sh_config::alerts::{ShAlertRule, ShAlertsConfig};
+use sh_config::source::ShEnabledDataSources;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Clone, PartialEq, Store, Default, Serialize, Deserialize)]
+#[store(storage = "local", storage_tab_sync)]
+struct ConfigAlertsState {
+    pub is_visible: bool,
+}
+
+/// The rule being edited, and where it goes in the list. `None` is a new rule
+#[derive(Clone, PartialEq)]
+struct Draft {
+    index: Option<usize>,
+    rule: ShAlertRule,
+}
+
+fn list_to_string(list: &[String]) -> String {
+    list.join(", ")
+}
+
+fn string_to_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn describe_rule(rule: &ShAlertRule) -> String {
+    let mut parts = Vec::new();
+    let lists = [
+        ("tail", &rule.tails),
+        ("ICAO", &rule.icao_hexes),
+        ("flight", &rule.flights),
+        ("label", &rule.labels),
+        ("squawk", &rule.squawks),
+    ];
+
+    for (name, list) in lists {
+        if !list.is_empty() {
+            parts.push(format!("{name} {}", list.join("/")));
+        }
+    }
+
+    if !rule.source_types.is_empty() {
+        let sources: Vec<&str> = SOURCE_TYPES
+            .iter()
+            .filter(|(source, _)| rule.source_types.contains(source))
+            .map(|(_, name)| *name)
+            .collect();
+        parts.push(format!("from {}", sources.join("/")));
+    }
+
+    if let Some(pattern) = &rule.text_pattern {
+        parts.push(format!("text /{pattern}/"));
+    }
+
+    if !rule.notify.is_empty() {
+        parts.push(format!("notify {}", rule.notify.join("/")));
+    }
+
+    parts.join(", ")
+}
+
+fn text_field(label: &'static str, value: String, oninput: Callback<InputEvent>) -> Html {
+    html! {
+        <div class="settings-item">
+            <label>{ label }</label>
+            <input type="text" {value} {oninput} />
+        </div>
+    }
+}
+
+fn send_rules(send_message: &Callback<UserWssMessage>, rules: Vec<ShAlertRule>) {
+    send_message.emit(UserWssMessage::new(
+        UserMessageTypes::UserUpdateAlerts,
+        MessageData::ShAlertsConfig(ShAlertsConfig { rules }),
+    ));
+}
+
+/// Alert rules take effect as soon as they are saved, so every change is sent straight away
+#[function_component(ShAlertRulesConfig)]
+pub fn sh_alert_rules_config(props: &WssCommunicationProps) -> Html {
+    log::debug!("Rendering alert rules settings.");
+
+    let config = use_selector(|state: &WebAppStateTemp| state.config.clone());
+    let config_error = use_selector(|state: &WebAppStateTemp| state.config_error.clone());
+    let (state, dispatch) = use_store::<ConfigAlertsState>();
+    let draft = use_state(|| None::<Draft>);
+
+    let rules = config
+        .as_ref()
+        .as_ref()
+        .map(|config| config.alerts.rules.clone());
+    let targets = config
+        .as_ref()
+        .as_ref()
+        .map(|config| config.notification_targets.clone())
+        .unwrap_or_default();
+
+    let show_panel = {
+        let is_visible = state.is_visible;
+        Callback::from(move |_: MouseEvent| {
+            dispatch.reduce_mut(move |state| state.is_visible = !is_visible);
+        })
+    };
+
+    let Some(rules) = rules else {
+        return html! {
+            <>
+            <input id="collapsible_alert_rules" class="toggle" type="checkbox" checked={state.is_visible} onclick={show_panel} />
+            <label for="collapsible_alert_rules" class="lbl-toggle">{"Alert Rules"}</label>
+            <div class="collapsible-content"><div class="content-inner">{"Loading..."}</div></div>
+            </>
+        };
+    };
+
+    let new_rule = {
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| {
+            draft.set(Some(Draft {
+                index: None,
+                rule: ShAlertRule {
+                    enabled: true,
+                    ..ShAlertRule::default()
+                },
+            }));
+        })
+    };
+
+    let rows = rules.iter().enumerate().map(|(index, rule)| {
+        let edit = {
+            let draft = draft.clone();
+            let rule = rule.clone();
+            Callback::from(move |_: MouseEvent| {
+                draft.set(Some(Draft {
+                    index: Some(index),
+                    rule: rule.clone(),
+                }));
+            })
+        };
+
+        let toggle = {
+            let send_message = props.send_message.clone();
+            let rules = rules.clone();
+            Callback::from(move |_: MouseEvent| {
+                let mut rules = rules.clone();
+                rules[index].enabled = !rules[index].enabled;
+                send_rules(&send_message, rules);
+            })
+        };
+
+        let delete = {
+            let send_message = props.send_message.clone();
+            let rules = rules.clone();
+            let draft = draft.clone();
+            Callback::from(move |_: MouseEvent| {
+                let mut rules = rules.clone();
+                rules.remove(index);
+                draft.set(None);
+                send_rules(&send_message, rules);
+            })
+        };
+
+        html! {
+            <div class="settings-item alert-rule">
+                <input type="checkbox" checked={rule.enabled} onclick={toggle} title="Enabled" />
+                <span class="alert-rule-name">{ &rule.name }</span>
+                <span class="alert-rule-summary">{ describe_rule(rule) }</span>
+                <button class="button" onclick={edit}>{"Edit"}</button>
+                <button class="button" onclick={delete}>{"Delete"}</button>
+            </div>
+        }
+    });
+
+    html! {
+        <>
+        <input id="collapsible_alert_rules" class="toggle" type="checkbox" checked={state.is_visible} onclick={show_panel} />
+        <label for="collapsible_alert_rules" class="lbl-toggle">{"Alert Rules"}</label>
+        <div class="collapsible-content">
+          <div class="content-inner">
+            if let Some(error) = config_error.as_ref() {
+                <p class="alert-rules-error">{ error }</p>
+            }
+            if rules.is_empty() {
+                <p>{"No alert rules have been set up."}</p>
+            }
+            { for rows }
+            {
+                (*draft).clone().map_or_else(
+                    || html! {
+                        <div class="settings-item buttons">
+                            <div><button class="button" onclick={new_rule}>{"Add Rule"}</button></div>
+                        </div>
+                    },
+                    |current| render_editor(&current, &draft, &rules, &targets, &props.send_message),
+                )
+            }
+          </div>
+        </div>
+        </>
+    }
+}
+
+fn render_editor(
+    current: &Draft,
+    draft: &UseStateHandle<Option<Draft>>,
+    rules: &[ShAlertRule],
+    targets: &[String],
+    send_message: &Callback<UserWssMessage>,
+) -> Html {
+    let rule = &current.rule;
+
+    // every input writes straight in to the draft
+    let update = |apply: fn(&mut ShAlertRule, String)| {
+        let draft = draft.clone();
+        Callback::from(move |event: InputEvent| {
+            let value = event.target_unchecked_into::<HtmlInputElement>().value();
+            if let Some(mut current) = (*draft).clone() {
+                apply(&mut current.rule, value);
+                draft.set(Some(current));
+            }
+        })
+    };
+
+    let source_checkbox = |(source, name): &(ShEnabledDataSources, &str)| {
+        let checked = rule.source_types.contains(source);
+        let onclick = {
+            let draft = draft.clone();
+            let source = source.clone();
+            Callback::from(move |_: MouseEvent| {
+                if let Some(mut current) = (*draft).clone() {
+                    if current.rule.source_types.contains(&source) {
+                        current.rule.source_types.retain(|known| *known != source);
+                    } else {
+                        current.rule.source_types.push(source.clone());
+                    }
+                    draft.set(Some(current));
+                }
+            })
+        };
+
+        html! {
+            <label class="alert-rule-source">
+                <input type="checkbox" {checked} {onclick} />{ *name }
+            </label>
+        }
+    };
+
+    let save = {
+        let draft = draft.clone();
+        let current = current.clone();
+        let rules = rules.to_vec();
+        let send_message = send_message.clone();
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let mut rules = rules.clone();
+            let mut rule = current.rule.clone();
+            rule.name = rule.name.trim().to_string();
+            match current.index {
+                Some(index) => rules[index] = rule,
+                None => rules.push(rule),
+            }
+
+            draft.set(None);
+            send_rules(&send_message, rules);
+        })
+    };
+
+    let cancel = {
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| draft.set(None))
+    };
+
+    html! {
+        <form class="alert-rule-editor" onsubmit={save}>
+            <p>{"Every field that is filled in has to match. Separate multiple values with commas."}</p>
+            { text_field("Name", rule.name.clone(), update(|rule, value| rule.name = value)) }
+            { text_field("Tails", list_to_string(&rule.tails), update(|rule, value| rule.tails = string_to_list(&value))) }
+            { text_field("ICAO Hex", list_to_string(&rule.icao_hexes), update(|rule, value| rule.icao_hexes = string_to_list(&value))) }
+            { text_field("Flights", list_to_string(&rule.flights), update(|rule, value| rule.flights = string_to_list(&value))) }
+            { text_field("Labels", list_to_string(&rule.labels), update(|rule, value| rule.labels = string_to_list(&value))) }
+            <div class="settings-item">
+                <label>{"Text Pattern"}</label>
+                <input type="text" placeholder="Regular expression" value={rule.text_pattern.clone().unwrap_or_default()}
+                    oninput={update(|rule, value| rule.text_pattern = Some(value).filter(|value| !value.is_empty()))} />
+            </div>
+            { text_field("Squawks", list_to_string(&rule.squawks), update(|rule, value| rule.squawks = string_to_list(&value))) }
+            <div class="settings-item">
+                <label>{"Sources"}</label>
+                { for SOURCE_TYPES.iter().map(source_checkbox) }
+            </div>
+            <div class="settings-item">
+                <label>{"Notify"}</label>
+                <input type="text" value={list_to_string(&rule.notify)} oninput={update(|rule, value| rule.notify = string_to_list(&value))}
+                    placeholder={if targets.is_empty() { "No notification targets are set up".to_string() } else { format!("Any of {}", targets.join(", ")) }} />
+            </div>
+            <div class="settings-item buttons">
+                <div><button type="submit" class="button">{"Save Rule"}</button></div>
+                <div><button type="button" class="button" onclick={cancel}>{"Cancel"}</button></div>
+            </div>
+        </form>
+    }
+}
@@ -3,25 +3,569 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+// Editing where the hub gets its data from. There is no transport or format to pick, as the hub
+// only reads acars_router JSON and ADS-B SBS (BaseStation), both over TCP. They can be added here
+// once the hub has clients for others
+
+use crate::common::wssprops::WssCommunicationProps;
+use crate::services::temp_state::WebAppStateTemp;
+use serde::{Deserialize, Serialize};
+use sh_common::source_status::{ShSourceKind, ShSourceTest, ShSourceTestResult};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use sh_config::acars_router_source::AcarsRouterSource;
+use sh_config::address::{
+    validate_address, validate_latitude, validate_longitude, validate_port, SHAdsbConfig,
+    ShAcarsRouterConfig,
+};
+use sh_config::adsb_source::AdsbSource;
+use sh_config::source::DataSources;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Clone, PartialEq, Store, Default, Serialize, Deserialize)]
+#[store(storage = "local", storage_tab_sync)]
+struct ConfigDataSourcesState {
+    pub is_visible: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SourceKind {
+    AcarsRouter,
+    Adsb,
+}
+
+/// The source being edited, as it has been typed, and where it goes in its list. `None` is a
+/// new source
+#[derive(Clone, PartialEq)]
+struct Draft {
+    kind: SourceKind,
+    index: Option<usize>,
+    address: String,
+    port: String,
+    latitude: String,
+    longitude: String,
+}
+
+impl Draft {
+    const fn new(kind: SourceKind) -> Self {
+        Self {
+            kind,
+            index: None,
+            address: String::new(),
+            port: String::new(),
+            latitude: String::new(),
+            longitude: String::new(),
+        }
+    }
+
+    /// What to ask the server to try connecting to, if the address and port are usable
+    fn source_test(&self) -> Option<ShSourceTest> {
+        validate_address(self.address.trim()).ok()?;
+        let kind = match self.kind {
+            SourceKind::AcarsRouter => ShSourceKind::AcarsRouter,
+            SourceKind::Adsb => ShSourceKind::Adsb,
+        };
+
+        Some(ShSourceTest {
+            kind,
+            address: self.address.trim().to_string(),
+            port: self.port().ok()?,
+        })
+    }
+
+    fn port(&self) -> Result<u32, String> {
+        let port = self
+            .port
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| "The port has to be a number".to_string())?;
+        validate_port(port)?;
+        Ok(port)
+    }
+
+    fn latitude(&self) -> Result<f64, String> {
+        let latitude = self
+            .latitude
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| "The latitude has to be a number".to_string())?;
+        validate_latitude(latitude)?;
+        Ok(latitude)
+    }
+
+    fn longitude(&self) -> Result<f64, String> {
+        let longitude = self
+            .longitude
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| "The longitude has to be a number".to_string())?;
+        validate_longitude(longitude)?;
+        Ok(longitude)
+    }
+
+    /// The sources with this one saved in to them, checked the same way the server will
+    fn apply(&self, sources: &DataSources) -> Result<DataSources, String> {
+        validate_address(self.address.trim())?;
+        let address = self.address.trim().to_string();
+        let port = self.port()?;
+        let mut sources = sources.clone();
 
+        match self.kind {
+            SourceKind::AcarsRouter => {
+                let source = ShAcarsRouterConfig::new_from_parts(address, port);
+                let mut addresses = sources.acars_routers.addresses().to_vec();
+                place(&mut addresses, self.index, source);
+                sources.acars_routers = AcarsRouterSource::from(addresses);
+            }
+            SourceKind::Adsb => {
+                let source = SHAdsbConfig::new_from_parts(
+                    address,
+                    port,
+                    self.latitude()?,
+                    self.longitude()?,
+                );
+                let mut addresses = sources.adsb_sources.addresses().to_vec();
+                place(&mut addresses, self.index, source);
+                sources.adsb_sources = AdsbSource::from(addresses);
+            }
+        }
+
+        sources.validate()?;
+        Ok(sources)
+    }
+}
+
+fn place<T>(addresses: &mut Vec<T>, index: Option<usize>, source: T) {
+    match index {
+        Some(index) => addresses[index] = source,
+        None => addresses.push(source),
+    }
+}
+
+fn send_sources(send_message: &Callback<UserWssMessage>, sources: DataSources) {
+    send_message.emit(UserWssMessage::new(
+        UserMessageTypes::UserUpdateDataSources,
+        MessageData::ShDataSources(sources),
+    ));
+}
+
+fn field_error(error: Option<String>) -> Html {
+    error.map_or_else(
+        || html! {},
+        |error| html! { <span class="field-error">{ error }</span> },
+    )
+}
+
+fn render_test_result(running: bool, result: Option<&ShSourceTestResult>) -> Html {
+    if running {
+        return html! { <p>{"Testing the connection..."}</p> };
+    }
+
+    let Some(result) = result else {
+        return html! {};
+    };
+
+    let format = result.format.map_or_else(
+        || "Nothing received".to_string(),
+        |format| {
+            result.decoder.as_ref().map_or_else(
+                || format.to_string(),
+                |decoder| format!("{format} from {decoder}"),
+            )
+        },
+    );
+
+    html! {
+        <div class="source-test">
+            <p>
+                { format!("{}: ", result.name) }
+                if result.is_usable() {
+                    <span class="source-test-ok">{"Working"}</span>
+                } else if result.reachable {
+                    <span class="field-error">{"Connected, but not usable yet"}</span>
+                } else {
+                    <span class="field-error">{"Not reachable"}</span>
+                }
+            </p>
+            if result.reachable {
+                <p>{ format!("{format}, {} bytes received", result.bytes_received) }</p>
+            }
+            if let Some(error) = &result.error {
+                <p class="field-error">{ error }</p>
+            }
+            if let Some(sample) = &result.sample {
+                <pre class="source-test-sample">{ sample }</pre>
+            }
+        </div>
+    }
+}
+
+/// Ask the server to try connecting to the source, remembering which one so only its result is
+/// shown
+fn test_source(
+    source_test: Option<ShSourceTest>,
+    testing: &UseStateHandle<Option<String>>,
+    send_message: &Callback<UserWssMessage>,
+) -> Callback<MouseEvent> {
+    let send_message = send_message.clone();
+    let testing = testing.clone();
+    Callback::from(move |_: MouseEvent| {
+        let Some(source_test) = source_test.clone() else {
+            return;
+        };
+
+        // so testing the same source again doesn't show the last result while it runs
+        Dispatch::<WebAppStateTemp>::global().reduce_mut(|state| state.source_test = None);
+        testing.set(Some(source_test.to_string()));
+        send_message.emit(UserWssMessage::new(
+            UserMessageTypes::UserTestSource,
+            MessageData::ShSourceTest(source_test),
+        ));
+    })
+}
+
+/// `detail` is left out when the name says everything there is to say
+fn render_source(
+    name: String,
+    detail: Option<String>,
+    edit: Callback<MouseEvent>,
+    delete: Callback<MouseEvent>,
+) -> Html {
+    html! {
+        <div class="settings-item alert-rule">
+            <span class="alert-rule-name">{ name }</span>
+            if let Some(detail) = detail {
+                <span class="alert-rule-summary">{ detail }</span>
+            }
+            <button class="button" onclick={edit}>{"Edit"}</button>
+            <button class="button" onclick={delete}>{"Delete"}</button>
+        </div>
+    }
+}
+
+/// Where the hub gets its data from. Every change is sent straight away, and the hub connects
+/// to new sources and lets go of removed ones as soon as it is saved
 #[function_component(ShDataSourcesConfig)]
-pub fn sh_data_sources_config() -> Html {
+pub fn sh_data_sources_config(props: &WssCommunicationProps) -> Html {
     log::debug!("Rendering data sources configuration page.");
 
+    let config = use_selector(|state: &WebAppStateTemp| state.config.clone());
+    let config_error = use_selector(|state: &WebAppStateTemp| state.config_error.clone());
+    let (state, dispatch) = use_store::<ConfigDataSourcesState>();
+    let draft = use_state(|| None::<Draft>);
+    // the source being tested, so only the result for it is shown
+    let testing = use_state(|| None::<String>);
+    let source_test = use_selector(|state: &WebAppStateTemp| state.source_test.clone());
+    let silence_timeout = use_node_ref();
+
+    let show_panel = {
+        let is_visible = state.is_visible;
+        Callback::from(move |_: MouseEvent| {
+            dispatch.reduce_mut(move |state| state.is_visible = !is_visible);
+        })
+    };
+
+    let Some(sources) = config
+        .as_ref()
+        .as_ref()
+        .map(|config| config.data_sources.clone())
+    else {
+        return html! {
+            <>
+            <input id="collapsible_data_sources" class="toggle" type="checkbox" checked={state.is_visible} onclick={show_panel} />
+            <label for="collapsible_data_sources" class="lbl-toggle">{"Data Sources"}</label>
+            <div class="collapsible-content"><div class="content-inner">{"Loading..."}</div></div>
+            </>
+        };
+    };
+
+    let new_source = |kind: SourceKind| {
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| draft.set(Some(Draft::new(kind))))
+    };
+
+    let delete = |kind: SourceKind, index: usize| {
+        let send_message = props.send_message.clone();
+        let sources = sources.clone();
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut sources = sources.clone();
+            match kind {
+                SourceKind::AcarsRouter => {
+                    let mut addresses = sources.acars_routers.addresses().to_vec();
+                    addresses.remove(index);
+                    sources.acars_routers = AcarsRouterSource::from(addresses);
+                }
+                SourceKind::Adsb => {
+                    let mut addresses = sources.adsb_sources.addresses().to_vec();
+                    addresses.remove(index);
+                    sources.adsb_sources = AdsbSource::from(addresses);
+                }
+            }
+            draft.set(None);
+            send_sources(&send_message, sources);
+        })
+    };
+
+    let acars_routers =
+        sources
+            .acars_routers
+            .addresses()
+            .iter()
+            .enumerate()
+            .map(|(index, source)| {
+                let edit = {
+                    let draft = draft.clone();
+                    let source = source.clone();
+                    Callback::from(move |_: MouseEvent| {
+                        draft.set(Some(Draft {
+                            index: Some(index),
+                            address: source.address().to_string(),
+                            port: source.port().to_string(),
+                            ..Draft::new(SourceKind::AcarsRouter)
+                        }));
+                    })
+                };
+
+                render_source(
+                    source.to_string(),
+                    None,
+                    edit,
+                    delete(SourceKind::AcarsRouter, index),
+                )
+            });
+
+    let adsb_sources =
+        sources
+            .adsb_sources
+            .addresses()
+            .iter()
+            .enumerate()
+            .map(|(index, source)| {
+                let edit = {
+                    let draft = draft.clone();
+                    let source = source.clone();
+                    Callback::from(move |_: MouseEvent| {
+                        draft.set(Some(Draft {
+                            kind: SourceKind::Adsb,
+                            index: Some(index),
+                            address: source.address().to_string(),
+                            port: source.port().to_string(),
+                            latitude: source.latitude().to_string(),
+                            longitude: source.longitude().to_string(),
+                        }));
+                    })
+                };
+
+                render_source(
+                    source.to_string(),
+                    Some(format!(
+                        "Receiver at {}, {}",
+                        source.latitude(),
+                        source.longitude()
+                    )),
+                    edit,
+                    delete(SourceKind::Adsb, index),
+                )
+            });
+
+    let save_timeout = {
+        let send_message = props.send_message.clone();
+        let sources = sources.clone();
+        let silence_timeout = silence_timeout.clone();
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let value = silence_timeout.cast::<HtmlInputElement>().unwrap().value();
+            let Ok(seconds) = value.trim().parse::<u64>() else {
+                log::error!("Silence timeout {value} is not a number of seconds");
+                return;
+            };
+
+            if seconds != sources.silence_timeout {
+                let mut sources = sources.clone();
+                sources.silence_timeout = seconds;
+                send_sources(&send_message, sources);
+            }
+        })
+    };
+
+    let editor = |kind: SourceKind| {
+        (*draft).clone().filter(|current| current.kind == kind).map_or_else(
+            || {
+                let label = match kind {
+                    SourceKind::AcarsRouter => "Add acars_router",
+                    SourceKind::Adsb => "Add ADS-B Source",
+                };
+                html! {
+                    <div class="settings-item buttons">
+                        <div><button class="button" onclick={new_source(kind)}>{ label }</button></div>
+                    </div>
+                }
+            },
+            |current| {
+                let result = (*source_test)
+                    .as_ref()
+                    .filter(|result| testing.as_ref() == Some(&result.name));
+                render_editor(
+                    &current,
+                    &draft,
+                    &sources,
+                    &props.send_message,
+                    &testing,
+                    result,
+                )
+            },
+        )
+    };
+
     html! {
         <>
-        <input id="collapsible_data_sources" class="toggle" type="checkbox" />
+        <input id="collapsible_data_sources" class="toggle" type="checkbox" checked={state.is_visible} onclick={show_panel} />
         <label for="collapsible_data_sources" class="lbl-toggle">{"Data Sources"}</label>
         <div class="collapsible-content">
           <div class="content-inner">
-            <p>
-              {"QUnit is by calling one of the object that are embedded in JavaScript, and faster JavaScript program could also used with
-              its elegant, well documented, and functional programming using JS, HTML pages Modernizr is a popular browsers without
-              plug-ins. Test-Driven Development."}
-            </p>
+            if let Some(error) = config_error.as_ref() {
+                <p class="alert-rules-error">{ error }</p>
+            }
+            <h4>{"acars_router"}</h4>
+            if sources.acars_routers.addresses().is_empty() {
+                <p>{"No acars_router sources have been set up."}</p>
+            }
+            { for acars_routers }
+            { editor(SourceKind::AcarsRouter) }
+            <h4>{"ADS-B"}</h4>
+            if sources.adsb_sources.addresses().is_empty() {
+                <p>{"No ADS-B sources have been set up."}</p>
+            }
+            { for adsb_sources }
+            { editor(SourceKind::Adsb) }
+            <form onsubmit={save_timeout}>
+                <div class="settings-item">
+                    <label>{"Silence Timeout (seconds, 0 to turn off)"}</label>
+                    <input type="number" min="0" ref={silence_timeout} value={sources.silence_timeout.to_string()} />
+                </div>
+                <div class="settings-item buttons">
+                    <div><button type="submit" class="button">{"Save Timeout"}</button></div>
+                </div>
+            </form>
           </div>
         </div>
         </>
     }
 }
+
+fn render_editor(
+    current: &Draft,
+    draft: &UseStateHandle<Option<Draft>>,
+    sources: &DataSources,
+    send_message: &Callback<UserWssMessage>,
+    testing: &UseStateHandle<Option<String>>,
+    test_result: Option<&ShSourceTestResult>,
+) -> Html {
+    // every input writes straight in to the draft
+    let update = |apply: fn(&mut Draft, String)| {
+        let draft = draft.clone();
+        Callback::from(move |event: InputEvent| {
+            let value = event.target_unchecked_into::<HtmlInputElement>().value();
+            if let Some(mut current) = (*draft).clone() {
+                apply(&mut current, value);
+                draft.set(Some(current));
+            }
+        })
+    };
+
+    // nothing is flagged until something has been typed
+    let address_error = Some(current.address.trim())
+        .filter(|address| !address.is_empty())
+        .and_then(|address| validate_address(address).err());
+    let port_error = Some(current.port.trim())
+        .filter(|port| !port.is_empty())
+        .and_then(|_| current.port().err());
+    let latitude_error = Some(current.latitude.trim())
+        .filter(|latitude| !latitude.is_empty())
+        .and_then(|_| current.latitude().err());
+    let longitude_error = Some(current.longitude.trim())
+        .filter(|longitude| !longitude.is_empty())
+        .and_then(|_| current.longitude().err());
+
+    let saved = current.apply(sources);
+    // the field errors already say what is wrong with each field
+    let overall_error = saved.as_ref().err().filter(|_| {
+        address_error.is_none()
+            && port_error.is_none()
+            && latitude_error.is_none()
+            && longitude_error.is_none()
+            && !current.address.trim().is_empty()
+            && !current.port.trim().is_empty()
+    });
+
+    let save = {
+        let draft = draft.clone();
+        let testing = testing.clone();
+        let send_message = send_message.clone();
+        let saved = saved.clone();
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            if let Ok(sources) = &saved {
+                testing.set(None);
+                draft.set(None);
+                send_sources(&send_message, sources.clone());
+            }
+        })
+    };
+
+    let cancel = {
+        let draft = draft.clone();
+        let testing = testing.clone();
+        Callback::from(move |_: MouseEvent| {
+            testing.set(None);
+            draft.set(None);
+        })
+    };
+
+    let source_test = current.source_test();
+    let test = test_source(source_test.clone(), testing, send_message);
+    // a result for what is being tested means the test has finished
+    let test_running = testing.is_some() && test_result.is_none();
+
+    html! {
+        <form class="alert-rule-editor" onsubmit={save}>
+            <div class="settings-item">
+                <label>{"Address"}</label>
+                <input type="text" placeholder="Host name or IP address" value={current.address.clone()}
+                    oninput={update(|draft, value| draft.address = value)} />
+                { field_error(address_error) }
+            </div>
+            <div class="settings-item">
+                <label>{"Port"}</label>
+                <input type="text" inputmode="numeric" value={current.port.clone()}
+                    oninput={update(|draft, value| draft.port = value)} />
+                { field_error(port_error) }
+            </div>
+            if current.kind == SourceKind::Adsb {
+                <div class="settings-item">
+                    <label>{"Receiver Latitude"}</label>
+                    <input type="text" inputmode="decimal" value={current.latitude.clone()}
+                        oninput={update(|draft, value| draft.latitude = value)} />
+                    { field_error(latitude_error) }
+                </div>
+                <div class="settings-item">
+                    <label>{"Receiver Longitude"}</label>
+                    <input type="text" inputmode="decimal" value={current.longitude.clone()}
+                        oninput={update(|draft, value| draft.longitude = value)} />
+                    { field_error(longitude_error) }
+                </div>
+            }
+            { field_error(overall_error.cloned()) }
+            { render_test_result(test_running, test_result) }
+            <div class="settings-item buttons">
+                <div><button type="submit" class="button" disabled={saved.is_err()}>{"Save Source"}</button></div>
+                <div><button type="button" class="button" onclick={test} disabled={source_test.is_none() || test_running}>{"Test Connection"}</button></div>
+                <div><button type="button" class="button" onclick={cancel}>{"Cancel"}</button></div>
+            </div>
+        </form>
+    }
+}
@@ -3,23 +3,87 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use crate::common::wssprops::WssCommunicationProps;
+use crate::components::setting::SOURCE_TYPES;
+use crate::services::temp_state::WebAppStateTemp;
+use serde::{Deserialize, Serialize};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use sh_config::source::{EnabledDataSources, ShEnabledDataSources};
 use yew::prelude::*;
+use yewdux::prelude::*;
 
+#[derive(Clone, PartialEq, Store, Default, Serialize, Deserialize)]
+#[store(storage = "local", storage_tab_sync)]
+struct ConfigEnabledDataSourcesState {
+    pub is_visible: bool,
+}
+
+/// Which kinds of data the hub uses. Data of a kind that is turned off is ignored as it comes
+/// in. Each change is sent straight away
 #[function_component(ShEnabledDataSourcesConfig)]
-pub fn sh_enabled_data_sources_config() -> Html {
+pub fn sh_enabled_data_sources_config(props: &WssCommunicationProps) -> Html {
     log::debug!("Rendering enabled data sources configuration.");
 
+    let config = use_selector(|state: &WebAppStateTemp| state.config.clone());
+    let (state, dispatch) = use_store::<ConfigEnabledDataSourcesState>();
+
+    let show_panel = {
+        let is_visible = state.is_visible;
+        Callback::from(move |_: MouseEvent| {
+            dispatch.reduce_mut(move |state| state.is_visible = !is_visible);
+        })
+    };
+
+    let enabled = config
+        .as_ref()
+        .as_ref()
+        .map(|config| config.enabled_data_sources.clone());
+
+    let source_checkbox =
+        |enabled: &EnabledDataSources, (source, name): &(ShEnabledDataSources, &str)| {
+            let checked = enabled.is_enabled(source);
+            let onclick = {
+                let send_message = props.send_message.clone();
+                let enabled = enabled.clone();
+                let source = source.clone();
+                Callback::from(move |_: MouseEvent| {
+                    let mut enabled = enabled.clone();
+                    if enabled.is_enabled(&source) {
+                        enabled.enabled_sources.retain(|known| *known != source);
+                    } else {
+                        enabled.enabled_sources.push(source.clone());
+                    }
+
+                    send_message.emit(UserWssMessage::new(
+                        UserMessageTypes::UserUpdateEnabledDataSources,
+                        MessageData::ShEnabledDataSources(enabled),
+                    ));
+                })
+            };
+
+            html! {
+                <label class="enabled-data-source">
+                    <input type="checkbox" {checked} {onclick} />{ *name }
+                </label>
+            }
+        };
+
     html! {
         <>
-        <input id="collapsible_enabled_data_sources" class="toggle" type="checkbox" />
+        <input id="collapsible_enabled_data_sources" class="toggle" type="checkbox" checked={state.is_visible} onclick={show_panel} />
         <label for="collapsible_enabled_data_sources" class="lbl-toggle">{"Enabled Data Sources"}</label>
         <div class="collapsible-content">
           <div class="content-inner">
-            <p>
-              {"QUnit is by calling one of the object that are embedded in JavaScript, and faster JavaScript program could also used with
-              its elegant, well documented, and functional programming using JS, HTML pages Modernizr is a popular browsers without
-              plug-ins. Test-Driven Development."}
-            </p>
+            {
+                enabled.map_or_else(
+                    || html! { "Loading..." },
+                    |enabled| html! {
+                        <div class="settings-item enabled-data-sources">
+                            { for SOURCE_TYPES.iter().map(|source| source_checkbox(&enabled, source)) }
+                        </div>
+                    },
+                )
+            }
           </div>
         </div>
         </>
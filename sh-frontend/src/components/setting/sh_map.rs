@@ -171,6 +171,8 @@ pub fn sh_map_config(props: &WssCommunicationProps) -> Html {
             {
               config.as_ref().as_ref().map_or_else(|| html! { "Loading..." }, |config| {
                   let map = config.map.clone();
+                  let latitude_locked = config.is_overridden("map.center_latitude");
+                  let longitude_locked = config.is_overridden("map.center_longitude");
 
                   let latitude = make_sure_string_has_five_digits(&map.center_latitude.to_string());
                   let longitude = make_sure_string_has_five_digits(&map.center_longitude.to_string());
@@ -180,8 +182,8 @@ pub fn sh_map_config(props: &WssCommunicationProps) -> Html {
 
                   html! {
                       <form onsubmit={onsubmit}>
-                          <div class="settings-item"><InputField input_node_ref={latitude_node} label={"Map Latitude"} name={"maplatitude"} input_value={latitude} field_type={InputFieldType::Number} number_properties={Some(latitude_options)} /></div>
-                          <div class="settings-item"><InputField input_node_ref={longitude_node} label={"Map Longitude"} name={"maplongitude"} field_type={InputFieldType::Number} input_value={longitude} number_properties={Some(longitude_options)}/></div>
+                          <div class="settings-item"><InputField input_node_ref={latitude_node} label={"Map Latitude"} name={"maplatitude"} input_value={latitude} field_type={InputFieldType::Number} number_properties={Some(latitude_options)} read_only={latitude_locked} /></div>
+                          <div class="settings-item"><InputField input_node_ref={longitude_node} label={"Map Longitude"} name={"maplongitude"} field_type={InputFieldType::Number} input_value={longitude} number_properties={Some(longitude_options)} read_only={longitude_locked} /></div>
                           <div class="settings-item buttons">
                           <div><button type="submit" class="button" id="update">{"Update Configuration"}</button></div>
                           <div><button type="submit" class="button" id="reset">{"Reset Configuration"}</button></div>
@@ -0,0 +1,246 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use crate::common::wssprops::WssCommunicationProps;
+use crate::services::temp_state::WebAppStateTemp;
+use serde::{Deserialize, Serialize};
+use sh_common::auth::{ShUser, ShUserRole, ShUserUpdate};
+use sh_common::{MessageData, UserMessageTypes, UserWssMessage};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yewdux::prelude::*;
+
+#[derive(Clone, PartialEq, Store, Default, Serialize, Deserialize)]
+#[store(storage = "local", storage_tab_sync)]
+struct ConfigUsersState {
+    pub is_visible: bool,
+}
+
+/// The user being edited. `existing` users can be saved without a new password
+#[derive(Clone, PartialEq)]
+struct Draft {
+    existing: bool,
+    name: String,
+    role: ShUserRole,
+    password: String,
+}
+
+fn role_from(value: &str) -> ShUserRole {
+    if value == "admin" {
+        ShUserRole::Admin
+    } else {
+        ShUserRole::Viewer
+    }
+}
+
+/// Accounts that can log in. Changes are sent straight away
+#[function_component(ShUsersConfig)]
+pub fn sh_users_config(props: &WssCommunicationProps) -> Html {
+    log::debug!("Rendering users settings.");
+
+    let users = use_selector(|state: &WebAppStateTemp| state.users.clone());
+    let config_error = use_selector(|state: &WebAppStateTemp| state.config_error.clone());
+    let (state, dispatch) = use_store::<ConfigUsersState>();
+    let draft = use_state(|| None::<Draft>);
+
+    {
+        let send_message = props.send_message.clone();
+        use_effect_with((), move |()| {
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserRequestUsers,
+                MessageData::NoData,
+            ));
+        });
+    }
+
+    let show_panel = {
+        let is_visible = state.is_visible;
+        Callback::from(move |_: MouseEvent| {
+            dispatch.reduce_mut(move |state| state.is_visible = !is_visible);
+        })
+    };
+
+    let new_user = {
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| {
+            draft.set(Some(Draft {
+                existing: false,
+                name: String::new(),
+                role: ShUserRole::Viewer,
+                password: String::new(),
+            }));
+        })
+    };
+
+    let rows = users
+        .iter()
+        .map(|user| render_user(user, &draft, &props.send_message));
+
+    html! {
+        <>
+        <input id="collapsible_users" class="toggle" type="checkbox" checked={state.is_visible} onclick={show_panel} />
+        <label for="collapsible_users" class="lbl-toggle">{"Users"}</label>
+        <div class="collapsible-content">
+          <div class="content-inner">
+            if let Some(error) = config_error.as_ref() {
+                <p class="alert-rules-error">{ error }</p>
+            }
+            if users.is_empty() {
+                <p>{"There are no accounts. Connections from this machine, or every connection if logging in is turned off, don't need one."}</p>
+            }
+            { for rows }
+            {
+                (*draft).clone().map_or_else(
+                    || html! {
+                        <div class="settings-item buttons">
+                            <div><button class="button" onclick={new_user}>{"Add User"}</button></div>
+                        </div>
+                    },
+                    |current| render_editor(&current, &draft, &props.send_message),
+                )
+            }
+          </div>
+        </div>
+        </>
+    }
+}
+
+fn render_user(
+    user: &ShUser,
+    draft: &UseStateHandle<Option<Draft>>,
+    send_message: &Callback<UserWssMessage>,
+) -> Html {
+    let edit = {
+        let draft = draft.clone();
+        let user = user.clone();
+        Callback::from(move |_: MouseEvent| {
+            draft.set(Some(Draft {
+                existing: true,
+                name: user.name.clone(),
+                role: user.role,
+                password: String::new(),
+            }));
+        })
+    };
+
+    let delete = {
+        let draft = draft.clone();
+        let send_message = send_message.clone();
+        let name = user.name.clone();
+        Callback::from(move |_: MouseEvent| {
+            draft.set(None);
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserDeleteUser,
+                MessageData::ShUserName(name.clone()),
+            ));
+        })
+    };
+
+    html! {
+        <div class="settings-item alert-rule">
+            <span class="alert-rule-name">{ &user.name }</span>
+            <span class="alert-rule-summary">{ user.role.to_string() }</span>
+            <button class="button" onclick={edit}>{"Edit"}</button>
+            <button class="button" onclick={delete}>{"Delete"}</button>
+        </div>
+    }
+}
+
+fn render_editor(
+    current: &Draft,
+    draft: &UseStateHandle<Option<Draft>>,
+    send_message: &Callback<UserWssMessage>,
+) -> Html {
+    let update_name = {
+        let draft = draft.clone();
+        Callback::from(move |event: InputEvent| {
+            let value = event.target_unchecked_into::<HtmlInputElement>().value();
+            if let Some(mut current) = (*draft).clone() {
+                current.name = value;
+                draft.set(Some(current));
+            }
+        })
+    };
+
+    let update_password = {
+        let draft = draft.clone();
+        Callback::from(move |event: InputEvent| {
+            let value = event.target_unchecked_into::<HtmlInputElement>().value();
+            if let Some(mut current) = (*draft).clone() {
+                current.password = value;
+                draft.set(Some(current));
+            }
+        })
+    };
+
+    let update_role = {
+        let draft = draft.clone();
+        Callback::from(move |event: Event| {
+            let value = event.target_unchecked_into::<HtmlSelectElement>().value();
+            if let Some(mut current) = (*draft).clone() {
+                current.role = role_from(&value);
+                draft.set(Some(current));
+            }
+        })
+    };
+
+    let save = {
+        let draft = draft.clone();
+        let current = current.clone();
+        let send_message = send_message.clone();
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let update = ShUserUpdate {
+                name: current.name.trim().to_string(),
+                role: current.role,
+                // an empty password on an existing user leaves it as it is
+                password: Some(current.password.clone()).filter(|password| !password.is_empty()),
+            };
+
+            draft.set(None);
+            send_message.emit(UserWssMessage::new(
+                UserMessageTypes::UserUpdateUser,
+                MessageData::ShUserUpdate(update),
+            ));
+        })
+    };
+
+    let cancel = {
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| draft.set(None))
+    };
+
+    let password_placeholder = if current.existing {
+        "Leave empty to keep the current password"
+    } else {
+        "At least 8 characters"
+    };
+
+    html! {
+        <form class="alert-rule-editor" onsubmit={save}>
+            <div class="settings-item">
+                <label>{"Username"}</label>
+                <input type="text" value={current.name.clone()} oninput={update_name} readonly={current.existing} />
+            </div>
+            <div class="settings-item">
+                <label>{"Role"}</label>
+                <select onchange={update_role}>
+                    <option value="viewer" selected={current.role == ShUserRole::Viewer}>{"Viewer"}</option>
+                    <option value="admin" selected={current.role == ShUserRole::Admin}>{"Admin"}</option>
+                </select>
+            </div>
+            <div class="settings-item">
+                <label>{"Password"}</label>
+                <input type="password" autocomplete="new-password" placeholder={password_placeholder}
+                    value={current.password.clone()} oninput={update_password} />
+            </div>
+            <div class="settings-item buttons">
+                <div><button type="submit" class="button">{"Save User"}</button></div>
+                <div><button type="button" class="button" onclick={cancel}>{"Cancel"}</button></div>
+            </div>
+        </form>
+    }
+}
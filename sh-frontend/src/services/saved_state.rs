@@ -12,6 +12,9 @@ use yewdux::prelude::*;
 pub struct WebAppState {
     pub left_panel: Panels,
     pub right_panel: Panels,
+    // Kept so a reload, or a new tab, doesn't have to log in again
+    #[serde(default)]
+    pub session_token: Option<String>,
 }
 
 impl Default for WebAppState {
@@ -19,6 +22,7 @@ impl Default for WebAppState {
         Self {
             left_panel: Panels::Messages,
             right_panel: Panels::Map,
+            session_token: None,
         }
     }
 }
@@ -3,14 +3,72 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::alert::ShAlert;
+use sh_common::auth::{ShAuthStatus, ShUser};
+use sh_common::consumer_status::ShConsumerStatus;
+use sh_common::emergency::ShEmergency;
+use sh_common::flight::{ShFlightSession, ShFlightSessionChange, ShFlightSessionDetail};
+use sh_common::ground_station::{ShGroundStation, ShGroundStationNetwork};
+use sh_common::lookup::ShFlightInfo;
+use sh_common::notification::ShNotificationDelivery;
+use sh_common::source_status::{ShSourceStatus, ShSourceTestResult};
+use sh_common::stats::{ShStats, ShStatsRange};
+use sh_common::timeline::ShFlightTimeline;
+use sh_common::weather::{ShWeatherProduct, ShWeatherQuery};
 use sh_config::web::sh_web_config::ShWebConfig;
+use std::collections::{HashMap, VecDeque};
 use yewdux::prelude::*;
 
+// Number of messages we keep for display. Matches what the server sends on connect
+const MAX_MESSAGES: usize = 250;
+// Number of alerts we keep for display. Matches the history the server sends
+const MAX_ALERTS: usize = 200;
+
 #[derive(Clone, PartialEq, Store)]
 pub struct WebAppStateTemp {
     pub right_panel_visible: bool,
     pub config: Option<ShWebConfig>,
     pub websocket_connected: bool,
+    // Flight info received from the server, keyed by the flight number as it was requested
+    pub flight_info: HashMap<String, ShFlightInfo>,
+    // Most recent messages, newest first
+    pub messages: VecDeque<ShAcarsMessage>,
+    pub ground_stations: Vec<ShGroundStation>,
+    // The flight the user has picked to see the timeline for
+    pub selected_flight: Option<String>,
+    pub flight_timeline: Option<ShFlightTimeline>,
+    // What the weather page is showing, newest first
+    pub weather_query: ShWeatherQuery,
+    pub weather: Vec<ShWeatherProduct>,
+    // Recent flights, most recently active first
+    pub flight_sessions: Vec<ShFlightSession>,
+    // The flight the user has opened, and everything we have for it once the server sends it
+    pub selected_session_id: Option<i64>,
+    pub selected_session: Option<ShFlightSessionDetail>,
+    // Alerts raised by the alert rules, newest first
+    pub alerts: VecDeque<ShAlert>,
+    // How sending alerts to the notification targets went, newest first
+    pub notification_deliveries: VecDeque<ShNotificationDelivery>,
+    // Emergencies the server has raised, the ones still going first, then newest first
+    pub emergencies: Vec<ShEmergency>,
+    // How the connection to each configured source is doing, in config order
+    pub sources: Vec<ShSourceStatus>,
+    // How everything the server runs is doing, in the order it was started
+    pub consumers: Vec<ShConsumerStatus>,
+    // What the last source test found, once the server has finished it
+    pub source_test: Option<ShSourceTestResult>,
+    // The range the statistics page is showing, and the server's numbers for it
+    pub stats_range: ShStatsRange,
+    pub stats: Option<ShStats>,
+    // Why the server turned down the last config change, if it did
+    pub config_error: Option<String>,
+    // Who the server says we are. Nothing until it has said
+    pub auth: Option<ShAuthStatus>,
+    // Why the server turned down logging in, or a request, if it did
+    pub auth_error: Option<String>,
+    // The accounts, for admins managing them
+    pub users: Vec<ShUser>,
 }
 
 impl Default for WebAppStateTemp {
@@ -19,6 +77,196 @@ impl Default for WebAppStateTemp {
             right_panel_visible: true,
             config: None,
             websocket_connected: false,
+            flight_info: HashMap::new(),
+            messages: VecDeque::with_capacity(MAX_MESSAGES),
+            ground_stations: Vec::new(),
+            selected_flight: None,
+            flight_timeline: None,
+            weather_query: ShWeatherQuery::default(),
+            weather: Vec::new(),
+            flight_sessions: Vec::new(),
+            selected_session_id: None,
+            selected_session: None,
+            alerts: VecDeque::with_capacity(MAX_ALERTS),
+            notification_deliveries: VecDeque::with_capacity(MAX_ALERTS),
+            emergencies: Vec::new(),
+            sources: Vec::new(),
+            consumers: Vec::new(),
+            source_test: None,
+            stats_range: ShStatsRange::default(),
+            stats: None,
+            config_error: None,
+            auth: None,
+            auth_error: None,
+            users: Vec::new(),
+        }
+    }
+}
+
+impl WebAppStateTemp {
+    #[must_use]
+    pub fn user(&self) -> Option<&ShUser> {
+        self.auth.as_ref().and_then(|auth| auth.user.as_ref())
+    }
+
+    #[must_use]
+    pub fn is_admin(&self) -> bool {
+        self.user().is_some_and(ShUser::is_admin)
+    }
+
+    pub fn add_message(&mut self, message: ShAcarsMessage) {
+        let network = ShGroundStationNetwork::from_message_type(message.message_type);
+        if let (Some(network), Some(station_id)) = (network, &message.ground_station_id) {
+            if let Some(station) = self.ground_stations.iter_mut().find(|station| {
+                station.network == network && station.id.eq_ignore_ascii_case(station_id)
+            }) {
+                station.message_count += 1;
+            }
+        }
+
+        if self.messages.len() >= MAX_MESSAGES {
+            self.messages.pop_back();
+        }
+
+        self.messages.push_front(message);
+    }
+
+    pub fn set_messages(&mut self, messages: Vec<ShAcarsMessage>) {
+        // The server sends them oldest first
+        self.messages = messages.into_iter().rev().take(MAX_MESSAGES).collect();
+    }
+
+    pub fn add_alert(&mut self, alert: ShAlert) {
+        if self.alerts.len() >= MAX_ALERTS {
+            self.alerts.pop_back();
+        }
+
+        self.alerts.push_front(alert);
+    }
+
+    pub fn add_notification_delivery(&mut self, delivery: ShNotificationDelivery) {
+        if self.notification_deliveries.len() >= MAX_ALERTS {
+            self.notification_deliveries.pop_back();
+        }
+
+        self.notification_deliveries.push_front(delivery);
+    }
+
+    /// Add a source, or replace the copy we have
+    pub fn update_source(&mut self, source: ShSourceStatus) {
+        match self
+            .sources
+            .iter_mut()
+            .find(|known| known.name == source.name)
+        {
+            Some(known) => *known = source,
+            None => self.sources.push(source),
+        }
+    }
+
+    /// Add a consumer, or replace the copy we have
+    pub fn update_consumer(&mut self, consumer: ShConsumerStatus) {
+        match self
+            .consumers
+            .iter_mut()
+            .find(|known| known.name == consumer.name)
+        {
+            Some(known) => *known = consumer,
+            None => self.consumers.push(consumer),
+        }
+    }
+
+    /// Add an emergency, or replace the copy we have if it has changed
+    pub fn update_emergency(&mut self, emergency: ShEmergency) {
+        match self
+            .emergencies
+            .iter_mut()
+            .find(|known| known.id == emergency.id)
+        {
+            Some(known) => *known = emergency,
+            None => self.emergencies.push(emergency),
+        }
+
+        self.emergencies.sort_by(|a, b| {
+            b.is_active()
+                .cmp(&a.is_active())
+                .then(b.started.total_cmp(&a.started))
+        });
+        self.emergencies.truncate(MAX_ALERTS);
+    }
+
+    #[must_use]
+    pub fn active_emergencies(&self) -> Vec<ShEmergency> {
+        self.emergencies
+            .iter()
+            .filter(|emergency| emergency.is_active())
+            .cloned()
+            .collect()
+    }
+
+    pub fn update_ground_station(&mut self, station: ShGroundStation) {
+        match self
+            .ground_stations
+            .iter_mut()
+            .find(|known| known.network == station.network && known.id == station.id)
+        {
+            Some(known) => *known = station,
+            None => self.ground_stations.push(station),
+        }
+    }
+
+    /// Add a product the server has just heard, if it is something the weather page is showing
+    pub fn add_weather(&mut self, product: ShWeatherProduct) {
+        if !self.weather_query.matches(&product) {
+            return;
+        }
+
+        if self.weather_query.latest_only {
+            self.weather.retain(|known| {
+                known.airport != product.airport || known.product_type != product.product_type
+            });
+        }
+
+        self.weather.insert(0, product);
+    }
+
+    /// Apply flights the server has changed. If the flight the user has open was replaced,
+    /// follow it to whichever new flight now covers when it started
+    pub fn update_flight_sessions(&mut self, change: ShFlightSessionChange) {
+        let replaced = self
+            .flight_sessions
+            .iter()
+            .find(|session| {
+                Some(session.id) == self.selected_session_id && change.removed.contains(&session.id)
+            })
+            .cloned();
+
+        self.flight_sessions
+            .retain(|session| !change.removed.contains(&session.id));
+
+        for session in change.updated {
+            match self
+                .flight_sessions
+                .iter_mut()
+                .find(|known| known.id == session.id)
+            {
+                Some(known) => *known = session,
+                None => self.flight_sessions.push(session),
+            }
+        }
+
+        self.flight_sessions.sort_by(|a, b| b.end.total_cmp(&a.end));
+
+        if let Some(replaced) = replaced {
+            self.selected_session_id = self
+                .flight_sessions
+                .iter()
+                .find(|session| {
+                    session.aircraft == replaced.aircraft
+                        && (session.start..=session.end).contains(&replaced.start)
+                })
+                .map(|session| session.id);
+            self.selected_session = None;
         }
     }
 }
@@ -0,0 +1,171 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// The command line. Options given here win over the config file and `SH_` environment variables
+
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::{Args, Parser, Subcommand};
+use sh_common::api::{ShExportFormat, ShMessageQuery};
+use sh_config::ShConfigOverrides;
+
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Reads ACARS, VDLM2, HFDL and ADS-B data and serves it to the web interface"
+)]
+pub struct Cli {
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Config file to use, instead of sh_config.toml in the data path"
+    )]
+    pub config: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "DIR",
+        help = "Where the config, database and lookup files are kept. Overrides SH_DATA_PATH"
+    )]
+    pub data_path: Option<String>,
+    #[arg(long, global = true, value_parser = ["error", "warn", "info", "debug", "trace"])]
+    pub log_level: Option<String>,
+    /// Address the web interface and API listen on
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub bind: Option<IpAddr>,
+    /// Same as the check-config command
+    #[arg(long, hide = true)]
+    pub check_config: bool,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the hub. The default
+    Run,
+    /// Say what is wrong with the config, without starting anything. Exits 1 if anything would
+    /// stop working
+    CheckConfig,
+    /// Print the config the hub starts with when there is no config file
+    PrintDefaultConfig,
+    /// Create the first account, an admin. The web interface only offers this on the hub's own
+    /// machine. The password is read from standard input
+    CreateAdmin { username: String },
+    /// Look after the database
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// Write stored messages out, oldest first. Every message unless it is narrowed down
+    Export {
+        /// File to write to, instead of standard output
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// csv or jsonl. Only jsonl can be imported again
+        #[arg(long, default_value = "jsonl", value_parser = ShExportFormat::from_str)]
+        format: ShExportFormat,
+        /// Add what is known about the aircraft each message was tied to
+        #[arg(long)]
+        aircraft_info: bool,
+        #[command(flatten)]
+        filter: MessageFilter,
+    },
+    /// Add messages from a JSON Lines file made by export. Messages already stored are skipped
+    Import {
+        /// File to read, or - for standard input
+        file: PathBuf,
+    },
+    /// Bring settings and message history over from docker-acarshub
+    ImportAcarshub {
+        /// acarshub's environment variables, as NAME=value lines like a docker compose .env
+        /// file. The environment this runs in is used if this isn't given
+        #[arg(long, value_name = "FILE")]
+        env_file: Option<PathBuf>,
+        /// acarshub's messages.db. Messages are only brought over if this is given
+        #[arg(long, value_name = "FILE")]
+        database: Option<String>,
+        /// Say what would be done, without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// The same search the REST API and the web interface have
+#[derive(Debug, Args)]
+pub struct MessageFilter {
+    /// Text the message has to contain, ignoring case
+    #[arg(long)]
+    pub text: Option<String>,
+    /// ICAO hex address or registration of the aircraft
+    #[arg(long)]
+    pub aircraft: Option<String>,
+    /// Flight number, in either IATA or ICAO form
+    #[arg(long)]
+    pub flight: Option<String>,
+    /// ACARS label, like H1
+    #[arg(long)]
+    pub label: Option<String>,
+    #[arg(long, value_parser = ["acars", "vdlm2", "hfdl", "inmarsat", "iridium"])]
+    pub message_type: Option<String>,
+    /// The configured source the message came in from, as address:port
+    #[arg(long)]
+    pub source: Option<String>,
+    /// Unix timestamp, in seconds. Only messages received at or after it
+    #[arg(long)]
+    pub since: Option<f64>,
+    /// Unix timestamp, in seconds. Only messages received before it
+    #[arg(long)]
+    pub until: Option<f64>,
+}
+
+impl MessageFilter {
+    #[must_use]
+    pub fn to_query(&self) -> ShMessageQuery {
+        ShMessageQuery {
+            text: self.text.clone(),
+            aircraft: self.aircraft.clone(),
+            flight: self.flight.clone(),
+            label: self.label.clone(),
+            message_type: self.message_type.clone(),
+            source: self.source.clone(),
+            since: self.since,
+            until: self.until,
+            limit: None,
+            offset: None,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommand {
+    /// Bring the database schema up to date. The hub does this itself when it starts
+    Migrate,
+    /// Give the space left by deleted rows back to the disk. Stop the hub first
+    Vacuum,
+}
+
+impl Cli {
+    #[must_use]
+    pub fn overrides(&self) -> ShConfigOverrides {
+        ShConfigOverrides {
+            config_file: self.config.clone(),
+            data_path: self.data_path.clone(),
+            log_level: self.log_level.clone(),
+            bind: self.bind,
+        }
+    }
+
+    /// What to do, with `--check-config` from before there were commands still working
+    #[must_use]
+    pub const fn command(&self) -> &Command {
+        match &self.command {
+            Some(command) => command,
+            None if self.check_config => &Command::CheckConfig,
+            None => &Command::Run,
+        }
+    }
+}
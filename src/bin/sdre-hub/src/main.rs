@@ -12,23 +12,99 @@
     clippy::all
 )]
 
+mod cli;
+mod password;
+
+use clap::Parser;
+use cli::{Cli, Command, DbCommand};
+use sdrehub::maintenance;
 use sdrehub::SdreHub;
+use sh_common::auth::ShLogin;
+use sh_config::acarshub::apply_acarshub_env;
+use sh_config::validation::has_errors;
 use sh_config::ShConfig;
+use std::collections::HashMap;
 use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // Check the command line args and see if `--version` was passed.
-    // If so, print version and exit 0
+    let cli = Cli::parse();
 
-    if std::env::args().any(|x| x == *"--version") {
-        // get the version from the Cargo.toml file
-        let version = env!("CARGO_PKG_VERSION");
-        println!("SDRE Hub version: {version}");
+    // nothing is read for the default config, so a broken one doesn't get in the way
+    if matches!(cli.command(), Command::PrintDefaultConfig) {
+        print!("{}", ShConfig::default().to_toml_string());
         return Ok(());
     }
 
-    let config = ShConfig::new();
+    let config = match ShConfig::load_with(&cli.overrides()) {
+        Ok(config) => config,
+        Err(issues) => {
+            eprintln!("Error reading the config:");
+            for issue in &issues {
+                eprintln!("  {issue}");
+            }
+            std::process::exit(1);
+        }
+    };
+
+    match cli.command() {
+        Command::Run => run(config).await,
+        Command::CheckConfig => check_config(&config),
+        Command::PrintDefaultConfig => Ok(()),
+        Command::CreateAdmin { username } => create_admin(&config, username).await,
+        Command::Db(DbCommand::Migrate) => {
+            let (applied, version) = maintenance::migrate_database(&config).await?;
+            for migration in &applied {
+                println!("Applied {migration}");
+            }
+            println!(
+                "{} is at schema version {}",
+                config.app.database_url,
+                version.unwrap_or_default()
+            );
+            Ok(())
+        }
+        Command::Db(DbCommand::Vacuum) => {
+            let (before, after) = maintenance::vacuum_database(&config).await?;
+            println!(
+                "{} went from {before} to {after} bytes",
+                config.app.database_url
+            );
+            Ok(())
+        }
+        Command::Export {
+            output,
+            format,
+            aircraft_info,
+            filter,
+        } => {
+            let query = filter.to_query();
+            let exported = if let Some(path) = output {
+                let mut file = BufWriter::new(File::create(path)?);
+                maintenance::export_messages(&config, query, *format, *aircraft_info, &mut file)
+                    .await?
+            } else {
+                let mut stdout = BufWriter::new(io::stdout());
+                maintenance::export_messages(&config, query, *format, *aircraft_info, &mut stdout)
+                    .await?
+            };
+            // stdout may be the export, so this goes to stderr
+            eprintln!("Exported {exported} messages");
+            Ok(())
+        }
+        Command::Import { file } => import(&config, file).await,
+        Command::ImportAcarshub {
+            env_file,
+            database,
+            dry_run,
+        } => import_acarshub(config, env_file.as_deref(), database.as_deref(), *dry_run).await,
+    }
+}
+
+async fn run(config: ShConfig) -> Result<(), Box<dyn Error>> {
     let hub = SdreHub::new(config);
 
     match hub.run().await {
@@ -41,3 +117,136 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+// say what is wrong with the config without starting anything, or writing the config out.
+// Exits 1 if anything would stop working
+fn check_config(config: &ShConfig) -> Result<(), Box<dyn Error>> {
+    if let Some(migration) = &config.migration {
+        println!(
+            "{} is config version {}, and will be upgraded to version {} when the hub starts",
+            config.app.config_file, migration.from, migration.to
+        );
+        for change in &migration.changes {
+            println!("  {change}");
+        }
+    }
+
+    let issues = config.issues();
+    if issues.is_empty() {
+        println!("{} has no problems", config.app.config_file);
+    }
+    for issue in &issues {
+        println!("{issue}");
+    }
+    std::process::exit(i32::from(has_errors(&issues)));
+}
+
+async fn create_admin(config: &ShConfig, username: &str) -> Result<(), Box<dyn Error>> {
+    // not an option, so it doesn't end up in the shell's history
+    let login = ShLogin {
+        username: username.to_string(),
+        password: password::prompt_password(&format!("Password for {username}: "))?,
+    };
+    maintenance::create_admin(config, &login).await?;
+    println!("Created the admin account {}", login.username.trim());
+    Ok(())
+}
+
+async fn import(config: &ShConfig, file: &Path) -> Result<(), Box<dyn Error>> {
+    let report = if file == Path::new("-") {
+        maintenance::import_messages(config, BufReader::new(io::stdin())).await?
+    } else {
+        maintenance::import_messages(config, BufReader::new(File::open(file)?)).await?
+    };
+
+    for line in &report.unreadable {
+        eprintln!("Skipped {line}");
+    }
+    println!(
+        "Imported {} messages, {} were already stored and {} lines couldn't be read",
+        report.imported,
+        report.duplicates,
+        report.unreadable.len()
+    );
+
+    Ok(())
+}
+
+/// `NAME=value` lines, as in a docker compose `.env` file or `environment:` list
+fn read_env_file(path: &Path) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading {}: {e}", path.display()))?;
+
+    Ok(contents
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches("- ")
+                .trim_start_matches("export ")
+        })
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, value)| {
+            let value = value.trim();
+            let unquoted = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .or_else(|| {
+                    value
+                        .strip_prefix('\'')
+                        .and_then(|value| value.strip_suffix('\''))
+                })
+                .unwrap_or(value);
+            (name.trim().to_string(), unquoted.to_string())
+        })
+        .collect())
+}
+
+async fn import_acarshub(
+    config: ShConfig,
+    env_file: Option<&Path>,
+    database: Option<&str>,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let env = match env_file {
+        Some(path) => read_env_file(path)?,
+        None => std::env::vars().collect(),
+    };
+
+    // the settings go in to the file as it is, so nothing from SH_ variables or the command
+    // line gets written along with them
+    let mut stored = config.stored();
+    let settings = apply_acarshub_env(&mut stored, &env);
+    if settings.changes.is_empty() {
+        println!("No acarshub settings to bring over");
+    }
+    for change in &settings.changes {
+        println!("Setting {change}");
+    }
+    for skipped in &settings.skipped {
+        println!("Not brought over {skipped}");
+    }
+    if !dry_run && !settings.changes.is_empty() {
+        stored.write_config()?;
+    }
+
+    if let Some(database) = database {
+        let report = maintenance::import_acarshub_messages(&config, database, dry_run).await?;
+        for message in &report.unreadable {
+            eprintln!("Skipped {message}");
+        }
+        println!(
+            "{} {} messages, {} were already stored and {} couldn't be read",
+            if dry_run { "Would import" } else { "Imported" },
+            report.imported,
+            report.duplicates,
+            report.unreadable.len()
+        );
+    }
+
+    if dry_run {
+        println!("This was a dry run, so nothing has been changed");
+    }
+
+    Ok(())
+}
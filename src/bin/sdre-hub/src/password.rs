@@ -0,0 +1,63 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Reading a password from the terminal without it showing up on screen. A password piped in
+// is read as it is, as there is no terminal to hide it from
+
+use std::io::{self, IsTerminal, Write};
+
+/// Show `prompt` and read a line, without the newline
+pub fn prompt_password(prompt: &str) -> io::Result<String> {
+    eprint!("{prompt}");
+    io::stderr().flush()?;
+
+    let stdin = io::stdin();
+    let password = if stdin.is_terminal() {
+        read_hidden()?
+    } else {
+        read_line()?
+    };
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_line() -> io::Result<String> {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line)
+}
+
+/// Turn echo off for the line, and back on whether or not reading it worked
+#[cfg(unix)]
+fn read_hidden() -> io::Result<String> {
+    let fd = libc::STDIN_FILENO;
+    let mut original = std::mem::MaybeUninit::<libc::termios>::uninit();
+    // SAFETY: `original` is only read once tcgetattr has filled it in
+    let original = unsafe {
+        if libc::tcgetattr(fd, original.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        original.assume_init()
+    };
+
+    let mut hidden = original;
+    hidden.c_lflag &= !libc::ECHO;
+    // the enter key still moves the cursor to the next line
+    hidden.c_lflag |= libc::ECHONL;
+    // SAFETY: `hidden` is a termios tcgetattr gave back, with only its flags changed
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &hidden) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let line = read_line();
+    // SAFETY: as above, and this is the terminal's own settings going back
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+    line
+}
+
+/// Nothing to turn echo off with here, so the password shows as it is typed
+#[cfg(not(unix))]
+fn read_hidden() -> io::Result<String> {
+    read_line()
+}
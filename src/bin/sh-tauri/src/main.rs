@@ -22,7 +22,16 @@ use std::error::Error;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let config = ShConfig::new();
+    let config = match ShConfig::load() {
+        Ok(config) => config,
+        Err(issues) => {
+            eprintln!("Error reading the config:");
+            for issue in &issues {
+                eprintln!("  {issue}");
+            }
+            std::process::exit(1);
+        }
+    };
     let hub = SdreHub::new(config);
 
     // https://rfdonnelly.github.io/posts/tauri-async-rust-process/
@@ -25,17 +25,241 @@
 #[macro_use]
 extern crate log;
 
+pub mod maintenance;
+mod processor;
+mod supervisor;
+
 use sh_api::ShAPIServer;
-use sh_common::ServerType;
-use sh_common_server::ShDataUserList;
+use sh_common::notification::ShNotificationDelivery;
+use sh_common::source_status::ShSourceKind;
+use sh_common_server::alerts::AlertEngine;
+use sh_common_server::metrics::ShMetrics;
+use sh_common_server::source_data::ShSourceData;
+use sh_common_server::state::ShHubState;
+use sh_config::migration::ShConfigMigration;
+use sh_config::validation::ShConfigIssue;
 use sh_config::ShConfig;
+use sh_database::ShDatabase;
+use sh_lookup::ground_stations::GroundStationRegistry;
+use sh_lookup::ShLookup;
+use sh_notifications::Notifier;
+use sh_sources::acars_router::ShAcarsRouterClient;
+use sh_sources::adsb::ShAdsbClient;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tokio::task::JoinSet;
+use std::time::Duration;
+use supervisor::{ConsumerKey, Supervisor};
+use tokio::sync::{mpsc, Mutex};
+
+// How many decoded messages can be waiting on the hub before the sources have to wait
+const SOURCE_CHANNEL_CAPACITY: usize = 1024;
+// How often learned data is written back to disk
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// How many finished notification deliveries can be waiting to be logged
+const DELIVERY_CHANNEL_CAPACITY: usize = 64;
+
+/// Open the database, load the lookup tables and build the app state from them. Notification
+/// deliveries are reported on the returned channel once they finish
+async fn build_state(
+    config_lock: &Mutex<ShConfig>,
+) -> (Arc<ShHubState>, mpsc::Receiver<ShNotificationDelivery>) {
+    let database_url = config_lock.lock().await.app.database_url.clone();
+    let database = match ShDatabase::connect(&database_url).await {
+        Ok(database) => database,
+        Err(e) => {
+            error!("Error opening database {database_url}: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    match database.close_open_emergencies(processor::now()).await {
+        Ok(0) => (),
+        Ok(closed) => info!("Ended {closed} emergencies left going by the last run"),
+        Err(e) => error!("Error ending emergencies from the last run: {e}"),
+    }
+
+    let (delivery_tx, delivery_rx) = mpsc::channel(DELIVERY_CHANNEL_CAPACITY);
+    let metrics = Arc::new(ShMetrics::new());
+    watch_queue(&metrics, "deliveries", &delivery_tx);
+
+    let config = config_lock.lock().await;
+    let state = Arc::new(ShHubState::new(
+        config.clone(),
+        ShLookup::new(&config.lookup, &config.app.data_path),
+        GroundStationRegistry::new(&config.lookup, &config.app.data_path),
+        database,
+        Notifier::new(config.notifications.clone(), delivery_tx),
+        metrics,
+    ));
+
+    let targets = config.notifications.target_names();
+    if let Err(e) = AlertEngine::validate(&config.alerts.rules, &targets) {
+        warn!("Problems with the alert rules in the config: {e}");
+    }
+    state.set_alert_rules(&config.alerts.rules).await;
+    state
+        .sources
+        .set_silence_timeout(config.data_sources.silence_timeout);
+    drop(config);
+
+    (state, delivery_rx)
+}
+
+/// Report how full a channel is on /metrics, without keeping it open
+fn watch_queue<T: Send + 'static>(metrics: &ShMetrics, queue: &str, sender: &mpsc::Sender<T>) {
+    let sender = sender.downgrade();
+    metrics.watch_queue(queue, move || {
+        sender
+            .upgrade()
+            .map_or(0, |sender| sender.max_capacity() - sender.capacity())
+    });
+}
+
+/// Log what is wrong with the config, the way it would have been logged had it stopped
+/// something
+fn log_issues(issues: impl IntoIterator<Item = ShConfigIssue>) {
+    for issue in issues {
+        if issue.is_error() {
+            error!("Config {issue}");
+        } else {
+            warn!("Config {issue}");
+        }
+    }
+}
+
+/// Say what was done to bring an older config file up to date
+fn log_migration(migration: &ShConfigMigration) {
+    info!(
+        "Config upgraded from version {} to {}",
+        migration.from, migration.to
+    );
+    for change in &migration.changes {
+        info!("Config {change}");
+    }
+}
+
+/// Run a client for every source in the config, and only those. Sources that are already
+/// running are left alone, and ones that had been given up on are tried again. They send what
+/// they receive on `source_tx`
+fn sync_sources(
+    consumers: &mut Supervisor,
+    state: &ShHubState,
+    source_tx: &mpsc::Sender<ShSourceData>,
+    config: &ShConfig,
+) {
+    let adsb_sources = config.data_sources.adsb_sources.addresses();
+    let acars_routers = config.data_sources.acars_routers.addresses();
+
+    for key in consumers.keys() {
+        let wanted = match &key {
+            ConsumerKey::ApiServer => continue,
+            ConsumerKey::Adsb(source) => adsb_sources
+                .iter()
+                .any(|address| address.to_string() == *source),
+            ConsumerKey::AcarsRouter(source) => acars_routers
+                .iter()
+                .any(|address| address.to_string() == *source),
+        };
+
+        if !wanted {
+            consumers.stop(&key);
+            if let ConsumerKey::Adsb(source) | ConsumerKey::AcarsRouter(source) = &key {
+                state.sources.remove(source);
+            }
+        }
+    }
+
+    for address in adsb_sources {
+        let key = ConsumerKey::Adsb(address.to_string());
+        if consumers.contains(&key) {
+            consumers.retry(&key);
+            continue;
+        }
+
+        state.sources.add(&address.to_string(), ShSourceKind::Adsb);
+        consumers.start(
+            key,
+            Arc::new(ShAdsbClient::new(
+                address.clone(),
+                source_tx.clone(),
+                Arc::clone(&state.metrics),
+                Arc::clone(&state.sources),
+            )),
+        );
+    }
+
+    for address in acars_routers {
+        let key = ConsumerKey::AcarsRouter(address.to_string());
+        if consumers.contains(&key) {
+            consumers.retry(&key);
+            continue;
+        }
+
+        state
+            .sources
+            .add(&address.to_string(), ShSourceKind::AcarsRouter);
+        consumers.start(
+            key,
+            Arc::new(ShAcarsRouterClient::new(
+                address.clone(),
+                source_tx.clone(),
+                Arc::clone(&state.metrics),
+                Arc::clone(&state.sources),
+            )),
+        );
+    }
+}
+
+/// Apply whatever changed between `old` and `new`, touching only what the change affects
+async fn apply_config(
+    consumers: &mut Supervisor,
+    state: &ShHubState,
+    source_tx: &mpsc::Sender<ShSourceData>,
+    old: &ShConfig,
+    new: &ShConfig,
+) {
+    // only what the change brought in, the rest has already been logged
+    let known = old.issues();
+    log_issues(
+        new.issues()
+            .into_iter()
+            .filter(|issue| !known.contains(issue)),
+    );
+
+    if old.app.log_level != new.app.log_level {
+        new.set_log_level();
+        info!("Log level is now {}", new.app.log_level);
+    }
+
+    if old.app.database_url != new.app.database_url
+        || old.app.data_path != new.app.data_path
+        || old.lookup != new.lookup
+    {
+        warn!("Changes to the database, data path and lookups are used after a restart");
+    }
+
+    if old.data_sources != new.data_sources || old.enabled_data_sources != new.enabled_data_sources
+    {
+        state
+            .sources
+            .set_silence_timeout(new.data_sources.silence_timeout);
+        sync_sources(consumers, state, source_tx, new);
+    }
+
+    if old.alerts != new.alerts {
+        state.set_alert_rules(&new.alerts.rules).await;
+    }
+
+    if old.notifications != new.notifications {
+        state.notifier.set_config(new.notifications.clone()).await;
+    }
+
+    if old.server != new.server || old.auth != new.auth {
+        consumers.restart(&ConsumerKey::ApiServer);
+    }
+}
 
 pub struct SdreHub {
     config: std::sync::Arc<Mutex<ShConfig>>,
-    data_users: ShDataUserList,
 }
 
 impl SdreHub {
@@ -43,18 +267,20 @@ impl SdreHub {
     pub fn new(config: ShConfig) -> Self {
         Self {
             config: std::sync::Arc::new(Mutex::new(config)),
-            data_users: Vec::new(),
         }
     }
 
     /// # Errors
-    /// - Error starting consumer: {e}
-    pub async fn run(mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// A consumer the hub can't do without, like the API server, kept failing
+    pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
         // get the config lock
         let config_lock = Arc::clone(&self.config);
 
         // init logging and stuff
         config_lock.lock().await.enable_logging();
+        if let Some(migration) = &config_lock.lock().await.migration {
+            log_migration(migration);
+        }
         let value = config_lock.lock().await.write_config();
         match value {
             Ok(()) => {}
@@ -64,42 +290,55 @@ impl SdreHub {
             }
         }
         config_lock.lock().await.show_config();
+        log_issues(config_lock.lock().await.issues());
 
-        // Start the web server
+        let (state, mut delivery_rx) = build_state(&config_lock).await;
 
-        let mut consumer_set = JoinSet::new();
+        // the config as it was last applied, to work out what each change changed
+        let mut config_updates = state.watch_config();
+        let mut applied = config_updates.borrow_and_update().clone();
 
-        // lets generate the consumers
-
-        self.data_users.push(Box::new(ShAPIServer::new()));
+        // kept, rather than left to the sources, so sources can be added as the config changes
+        let (source_tx, mut source_rx) = mpsc::channel::<ShSourceData>(SOURCE_CHANNEL_CAPACITY);
+        watch_queue(&state.metrics, "sources", &source_tx);
 
         debug!("Starting consumers");
 
-        for fut in self.data_users {
-            // if the kind of ShDataUser is ShAPIServer, then we can spawn it
-            let config = match fut.get_server_type() {
-                ServerType::WebSocket => {
-                    // Start the web server
-                    Some(Arc::clone(&self.config))
+        let mut consumers = Supervisor::new(
+            Arc::clone(&self.config),
+            Arc::clone(&state.consumers),
+            Arc::clone(&state.metrics),
+        );
+        consumers.start(
+            ConsumerKey::ApiServer,
+            Arc::new(ShAPIServer::new(Arc::clone(&state))),
+        );
+        sync_sources(&mut consumers, &state, &source_tx, &applied);
+
+        let mut save_interval = tokio::time::interval(SAVE_INTERVAL);
+
+        loop {
+            tokio::select! {
+                Some(data) = source_rx.recv() => {
+                    processor::process_source_data(&state, &applied.enabled_data_sources, data).await;
+                }
+                Some(delivery) = delivery_rx.recv() => {
+                    processor::record_delivery(&state, delivery).await;
                 }
-                ServerType::Other => None,
-            };
-
-            consumer_set.spawn(tokio::spawn(async move {
-                match fut.start(config).await {
-                    Ok(()) => {}
-                    Err(e) => {
-                        error!("Error starting consumer: {e}");
+                Ok(()) = config_updates.changed() => {
+                    let config = config_updates.borrow_and_update().clone();
+                    apply_config(&mut consumers, &state, &source_tx, &applied, &config).await;
+                    applied = config;
+                }
+                supervised = consumers.supervise() => {
+                    match supervised {
+                        Some(Ok(())) => {}
+                        Some(Err(e)) => return Err(e.into()),
+                        None => break,
                     }
                 }
-            }));
-        }
-
-        while let Some(res) = consumer_set.join_next().await {
-            match res {
-                Ok(_) => {}
-                Err(e) => {
-                    error!("Error starting consumer: {e}");
+                _ = save_interval.tick() => {
+                    processor::housekeeping(&state).await;
                 }
             }
         }
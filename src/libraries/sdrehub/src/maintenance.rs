@@ -0,0 +1,219 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Jobs the command line runs on the hub's data without starting the hub. Messages are moved in
+// and out as JSON Lines, one message as the hub stores it per line, and can be brought over from
+// docker-acarshub. Exports can also be CSV, for spreadsheets
+
+use std::error::Error;
+use std::io::{BufRead, Write};
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::api::{ShExportFormat, ShMessageQuery};
+use sh_common::auth::ShLogin;
+use sh_common_server::export::ShMessageExport;
+use sh_config::ShConfig;
+use sh_database::acarshub::AcarshubDatabase;
+use sh_database::ShDatabase;
+use sh_lookup::ShLookup;
+
+// Messages read from a database at a time
+const PAGE_SIZE: i64 = 1000;
+
+/// What came of an import
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Already in the database, so they were left out
+    pub duplicates: usize,
+    /// Messages that couldn't be read, and why
+    pub unreadable: Vec<String>,
+}
+
+async fn connect(config: &ShConfig) -> Result<ShDatabase, Box<dyn Error>> {
+    let url = &config.app.database_url;
+    ShDatabase::connect(url)
+        .await
+        .map_err(|e| format!("Error opening database {url}: {e}").into())
+}
+
+/// Create the first account, an admin, for a hub whose web interface is only used from other
+/// machines
+///
+/// # Errors
+/// There are accounts already, or the username or password won't do
+pub async fn create_admin(config: &ShConfig, login: &ShLogin) -> Result<(), Box<dyn Error>> {
+    Ok(sh_api::create_admin(config, login).await?)
+}
+
+/// Bring the database schema up to date. Returns the migrations that were applied and the
+/// version the schema is at now
+///
+/// # Errors
+/// The database couldn't be opened, or a migration failed
+pub async fn migrate_database(
+    config: &ShConfig,
+) -> Result<(Vec<String>, Option<i64>), Box<dyn Error>> {
+    let url = &config.app.database_url;
+    let database = ShDatabase::open(url)
+        .await
+        .map_err(|e| format!("Error opening database {url}: {e}"))?;
+
+    let applied = database.migrate().await?;
+    Ok((applied, database.schema_version().await?))
+}
+
+/// Give the space left by deleted rows back. Returns the size of the database before and after,
+/// in bytes
+///
+/// # Errors
+/// The database couldn't be opened or vacuumed
+pub async fn vacuum_database(config: &ShConfig) -> Result<(i64, i64), Box<dyn Error>> {
+    let database = connect(config).await?;
+    Ok(database.vacuum().await?)
+}
+
+/// The key the hub files `flight` under, so searches match the way messages were stored
+fn flight_key(lookup: &ShLookup, flight: &str) -> String {
+    lookup
+        .normalize_flight(flight)
+        .unwrap_or_else(|| flight.trim().to_uppercase())
+}
+
+/// Write the messages matching `query` to `output`, oldest first. With `with_aircraft`, what is
+/// known about each message's aircraft goes with it. Returns how many were written
+///
+/// # Errors
+/// The database couldn't be read, or `output` couldn't be written to
+pub async fn export_messages(
+    config: &ShConfig,
+    mut query: ShMessageQuery,
+    format: ShExportFormat,
+    with_aircraft: bool,
+    output: &mut (impl Write + Send),
+) -> Result<usize, Box<dyn Error>> {
+    let database = connect(config).await?;
+    if let Some(flight) = &query.flight {
+        let lookup = ShLookup::new(&config.lookup, &config.app.data_path);
+        query.flight = Some(flight_key(&lookup, flight));
+    }
+
+    let mut export = ShMessageExport::new(query, format, with_aircraft);
+    while let Some(chunk) = export.next_chunk(&database).await? {
+        output.write_all(&chunk)?;
+    }
+
+    output.flush()?;
+    Ok(export.exported())
+}
+
+/// Stores messages the same way the hub does as they come in, leaving out ones it already has
+struct Importer {
+    database: ShDatabase,
+    lookup: ShLookup,
+    report: ImportReport,
+}
+
+impl Importer {
+    async fn new(config: &ShConfig) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            database: connect(config).await?,
+            lookup: ShLookup::new(&config.lookup, &config.app.data_path),
+            report: ImportReport::default(),
+        })
+    }
+
+    async fn add(&mut self, message: &ShAcarsMessage) -> Result<(), Box<dyn Error>> {
+        if self.database.has_message(message).await? {
+            self.report.duplicates += 1;
+            return Ok(());
+        }
+
+        let flight = message
+            .flight
+            .as_deref()
+            .map(|flight| flight_key(&self.lookup, flight));
+        self.database.add_message(message, flight).await?;
+        self.report.imported += 1;
+
+        Ok(())
+    }
+}
+
+/// Add the messages in `input` to the database, building flights from them as if they had just
+/// come in. Messages that are already stored are skipped, so importing the same file twice is
+/// harmless
+///
+/// # Errors
+/// The database couldn't be opened or written to, or `input` couldn't be read
+pub async fn import_messages(
+    config: &ShConfig,
+    input: impl BufRead + Send,
+) -> Result<ImportReport, Box<dyn Error>> {
+    let mut importer = Importer::new(config).await?;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<ShAcarsMessage>(&line) {
+            Ok(message) => importer.add(&message).await?,
+            Err(e) => importer
+                .report
+                .unreadable
+                .push(format!("line {}: {e}", index + 1)),
+        }
+    }
+
+    Ok(importer.report)
+}
+
+/// Add the messages in docker-acarshub's `messages.db` at `path` to the database, the same way
+/// as `import_messages`. With `dry_run` nothing is stored, and `imported` is how many would
+/// have been
+///
+/// # Errors
+/// Either database couldn't be opened, or the messages couldn't be read or stored
+pub async fn import_acarshub_messages(
+    config: &ShConfig,
+    path: &str,
+    dry_run: bool,
+) -> Result<ImportReport, Box<dyn Error>> {
+    let acarshub = AcarshubDatabase::open(path)
+        .await
+        .map_err(|e| format!("Error opening acarshub database {path}: {e}"))?;
+    let mut importer = if dry_run {
+        None
+    } else {
+        Some(Importer::new(config).await?)
+    };
+    let mut report = ImportReport::default();
+    let mut after = 0;
+
+    loop {
+        let (messages, next) = acarshub.messages_after(after, PAGE_SIZE).await?;
+        let Some(next) = next else {
+            break;
+        };
+
+        for message in messages {
+            match (message, importer.as_mut()) {
+                (Ok(message), Some(importer)) => importer.add(&message).await?,
+                (Ok(_), None) => report.imported += 1,
+                (Err(e), _) => report.unreadable.push(e),
+            }
+        }
+        after = next;
+    }
+
+    if let Some(importer) = importer {
+        report.imported = importer.report.imported;
+        report.duplicates = importer.report.duplicates;
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,306 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Everything the sources produce comes through here on its way to the app state
+// and out to the users.
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::alert::ShAlert;
+use sh_common::emergency::ShEmergency;
+use sh_common::flight::ShFlightSessionChange;
+use sh_common::ground_station::ShGroundStation;
+use sh_common::notification::ShNotificationDelivery;
+use sh_common::{MessageData, ServerMessageTypes, ServerWssMessage};
+use sh_common_server::source_data::ShSourceData;
+use sh_common_server::state::ShHubState;
+use sh_config::source::{EnabledDataSources, ShEnabledDataSources};
+use std::future::Future;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+/// Run a database write, recording how long it took
+async fn timed_write<T>(state: &ShHubState, operation: &str, write: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    let result = write.await;
+    state.metrics.database_write(operation, started.elapsed());
+    result
+}
+
+pub async fn process_source_data(
+    state: &ShHubState,
+    enabled: &EnabledDataSources,
+    data: ShSourceData,
+) {
+    match data {
+        ShSourceData::AcarsMessage(message) => process_message(state, enabled, *message).await,
+        ShSourceData::GroundStation(station) => process_ground_station(state, station).await,
+        ShSourceData::AdsbPosition(position) => {
+            process_adsb_position(state, enabled, &position).await;
+        }
+    }
+}
+
+/// Save what we have learned and forget what is too old to be useful
+pub async fn housekeeping(state: &ShHubState) {
+    let saved = state.ground_stations.write().await.save_learned();
+    if let Err(e) = saved {
+        error!("Error saving learned ground stations: {e}");
+    }
+
+    let now = now();
+    state.sources.check_silence(now);
+    state.timelines.write().await.prune(now);
+    state.weather.write().await.prune(now);
+    state.alerts.write().await.prune(now);
+    let ended = state.emergencies.write().await.prune(now);
+    record_emergencies(state, ended).await;
+    state.database.forget_idle_aircraft(now).await;
+
+    let aircraft = state.timelines.read().await.aircraft_tracked(now);
+    state.stats.write().await.sample_aircraft(now, aircraft);
+    state.flush_stats().await;
+    if let Err(e) = state.database.prune_stats(now).await {
+        error!("Error removing old statistics: {e}");
+    }
+}
+
+async fn process_message(
+    state: &ShHubState,
+    enabled: &EnabledDataSources,
+    mut message: ShAcarsMessage,
+) {
+    if !enabled.is_enabled(&message.message_type.source_type()) {
+        trace!(
+            "Dropping {} message from {} as the source type is not enabled",
+            message.message_type,
+            message.source
+        );
+        return;
+    }
+
+    let updated_station = state
+        .ground_stations
+        .write()
+        .await
+        .observe_message(&mut message);
+
+    if let Some(station) = updated_station {
+        broadcast_ground_station(state, station);
+    }
+
+    state.stats.write().await.add_message(&message);
+
+    let flight = message
+        .flight
+        .as_deref()
+        .map(|flight| state.flight_key(flight));
+    let events = sh_parsers::oooi::extract(&message);
+    for event in &events {
+        debug!(
+            "{} reported {} at {}",
+            flight.as_deref().unwrap_or("Unknown flight"),
+            event.event_type,
+            event.time
+        );
+    }
+    state
+        .timelines
+        .write()
+        .await
+        .add_message(flight.clone(), &message, events);
+
+    let alerts = state
+        .alerts
+        .read()
+        .await
+        .check_message(&message, flight.as_deref());
+    raise_alerts(state, alerts).await;
+
+    let emergencies = state.emergencies.write().await.check_message(&message);
+    record_emergencies(state, emergencies).await;
+
+    match timed_write(
+        state,
+        "message",
+        state.database.add_message(&message, flight),
+    )
+    .await
+    {
+        Ok(change) => broadcast_flight_sessions(state, change),
+        Err(e) => error!("Error storing message: {e}"),
+    }
+
+    for product in sh_parsers::weather::extract(&message) {
+        let added = state.weather.write().await.add(product.clone());
+        if added {
+            debug!("Heard {} for {}", product.product_type, product.airport);
+            state.broadcast(ServerWssMessage::new(
+                ServerMessageTypes::ServerNewWeather,
+                MessageData::ShWeatherProduct(product),
+            ));
+        }
+    }
+
+    state.add_recent_message(message.clone()).await;
+    state.broadcast(ServerWssMessage::new(
+        ServerMessageTypes::ServerNewAcarsMessage,
+        MessageData::ShAcarsMessage(Box::new(message)),
+    ));
+}
+
+async fn process_adsb_position(
+    state: &ShHubState,
+    enabled: &EnabledDataSources,
+    position: &ShAdsbPosition,
+) {
+    if !enabled.is_enabled(&ShEnabledDataSources::Adsb) {
+        return;
+    }
+
+    let flight = position
+        .callsign
+        .as_deref()
+        .map(|callsign| state.flight_key(callsign));
+
+    state
+        .timelines
+        .write()
+        .await
+        .add_position(flight.clone(), position);
+
+    let alerts = state
+        .alerts
+        .write()
+        .await
+        .check_position(position, flight.as_deref());
+    raise_alerts(state, alerts).await;
+
+    let emergencies = state.emergencies.write().await.check_position(position);
+    record_emergencies(state, emergencies).await;
+
+    match timed_write(
+        state,
+        "position",
+        state.database.add_position(position, flight),
+    )
+    .await
+    {
+        Ok(change) => broadcast_flight_sessions(state, change),
+        Err(e) => error!("Error storing position: {e}"),
+    }
+}
+
+async fn process_ground_station(state: &ShHubState, station: ShGroundStation) {
+    let updated_station = state.ground_stations.write().await.learn(station);
+
+    if let Some(station) = updated_station {
+        debug!("Learned about ground station {}", station.display_name());
+        broadcast_ground_station(state, station);
+    }
+}
+
+async fn raise_alerts(state: &ShHubState, alerts: Vec<ShAlert>) {
+    for mut alert in alerts {
+        info!(
+            "Alert \"{}\" raised by {}: {}",
+            alert.rule,
+            alert.aircraft(),
+            alert.description
+        );
+
+        if let Err(e) = timed_write(state, "alert", state.database.add_alert(&mut alert)).await {
+            error!("Error storing alert: {e}");
+        }
+
+        let targets = state.alerts.read().await.notify_targets(&alert.rule);
+        if !targets.is_empty() {
+            state.notifier.notify(&alert, &targets).await;
+        }
+
+        state.broadcast(ServerWssMessage::new(
+            ServerMessageTypes::ServerAlert,
+            MessageData::ShAlert(Box::new(alert)),
+        ));
+    }
+}
+
+/// Store emergencies that started, changed or ended and let users know
+async fn record_emergencies(state: &ShHubState, emergencies: Vec<ShEmergency>) {
+    for mut emergency in emergencies {
+        let new = emergency.id == 0;
+        if new {
+            warn!(
+                "Emergency: {} from {}",
+                emergency.kind,
+                emergency.aircraft()
+            );
+        } else if !emergency.is_active() {
+            info!(
+                "Emergency over: {} from {}",
+                emergency.kind,
+                emergency.aircraft()
+            );
+        }
+
+        match timed_write(
+            state,
+            "emergency",
+            state.database.save_emergency(&mut emergency),
+        )
+        .await
+        {
+            Ok(()) if new => state.emergencies.write().await.stored(&emergency),
+            Ok(()) => (),
+            Err(e) => error!("Error storing emergency: {e}"),
+        }
+
+        state.broadcast(ServerWssMessage::new(
+            ServerMessageTypes::ServerEmergency,
+            MessageData::ShEmergency(Box::new(emergency)),
+        ));
+    }
+}
+
+/// Log how sending an alert out went, and let users know
+pub async fn record_delivery(state: &ShHubState, mut delivery: ShNotificationDelivery) {
+    if let Err(e) = timed_write(
+        state,
+        "delivery",
+        state.database.add_delivery(&mut delivery),
+    )
+    .await
+    {
+        error!("Error storing notification delivery: {e}");
+    }
+
+    state.broadcast(ServerWssMessage::new(
+        ServerMessageTypes::ServerNotificationDelivery,
+        MessageData::ShNotificationDelivery(delivery),
+    ));
+}
+
+fn broadcast_ground_station(state: &ShHubState, station: ShGroundStation) {
+    state.broadcast(ServerWssMessage::new(
+        ServerMessageTypes::ServerGroundStationUpdate,
+        MessageData::ShGroundStation(station),
+    ));
+}
+
+fn broadcast_flight_sessions(state: &ShHubState, change: ShFlightSessionChange) {
+    if change.is_empty() {
+        return;
+    }
+
+    state.broadcast(ServerWssMessage::new(
+        ServerMessageTypes::ServerFlightSessionUpdate,
+        MessageData::ShFlightSessionChange(change),
+    ));
+}
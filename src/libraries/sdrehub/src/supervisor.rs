@@ -0,0 +1,414 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// The consumers the hub is running, kept by what they are so the ones a config change
+// affects can be found and stopped or restarted. Consumers that stop on their own, panics
+// included, are started again as their restart policy says, waiting longer each time, and
+// one that keeps stopping is given up on
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use sh_common::consumer_status::{ShConsumerState, ShRestartPolicy};
+use sh_common::ServerType;
+use sh_common_server::consumers::ConsumerMonitor;
+use sh_common_server::metrics::ShMetrics;
+use sh_common_server::ShDataUser;
+use sh_config::ShConfig;
+use tokio::sync::Mutex;
+use tokio::task::{AbortHandle, JoinError, JoinSet};
+use tokio::time::Instant;
+
+// The first wait before starting a consumer again, doubled each time it stops in a row
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// Running this long means it had recovered, so the next wait starts short again
+const STABLE_RUN: Duration = Duration::from_secs(60);
+// Stopping this many times inside the window is a crash loop
+const CRASH_LOOP_STOPS: usize = 5;
+const CRASH_LOOP_WINDOW: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ConsumerKey {
+    ApiServer,
+    Adsb(String),
+    AcarsRouter(String),
+}
+
+impl ConsumerKey {
+    /// The web server should always be there. Sources only return without an error once the
+    /// hub is going away
+    const fn restart_policy(&self) -> ShRestartPolicy {
+        match self {
+            Self::ApiServer => ShRestartPolicy::Always,
+            Self::Adsb(_) | Self::AcarsRouter(_) => ShRestartPolicy::OnFailure,
+        }
+    }
+
+    /// Nothing can be seen or changed without the web server, so the hub gives up with it
+    const fn is_essential(&self) -> bool {
+        matches!(self, Self::ApiServer)
+    }
+}
+
+impl Display for ConsumerKey {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::ApiServer => write!(f, "API server"),
+            Self::Adsb(source) => write!(f, "ADS-B source {source}"),
+            Self::AcarsRouter(source) => write!(f, "acars_router {source}"),
+        }
+    }
+}
+
+pub type Consumer = Arc<dyn ShDataUser + Send + Sync>;
+
+struct Supervised {
+    consumer: Consumer,
+    policy: ShRestartPolicy,
+    // None once it has been left stopped
+    task: Option<AbortHandle>,
+    // When the current run started, or will once the backoff is over
+    started: Instant,
+    backoff: Duration,
+    stops: VecDeque<Instant>,
+}
+
+impl Supervised {
+    fn waiting(&self) -> bool {
+        self.task.is_some() && self.started > Instant::now()
+    }
+}
+
+fn panic_message(error: JoinError) -> String {
+    match error.try_into_panic() {
+        Ok(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| (*message).to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "no message".to_string());
+            format!("Panicked: {message}")
+        }
+        Err(error) => error.to_string(),
+    }
+}
+
+pub struct Supervisor {
+    config: Arc<Mutex<ShConfig>>,
+    monitor: Arc<ConsumerMonitor>,
+    metrics: Arc<ShMetrics>,
+    running: HashMap<ConsumerKey, Supervised>,
+    tasks: JoinSet<Result<(), String>>,
+}
+
+impl Supervisor {
+    pub fn new(
+        config: Arc<Mutex<ShConfig>>,
+        monitor: Arc<ConsumerMonitor>,
+        metrics: Arc<ShMetrics>,
+    ) -> Self {
+        Self {
+            config,
+            monitor,
+            metrics,
+            running: HashMap::new(),
+            tasks: JoinSet::new(),
+        }
+    }
+
+    /// Run `consumer` on its own task once `delay` is up. A panic ends the task rather than
+    /// the hub
+    fn spawn(&mut self, key: &ConsumerKey, consumer: Consumer, delay: Duration) -> AbortHandle {
+        // only the web server gets to see and change the config
+        let config = match consumer.get_server_type() {
+            ServerType::WebSocket => Some(Arc::clone(&self.config)),
+            ServerType::Other => None,
+        };
+        let monitor = Arc::clone(&self.monitor);
+        let name = key.to_string();
+
+        self.tasks.spawn(async move {
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+                info!("Starting {name} again");
+                monitor.running(&name);
+            }
+
+            consumer.start(config).await.map_err(|e| e.to_string())
+        })
+    }
+
+    pub fn start(&mut self, key: ConsumerKey, consumer: Consumer) {
+        debug!("Starting {key}");
+
+        let policy = key.restart_policy();
+        self.monitor.add(&key.to_string(), policy);
+        let task = self.spawn(&key, Arc::clone(&consumer), Duration::ZERO);
+
+        let supervised = Supervised {
+            consumer,
+            policy,
+            task: Some(task),
+            started: Instant::now(),
+            backoff: INITIAL_BACKOFF,
+            stops: VecDeque::new(),
+        };
+        if let Some(replaced) = self.running.insert(key, supervised) {
+            replaced.consumer.stop();
+        }
+    }
+
+    pub fn contains(&self, key: &ConsumerKey) -> bool {
+        self.running.contains_key(key)
+    }
+
+    pub fn keys(&self) -> Vec<ConsumerKey> {
+        self.running.keys().cloned().collect()
+    }
+
+    /// Stop a consumer for good. It finishes up in the background
+    pub fn stop(&mut self, key: &ConsumerKey) {
+        if let Some(supervised) = self.running.remove(key) {
+            info!("Stopping {key}");
+            supervised.consumer.stop();
+
+            // one waiting to be started again has nothing to finish
+            if supervised.waiting() {
+                if let Some(task) = &supervised.task {
+                    task.abort();
+                }
+            }
+
+            self.monitor.remove(&key.to_string());
+        }
+    }
+
+    /// Restart a running consumer. One that has been left stopped gets started again
+    pub fn restart(&mut self, key: &ConsumerKey) {
+        let Some(supervised) = self.running.get(key) else {
+            return;
+        };
+
+        if supervised.task.is_some() {
+            info!("Restarting {key}");
+            supervised.consumer.restart();
+        } else {
+            self.start_again(key);
+        }
+    }
+
+    /// Give a consumer that has been left stopped another go, with a clean slate. Running
+    /// consumers are left alone
+    pub fn retry(&mut self, key: &ConsumerKey) {
+        if self
+            .running
+            .get(key)
+            .is_some_and(|supervised| supervised.task.is_none())
+        {
+            self.start_again(key);
+        }
+    }
+
+    fn start_again(&mut self, key: &ConsumerKey) {
+        let Some(supervised) = self.running.get(key) else {
+            return;
+        };
+
+        info!("Starting {key} again");
+        let consumer = Arc::clone(&supervised.consumer);
+        self.monitor.add(&key.to_string(), supervised.policy);
+        let task = self.spawn(key, consumer, Duration::ZERO);
+
+        if let Some(supervised) = self.running.get_mut(key) {
+            supervised.task = Some(task);
+            supervised.started = Instant::now();
+            supervised.backoff = INITIAL_BACKOFF;
+            supervised.stops.clear();
+        }
+    }
+
+    /// Wait for a consumer to stop and deal with it. Gives back nothing once there are no
+    /// consumers left at all, and an error if one the hub can't do without has been left
+    /// stopped
+    pub async fn supervise(&mut self) -> Option<Result<(), String>> {
+        let (id, outcome) = match self.tasks.join_next_with_id().await? {
+            Ok((id, outcome)) => (id, outcome),
+            // aborted by `stop`
+            Err(e) if e.is_cancelled() => return Some(Ok(())),
+            Err(e) => (e.id(), Err(panic_message(e))),
+        };
+
+        // consumers that were stopped on purpose aren't looked after any more, but the rest are
+        let Some(key) = self
+            .running
+            .iter()
+            .find(|(_, supervised)| supervised.task.as_ref().is_some_and(|task| task.id() == id))
+            .map(|(key, _)| key.clone())
+        else {
+            return Some(Ok(()));
+        };
+
+        Some(self.stopped(&key, outcome))
+    }
+
+    fn stopped(&mut self, key: &ConsumerKey, outcome: Result<(), String>) -> Result<(), String> {
+        let name = key.to_string();
+        let Some(supervised) = self.running.get_mut(key) else {
+            return Ok(());
+        };
+
+        let error = outcome.err();
+        match &error {
+            Some(e) => error!("{name} failed: {e}"),
+            None => warn!("{name} has stopped"),
+        }
+
+        let restart = match supervised.policy {
+            ShRestartPolicy::Always => true,
+            ShRestartPolicy::OnFailure => error.is_some(),
+            ShRestartPolicy::Never => false,
+        };
+
+        let now = Instant::now();
+        supervised.stops.push_back(now);
+        while supervised
+            .stops
+            .front()
+            .is_some_and(|stop| now.duration_since(*stop) > CRASH_LOOP_WINDOW)
+        {
+            supervised.stops.pop_front();
+        }
+
+        let left_as = if !restart {
+            Some(if error.is_some() {
+                ShConsumerState::Failed
+            } else {
+                ShConsumerState::Stopped
+            })
+        } else if supervised.stops.len() >= CRASH_LOOP_STOPS {
+            error!(
+                "{name} has stopped {CRASH_LOOP_STOPS} times in {} minutes, giving up on it",
+                CRASH_LOOP_WINDOW.as_secs() / 60
+            );
+            Some(ShConsumerState::CrashLooping)
+        } else {
+            None
+        };
+
+        if let Some(state) = left_as {
+            supervised.task = None;
+            let failed = state != ShConsumerState::Stopped;
+            self.monitor.ended(&name, state, error);
+
+            return if failed && key.is_essential() {
+                Err(format!(
+                    "{name} can't be kept running, and the hub can't do without it"
+                ))
+            } else {
+                Ok(())
+            };
+        }
+
+        if now.duration_since(supervised.started) >= STABLE_RUN {
+            supervised.backoff = INITIAL_BACKOFF;
+        }
+        let delay = supervised.backoff;
+        supervised.backoff = (delay * 2).min(MAX_BACKOFF);
+        supervised.started = now + delay;
+        let consumer = Arc::clone(&supervised.consumer);
+
+        info!("Starting {name} again in {} seconds", delay.as_secs());
+        self.monitor.restarting(&name, error, delay);
+        self.metrics.consumer_restarted(&name);
+
+        let task = self.spawn(key, consumer, delay);
+        if let Some(supervised) = self.running.get_mut(key) {
+            supervised.task = Some(task);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use tokio::sync::{broadcast, Notify};
+
+    /// Runs until it is told to stop
+    struct Waiting {
+        stopped: Notify,
+    }
+
+    #[async_trait]
+    impl ShDataUser for Waiting {
+        async fn start(
+            &self,
+            _data: Option<Arc<Mutex<ShConfig>>>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            self.stopped.notified().await;
+            Ok(())
+        }
+
+        fn stop(&self) {
+            self.stopped.notify_one();
+        }
+
+        fn restart(&self) {}
+
+        fn get_server_type(&self) -> ServerType {
+            ServerType::Other
+        }
+    }
+
+    fn supervisor() -> Supervisor {
+        let (server_messages, _) = broadcast::channel(16);
+        Supervisor::new(
+            Arc::new(Mutex::new(ShConfig::default())),
+            Arc::new(ConsumerMonitor::new(server_messages)),
+            Arc::new(ShMetrics::new()),
+        )
+    }
+
+    fn waiting() -> Consumer {
+        Arc::new(Waiting {
+            stopped: Notify::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn stopping_a_consumer_keeps_supervising_the_rest() {
+        let mut supervisor = supervisor();
+        let removed = ConsumerKey::Adsb("removed:30003".to_string());
+        let kept = ConsumerKey::Adsb("kept:30003".to_string());
+        supervisor.start(removed.clone(), waiting());
+        supervisor.start(kept.clone(), waiting());
+
+        supervisor.stop(&removed);
+        let supervised = tokio::time::timeout(Duration::from_secs(5), supervisor.supervise())
+            .await
+            .expect("the stopped consumer should finish");
+        assert_eq!(supervised, Some(Ok(())));
+
+        // the kept one is still running, so there is nothing more to report yet
+        assert!(supervisor.contains(&kept));
+        assert!(!supervisor.contains(&removed));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), supervisor.supervise())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_left_to_supervise() {
+        let mut supervisor = supervisor();
+        assert_eq!(supervisor.supervise().await, None);
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// With the `embed-frontend` feature, builds a list of everything trunk put in `dist` so the
+// files can be built into the binary. `SH_FRONTEND_DIST` points somewhere else if needed
+
+use std::env;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=SH_FRONTEND_DIST");
+
+    if env::var_os("CARGO_FEATURE_EMBED_FRONTEND").is_none() {
+        return;
+    }
+
+    let dist = env::var_os("SH_FRONTEND_DIST").map_or_else(
+        || {
+            Path::new(&env::var("CARGO_MANIFEST_DIR").expect("cargo should set the manifest dir"))
+                .join("../../../sh-frontend/dist")
+        },
+        PathBuf::from,
+    );
+    let dist = dist.canonicalize().unwrap_or_else(|e| {
+        panic!(
+            "The frontend should be built with `trunk build --release` before embedding it. Couldn't find {}: {e}",
+            dist.display()
+        )
+    });
+    println!("cargo:rerun-if-changed={}", dist.display());
+
+    let mut files = Vec::new();
+    collect_files(&dist, &mut files);
+    files.sort();
+
+    let mut generated = String::from("pub static EMBEDDED_FILES: &[(&str, &[u8])] = &[\n");
+    for file in &files {
+        let route = file
+            .strip_prefix(&dist)
+            .expect("files should be inside dist")
+            .components()
+            .fold(String::new(), |route, component| {
+                format!("{route}/{}", component.as_os_str().to_string_lossy())
+            });
+        println!("cargo:rerun-if-changed={}", file.display());
+        writeln!(
+            generated,
+            "    ({route:?}, include_bytes!({:?})),",
+            file.display().to_string()
+        )
+        .expect("writing to a string should not fail");
+    }
+    generated.push_str("];\n");
+
+    let out = PathBuf::from(env::var("OUT_DIR").expect("cargo should set the out dir"));
+    std::fs::write(out.join("embedded_frontend.rs"), generated)
+        .expect("the embedded file list should be writable");
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries =
+        std::fs::read_dir(dir).unwrap_or_else(|e| panic!("Couldn't read {}: {e}", dir.display()));
+
+    for entry in entries {
+        let path = entry.expect("directory entries should be readable").path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,608 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Who is allowed to do what. Accounts are kept in `sh_users.toml` in the data path with their
+// passwords hashed, and logging in hands out a token that lasts `session_hours`. Sessions are
+// only kept in memory, so restarting the hub logs everyone out
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sh_common::auth::{ShDownloadToken, ShLogin, ShSession, ShUser, ShUserRole, ShUserUpdate};
+use sh_common::UserMessageTypes;
+use sh_config::auth::ShAuthConfig;
+
+use crate::now;
+
+const USERS_FILE: &str = "sh_users.toml";
+const MIN_PASSWORD_LENGTH: usize = 8;
+// a failed login waits this long before answering, to slow down anyone guessing
+const FAILED_LOGIN_DELAY: Duration = Duration::from_secs(1);
+// how long a download token can wait to be used
+const DOWNLOAD_SECONDS: f64 = 60.0;
+// who connections that don't have to log in act as
+const TRUSTED_USER: &str = "local";
+pub const SETUP_ELSEWHERE: &str =
+    "The admin account can only be created from the hub's own machine, or with sdre-hub create-admin";
+
+#[derive(Serialize, Deserialize, Clone)]
+struct StoredUser {
+    name: String,
+    role: ShUserRole,
+    password_hash: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct UsersFile {
+    #[serde(default)]
+    users: Vec<StoredUser>,
+}
+
+struct Session {
+    user: String,
+    expires: f64,
+}
+
+pub struct Auth {
+    config: ShAuthConfig,
+    path: PathBuf,
+    users: RwLock<Vec<StoredUser>>,
+    // keyed by token
+    sessions: Mutex<HashMap<String, Session>>,
+    // keyed by token, and each taken out when it is used
+    downloads: Mutex<HashMap<String, Session>>,
+}
+
+impl Auth {
+    /// Read the accounts from the data path. No file just means no accounts yet
+    pub fn load(config: &ShAuthConfig, data_path: &str) -> Result<Self, String> {
+        let path = Path::new(data_path).join(USERS_FILE);
+        let users = read_users(&path)?;
+
+        if !config.enabled {
+            warn!("Logging in is turned off. Anyone who can reach the hub can change its settings");
+        } else if users.is_empty() {
+            warn!("There are no user accounts yet. Create the admin account from the web interface on this machine, or with sdre-hub create-admin");
+        } else {
+            info!("Loaded {} user accounts", users.len());
+        }
+
+        Ok(Self {
+            config: config.clone(),
+            path,
+            users: RwLock::new(users),
+            sessions: Mutex::new(HashMap::new()),
+            downloads: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn users_read(&self) -> RwLockReadGuard<'_, Vec<StoredUser>> {
+        self.users.read().expect("users should not be poisoned")
+    }
+
+    fn users_write(&self) -> RwLockWriteGuard<'_, Vec<StoredUser>> {
+        self.users.write().expect("users should not be poisoned")
+    }
+
+    fn sessions(&self) -> MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions
+            .lock()
+            .expect("sessions should not be poisoned")
+    }
+
+    fn downloads(&self) -> MutexGuard<'_, HashMap<String, Session>> {
+        self.downloads
+            .lock()
+            .expect("downloads should not be poisoned")
+    }
+
+    /// Who a connection from `address` acts as without logging in, if it doesn't have to
+    pub fn trusted_user(&self, address: Option<IpAddr>) -> Option<ShUser> {
+        let trusted = !self.config.enabled || (self.config.trust_localhost && is_local(address));
+
+        trusted.then(|| ShUser {
+            name: TRUSTED_USER.to_string(),
+            role: ShUserRole::Admin,
+        })
+    }
+
+    pub fn setup_needed(&self) -> bool {
+        self.users_read().is_empty()
+    }
+
+    fn user(&self, name: &str) -> Option<ShUser> {
+        self.users_read()
+            .iter()
+            .find(|user| user.name == name)
+            .map(|user| ShUser {
+                name: user.name.clone(),
+                role: user.role,
+            })
+    }
+
+    pub fn users(&self) -> Vec<ShUser> {
+        self.users_read()
+            .iter()
+            .map(|user| ShUser {
+                name: user.name.clone(),
+                role: user.role,
+            })
+            .collect()
+    }
+
+    /// Who the session `token` belongs to, if it hasn't ended. The user's role is looked up each
+    /// time, so changes to it take effect straight away
+    pub fn session_user(&self, token: &str) -> Option<ShUser> {
+        let name = self
+            .sessions()
+            .get(token)
+            .filter(|session| session.expires > now())
+            .map(|session| session.user.clone())?;
+
+        self.user(&name)
+    }
+
+    fn start_session(&self, user: ShUser) -> ShSession {
+        let now = now();
+        #[allow(clippy::cast_precision_loss)]
+        let expires = now + (self.config.session_hours * 3600) as f64;
+        let token = new_token();
+
+        let mut sessions = self.sessions();
+        sessions.retain(|_, session| session.expires > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                user: user.name.clone(),
+                expires,
+            },
+        );
+        drop(sessions);
+
+        ShSession {
+            token,
+            user,
+            expires,
+        }
+    }
+
+    /// A token `user` can download one file with
+    pub fn start_download(&self, user: &ShUser) -> ShDownloadToken {
+        let now = now();
+        let expires = now + DOWNLOAD_SECONDS;
+        let token = new_token();
+
+        let mut downloads = self.downloads();
+        downloads.retain(|_, download| download.expires > now);
+        downloads.insert(
+            token.clone(),
+            Session {
+                user: user.name.clone(),
+                expires,
+            },
+        );
+        drop(downloads);
+
+        ShDownloadToken { token, expires }
+    }
+
+    /// Who the download `token` was given to, if it hasn't run out. It can't be used again
+    pub fn download_user(&self, token: &str) -> Option<ShUser> {
+        let name = self
+            .downloads()
+            .remove(token)
+            .filter(|download| download.expires > now())
+            .map(|download| download.user)?;
+
+        self.user(&name)
+    }
+
+    pub async fn login(&self, login: &ShLogin) -> Result<ShSession, String> {
+        let hash = self
+            .users_read()
+            .iter()
+            .find(|user| user.name == login.username)
+            .map(|user| user.password_hash.clone());
+
+        let verified = match hash {
+            Some(hash) => verify_password(login.password.clone(), hash).await,
+            None => false,
+        };
+
+        match self.user(&login.username) {
+            Some(user) if verified => {
+                info!("{} logged in", user.name);
+                Ok(self.start_session(user))
+            }
+            _ => {
+                warn!("Failed login for {}", login.username);
+                tokio::time::sleep(FAILED_LOGIN_DELAY).await;
+                Err("Incorrect username or password".to_string())
+            }
+        }
+    }
+
+    pub fn logout(&self, token: &str) {
+        self.sessions().remove(token);
+    }
+
+    /// Create the first account, as an admin. Only works while there are no accounts. Anyone
+    /// could do this, so the web interface only offers it to connections from this machine
+    pub async fn setup_admin(&self, login: &ShLogin) -> Result<ShSession, String> {
+        if !self.setup_needed() {
+            return Err("The admin account has already been created".to_string());
+        }
+
+        let name = validate_username(&login.username)?;
+        validate_password(&login.password)?;
+        let password_hash = hash_password(login.password.clone()).await?;
+
+        let mut users = self.users_write();
+        // someone else may have got there while the password was being hashed, or made it with
+        // the command line while the hub was running
+        if users.is_empty() {
+            *users = read_users(&self.path)?;
+        }
+        if !users.is_empty() {
+            return Err("The admin account has already been created".to_string());
+        }
+
+        let updated = vec![StoredUser {
+            name: name.clone(),
+            role: ShUserRole::Admin,
+            password_hash,
+        }];
+        self.save(&updated)?;
+        *users = updated;
+        drop(users);
+
+        info!("Created the admin account {name}");
+        Ok(self.start_session(ShUser {
+            name,
+            role: ShUserRole::Admin,
+        }))
+    }
+
+    /// Add a user, or change one's role or password. Gives back what was done
+    pub async fn update_user(&self, update: &ShUserUpdate) -> Result<String, String> {
+        let name = validate_username(&update.name)?;
+        let password_hash = match &update.password {
+            Some(password) => {
+                validate_password(password)?;
+                Some(hash_password(password.clone()).await?)
+            }
+            None => None,
+        };
+
+        let mut users = self.users_write();
+        let mut updated = users.clone();
+        let result = if let Some(user) = updated.iter_mut().find(|user| user.name == name) {
+            user.role = update.role;
+            if let Some(password_hash) = password_hash {
+                user.password_hash = password_hash;
+            }
+            format!("User {name} has been updated.")
+        } else {
+            let Some(password_hash) = password_hash else {
+                return Err(format!("A password is needed for the new user {name}"));
+            };
+            updated.push(StoredUser {
+                name: name.clone(),
+                role: update.role,
+                password_hash,
+            });
+            format!("User {name} has been added.")
+        };
+
+        check_admin_left(&updated)?;
+        self.save(&updated)?;
+        *users = updated;
+        drop(users);
+
+        info!("{result}");
+        Ok(result)
+    }
+
+    /// Remove a user, ending any sessions they have
+    pub fn delete_user(&self, name: &str) -> Result<String, String> {
+        let mut users = self.users_write();
+        let mut updated = users.clone();
+        updated.retain(|user| user.name != name);
+        if updated.len() == users.len() {
+            return Err(format!("There is no user called {name}"));
+        }
+
+        check_admin_left(&updated)?;
+        self.save(&updated)?;
+        *users = updated;
+        drop(users);
+
+        self.sessions().retain(|_, session| session.user != name);
+        info!("Removed user {name}");
+        Ok(format!("User {name} has been removed."))
+    }
+
+    fn save(&self, users: &[StoredUser]) -> Result<(), String> {
+        let file = UsersFile {
+            users: users.to_vec(),
+        };
+        let contents = toml::to_string(&file).map_err(|e| e.to_string())?;
+        std::fs::write(&self.path, contents)
+            .map_err(|e| format!("Error writing {}: {e}", self.path.display()))?;
+
+        // the hashes are nobody else's business
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Err(e) =
+                std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600))
+            {
+                warn!("Error limiting access to {}: {e}", self.path.display());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The role needed to send a message. Nothing means anyone can, logged in or not
+pub const fn required_role(message_type: &UserMessageTypes) -> Option<ShUserRole> {
+    match message_type {
+        UserMessageTypes::UserLogin
+        | UserMessageTypes::UserResumeSession
+        | UserMessageTypes::UserLogout
+        // limited to connections from this machine where it is handled, see `Connection`
+        | UserMessageTypes::UserSetupAdmin => None,
+        UserMessageTypes::UserUpdateAppConfig
+        | UserMessageTypes::UserUpdateMapConfig
+        | UserMessageTypes::UserUpdateDataSources
+        | UserMessageTypes::UserUpdateEnabledDataSources
+        | UserMessageTypes::UserTestSource
+        | UserMessageTypes::UserUpdateAlerts
+        | UserMessageTypes::UserRequestUsers
+        | UserMessageTypes::UserUpdateUser
+        | UserMessageTypes::UserDeleteUser => Some(ShUserRole::Admin),
+        UserMessageTypes::UserRequestConfig
+        | UserMessageTypes::UserRequestFlightInfo
+        | UserMessageTypes::UserRequestRecentMessages
+        | UserMessageTypes::UserRequestGroundStations
+        | UserMessageTypes::UserRequestFlightTimeline
+        | UserMessageTypes::UserRequestWeather
+        | UserMessageTypes::UserRequestFlightSessions
+        | UserMessageTypes::UserRequestFlightSession
+        | UserMessageTypes::UserRequestAlertHistory
+        | UserMessageTypes::UserRequestNotificationLog
+        | UserMessageTypes::UserRequestEmergencies
+        | UserMessageTypes::UserRequestStats
+        | UserMessageTypes::UserRequestSourceStatus
+        | UserMessageTypes::UserRequestConsumerStatus => Some(ShUserRole::Viewer),
+    }
+}
+
+/// Whether `user` can do something that needs `role`. The error says why not
+pub fn check_role(user: Option<&ShUser>, role: ShUserRole) -> Result<(), String> {
+    match user {
+        None => Err("Please log in first".to_string()),
+        Some(user) if user.role < role => Err("Only admins can do that".to_string()),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Who a WebSocket connection is acting as
+pub struct Connection {
+    trusted: Option<ShUser>,
+    // from this machine, so it can create the admin account
+    local: bool,
+    pub token: Option<String>,
+}
+
+impl Connection {
+    pub fn new(auth: &Auth, address: Option<IpAddr>) -> Self {
+        Self {
+            trusted: auth.trusted_user(address),
+            local: is_local(address),
+            token: None,
+        }
+    }
+
+    /// Whether the first account can be created from here. Otherwise whoever reached the hub
+    /// first after it started would get to be its admin
+    pub const fn can_set_up(&self) -> bool {
+        self.trusted.is_some() || self.local
+    }
+
+    /// Checked each time, as sessions end and users get removed. Logging in on a trusted
+    /// connection acts as that user until they log out
+    pub fn user(&self, auth: &Auth) -> Option<ShUser> {
+        self.token
+            .as_deref()
+            .and_then(|token| auth.session_user(token))
+            .or_else(|| self.trusted.clone())
+    }
+}
+
+/// Unix socket connections have no address, so they never count, as they come from a proxy
+fn is_local(address: Option<IpAddr>) -> bool {
+    address.is_some_and(|address| address.to_canonical().is_loopback())
+}
+
+/// The accounts in `path`. No file just means no accounts yet
+fn read_users(path: &Path) -> Result<Vec<StoredUser>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => toml::from_str::<UsersFile>(&contents)
+            .map(|file| file.users)
+            .map_err(|e| format!("Error reading {}: {e}", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Error reading {}: {e}", path.display())),
+    }
+}
+
+fn validate_username(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A username is needed".to_string());
+    }
+    if name == TRUSTED_USER {
+        return Err(format!("The username {TRUSTED_USER} is reserved"));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(format!(
+            "Passwords need to be at least {MIN_PASSWORD_LENGTH} characters"
+        ));
+    }
+    Ok(())
+}
+
+fn check_admin_left(users: &[StoredUser]) -> Result<(), String> {
+    if users.iter().any(|user| user.role == ShUserRole::Admin) {
+        Ok(())
+    } else {
+        Err("There has to be at least one admin".to_string())
+    }
+}
+
+fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+        .iter()
+        .fold(String::with_capacity(64), |mut token, byte| {
+            let _ = write!(token, "{byte:02x}");
+            token
+        })
+}
+
+// hashing is slow on purpose, so it is kept off the async threads
+
+async fn hash_password(password: String) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| format!("Error hashing password: {e}"))
+    })
+    .await
+    .map_err(|e| format!("Error hashing password: {e}"))?
+}
+
+async fn verify_password(password: String, hash: String) -> bool {
+    tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    })
+    .await
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn auth(name: &str) -> Auth {
+        let path = std::env::temp_dir().join(format!("sh-api-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Auth::load(&ShAuthConfig::default(), path.to_str().unwrap()).unwrap()
+    }
+
+    fn login(username: &str) -> ShLogin {
+        ShLogin {
+            username: username.to_string(),
+            password: "correct horse".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_this_machine_can_set_up() {
+        let auth = auth("set-up");
+        let local = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let remote = Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10)));
+
+        assert!(Connection::new(&auth, local).can_set_up());
+        assert!(!Connection::new(&auth, remote).can_set_up());
+        // a proxy on the same machine
+        assert!(!Connection::new(&auth, None).can_set_up());
+    }
+
+    #[test]
+    fn localhost_is_only_trusted_when_asked() {
+        let local = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let remote = Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10)));
+
+        let auth = auth("trust");
+        assert!(auth.trusted_user(local).is_none());
+
+        let config = ShAuthConfig {
+            trust_localhost: true,
+            ..ShAuthConfig::default()
+        };
+        let auth = Auth { config, ..auth };
+        assert!(auth.trusted_user(local).is_some());
+        assert!(auth.trusted_user(remote).is_none());
+        assert!(auth.trusted_user(None).is_none());
+    }
+
+    #[tokio::test]
+    async fn set_up_once() {
+        let auth = auth("set-up-once");
+        assert!(auth.setup_needed());
+
+        auth.setup_admin(&login("first")).await.unwrap();
+        assert!(!auth.setup_needed());
+        assert!(auth.setup_admin(&login("second")).await.is_err());
+        assert_eq!(auth.users().len(), 1);
+
+        std::fs::remove_dir_all(auth.path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn download_tokens_work_once() {
+        let auth = auth("download");
+        let session = auth.setup_admin(&login("admin")).await.unwrap();
+
+        let download = auth.start_download(&session.user);
+        assert!(auth.session_user(&download.token).is_none());
+        assert_eq!(auth.download_user(&download.token), Some(session.user));
+        assert!(auth.download_user(&download.token).is_none());
+        // a session token isn't a download token
+        assert!(auth.download_user(&session.token).is_none());
+
+        std::fs::remove_dir_all(auth.path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn set_up_from_the_command_line_while_running() {
+        let running = auth("set-up-elsewhere");
+        let data_path = running.path.parent().unwrap().to_str().unwrap().to_string();
+        let command_line = Auth::load(&ShAuthConfig::default(), &data_path).unwrap();
+        command_line.setup_admin(&login("cli")).await.unwrap();
+
+        assert!(running.setup_admin(&login("web")).await.is_err());
+        let users = running.users();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name, "cli");
+
+        std::fs::remove_dir_all(data_path).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Changes to the config, made the same way whether they come in over the WebSocket or the
+// REST API. Each change is written out straight away and undone if that fails, then handed
+// to the hub to apply
+
+use std::fmt::{self, Display, Formatter};
+
+use sh_common_server::alerts::AlertEngine;
+use sh_config::alerts::ShAlertsConfig;
+use sh_config::map::ShMapConfig;
+use sh_config::source::{DataSources, EnabledDataSources, ShEnabledDataSources};
+use sh_config::web::sh_web_sdrehub::ShWebSDREHub;
+use sh_config::ShConfig;
+
+use crate::ShAPIServerState;
+
+pub enum ConfigUpdateError {
+    /// The change can't be used, and nothing was changed
+    Invalid(String),
+    /// The config file could not be written, and the change was undone
+    Write(String),
+}
+
+impl Display for ConfigUpdateError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Invalid(e) => write!(f, "{e}"),
+            Self::Write(e) => write!(f, "Error writing config file: {e}"),
+        }
+    }
+}
+
+/// Settings set by `SH_` variables or the command line aren't written to the file, so changing
+/// them here would be lost. Turned away instead, naming what can't be changed
+fn check_overridden(
+    config: &ShConfig,
+    change: impl FnOnce(&mut ShConfig),
+) -> Result<(), ConfigUpdateError> {
+    let mut changed = config.clone();
+    change(&mut changed);
+
+    let overridden = config.overridden_changes(&changed);
+    if overridden.is_empty() {
+        return Ok(());
+    }
+    Err(ConfigUpdateError::Invalid(format!(
+        "{} set by an SH_ environment variable or the command line, and can't be changed here",
+        if overridden.len() == 1 {
+            format!("{} is", overridden[0])
+        } else {
+            format!("{} are", overridden.join(", "))
+        }
+    )))
+}
+
+/// Update the app config. Only the log level can be changed. Gives back what changed, or
+/// nothing if nothing did
+pub async fn update_app_config(
+    state: &ShAPIServerState,
+    data: &ShWebSDREHub,
+) -> Result<Option<String>, ConfigUpdateError> {
+    let mut config = state.config.lock().await;
+
+    if config.app.log_level == data.log_level {
+        return Ok(None);
+    }
+
+    debug!("New log level: {}", data.log_level);
+    check_overridden(&config, |changed| {
+        changed.app.log_level.clone_from(&data.log_level);
+    })?;
+    let previous = std::mem::replace(&mut config.app.log_level, data.log_level.clone());
+    if let Err(e) = config.write_config() {
+        config.app.log_level = previous;
+        return Err(ConfigUpdateError::Write(e.to_string()));
+    }
+    state.hub.config_changed(&config);
+    drop(config);
+
+    Ok(Some("Log level has been updated.".to_string()))
+}
+
+/// Update the map config. Gives back what changed, or nothing if nothing did
+pub async fn update_map_config(
+    state: &ShAPIServerState,
+    data: &ShMapConfig,
+) -> Result<Option<String>, ConfigUpdateError> {
+    let mut config = state.config.lock().await;
+
+    if config.map == *data {
+        return Ok(None);
+    }
+
+    debug!("New map config: {data:?}");
+    check_overridden(&config, |changed| changed.map = data.clone())?;
+    let previous = std::mem::replace(&mut config.map, data.clone());
+    if let Err(e) = config.write_config() {
+        config.map = previous;
+        return Err(ConfigUpdateError::Write(e.to_string()));
+    }
+    state.hub.config_changed(&config);
+    drop(config);
+
+    Ok(Some("Map config has been updated.".to_string()))
+}
+
+/// Replace the sources the hub reads from. Nothing is written if any of them can't be used
+pub async fn update_data_sources(
+    state: &ShAPIServerState,
+    data: &DataSources,
+) -> Result<Option<String>, ConfigUpdateError> {
+    data.validate().map_err(ConfigUpdateError::Invalid)?;
+
+    let mut config = state.config.lock().await;
+
+    if config.data_sources == *data {
+        return Ok(None);
+    }
+
+    debug!("New data sources: {data:?}");
+    check_overridden(&config, |changed| changed.data_sources = data.clone())?;
+    let previous = std::mem::replace(&mut config.data_sources, data.clone());
+    if let Err(e) = config.write_config() {
+        config.data_sources = previous;
+        return Err(ConfigUpdateError::Write(e.to_string()));
+    }
+    state.hub.config_changed(&config);
+    drop(config);
+
+    Ok(Some("Data sources have been updated.".to_string()))
+}
+
+/// Change which kinds of data are used. Gives back what changed, or nothing if nothing did
+pub async fn update_enabled_data_sources(
+    state: &ShAPIServerState,
+    data: &EnabledDataSources,
+) -> Result<Option<String>, ConfigUpdateError> {
+    // each kind once, and nothing for "none"
+    let mut enabled = EnabledDataSources::default();
+    for source in &data.enabled_sources {
+        if *source != ShEnabledDataSources::None && !enabled.is_enabled(source) {
+            enabled.enabled_sources.push(source.clone());
+        }
+    }
+
+    let mut config = state.config.lock().await;
+
+    if config.enabled_data_sources == enabled {
+        return Ok(None);
+    }
+
+    debug!("New enabled data sources: {enabled:?}");
+    check_overridden(&config, |changed| {
+        changed.enabled_data_sources = enabled.clone();
+    })?;
+    let previous = std::mem::replace(&mut config.enabled_data_sources, enabled);
+    if let Err(e) = config.write_config() {
+        config.enabled_data_sources = previous;
+        return Err(ConfigUpdateError::Write(e.to_string()));
+    }
+    state.hub.config_changed(&config);
+    drop(config);
+
+    Ok(Some("Enabled data sources have been updated.".to_string()))
+}
+
+/// Replace the alert rules. Nothing is written if they can't be used
+pub async fn update_alerts(
+    state: &ShAPIServerState,
+    data: &ShAlertsConfig,
+) -> Result<Option<String>, ConfigUpdateError> {
+    let mut config = state.config.lock().await;
+
+    let targets = config.notifications.target_names();
+    AlertEngine::validate(&data.rules, &targets).map_err(ConfigUpdateError::Invalid)?;
+
+    check_overridden(&config, |changed| changed.alerts = data.clone())?;
+    let previous = std::mem::replace(&mut config.alerts, data.clone());
+    if let Err(e) = config.write_config() {
+        config.alerts = previous;
+        return Err(ConfigUpdateError::Write(e.to_string()));
+    }
+    state.hub.config_changed(&config);
+    drop(config);
+
+    Ok(Some("Alert rules have been updated.".to_string()))
+}
@@ -0,0 +1,180 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// The web interface, read from `server.frontend_path` or built into the hub with the
+// `embed-frontend` feature. Paths that aren't files get index.html, so the page can be
+// reloaded on any path
+
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::{header, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+
+use crate::ShAPIServerState;
+
+#[cfg(feature = "embed-frontend")]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded_frontend.rs"));
+}
+
+// trunk puts a hash in the names of the files it builds, so they can be kept for a while. The
+// page itself has to be checked each time to pick up new builds
+const ASSET_CACHE_CONTROL: &str = "public, max-age=2592000";
+const PAGE_CACHE_CONTROL: &str = "no-cache";
+
+pub enum Frontend {
+    Directory(PathBuf),
+    #[cfg(feature = "embed-frontend")]
+    Embedded,
+    #[cfg(not(feature = "embed-frontend"))]
+    Missing,
+}
+
+impl Frontend {
+    pub fn new(path: Option<&str>) -> Self {
+        if let Some(path) = path {
+            if !Path::new(path).join("index.html").is_file() {
+                warn!("There is no index.html in {path}. Has the frontend been built?");
+            }
+
+            info!("Serving the web interface from {path}");
+            return Self::Directory(PathBuf::from(path));
+        }
+
+        #[cfg(feature = "embed-frontend")]
+        {
+            info!("Serving the built in web interface");
+            Self::Embedded
+        }
+
+        #[cfg(not(feature = "embed-frontend"))]
+        {
+            info!("No web interface to serve. Set server.frontend_path to serve one");
+            Self::Missing
+        }
+    }
+
+    async fn file(&self, path: &str) -> Option<Cow<'static, [u8]>> {
+        match self {
+            Self::Directory(root) => {
+                let relative = Path::new(path.trim_start_matches('/'));
+                // nothing outside of the directory
+                if !relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    return None;
+                }
+
+                let file = root.join(relative);
+                if !file.is_file() {
+                    return None;
+                }
+
+                match tokio::fs::read(&file).await {
+                    Ok(contents) => Some(Cow::Owned(contents)),
+                    Err(e) => {
+                        error!("Error reading {}: {e}", file.display());
+                        None
+                    }
+                }
+            }
+            #[cfg(feature = "embed-frontend")]
+            Self::Embedded => embedded::EMBEDDED_FILES
+                .iter()
+                .find(|(name, _)| *name == path)
+                .map(|(_, contents)| Cow::Borrowed(*contents)),
+            #[cfg(not(feature = "embed-frontend"))]
+            Self::Missing => None,
+        }
+    }
+}
+
+fn content_type(path: &str) -> &'static str {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+
+    match extension.to_ascii_lowercase().as_str() {
+        "html" => "text/html; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "wasm" => "application/wasm",
+        "css" => "text/css; charset=utf-8",
+        "json" | "map" => "application/json",
+        "webmanifest" => "application/manifest+json",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn file_response(path: &str, contents: Cow<'static, [u8]>) -> Response {
+    let content_type = content_type(path);
+    let cache_control = if content_type.starts_with("text/html") {
+        PAGE_CACHE_CONTROL
+    } else {
+        ASSET_CACHE_CONTROL
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, cache_control),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        contents,
+    )
+        .into_response()
+}
+
+/// Everything the other routes don't handle ends up here
+pub async fn frontend_handler(
+    State(server): State<Arc<ShAPIServerState>>,
+    method: Method,
+    uri: Uri,
+) -> Response {
+    let path = uri.path();
+
+    // unknown API paths shouldn't get a web page back
+    if path.starts_with("/api/") {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    if method != Method::GET && method != Method::HEAD {
+        return StatusCode::METHOD_NOT_ALLOWED.into_response();
+    }
+
+    let path = if path.ends_with('/') {
+        Cow::Owned(format!("{path}index.html"))
+    } else {
+        Cow::Borrowed(path)
+    };
+
+    if let Some(contents) = server.frontend.file(&path).await {
+        return file_response(&path, contents);
+    }
+
+    // a missing file is a 404, anything else is a page of the app
+    if Path::new(path.as_ref()).extension().is_some() {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    server.frontend.file("/index.html").await.map_or_else(
+        || StatusCode::NOT_FOUND.into_response(),
+        |contents| file_response("/index.html", contents),
+    )
+}
@@ -12,21 +12,27 @@
     clippy::all
 )]
 
+use sh_common_server::lifecycle::{Lifecycle, LifecycleSignal, LifecycleWatch};
+use sh_common_server::metrics::ShMetrics;
+use sh_common_server::state::ShHubState;
 use sh_common_server::ShDataUser;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, Mutex};
 
 use async_trait::async_trait;
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        ConnectInfo, State,
     },
-    response::Response,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     routing::get,
     Router,
 };
 
+use sh_common::auth::{ShAuthStatus, ShLogin, ShSession};
+use sh_common::timeline::ShFlightTimeline;
 use sh_common::{
     MessageData, ServerMessageTypes, ServerType, ServerWssMessage, UserMessageTypes, UserWssMessage,
 };
@@ -34,10 +40,29 @@ use sh_config::ShConfig;
 #[macro_use]
 extern crate log;
 
-pub struct ShAPIServer {}
+mod auth;
+mod config;
+mod frontend;
+mod listener;
+mod rest;
+
+use crate::auth::{Auth, Connection, SETUP_ELSEWHERE};
+use crate::config::ConfigUpdateError;
+use crate::frontend::Frontend;
+use crate::listener::Peer;
+
+pub struct ShAPIServer {
+    state: Arc<ShHubState>,
+    lifecycle: Lifecycle,
+}
 
 struct ShAPIServerState {
     config: Arc<Mutex<ShConfig>>,
+    hub: Arc<ShHubState>,
+    auth: Auth,
+    frontend: Frontend,
+    // WebSockets outlive the server that accepted them, so they watch for it stopping too
+    lifecycle: LifecycleWatch,
 }
 
 #[async_trait]
@@ -49,21 +74,21 @@ impl ShDataUser for ShAPIServer {
         // Start the web server
         let Some(config) = data else {
             error!("No configuration provided to start the API server");
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
+            return Err(Box::new(std::io::Error::other(
                 "No configuration provided to start the API server",
             )));
         };
         self.run_apiserver(config).await
     }
 
-    // TODO: Can we dynamically start/stop/restart the web server?
     fn stop(&self) {
-        // Stop the web server
+        self.lifecycle.stop();
     }
 
+    /// Listen again with the current server and login settings. Everyone is disconnected and
+    /// logged out
     fn restart(&self) {
-        // Restart the web server
+        self.lifecycle.restart();
     }
 
     fn get_server_type(&self) -> sh_common::ServerType {
@@ -71,52 +96,67 @@ impl ShDataUser for ShAPIServer {
     }
 }
 
-impl Default for ShAPIServer {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl ShAPIServer {
     #[must_use]
-    pub const fn new() -> Self {
-        Self {}
+    pub fn new(state: Arc<ShHubState>) -> Self {
+        Self {
+            state,
+            lifecycle: Lifecycle::new(),
+        }
     }
 
+    /// Serve until stopped, starting over with the current config each time we are restarted
+    ///
     /// # Errors
-    /// - Error binding socket for websocket server: {e}
+    /// - The users can't be loaded, or the server can't listen where it has been told to
     pub async fn run_apiserver(
         &self,
         config: Arc<Mutex<ShConfig>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let listener = match tokio::net::TcpListener::bind("0.0.0.0:3000").await {
-            Ok(listener) => listener,
-            Err(e) => {
-                error!("Error binding socket for websocket server: {e}");
-                return Err(Box::new(e));
+        let mut lifecycle = self.lifecycle.watch();
+
+        loop {
+            let (auth, server_config) = {
+                let config = config.lock().await;
+                (
+                    Auth::load(&config.auth, &config.app.data_path),
+                    config.server.clone(),
+                )
+            };
+            let auth = match auth {
+                Ok(auth) => auth,
+                Err(e) => {
+                    error!("{e}");
+                    return Err(Box::new(std::io::Error::other(e)));
+                }
+            };
+
+            let server = Arc::new(ShAPIServerState {
+                config: Arc::clone(&config),
+                hub: Arc::clone(&self.state),
+                auth,
+                frontend: Frontend::new(server_config.frontend_path.as_deref()),
+                lifecycle: lifecycle.clone(),
+            });
+
+            let mut shutdown = lifecycle.clone();
+            let shutdown = async move {
+                shutdown.signalled().await;
+            };
+            if let Err(e) = listener::serve(&server_config, app(server), shutdown).await {
+                error!("{e}");
+                return Err(Box::new(std::io::Error::other(e)));
             }
-        };
 
-        let local_addr = match listener.local_addr() {
-            Ok(addr) => addr,
-            Err(e) => {
-                error!("Error getting local address for websocket server: {e}");
-                return Err(Box::new(e));
+            // the server only stops when it is told to, so this is the signal it got
+            match lifecycle.signalled().await {
+                LifecycleSignal::Stop => {
+                    info!("API server stopped");
+                    return Ok(());
+                }
+                LifecycleSignal::Restart => debug!("API server stopped, starting it again"),
             }
-        };
-
-        let server = Arc::new(ShAPIServerState { config });
-
-        info!("listening for websocket connections on {local_addr}");
-        if axum::serve(listener, app(server)).await.is_err() {
-            error!("Error starting WebSocket server");
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Error starting WebSocket server",
-            )));
         }
-
-        Ok(())
     }
 }
 
@@ -125,16 +165,196 @@ fn app(server: Arc<ShAPIServerState>) -> Router {
 
     Router::new()
         .route("/sdre-hub", get(ws_handler))
+        .route("/metrics", get(metrics_handler))
+        .nest("/api/v1", rest::router(&server))
+        .fallback(frontend::frontend_handler)
         .with_state(server)
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(server): State<Arc<ShAPIServerState>>) -> Response {
-    debug!("WebSocket connection initiated");
-    ws.on_upgrade(|socket| ws_handle_socket(socket, server))
+/// Counts a connected user for as long as it is held, however the connection ends
+struct ConnectedClient(Arc<ShMetrics>);
+
+impl ConnectedClient {
+    fn new(metrics: &Arc<ShMetrics>) -> Self {
+        metrics.websocket_client_connected();
+        Self(Arc::clone(metrics))
+    }
+}
+
+impl Drop for ConnectedClient {
+    fn drop(&mut self) {
+        self.0.websocket_client_disconnected();
+    }
+}
+
+fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+async fn metrics_handler(State(server): State<Arc<ShAPIServerState>>) -> Response {
+    let hub = &server.hub;
+    let aircraft = hub.timelines.read().await.aircraft_tracked(now());
+    hub.metrics.set_aircraft_tracked(aircraft);
+
+    match hub.metrics.encode() {
+        Ok(body) => (
+            [(
+                header::CONTENT_TYPE,
+                "application/openmetrics-text; version=1.0.0; charset=utf-8",
+            )],
+            body,
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Error encoding metrics: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Tell the user how a config change went. Changes that changed nothing aren't answered
+async fn send_config_result(
+    socket: &mut WebSocket,
+    result: Result<Option<String>, ConfigUpdateError>,
+) {
+    let (response_type, data) = match result {
+        Ok(None) => return,
+        Ok(Some(changed)) => (
+            ServerMessageTypes::ServerWriteConfigSuccess,
+            MessageData::ShConfigSuccess(changed),
+        ),
+        Err(e) => (
+            ServerMessageTypes::ServerWriteConfigFailure,
+            MessageData::ShConfigFailure(e.to_string()),
+        ),
+    };
+
+    let message = ServerWssMessage::new(response_type, data);
+    let config = serde_json::to_string(&message).unwrap();
+    socket.send(Message::Text(config.into())).await.unwrap();
+}
+
+/// Create the first account from the command line, for when the web interface isn't used from
+/// the hub's own machine
+///
+/// # Errors
+/// There are accounts already, or the username or password won't do
+pub async fn create_admin(config: &ShConfig, login: &ShLogin) -> Result<(), String> {
+    let auth = Auth::load(&config.auth, &config.app.data_path)?;
+    auth.setup_admin(login).await.map(|_| ())
+}
+
+/// Send a message to the one user on `socket`
+async fn send_reply(socket: &mut WebSocket, message_type: ServerMessageTypes, data: MessageData) {
+    let message = ServerWssMessage::new(message_type, data);
+    let message_serialized = serde_json::to_string(&message).unwrap();
+    socket
+        .send(Message::Text(message_serialized.into()))
+        .await
+        .unwrap();
+}
+
+/// Tell the user who they are acting as, with their session token if they just started using it
+async fn send_auth_status(
+    socket: &mut WebSocket,
+    state: &ShAPIServerState,
+    connection: &Connection,
+    with_token: bool,
+) {
+    let user = connection.user(&state.auth);
+    let token = if with_token && user.is_some() {
+        connection.token.clone()
+    } else {
+        None
+    };
+    let status = ShAuthStatus {
+        user,
+        token,
+        setup_needed: state.auth.setup_needed(),
+    };
+
+    send_reply(
+        socket,
+        ServerMessageTypes::ServerAuthStatus,
+        MessageData::ShAuthStatus(status),
+    )
+    .await;
+}
+
+/// Start using the session from logging in, or say why there isn't one
+async fn send_login_result(
+    socket: &mut WebSocket,
+    state: &ShAPIServerState,
+    connection: &mut Connection,
+    result: Result<ShSession, String>,
+) {
+    match result {
+        Ok(session) => {
+            connection.token = Some(session.token);
+            send_auth_status(socket, state, connection, true).await;
+        }
+        Err(e) => {
+            send_reply(
+                socket,
+                ServerMessageTypes::ServerAuthFailure,
+                MessageData::ShAuthFailure(e),
+            )
+            .await;
+        }
+    }
+}
+
+/// Tell the user how a change to the accounts went, and send the accounts as they are now
+async fn send_user_result(
+    socket: &mut WebSocket,
+    state: &ShAPIServerState,
+    result: Result<String, String>,
+) {
+    match result {
+        Ok(changed) => {
+            send_reply(
+                socket,
+                ServerMessageTypes::ServerWriteConfigSuccess,
+                MessageData::ShConfigSuccess(changed),
+            )
+            .await;
+            send_reply(
+                socket,
+                ServerMessageTypes::ServerResponseUsers,
+                MessageData::ShUsers(state.auth.users()),
+            )
+            .await;
+        }
+        Err(e) => {
+            send_reply(
+                socket,
+                ServerMessageTypes::ServerWriteConfigFailure,
+                MessageData::ShConfigFailure(e),
+            )
+            .await;
+        }
+    }
+}
+
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    ConnectInfo(peer): ConnectInfo<Peer>,
+    headers: HeaderMap,
+    State(server): State<Arc<ShAPIServerState>>,
+) -> Response {
+    debug!("WebSocket connection initiated from {peer}");
+    let connection = Connection::new(&server.auth, peer.address(&headers));
+    ws.on_upgrade(|socket| ws_handle_socket(socket, server, connection))
 }
 
 #[allow(clippy::too_many_lines)]
-async fn ws_handle_socket(mut socket: WebSocket, state: Arc<ShAPIServerState>) {
+async fn ws_handle_socket(
+    mut socket: WebSocket,
+    state: Arc<ShAPIServerState>,
+    mut connection: Connection,
+) {
     // send the initial message
     // let response_type = ServerMessageTypes::ServerResponseConfig;
     // let config = state.config.lock().unwrap().clone();
@@ -143,15 +363,61 @@ async fn ws_handle_socket(mut socket: WebSocket, state: Arc<ShAPIServerState>) {
     //                     let config = serde_json::to_string(&message).unwrap();
     //                     socket.send(Message::Text(config)).await.unwrap();
 
-    while let Some(Ok(msg)) = socket.recv().await {
-        // deserialize the message and see if it's a request for config
-        debug!("Received message: {msg:?}");
+    let _client = ConnectedClient::new(&state.hub.metrics);
+    let mut server_messages = state.hub.subscribe();
+    let mut lifecycle = state.lifecycle.clone();
+    // replies to requests that take a while are worked out elsewhere and sent from here, so the
+    // socket keeps going in the meantime
+    let (replies_tx, mut replies) = mpsc::unbounded_channel::<ServerWssMessage>();
+
+    // the user needs to know if they have to log in before asking for anything
+    send_auth_status(&mut socket, &state, &connection, false).await;
+
+    loop {
+        let msg = tokio::select! {
+            msg = socket.recv() => msg,
+            _ = lifecycle.signalled() => {
+                // the user reconnects to wherever the server is now
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            Some(reply) = replies.recv() => {
+                let reply_serialized = serde_json::to_string(&reply).unwrap();
+                if socket.send(Message::Text(reply_serialized.into())).await.is_err() {
+                    trace!("Socket closed while sending a reply");
+                    break;
+                }
+                continue;
+            }
+            server_message = server_messages.recv() => {
+                match server_message {
+                    // nothing goes out to users who haven't logged in
+                    Ok(_) if connection.user(&state.auth).is_none() => (),
+                    Ok(message) => {
+                        let message_serialized = serde_json::to_string(&message).unwrap();
+                        if socket.send(Message::Text(message_serialized.into())).await.is_err() {
+                            trace!("Socket closed while sending server message");
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("WebSocket client fell behind and missed {skipped} messages");
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+                continue;
+            }
+        };
+
+        let Some(Ok(msg)) = msg else {
+            break;
+        };
 
         match msg {
             Message::Text(text) => {
                 let text = text.trim_matches('"').replace("\\\"", "\"");
 
-                debug!("Received text message after trimming and replacement: {text}");
+                // the text isn't logged, it can hold passwords
                 let message: UserWssMessage = match serde_json::from_str(&text) {
                     Ok(message) => message,
                     Err(e) => {
@@ -159,6 +425,26 @@ async fn ws_handle_socket(mut socket: WebSocket, state: Arc<ShAPIServerState>) {
                         continue;
                     }
                 };
+                debug!("Received {:?} message", message.message_type);
+
+                if let Some(role) = auth::required_role(&message.message_type) {
+                    let user = connection.user(&state.auth);
+                    if let Err(e) = auth::check_role(user.as_ref(), role) {
+                        debug!("Refusing {:?} message: {e}", message.message_type);
+                        if user.is_none() {
+                            // most likely the session ended, so the user needs to log in again
+                            send_auth_status(&mut socket, &state, &connection, false).await;
+                        } else {
+                            send_reply(
+                                &mut socket,
+                                ServerMessageTypes::ServerAuthFailure,
+                                MessageData::ShAuthFailure(e),
+                            )
+                            .await;
+                        }
+                        continue;
+                    }
+                }
 
                 match message.message_type {
                     UserMessageTypes::UserRequestConfig => {
@@ -180,123 +466,370 @@ async fn ws_handle_socket(mut socket: WebSocket, state: Arc<ShAPIServerState>) {
                             .unwrap();
                     }
                     UserMessageTypes::UserUpdateAppConfig => {
-                        // check the data
-                        if message.data == MessageData::NoData {
-                            error!("Received UserUpdateAppConfig message without data");
-                            continue;
-                        }
-
-                        debug!("Received UserUpdateAppConfig message with data");
-                        // FIXME: This needs to be a match once messagedata has more shit in it
+                        // TODO: Maybe. For now the DB path and config path are not going to be user configurable.
                         let MessageData::ShAppConfig(data) = message.data else {
                             error!("Received UserUpdateAppConfig message with incorrect data type");
                             continue;
                         };
 
-                        // TODO: Maybe. For now the DB path and config path are not going to be user configurable.
-
-                        // check and see if the log level has changed
+                        debug!("Received app config: {data:?}");
+                        let result = config::update_app_config(&state, &data).await;
+                        send_config_result(&mut socket, result).await;
+                    }
+                    UserMessageTypes::UserUpdateMapConfig => {
+                        let MessageData::ShMapConfig(data) = message.data else {
+                            error!("Received UserUpdateMapConfig message with incorrect data type");
+                            continue;
+                        };
 
-                        let mut config = state.config.lock().await;
+                        debug!("Received UserUpdateMapConfig message with data");
+                        let result = config::update_map_config(&state, &data).await;
+                        send_config_result(&mut socket, result).await;
+                    }
+                    UserMessageTypes::UserUpdateDataSources => {
+                        let MessageData::ShDataSources(data) = message.data else {
+                            error!(
+                                "Received UserUpdateDataSources message with incorrect data type"
+                            );
+                            continue;
+                        };
 
-                        if *config.app.log_level != data.log_level {
-                            debug!("New log level: {}", data.log_level);
+                        debug!("Received UserUpdateDataSources message with data");
+                        let result = config::update_data_sources(&state, &data).await;
+                        send_config_result(&mut socket, result).await;
+                    }
+                    UserMessageTypes::UserUpdateEnabledDataSources => {
+                        let MessageData::ShEnabledDataSources(data) = message.data else {
+                            error!("Received UserUpdateEnabledDataSources message with incorrect data type");
+                            continue;
+                        };
 
-                            let new_log_level = data.log_level.clone();
+                        debug!("Received UserUpdateEnabledDataSources message with data");
+                        let result = config::update_enabled_data_sources(&state, &data).await;
+                        send_config_result(&mut socket, result).await;
+                    }
+                    UserMessageTypes::UserRequestFlightInfo => {
+                        let MessageData::ShFlightLookup(flight) = message.data else {
+                            error!(
+                                "Received UserRequestFlightInfo message with incorrect data type"
+                            );
+                            continue;
+                        };
 
-                            config.app.log_level = new_log_level;
-                            match config.write_config() {
-                                Ok(()) => {
-                                    // tell the user that the config write was successful and they need to restart
-                                    // because the log level changed
+                        let response_type = ServerMessageTypes::ServerResponseFlightInfo;
+                        let data = MessageData::ShFlightInfo(state.hub.lookup.flight_info(&flight));
+                        let message = ServerWssMessage::new(response_type, data);
+                        let flight_info_serialized = serde_json::to_string(&message).unwrap();
+                        debug!("Sending flight info message: {flight_info_serialized}");
+                        socket
+                            .send(Message::Text(flight_info_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestRecentMessages => {
+                        let response_type = ServerMessageTypes::ServerResponseRecentMessages;
+                        let data = MessageData::ShAcarsMessages(state.hub.recent_messages().await);
+                        let message = ServerWssMessage::new(response_type, data);
+                        let messages_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(messages_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestFlightTimeline => {
+                        let MessageData::ShFlightLookup(flight) = message.data else {
+                            error!(
+                                "Received UserRequestFlightTimeline message with incorrect data type"
+                            );
+                            continue;
+                        };
 
-                                    let response_type =
-                                        ServerMessageTypes::ServerWriteConfigSuccess;
-                                    let data = MessageData::ShConfigSuccess("Log level has been updated. Please restart the server for the change to take affect".to_string());
+                        // A flight we know nothing about gets an empty timeline
+                        let flight = state.hub.flight_key(&flight);
+                        let timeline = state
+                            .hub
+                            .timelines
+                            .read()
+                            .await
+                            .timeline(&flight)
+                            .unwrap_or_else(|| ShFlightTimeline::new(flight));
 
-                                    let message = ServerWssMessage::new(response_type, data);
+                        let response_type = ServerMessageTypes::ServerResponseFlightTimeline;
+                        let data = MessageData::ShFlightTimeline(Box::new(timeline));
+                        let message = ServerWssMessage::new(response_type, data);
+                        let timeline_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(timeline_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestGroundStations => {
+                        let response_type = ServerMessageTypes::ServerResponseGroundStations;
+                        let stations = state.hub.ground_stations.read().await.stations();
+                        let data = MessageData::ShGroundStations(stations);
+                        let message = ServerWssMessage::new(response_type, data);
+                        let stations_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(stations_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestWeather => {
+                        let MessageData::ShWeatherQuery(query) = message.data else {
+                            error!("Received UserRequestWeather message with incorrect data type");
+                            continue;
+                        };
 
-                                    let config = serde_json::to_string(&message).unwrap();
+                        let response_type = ServerMessageTypes::ServerResponseWeather;
+                        let products = state.hub.weather.read().await.query(&query);
+                        let data = MessageData::ShWeatherProducts(products);
+                        let message = ServerWssMessage::new(response_type, data);
+                        let weather_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(weather_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestFlightSessions => {
+                        let flights = match state.hub.database.recent_flights().await {
+                            Ok(flights) => flights,
+                            Err(e) => {
+                                error!("Error reading recent flights: {e}");
+                                Vec::new()
+                            }
+                        };
 
-                                    socket.send(Message::Text(config.into())).await.unwrap();
-                                }
+                        let response_type = ServerMessageTypes::ServerResponseFlightSessions;
+                        let data = MessageData::ShFlightSessions(flights);
+                        let message = ServerWssMessage::new(response_type, data);
+                        let flights_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(flights_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestFlightSession => {
+                        let MessageData::ShFlightSessionId(id) = message.data else {
+                            error!(
+                                "Received UserRequestFlightSession message with incorrect data type"
+                            );
+                            continue;
+                        };
 
-                                Err(e) => {
-                                    // tell the user that the config write failed
-                                    let response_type =
-                                        ServerMessageTypes::ServerWriteConfigFailure;
-                                    let data = MessageData::ShConfigFailure(format!(
-                                        "Error writing config file: {e}"
-                                    ));
+                        // A flight that no longer exists, usually because late data merged it
+                        // in to another, gets no data
+                        let data = match state.hub.database.flight(id).await {
+                            Ok(Some(flight)) => {
+                                MessageData::ShFlightSessionDetail(Box::new(flight))
+                            }
+                            Ok(None) => MessageData::NoData,
+                            Err(e) => {
+                                error!("Error reading flight {id}: {e}");
+                                MessageData::NoData
+                            }
+                        };
 
-                                    let message = ServerWssMessage::new(response_type, data);
+                        let response_type = ServerMessageTypes::ServerResponseFlightSession;
+                        let message = ServerWssMessage::new(response_type, data);
+                        let flight_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(flight_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserUpdateAlerts => {
+                        let MessageData::ShAlertsConfig(data) = message.data else {
+                            error!("Received UserUpdateAlerts message with incorrect data type");
+                            continue;
+                        };
 
-                                    let config = serde_json::to_string(&message).unwrap();
+                        debug!("Received UserUpdateAlerts message with data");
+                        let result = config::update_alerts(&state, &data).await;
+                        send_config_result(&mut socket, result).await;
+                    }
+                    UserMessageTypes::UserRequestNotificationLog => {
+                        let deliveries = match state.hub.database.recent_deliveries().await {
+                            Ok(deliveries) => deliveries,
+                            Err(e) => {
+                                error!("Error reading notification log: {e}");
+                                Vec::new()
+                            }
+                        };
 
-                                    socket.send(Message::Text(config.into())).await.unwrap();
-                                }
+                        let response_type = ServerMessageTypes::ServerResponseNotificationLog;
+                        let data = MessageData::ShNotificationDeliveries(deliveries);
+                        let message = ServerWssMessage::new(response_type, data);
+                        let deliveries_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(deliveries_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestEmergencies => {
+                        let emergencies = match state.hub.database.recent_emergencies().await {
+                            Ok(emergencies) => emergencies,
+                            Err(e) => {
+                                error!("Error reading emergencies: {e}");
+                                Vec::new()
                             }
-                        }
+                        };
 
-                        debug!("Received app config: {data:?}");
-                        debug!("Current app state: {config:?}");
+                        let response_type = ServerMessageTypes::ServerResponseEmergencies;
+                        let data = MessageData::ShEmergencies(emergencies);
+                        let message = ServerWssMessage::new(response_type, data);
+                        let emergencies_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(emergencies_serialized.into()))
+                            .await
+                            .unwrap();
                     }
-                    UserMessageTypes::UserUpdateMapConfig => {
-                        // check the data
-                        if message.data == MessageData::NoData {
-                            error!("Received UserUpdateMapConfig message without data");
+                    UserMessageTypes::UserRequestSourceStatus => {
+                        let response_type = ServerMessageTypes::ServerResponseSourceStatus;
+                        let data = MessageData::ShSourceStatuses(state.hub.sources.statuses());
+                        let message = ServerWssMessage::new(response_type, data);
+                        let sources_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(sources_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestConsumerStatus => {
+                        let response_type = ServerMessageTypes::ServerResponseConsumerStatus;
+                        let data = MessageData::ShConsumerStatuses(state.hub.consumers.statuses());
+                        let message = ServerWssMessage::new(response_type, data);
+                        let consumers_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(consumers_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserTestSource => {
+                        let MessageData::ShSourceTest(source) = message.data else {
+                            error!("Received UserTestSource message with incorrect data type");
                             continue;
-                        }
+                        };
 
-                        let MessageData::ShMapConfig(data) = message.data else {
-                            error!("Received UserUpdateMapConfig message with incorrect data type");
+                        // the test listens for a few seconds, so it's left to get on with it
+                        let replies_tx = replies_tx.clone();
+                        tokio::spawn(async move {
+                            let result = sh_sources::probe::test_source(&source).await;
+                            let _ = replies_tx.send(ServerWssMessage::new(
+                                ServerMessageTypes::ServerResponseSourceTest,
+                                MessageData::ShSourceTestResult(Box::new(result)),
+                            ));
+                        });
+                    }
+                    UserMessageTypes::UserRequestStats => {
+                        let MessageData::ShStatsRange(range) = message.data else {
+                            error!("Received UserRequestStats message with incorrect data type");
                             continue;
                         };
 
-                        debug!("Received UserUpdateMapConfig message with data");
-
-                        let mut config = state.config.lock().await;
-
-                        if config.map != data {
-                            debug!("New map config: {data:?}");
-
-                            let new_map_config = data.clone();
-
-                            config.map = new_map_config;
-                            match config.write_config() {
-                                Ok(()) => {
-                                    // tell the user that the config write was successful and they need to restart
-                                    // because the map config changed
-
-                                    let response_type =
-                                        ServerMessageTypes::ServerWriteConfigSuccess;
-                                    let data = MessageData::ShConfigSuccess(
-                                        "Map config has been updated.".to_string(),
-                                    );
+                        // include what has been counted but not written out yet
+                        state.hub.flush_stats().await;
+                        let summary = match state.hub.database.stats(range, now()).await {
+                            Ok(summary) => summary,
+                            Err(e) => {
+                                error!("Error reading statistics: {e}");
+                                continue;
+                            }
+                        };
 
-                                    let message = ServerWssMessage::new(response_type, data);
+                        let response_type = ServerMessageTypes::ServerResponseStats;
+                        let data = MessageData::ShStats(Box::new(summary));
+                        let message = ServerWssMessage::new(response_type, data);
+                        let stats_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(stats_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserRequestAlertHistory => {
+                        let alerts = match state.hub.database.recent_alerts().await {
+                            Ok(alerts) => alerts,
+                            Err(e) => {
+                                error!("Error reading alert history: {e}");
+                                Vec::new()
+                            }
+                        };
 
-                                    let config = serde_json::to_string(&message).unwrap();
+                        let response_type = ServerMessageTypes::ServerResponseAlertHistory;
+                        let data = MessageData::ShAlerts(alerts);
+                        let message = ServerWssMessage::new(response_type, data);
+                        let alerts_serialized = serde_json::to_string(&message).unwrap();
+                        socket
+                            .send(Message::Text(alerts_serialized.into()))
+                            .await
+                            .unwrap();
+                    }
+                    UserMessageTypes::UserLogin => {
+                        let MessageData::ShLogin(login) = message.data else {
+                            error!("Received UserLogin message with incorrect data type");
+                            continue;
+                        };
 
-                                    socket.send(Message::Text(config.into())).await.unwrap();
-                                }
+                        let result = state.auth.login(&login).await;
+                        send_login_result(&mut socket, &state, &mut connection, result).await;
+                    }
+                    UserMessageTypes::UserSetupAdmin => {
+                        let MessageData::ShLogin(login) = message.data else {
+                            error!("Received UserSetupAdmin message with incorrect data type");
+                            continue;
+                        };
 
-                                Err(e) => {
-                                    // tell the user that the config write failed
-                                    let response_type =
-                                        ServerMessageTypes::ServerWriteConfigFailure;
-                                    let data = MessageData::ShConfigFailure(format!(
-                                        "Error writing config file: {e}"
-                                    ));
+                        let result = if connection.can_set_up() {
+                            state.auth.setup_admin(&login).await
+                        } else {
+                            warn!("Refusing to create the admin account for a remote connection");
+                            Err(SETUP_ELSEWHERE.to_string())
+                        };
+                        send_login_result(&mut socket, &state, &mut connection, result).await;
+                    }
+                    UserMessageTypes::UserResumeSession => {
+                        let MessageData::ShSessionToken(token) = message.data else {
+                            error!("Received UserResumeSession message with incorrect data type");
+                            continue;
+                        };
 
-                                    let message = ServerWssMessage::new(response_type, data);
+                        // a session that has ended gets the status without it, so the user
+                        // knows to log in again
+                        let resumed = state.auth.session_user(&token).is_some();
+                        if resumed {
+                            connection.token = Some(token);
+                        }
+                        send_auth_status(&mut socket, &state, &connection, resumed).await;
+                    }
+                    UserMessageTypes::UserLogout => {
+                        if let Some(token) = connection.token.take() {
+                            state.auth.logout(&token);
+                        }
+                        send_auth_status(&mut socket, &state, &connection, false).await;
+                    }
+                    UserMessageTypes::UserRequestUsers => {
+                        send_reply(
+                            &mut socket,
+                            ServerMessageTypes::ServerResponseUsers,
+                            MessageData::ShUsers(state.auth.users()),
+                        )
+                        .await;
+                    }
+                    UserMessageTypes::UserUpdateUser => {
+                        let MessageData::ShUserUpdate(update) = message.data else {
+                            error!("Received UserUpdateUser message with incorrect data type");
+                            continue;
+                        };
 
-                                    let config = serde_json::to_string(&message).unwrap();
+                        let result = state.auth.update_user(&update).await;
+                        send_user_result(&mut socket, &state, result).await;
+                    }
+                    UserMessageTypes::UserDeleteUser => {
+                        let MessageData::ShUserName(name) = message.data else {
+                            error!("Received UserDeleteUser message with incorrect data type");
+                            continue;
+                        };
 
-                                    socket.send(Message::Text(config.into())).await.unwrap();
-                                }
-                            }
-                        }
+                        let result = state.auth.delete_user(&name);
+                        send_user_result(&mut socket, &state, result).await;
                     }
                 }
             }
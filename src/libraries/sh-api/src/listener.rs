@@ -0,0 +1,252 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Where the server listens: a TCP port, the same port with TLS, or a Unix socket for a
+// reverse proxy. Handlers see every kind of connection as a `Peer`
+
+use std::fmt::{self, Display, Formatter};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::connect_info::Connected;
+use axum::http::{header, HeaderMap};
+use axum::serve::{IncomingStream, Listener};
+use axum::Router;
+use sh_config::server::ShServerConfig;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+// long enough for a slow client, short enough that a stuck one doesn't hang around
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Who is on the other end of a connection. Unix socket connections have no address
+#[derive(Debug, Clone, Copy)]
+pub struct Peer(pub Option<IpAddr>);
+
+impl Display for Peer {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.0 {
+            Some(address) => write!(f, "{address}"),
+            None => write!(f, "the Unix socket"),
+        }
+    }
+}
+
+impl Peer {
+    /// The address to go by when deciding whether a request is local. A reverse proxy says it
+    /// passed the request on with `Forwarded` or `X-Forwarded-For`, and then its own address
+    /// says nothing about where the user is
+    pub fn address(self, headers: &HeaderMap) -> Option<IpAddr> {
+        let proxied =
+            headers.contains_key(header::FORWARDED) || headers.contains_key("x-forwarded-for");
+        self.0.filter(|_| !proxied)
+    }
+}
+
+impl Connected<IncomingStream<'_, TcpListener>> for Peer {
+    fn connect_info(stream: IncomingStream<'_, TcpListener>) -> Self {
+        Self(Some(stream.remote_addr().ip()))
+    }
+}
+
+impl Connected<IncomingStream<'_, TlsListener>> for Peer {
+    fn connect_info(stream: IncomingStream<'_, TlsListener>) -> Self {
+        Self(Some(stream.remote_addr().ip()))
+    }
+}
+
+#[cfg(unix)]
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for Peer {
+    fn connect_info(_stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self(None)
+    }
+}
+
+/// A TCP listener that hands out connections once their TLS handshake is done. Handshakes
+/// run on their own tasks so a slow client can't hold up everyone else
+pub struct TlsListener {
+    local_addr: SocketAddr,
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+}
+
+impl TlsListener {
+    fn new(mut listener: TcpListener, acceptor: TlsAcceptor) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (sender, connections) = mpsc::channel(64);
+
+        tokio::spawn(async move {
+            loop {
+                // stop accepting, and let go of the port, once the server has stopped
+                let (stream, address) = tokio::select! {
+                    accepted = Listener::accept(&mut listener) => accepted,
+                    () = sender.closed() => return,
+                };
+                let acceptor = acceptor.clone();
+                let sender = sender.clone();
+
+                tokio::spawn(async move {
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                    {
+                        Ok(Ok(stream)) => {
+                            // the server has stopped if nobody is listening
+                            let _ = sender.send((stream, address)).await;
+                        }
+                        Ok(Err(e)) => debug!("TLS handshake with {address} failed: {e}"),
+                        Err(_) => debug!("TLS handshake with {address} timed out"),
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            local_addr,
+            connections,
+        })
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        self.connections
+            .recv()
+            .await
+            .expect("TLS accept loop should run as long as the listener")
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+fn tls_acceptor(cert: &str, key: &str) -> Result<TlsAcceptor, String> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .map_err(|e| format!("Error reading TLS certificate {cert}: {e}"))?;
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| format!("Error reading TLS key {key}: {e}"))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| format!("Error setting up TLS: {e}"))?;
+    // the server only speaks HTTP/1.1, which WebSockets need anyway
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Listen where the config says and serve `app` until `shutdown` finishes. Requests that have
+/// started are finished first
+///
+/// # Errors
+/// The TLS files can't be read, or the address or socket can't be listened on
+pub async fn serve(
+    config: &ShServerConfig,
+    app: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    let app = app.into_make_service_with_connect_info::<Peer>();
+
+    if let Some(path) = &config.unix_socket {
+        if config.tls_cert.is_some() || config.tls_key.is_some() {
+            warn!("TLS is not used on the Unix socket. The reverse proxy should take care of it");
+        }
+
+        return serve_unix(path, app, shutdown).await;
+    }
+
+    let tls = config.tls()?;
+    let listener = TcpListener::bind(config.address())
+        .await
+        .map_err(|e| format!("Error listening on {}: {e}", config.address()))?;
+    let local_addr = listener
+        .local_addr()
+        .map_err(|e| format!("Error getting local address for the server: {e}"))?;
+
+    let result = if let Some((cert, key)) = tls {
+        let listener = TlsListener::new(listener, tls_acceptor(cert, key)?)
+            .map_err(|e| format!("Error setting up TLS: {e}"))?;
+        info!("Listening on https://{local_addr}");
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown)
+            .await
+    } else {
+        info!("Listening on http://{local_addr}");
+        axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown)
+            .await
+    };
+
+    result.map_err(|e| format!("Error running the server: {e}"))
+}
+
+#[cfg(unix)]
+async fn serve_unix(
+    path: &str,
+    app: axum::extract::connect_info::IntoMakeServiceWithConnectInfo<Router, Peer>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // a socket left behind by the last run would stop us binding, anything else is a mistake
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            std::fs::remove_file(path)
+                .map_err(|e| format!("Error removing old Unix socket {path}: {e}"))?;
+        }
+        Ok(_) => return Err(format!("{path} exists and is not a Unix socket")),
+        Err(_) => (),
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|e| format!("Error listening on Unix socket {path}: {e}"))?;
+    info!("Listening on Unix socket {path}");
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+        .map_err(|e| format!("Error running the server: {e}"))
+}
+
+#[cfg(not(unix))]
+async fn serve_unix(
+    path: &str,
+    _app: axum::extract::connect_info::IntoMakeServiceWithConnectInfo<Router, Peer>,
+    _shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), String> {
+    Err(format!(
+        "Can't listen on {path}, Unix sockets are not supported on this system"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn proxied_requests_have_no_address() {
+        let peer = Peer(Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+        assert_eq!(peer.address(&HeaderMap::new()), peer.0);
+
+        for name in ["forwarded", "x-forwarded-for"] {
+            let mut headers = HeaderMap::new();
+            headers.insert(name, "for=192.0.2.60".parse().unwrap());
+            assert_eq!(peer.address(&headers), None, "{name}");
+        }
+    }
+}
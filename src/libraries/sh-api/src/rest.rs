@@ -0,0 +1,616 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// The REST API, for scripts and anything else that would rather not speak the WebSocket
+// envelope. It sends the same types the WebSocket does, and every error is a `ShApiError`
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+        ConnectInfo, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use futures_util::stream;
+use serde::Deserialize;
+use sh_common::api::{
+    ShAircraftDetail, ShAircraftSummary, ShApiError, ShConfigUpdate, ShConfigUpdateResult,
+    ShExportFormat, ShExportedMessage, ShMessageQuery, ShStoredMessage,
+};
+use sh_common::auth::{ShDownloadToken, ShLogin, ShSession, ShUserRole};
+use sh_common::consumer_status::ShConsumerStatus;
+use sh_common::source_status::{ShSourceStatus, ShSourceTest, ShSourceTestResult};
+use sh_common::stats::{ShStats, ShStatsRange};
+use sh_common_server::export::ShMessageExport;
+use sh_config::web::sh_web_config::ShWebConfig;
+use utoipa::{IntoParams, OpenApi};
+
+use crate::config::{self, ConfigUpdateError};
+use crate::listener::Peer;
+use crate::{now, ShAPIServerState};
+
+// Aircraft returned by the aircraft list when the query doesn't say, and the most it returns
+const DEFAULT_AIRCRAFT_LIMIT: i64 = 100;
+const MAX_AIRCRAFT_LIMIT: i64 = 1000;
+
+type ApiState = State<Arc<ShAPIServerState>>;
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "SDR-E Hub",
+        description = "Messages, aircraft, statistics and config from SDR-E Hub. Unless the hub \
+            lets everyone in, log in first and send the token as `Authorization: Bearer <token>`. \
+            Changing anything needs an admin"
+    ),
+    paths(
+        login,
+        logout,
+        get_config,
+        update_config,
+        search_messages,
+        export_messages,
+        export_token,
+        get_message,
+        list_aircraft,
+        get_aircraft,
+        get_stats,
+        get_sources,
+        test_source,
+        get_consumers,
+    )
+)]
+struct ApiDoc;
+
+pub fn router(server: &Arc<ShAPIServerState>) -> Router<Arc<ShAPIServerState>> {
+    let protected = Router::new()
+        .route("/config", get(get_config).put(update_config))
+        .route("/messages", get(search_messages))
+        .route("/messages/export", get(export_messages))
+        .route("/messages/{id}", get(get_message))
+        .route("/aircraft", get(list_aircraft))
+        .route("/aircraft/{aircraft}", get(get_aircraft))
+        .route("/stats", get(get_stats))
+        .route("/sources", get(get_sources))
+        .route("/sources/test", post(test_source))
+        .route("/consumers", get(get_consumers))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(server),
+            require_user,
+        ));
+
+    Router::new()
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        // viewers can download, so this doesn't need the admin every other POST does
+        .route("/messages/export/token", post(export_token))
+        .route("/openapi.json", get(openapi))
+        .merge(protected)
+        .fallback(not_found)
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+/// A download token as a `token` query parameter, for links that can't set headers. Session
+/// tokens are never taken here, so they stay out of URLs and logs
+fn query_token(request: &Request) -> Option<&str> {
+    if request.method() != Method::GET {
+        return None;
+    }
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("token="))
+}
+
+/// Work out who is calling, from their token or from where they are calling from. Reading
+/// needs a viewer, and anything else needs an admin
+async fn require_user(
+    State(server): ApiState,
+    ConnectInfo(peer): ConnectInfo<Peer>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let user = bearer_token(request.headers())
+        .and_then(|token| server.auth.session_user(token))
+        .or_else(|| query_token(&request).and_then(|token| server.auth.download_user(token)))
+        .or_else(|| server.auth.trusted_user(peer.address(request.headers())));
+    let role = if request.method() == Method::GET {
+        ShUserRole::Viewer
+    } else {
+        ShUserRole::Admin
+    };
+
+    match user {
+        None => (
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            ApiError::new(StatusCode::UNAUTHORIZED, "Please log in first"),
+        )
+            .into_response(),
+        Some(user) if user.role < role => {
+            ApiError::new(StatusCode::FORBIDDEN, "Only admins can do that").into_response()
+        }
+        Some(_) => next.run(request).await,
+    }
+}
+
+/// An error, sent as a `ShApiError` with a matching status code
+struct ApiError {
+    status: StatusCode,
+    error: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, error: impl Display) -> Self {
+        Self {
+            status,
+            error: error.to_string(),
+        }
+    }
+
+    fn not_found(what: impl Display) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("{what} not found"))
+    }
+
+    /// The database let us down. The details go to the log rather than the user
+    fn database(e: &impl Display) -> Self {
+        error!("Error reading the database for the REST API: {e}");
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Error reading the database",
+        )
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::new(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::new(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::new(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<ConfigUpdateError> for ApiError {
+    fn from(e: ConfigUpdateError) -> Self {
+        let status = match e {
+            ConfigUpdateError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ConfigUpdateError::Write(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status, e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ShApiError {
+            status: self.status.as_u16(),
+            error: self.error,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct PageQuery {
+    /// Most entries to return. Defaults to 100, and at most 1000 are returned
+    limit: Option<i64>,
+    /// Number of entries to skip, for paging
+    offset: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportQuery {
+    /// `csv` or `jsonl`. Defaults to `jsonl`
+    format: Option<String>,
+    /// Add what is known about the aircraft each message was tied to
+    #[serde(default)]
+    aircraft_info: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct StatsQuery {
+    /// `1h`, `24h`, `7d` or `30d`. Defaults to `24h`
+    range: Option<String>,
+}
+
+async fn not_found() -> ApiError {
+    ApiError::not_found("Endpoint")
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Log in, for a token to send with everything else
+#[utoipa::path(
+    post,
+    path = "/api/v1/login",
+    tag = "auth",
+    request_body = ShLogin,
+    responses(
+        (status = 200, body = ShSession),
+        (status = 400, body = ShApiError),
+        (status = 401, body = ShApiError),
+    )
+)]
+async fn login(
+    State(server): ApiState,
+    login: Result<Json<ShLogin>, JsonRejection>,
+) -> ApiResult<ShSession> {
+    let Json(login) = login?;
+
+    server
+        .auth
+        .login(&login)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::new(StatusCode::UNAUTHORIZED, e))
+}
+
+/// End the session the token in the `Authorization` header belongs to
+#[utoipa::path(
+    post,
+    path = "/api/v1/logout",
+    tag = "auth",
+    responses((status = 204))
+)]
+async fn logout(State(server): ApiState, headers: HeaderMap) -> StatusCode {
+    if let Some(token) = bearer_token(&headers) {
+        server.auth.logout(token);
+    }
+    StatusCode::NO_CONTENT
+}
+
+/// A token for downloading one export, for a link that can't send the `Authorization` header.
+/// Give it as `?token=` on `/api/v1/messages/export` within a minute
+#[utoipa::path(
+    post,
+    path = "/api/v1/messages/export/token",
+    tag = "messages",
+    responses(
+        (status = 200, body = ShDownloadToken),
+        (status = 401, body = ShApiError),
+    )
+)]
+async fn export_token(
+    State(server): ApiState,
+    ConnectInfo(peer): ConnectInfo<Peer>,
+    headers: HeaderMap,
+) -> ApiResult<ShDownloadToken> {
+    bearer_token(&headers)
+        .and_then(|token| server.auth.session_user(token))
+        .or_else(|| server.auth.trusted_user(peer.address(&headers)))
+        .map(|user| Json(server.auth.start_download(&user)))
+        .ok_or_else(|| ApiError::new(StatusCode::UNAUTHORIZED, "Please log in first"))
+}
+
+/// The config, as the web interface sees it
+#[utoipa::path(
+    get,
+    path = "/api/v1/config",
+    tag = "config",
+    responses((status = 200, body = ShWebConfig))
+)]
+async fn get_config(State(server): ApiState) -> Json<ShWebConfig> {
+    Json(server.config.lock().await.to_web_config())
+}
+
+/// Change the app, map, data source or alert config. Each part sent is applied in that order,
+/// and an error stops the parts after it being applied
+#[utoipa::path(
+    put,
+    path = "/api/v1/config",
+    tag = "config",
+    request_body = ShConfigUpdate,
+    responses(
+        (status = 200, body = ShConfigUpdateResult),
+        (status = 400, body = ShApiError),
+        (status = 422, description = "The change can't be used", body = ShApiError),
+        (status = 500, description = "The config file could not be written", body = ShApiError),
+    )
+)]
+async fn update_config(
+    State(server): ApiState,
+    update: Result<Json<ShConfigUpdate>, JsonRejection>,
+) -> ApiResult<ShConfigUpdateResult> {
+    let Json(update) = update?;
+    let mut changes = Vec::new();
+
+    if let Some(app) = &update.app {
+        changes.extend(config::update_app_config(&server, app).await?);
+    }
+    if let Some(map) = &update.map {
+        changes.extend(config::update_map_config(&server, map).await?);
+    }
+    if let Some(enabled) = &update.enabled_data_sources {
+        changes.extend(config::update_enabled_data_sources(&server, enabled).await?);
+    }
+    if let Some(sources) = &update.data_sources {
+        changes.extend(config::update_data_sources(&server, sources).await?);
+    }
+    if let Some(alerts) = &update.alerts {
+        changes.extend(config::update_alerts(&server, alerts).await?);
+    }
+
+    Ok(Json(ShConfigUpdateResult {
+        changes,
+        config: server.config.lock().await.to_web_config(),
+    }))
+}
+
+/// Search the stored messages
+#[utoipa::path(
+    get,
+    path = "/api/v1/messages",
+    tag = "messages",
+    params(ShMessageQuery),
+    responses(
+        (status = 200, description = "Matching messages, newest first", body = Vec<ShStoredMessage>),
+        (status = 400, body = ShApiError),
+    )
+)]
+async fn search_messages(
+    State(server): ApiState,
+    query: Result<Query<ShMessageQuery>, QueryRejection>,
+) -> ApiResult<Vec<ShStoredMessage>> {
+    let Query(mut query) = query?;
+    // flights are stored under the same key the hub files them under
+    query.flight = query.flight.map(|flight| server.hub.flight_key(&flight));
+
+    server
+        .hub
+        .database
+        .search_messages(&query)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::database(&e))
+}
+
+/// Download the messages matching a search, oldest first, as CSV or JSON Lines. Every match is
+/// sent, so `limit` and `offset` are ignored. The file is sent as it is read from the database,
+/// so it can be as big as it needs to be. A link that can't send the `Authorization` header
+/// can give a token from `/api/v1/messages/export/token` as `?token=` instead
+#[utoipa::path(
+    get,
+    path = "/api/v1/messages/export",
+    tag = "messages",
+    params(ShMessageQuery, ExportQuery),
+    responses(
+        (status = 200, description = "The file. Each line of JSON Lines is one message", content(
+            (String = "text/csv"),
+            (ShExportedMessage = "application/x-ndjson"),
+        )),
+        (status = 400, body = ShApiError),
+    )
+)]
+async fn export_messages(
+    State(server): ApiState,
+    query: Result<Query<ShMessageQuery>, QueryRejection>,
+    export: Result<Query<ExportQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
+    let Query(mut query) = query?;
+    let Query(export) = export?;
+    let format = match export.format.as_deref() {
+        Some(format) => format
+            .parse::<ShExportFormat>()
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?,
+        None => ShExportFormat::default(),
+    };
+    query.flight = query.flight.map(|flight| server.hub.flight_key(&flight));
+
+    let chunks = stream::unfold(
+        Some(ShMessageExport::new(query, format, export.aircraft_info)),
+        move |export| {
+            let server = Arc::clone(&server);
+            async move {
+                let mut export = export?;
+                match export.next_chunk(&server.hub.database).await {
+                    Ok(chunk) => chunk.map(|chunk| (Ok(chunk), Some(export))),
+                    // the headers have gone, so all that can be done is cut the file short
+                    Err(e) => {
+                        error!("Error exporting messages for the REST API: {e}");
+                        Some((Err(e), None))
+                    }
+                }
+            }
+        },
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"messages.{format}\""),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+/// A single stored message
+#[utoipa::path(
+    get,
+    path = "/api/v1/messages/{id}",
+    tag = "messages",
+    params(("id" = i64, Path, description = "The id the message was stored with")),
+    responses(
+        (status = 200, body = ShStoredMessage),
+        (status = 404, body = ShApiError),
+    )
+)]
+async fn get_message(
+    State(server): ApiState,
+    id: Result<Path<i64>, PathRejection>,
+) -> ApiResult<ShStoredMessage> {
+    let Path(id) = id?;
+
+    match server.hub.database.message(id).await {
+        Ok(Some(message)) => Ok(Json(message)),
+        Ok(None) => Err(ApiError::not_found(format!("Message {id}"))),
+        Err(e) => Err(ApiError::database(&e)),
+    }
+}
+
+/// Aircraft we have flights for
+#[utoipa::path(
+    get,
+    path = "/api/v1/aircraft",
+    tag = "aircraft",
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Aircraft, most recently seen first", body = Vec<ShAircraftSummary>),
+        (status = 400, body = ShApiError),
+    )
+)]
+async fn list_aircraft(
+    State(server): ApiState,
+    query: Result<Query<PageQuery>, QueryRejection>,
+) -> ApiResult<Vec<ShAircraftSummary>> {
+    let Query(query) = query?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_AIRCRAFT_LIMIT)
+        .clamp(1, MAX_AIRCRAFT_LIMIT);
+    let offset = query.offset.unwrap_or_default().max(0);
+
+    server
+        .hub
+        .database
+        .aircraft_list(limit, offset)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::database(&e))
+}
+
+/// An aircraft's recent flights, last position and recent messages
+#[utoipa::path(
+    get,
+    path = "/api/v1/aircraft/{aircraft}",
+    tag = "aircraft",
+    params(("aircraft" = String, Path, description = "ICAO hex address, or registration for aircraft we have no address for")),
+    responses(
+        (status = 200, body = ShAircraftDetail),
+        (status = 404, body = ShApiError),
+    )
+)]
+async fn get_aircraft(
+    State(server): ApiState,
+    aircraft: Result<Path<String>, PathRejection>,
+) -> ApiResult<ShAircraftDetail> {
+    let Path(aircraft) = aircraft?;
+
+    match server.hub.database.aircraft(&aircraft).await {
+        Ok(Some(detail)) => Ok(Json(detail)),
+        Ok(None) => Err(ApiError::not_found(format!("Aircraft {aircraft}"))),
+        Err(e) => Err(ApiError::database(&e)),
+    }
+}
+
+/// Message and aircraft counts over a range
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats",
+    tag = "stats",
+    params(StatsQuery),
+    responses(
+        (status = 200, body = ShStats),
+        (status = 400, body = ShApiError),
+    )
+)]
+async fn get_stats(
+    State(server): ApiState,
+    query: Result<Query<StatsQuery>, QueryRejection>,
+) -> ApiResult<ShStats> {
+    let Query(query) = query?;
+    let range = match query.range.as_deref() {
+        Some(range) => range
+            .parse::<ShStatsRange>()
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?,
+        None => ShStatsRange::default(),
+    };
+
+    // include what has been counted but not written out yet
+    server.hub.flush_stats().await;
+    server
+        .hub
+        .database
+        .stats(range, now())
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::database(&e))
+}
+
+/// How the connection to each configured source is doing
+#[utoipa::path(
+    get,
+    path = "/api/v1/sources",
+    tag = "sources",
+    responses((status = 200, body = Vec<ShSourceStatus>))
+)]
+async fn get_sources(State(server): ApiState) -> Json<Vec<ShSourceStatus>> {
+    Json(server.hub.sources.statuses())
+}
+
+/// Connect to a source that isn't in the config yet, listen to it for a few seconds and say
+/// what it sends. The configured sources aren't touched
+#[utoipa::path(
+    post,
+    path = "/api/v1/sources/test",
+    tag = "sources",
+    request_body = ShSourceTest,
+    responses(
+        (status = 200, description = "What the test found, even if the source can't be used", body = ShSourceTestResult),
+        (status = 400, body = ShApiError),
+    )
+)]
+async fn test_source(
+    test: Result<Json<ShSourceTest>, JsonRejection>,
+) -> ApiResult<ShSourceTestResult> {
+    let Json(test) = test?;
+    Ok(Json(sh_sources::probe::test_source(&test).await))
+}
+
+/// How everything the hub runs, like the web server and the source clients, is doing
+#[utoipa::path(
+    get,
+    path = "/api/v1/consumers",
+    tag = "consumers",
+    responses((status = 200, body = Vec<ShConsumerStatus>))
+)]
+async fn get_consumers(State(server): ApiState) -> Json<Vec<ShConsumerStatus>> {
+    Json(server.hub.consumers.statuses())
+}
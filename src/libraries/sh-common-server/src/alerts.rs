@@ -0,0 +1,344 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::alert::ShAlert;
+use sh_config::alerts::ShAlertRule;
+use sh_config::source::ShEnabledDataSources;
+
+// ADS-B sends a position every second or so. Once a rule has fired for an aircraft it stays
+// quiet until the aircraft has stopped matching for this long, otherwise a squawk rule would
+// raise an alert for every report
+const POSITION_COOLDOWN: f64 = 10.0 * 60.0;
+
+/// A rule with everything it matches on normalized, ready to check
+#[derive(Debug)]
+struct CompiledRule {
+    rule: ShAlertRule,
+    tails: Vec<String>,
+    icao_hexes: Vec<String>,
+    flights: Vec<String>,
+    labels: Vec<String>,
+    text_pattern: Option<Regex>,
+}
+
+/// The parts of a message or position a rule can match on
+struct Candidate<'a> {
+    timestamp: f64,
+    icao_hex: Option<String>,
+    tail: Option<String>,
+    flight: Option<&'a str>,
+    label: Option<String>,
+    text: Option<&'a str>,
+    squawk: Option<&'a str>,
+    source_type: ShEnabledDataSources,
+}
+
+/// `AlertEngine` checks incoming data against the alert rules from the config
+#[derive(Debug, Default)]
+pub struct AlertEngine {
+    rules: Vec<CompiledRule>,
+    /// When each rule last fired for each aircraft, from positions only
+    last_raised: HashMap<(String, String), f64>,
+}
+
+fn normalize_tail(tail: &str) -> String {
+    tail.trim()
+        .trim_start_matches('.')
+        .replace('-', "")
+        .to_uppercase()
+}
+
+const fn source_name(source: &ShEnabledDataSources) -> &'static str {
+    match source {
+        ShEnabledDataSources::Acars => "ACARS",
+        ShEnabledDataSources::Adsb => "ADS-B",
+        ShEnabledDataSources::Hfdl => "HFDL",
+        ShEnabledDataSources::Inmarsat => "Inmarsat",
+        ShEnabledDataSources::Iridium => "Iridium",
+        ShEnabledDataSources::Vdlm2 => "VDLM2",
+        ShEnabledDataSources::None => "none",
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_uppercase()
+}
+
+fn compile_pattern(rule: &ShAlertRule) -> Result<Option<Regex>, String> {
+    rule.text_pattern
+        .as_deref()
+        .filter(|pattern| !pattern.is_empty())
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| {
+            format!(
+                "Alert rule \"{}\" has an invalid text pattern: {e}",
+                rule.name
+            )
+        })
+}
+
+impl AlertEngine {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check a set of rules can be used
+    ///
+    /// # Errors
+    /// Returns a description of every problem found
+    pub fn validate(rules: &[ShAlertRule], notification_targets: &[String]) -> Result<(), String> {
+        let mut problems = Vec::new();
+        let mut names = HashSet::new();
+
+        for rule in rules {
+            if rule.name.trim().is_empty() {
+                problems.push("Alert rules must have a name".to_string());
+            } else if !names.insert(rule.name.trim()) {
+                problems.push(format!(
+                    "More than one alert rule is named \"{}\"",
+                    rule.name
+                ));
+            }
+
+            if !rule.has_criteria() {
+                problems.push(format!(
+                    "Alert rule \"{}\" has nothing to match on",
+                    rule.name
+                ));
+            }
+
+            if rule.wants_messages() && rule.wants_positions() {
+                problems.push(format!(
+                    "Alert rule \"{}\" can never match as it has both message and ADS-B only fields",
+                    rule.name
+                ));
+            }
+
+            if let Err(e) = compile_pattern(rule) {
+                problems.push(e);
+            }
+
+            for target in &rule.notify {
+                if !notification_targets.contains(target) {
+                    problems.push(format!(
+                        "Alert rule \"{}\" sends to \"{target}\", which is not a notification target",
+                        rule.name
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join(", "))
+        }
+    }
+
+    /// Replace the rules. Flight numbers are stored in the form `flight_key` gives back so they
+    /// match however the aircraft sends them. Rules that can't be used are skipped
+    pub fn set_rules(&mut self, rules: &[ShAlertRule], flight_key: impl Fn(&str) -> String) {
+        self.rules = rules
+            .iter()
+            .filter_map(|rule| {
+                let text_pattern = match compile_pattern(rule) {
+                    Ok(pattern) => pattern,
+                    Err(e) => {
+                        error!("{e}. Skipping the rule");
+                        return None;
+                    }
+                };
+
+                Some(CompiledRule {
+                    rule: rule.clone(),
+                    tails: rule.tails.iter().map(|tail| normalize_tail(tail)).collect(),
+                    icao_hexes: rule.icao_hexes.iter().map(|hex| normalize(hex)).collect(),
+                    flights: rule
+                        .flights
+                        .iter()
+                        .map(|flight| flight_key(flight))
+                        .collect(),
+                    labels: rule.labels.iter().map(|label| normalize(label)).collect(),
+                    text_pattern,
+                })
+            })
+            .collect();
+
+        self.last_raised.clear();
+        info!("Loaded {} alert rules", self.rules.len());
+    }
+
+    /// The notification targets alerts from `rule` are sent to
+    #[must_use]
+    pub fn notify_targets(&self, rule: &str) -> Vec<String> {
+        self.rules
+            .iter()
+            .find(|compiled| compiled.rule.name == rule)
+            .map(|compiled| compiled.rule.notify.clone())
+            .unwrap_or_default()
+    }
+
+    /// Alerts raised by a message. `flight` is the normalized flight number
+    #[must_use]
+    pub fn check_message(&self, message: &ShAcarsMessage, flight: Option<&str>) -> Vec<ShAlert> {
+        let candidate = Candidate {
+            timestamp: message.timestamp,
+            icao_hex: message.icao_hex.as_deref().map(normalize),
+            tail: message.tail.as_deref().map(normalize_tail),
+            flight,
+            label: message.label.as_deref().map(normalize),
+            text: message.text.as_deref(),
+            squawk: None,
+            source_type: message.message_type.source_type(),
+        };
+
+        self.rules
+            .iter()
+            .filter(|compiled| !compiled.rule.wants_positions())
+            .filter_map(|compiled| {
+                let mut alert = compiled.check(&candidate)?;
+                alert.flight.clone_from(&message.flight);
+                alert.message = Some(Box::new(message.clone()));
+                Some(alert)
+            })
+            .collect()
+    }
+
+    /// Alerts raised by an ADS-B position. `flight` is the normalized callsign
+    pub fn check_position(
+        &mut self,
+        position: &ShAdsbPosition,
+        flight: Option<&str>,
+    ) -> Vec<ShAlert> {
+        let candidate = Candidate {
+            timestamp: position.timestamp,
+            icao_hex: Some(normalize(&position.icao_hex)),
+            tail: None,
+            flight,
+            label: None,
+            text: None,
+            squawk: position.squawk.as_deref().map(str::trim),
+            source_type: ShEnabledDataSources::Adsb,
+        };
+
+        let mut alerts = Vec::new();
+        for compiled in self
+            .rules
+            .iter()
+            .filter(|compiled| !compiled.rule.wants_messages())
+        {
+            let Some(mut alert) = compiled.check(&candidate) else {
+                continue;
+            };
+
+            let key = (compiled.rule.name.clone(), position.icao_hex.clone());
+            let cooling_down = self
+                .last_raised
+                .get(&key)
+                .is_some_and(|last| position.timestamp - last < POSITION_COOLDOWN);
+            self.last_raised.insert(key, position.timestamp);
+            if cooling_down {
+                continue;
+            }
+
+            alert.flight.clone_from(&position.callsign);
+            alert.position = Some(position.clone());
+            alerts.push(alert);
+        }
+
+        alerts
+    }
+
+    /// Forget cooldowns that have run out
+    pub fn prune(&mut self, now: f64) {
+        self.last_raised
+            .retain(|_, last| now - *last < POSITION_COOLDOWN);
+    }
+}
+
+impl CompiledRule {
+    /// The alert, without the message or position, if the rule matches
+    fn check(&self, candidate: &Candidate) -> Option<ShAlert> {
+        let rule = &self.rule;
+        if !rule.enabled {
+            return None;
+        }
+
+        let mut matched = Vec::new();
+
+        if !self.tails.is_empty() {
+            let tail = candidate
+                .tail
+                .as_ref()
+                .filter(|tail| self.tails.contains(tail))?;
+            matched.push(format!("tail {tail}"));
+        }
+
+        if !self.icao_hexes.is_empty() {
+            let hex = candidate
+                .icao_hex
+                .as_ref()
+                .filter(|hex| self.icao_hexes.contains(hex))?;
+            matched.push(format!("ICAO {hex}"));
+        }
+
+        if !self.flights.is_empty() {
+            let flight = candidate
+                .flight
+                .filter(|flight| self.flights.iter().any(|rule_flight| rule_flight == flight))?;
+            matched.push(format!("flight {flight}"));
+        }
+
+        if !self.labels.is_empty() {
+            let label = candidate
+                .label
+                .as_ref()
+                .filter(|label| self.labels.contains(label))?;
+            matched.push(format!("label {label}"));
+        }
+
+        if !rule.squawks.is_empty() {
+            let squawk = candidate.squawk.filter(|squawk| {
+                rule.squawks
+                    .iter()
+                    .any(|rule_squawk| rule_squawk.trim() == *squawk)
+            })?;
+            matched.push(format!("squawk {squawk}"));
+        }
+
+        if !rule.source_types.is_empty() {
+            if !rule.source_types.contains(&candidate.source_type) {
+                return None;
+            }
+            matched.push(format!("source {}", source_name(&candidate.source_type)));
+        }
+
+        if let Some(pattern) = &self.text_pattern {
+            if !candidate.text.is_some_and(|text| pattern.is_match(text)) {
+                return None;
+            }
+            matched.push(format!("text /{pattern}/"));
+        }
+
+        Some(ShAlert {
+            id: 0,
+            rule: rule.name.clone(),
+            timestamp: candidate.timestamp,
+            icao_hex: candidate.icao_hex.clone(),
+            tail: candidate.tail.clone(),
+            flight: None,
+            description: matched.join(", "),
+            message: None,
+            position: None,
+        })
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sh_common::consumer_status::{ShConsumerState, ShConsumerStatus, ShRestartPolicy};
+use sh_common::{MessageData, ServerMessageTypes, ServerWssMessage};
+use tokio::sync::broadcast;
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+/// `ConsumerMonitor` keeps track of how everything the hub runs is doing, as the supervisor
+/// starts, restarts and gives up on them, and users are sent each change
+pub struct ConsumerMonitor {
+    // In the order the consumers were started
+    statuses: Mutex<Vec<ShConsumerStatus>>,
+    server_messages: broadcast::Sender<ServerWssMessage>,
+}
+
+impl ConsumerMonitor {
+    #[must_use]
+    pub fn new(server_messages: broadcast::Sender<ServerWssMessage>) -> Self {
+        Self {
+            statuses: Mutex::new(Vec::new()),
+            server_messages,
+        }
+    }
+
+    fn statuses_mut(&self) -> MutexGuard<'_, Vec<ShConsumerStatus>> {
+        self.statuses
+            .lock()
+            .expect("consumer statuses should not be poisoned")
+    }
+
+    /// Change the status of `consumer` and let users know. Consumers nobody added are ignored
+    fn update(&self, consumer: &str, change: impl FnOnce(&mut ShConsumerStatus)) {
+        let mut statuses = self.statuses_mut();
+        let Some(status) = statuses.iter_mut().find(|status| status.name == consumer) else {
+            return;
+        };
+
+        change(status);
+        let status = status.clone();
+        drop(statuses);
+        self.send(
+            ServerMessageTypes::ServerConsumerStatus,
+            MessageData::ShConsumerStatus(status),
+        );
+    }
+
+    fn send(&self, message_type: ServerMessageTypes, data: MessageData) {
+        if self
+            .server_messages
+            .send(ServerWssMessage::new(message_type, data))
+            .is_err()
+        {
+            trace!("No users connected to receive the consumer status");
+        }
+    }
+
+    /// Start following a consumer that has just been started. One that was already followed
+    /// starts over with a clean status
+    pub fn add(&self, consumer: &str, policy: ShRestartPolicy) {
+        let status = ShConsumerStatus::new(consumer.to_string(), policy, now());
+
+        let mut statuses = self.statuses_mut();
+        match statuses.iter_mut().find(|known| known.name == consumer) {
+            Some(known) => *known = status.clone(),
+            None => statuses.push(status.clone()),
+        }
+        drop(statuses);
+
+        self.send(
+            ServerMessageTypes::ServerConsumerStatus,
+            MessageData::ShConsumerStatus(status),
+        );
+    }
+
+    /// Stop following a consumer that has been stopped for good. Users get the whole list
+    /// again, as there is no message for a single consumer going away
+    pub fn remove(&self, consumer: &str) {
+        let statuses = {
+            let mut statuses = self.statuses_mut();
+            statuses.retain(|status| status.name != consumer);
+            statuses.clone()
+        };
+
+        self.send(
+            ServerMessageTypes::ServerResponseConsumerStatus,
+            MessageData::ShConsumerStatuses(statuses),
+        );
+    }
+
+    /// `consumer` has been started again after waiting out its backoff
+    pub fn running(&self, consumer: &str) {
+        self.update(consumer, |status| {
+            status.state = ShConsumerState::Running;
+            status.since = now();
+            status.restart_at = None;
+        });
+    }
+
+    /// `consumer` stopped on its own, with `error` if it failed, and will be started again
+    /// after `delay`
+    pub fn restarting(&self, consumer: &str, error: Option<String>, delay: Duration) {
+        self.update(consumer, |status| {
+            let now = now();
+            status.state = ShConsumerState::Restarting;
+            status.since = now;
+            status.restart_at = Some(now + delay.as_secs_f64());
+            status.restarts += 1;
+            if error.is_some() {
+                status.last_error = error;
+            }
+        });
+    }
+
+    /// `consumer` stopped on its own and won't be started again
+    pub fn ended(&self, consumer: &str, state: ShConsumerState, error: Option<String>) {
+        self.update(consumer, |status| {
+            status.state = state;
+            status.since = now();
+            status.restart_at = None;
+            if error.is_some() {
+                status.last_error = error;
+            }
+        });
+    }
+
+    /// Every consumer, in the order they were started
+    #[must_use]
+    pub fn statuses(&self) -> Vec<ShConsumerStatus> {
+        self.statuses_mut().clone()
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// The built in emergency detector. Unlike alert rules there is nothing to set up, it watches
+// every position for the emergency squawks and every message for distress phrasing
+
+use std::collections::HashMap;
+
+use regex::{Regex, RegexBuilder};
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::emergency::{ShEmergency, ShEmergencyKind};
+
+// A squawk emergency is over once the aircraft hasn't been heard from for this long
+const SQUAWK_LOST: f64 = 10.0 * 60.0;
+// A distress emergency is over once the aircraft has gone this long without another distress
+// message. There's nothing in a message that says the emergency has ended
+const DISTRESS_QUIET: f64 = 30.0 * 60.0;
+// How often users are sent the new location of an aircraft in an emergency, so the map pin
+// follows it without a message for every position
+const LOCATION_UPDATE: f64 = 30.0;
+// Longest bit of message text put on an emergency's timeline
+const MAX_EXCERPT: usize = 200;
+
+// Phrasing that only turns up in messages when something has gone wrong. Plain "EMERGENCY"
+// isn't here as it shows up in routine messages about emergency equipment
+const DISTRESS_PHRASES: &[&str] = &[
+    "MAYDAY",
+    r"PAN[\s-]?PAN",
+    r"HIJACK(?:ED|ING)?",
+    r"UNLAWFUL\s+INTERFERENCE",
+    r"BOMB\s+THREAT",
+    r"DECLAR(?:E|ED|ING)\s+(?:AN\s+)?EMERGENCY",
+    r"EMERGENCY\s+(?:DESCENT|LANDING)",
+    r"FUEL\s+EMERGENCY",
+    r"SMOKE\s+IN\s+(?:THE\s+)?(?:CABIN|COCKPIT|FLIGHT\s+DECK)",
+    r"FIRE\s+ON\s+BOARD",
+    r"ENGINE\s+FIRE",
+];
+
+/// An emergency that is still going, and what we need to know to decide when it is over
+#[derive(Debug)]
+struct Tracked {
+    emergency: ShEmergency,
+    /// When we last heard something that keeps the emergency going
+    last_heard: f64,
+    /// When users were last sent the emergency
+    last_sent: f64,
+}
+
+impl Tracked {
+    fn new(emergency: ShEmergency) -> Self {
+        let started = emergency.started;
+        Self {
+            emergency,
+            last_heard: started,
+            last_sent: started,
+        }
+    }
+
+    /// Move the aircraft along. True if users are due the new location
+    fn observe_position(&mut self, position: &ShAdsbPosition) -> bool {
+        let emergency = &mut self.emergency;
+        let before = emergency.location();
+
+        if position.latitude.is_some() && position.longitude.is_some() {
+            emergency.latitude = position.latitude;
+            emergency.longitude = position.longitude;
+        }
+
+        if position.altitude.is_some() {
+            emergency.altitude = position.altitude;
+        }
+
+        if emergency.flight.is_none() {
+            emergency.flight = position
+                .callsign
+                .as_deref()
+                .map(|callsign| callsign.trim().to_string());
+        }
+
+        emergency.location() != before && position.timestamp - self.last_sent >= LOCATION_UPDATE
+    }
+
+    /// A copy to store and send out
+    fn snapshot(&mut self, timestamp: f64) -> ShEmergency {
+        self.last_sent = timestamp;
+        self.emergency.clone()
+    }
+
+    /// End the emergency, giving back its final state
+    fn clear(mut self, timestamp: f64, reason: String) -> ShEmergency {
+        self.emergency.add_event(timestamp, reason);
+        self.emergency.cleared = Some(timestamp);
+        self.emergency
+    }
+}
+
+/// `EmergencyDetector` raises an emergency when an aircraft squawks 7500, 7600 or 7700 or sends
+/// a message with distress phrasing. Each aircraft has at most one of each going at once, later
+/// reports are added to its timeline instead of raising it again
+#[derive(Debug)]
+pub struct EmergencyDetector {
+    distress: Regex,
+    /// Squawk emergencies by ICAO hex
+    squawks: HashMap<String, Tracked>,
+    /// Distress emergencies by ICAO hex, or tail if the message had no hex
+    distress_calls: HashMap<String, Tracked>,
+}
+
+impl Default for EmergencyDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_uppercase()
+}
+
+fn normalize_tail(tail: &str) -> String {
+    tail.trim()
+        .trim_start_matches('.')
+        .replace('-', "")
+        .to_uppercase()
+}
+
+/// The message text on one line, cut down to something that fits on a timeline
+fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    if text.chars().count() > MAX_EXCERPT {
+        format!("{}...", text.chars().take(MAX_EXCERPT).collect::<String>())
+    } else {
+        text
+    }
+}
+
+fn describe_message(message: &ShAcarsMessage, text: &str) -> String {
+    message.label.as_deref().map_or_else(
+        || format!("Message: {}", excerpt(text)),
+        |label| format!("Message ({label}): {}", excerpt(text)),
+    )
+}
+
+impl EmergencyDetector {
+    /// # Panics
+    /// The distress phrases are not a valid regex
+    #[must_use]
+    pub fn new() -> Self {
+        let distress = RegexBuilder::new(&format!(r"\b(?:{})\b", DISTRESS_PHRASES.join("|")))
+            .case_insensitive(true)
+            .build()
+            .expect("distress phrases should be a valid regex");
+
+        Self {
+            distress,
+            squawks: HashMap::new(),
+            distress_calls: HashMap::new(),
+        }
+    }
+
+    /// Emergencies that started, changed or ended because of a position
+    pub fn check_position(&mut self, position: &ShAdsbPosition) -> Vec<ShEmergency> {
+        let hex = normalize(&position.icao_hex);
+        let timestamp = position.timestamp;
+        let squawk = position.squawk.as_deref().map(str::trim);
+        let kind = squawk.and_then(ShEmergencyKind::from_squawk);
+        let mut changed = Vec::new();
+
+        if let Some(mut tracked) = self.squawks.remove(&hex) {
+            let moved = tracked.observe_position(position);
+            tracked.last_heard = timestamp;
+
+            match (kind, squawk) {
+                (Some(kind), _) if kind != tracked.emergency.kind => {
+                    tracked
+                        .emergency
+                        .add_event(timestamp, format!("Squawk changed to {kind}"));
+                    tracked.emergency.kind = kind;
+                    changed.push(tracked.snapshot(timestamp));
+                }
+                (None, Some(squawk)) => {
+                    changed.push(tracked.clear(
+                        timestamp,
+                        format!("Squawk changed to {squawk}, emergency over"),
+                    ));
+                    return changed;
+                }
+                _ if moved => changed.push(tracked.snapshot(timestamp)),
+                _ => (),
+            }
+
+            self.squawks.insert(hex.clone(), tracked);
+        } else if let Some(kind) = kind {
+            let mut emergency = ShEmergency {
+                id: 0,
+                kind,
+                icao_hex: Some(hex.clone()),
+                tail: None,
+                flight: None,
+                started: timestamp,
+                cleared: None,
+                latitude: None,
+                longitude: None,
+                altitude: None,
+                events: Vec::new(),
+            };
+            emergency.add_event(timestamp, format!("Squawking {kind}"));
+
+            let mut tracked = Tracked::new(emergency);
+            tracked.observe_position(position);
+            changed.push(tracked.snapshot(timestamp));
+            self.squawks.insert(hex.clone(), tracked);
+        }
+
+        // distress calls follow the aircraft around the map too
+        if let Some(tracked) = self.distress_calls.get_mut(&hex) {
+            if tracked.observe_position(position) {
+                changed.push(tracked.snapshot(timestamp));
+            }
+        }
+
+        changed
+    }
+
+    /// Emergencies that started or changed because of a message
+    pub fn check_message(&mut self, message: &ShAcarsMessage) -> Vec<ShEmergency> {
+        let Some(text) = message
+            .text
+            .as_deref()
+            .filter(|text| !text.trim().is_empty())
+        else {
+            return Vec::new();
+        };
+
+        let hex = message.icao_hex.as_deref().map(normalize);
+        let tail = message.tail.as_deref().map(normalize_tail);
+        let Some(key) = hex.clone().or_else(|| tail.clone()) else {
+            return Vec::new();
+        };
+
+        let timestamp = message.timestamp;
+        let phrase = self
+            .distress
+            .find(text)
+            .map(|found| excerpt(found.as_str()).to_uppercase());
+
+        // anything said during a squawk emergency is part of its story, distress or not
+        if let Some(tracked) = hex.as_ref().and_then(|hex| self.squawks.get_mut(hex)) {
+            let emergency = &mut tracked.emergency;
+            if emergency.tail.is_none() {
+                emergency.tail.clone_from(&tail);
+            }
+            if emergency.flight.is_none() {
+                emergency.flight.clone_from(&message.flight);
+            }
+
+            let description = describe_message(message, text);
+            emergency.add_event(
+                timestamp,
+                phrase.map_or(description.clone(), |phrase| {
+                    format!("{description} (distress phrasing \"{phrase}\")")
+                }),
+            );
+            return vec![tracked.snapshot(timestamp)];
+        }
+
+        let Some(phrase) = phrase else {
+            return Vec::new();
+        };
+
+        let description = format!("{} (\"{phrase}\")", describe_message(message, text));
+        if let Some(tracked) = self.distress_calls.get_mut(&key) {
+            tracked.last_heard = timestamp;
+            tracked.emergency.add_event(timestamp, description);
+            return vec![tracked.snapshot(timestamp)];
+        }
+
+        let mut emergency = ShEmergency {
+            id: 0,
+            kind: ShEmergencyKind::Distress,
+            icao_hex: hex,
+            tail,
+            flight: message.flight.clone(),
+            started: timestamp,
+            cleared: None,
+            latitude: None,
+            longitude: None,
+            altitude: None,
+            events: Vec::new(),
+        };
+        emergency.add_event(timestamp, description);
+
+        let mut tracked = Tracked::new(emergency);
+        let snapshot = tracked.snapshot(timestamp);
+        self.distress_calls.insert(key, tracked);
+
+        vec![snapshot]
+    }
+
+    /// Note the id an emergency was stored with, so later changes update it
+    pub fn stored(&mut self, emergency: &ShEmergency) {
+        let tracked = self
+            .squawks
+            .values_mut()
+            .chain(self.distress_calls.values_mut())
+            .find(|tracked| {
+                let known = &tracked.emergency;
+                known.id == 0
+                    && known.started.total_cmp(&emergency.started).is_eq()
+                    && known.icao_hex == emergency.icao_hex
+                    && known.tail == emergency.tail
+            });
+
+        if let Some(tracked) = tracked {
+            tracked.emergency.id = emergency.id;
+        }
+    }
+
+    /// End emergencies that have gone quiet, giving back each one that ended
+    pub fn prune(&mut self, now: f64) -> Vec<ShEmergency> {
+        let mut cleared = Vec::new();
+
+        let lost: Vec<String> = self
+            .squawks
+            .iter()
+            .filter(|(_, tracked)| now - tracked.last_heard >= SQUAWK_LOST)
+            .map(|(hex, _)| hex.clone())
+            .collect();
+        for hex in lost {
+            if let Some(tracked) = self.squawks.remove(&hex) {
+                cleared
+                    .push(tracked.clear(now, "No longer heard from, emergency over".to_string()));
+            }
+        }
+
+        let quiet: Vec<String> = self
+            .distress_calls
+            .iter()
+            .filter(|(_, tracked)| now - tracked.last_heard >= DISTRESS_QUIET)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in quiet {
+            if let Some(tracked) = self.distress_calls.remove(&key) {
+                cleared.push(tracked.clear(
+                    now,
+                    "No further distress messages, emergency over".to_string(),
+                ));
+            }
+        }
+
+        cleared
+    }
+}
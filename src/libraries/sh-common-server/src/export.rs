@@ -0,0 +1,180 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Exporting stored messages for other tools, like spreadsheets and pandas. Messages are read a
+// page at a time and handed out as chunks of the file, so a large export is never held in
+// memory. The REST API streams the chunks, and the command line writes them to a file
+
+use std::io;
+
+use sh_common::api::{ShExportFormat, ShExportedMessage, ShMessageQuery};
+use sh_database::{ExportCursor, ShDatabase};
+
+// Messages read from the database for each chunk
+const PAGE_SIZE: i64 = 1000;
+
+const MESSAGE_COLUMNS: [&str; 19] = [
+    "id",
+    "flight_id",
+    "timestamp",
+    "message_type",
+    "decoder",
+    "source",
+    "station_id",
+    "frequency",
+    "signal_level",
+    "direction",
+    "tail",
+    "flight",
+    "icao_hex",
+    "label",
+    "block_id",
+    "message_number",
+    "text",
+    "ground_station_id",
+    "ground_station_name",
+];
+
+// Prefixed, as the message has its own tail and ICAO hex address
+const AIRCRAFT_COLUMNS: [&str; 6] = [
+    "aircraft",
+    "aircraft_icao_hex",
+    "aircraft_tail",
+    "aircraft_callsign",
+    "flight_start",
+    "flight_end",
+];
+
+/// An export of the messages matching a query, oldest first
+pub struct ShMessageExport {
+    query: ShMessageQuery,
+    format: ShExportFormat,
+    with_aircraft: bool,
+    after: Option<ExportCursor>,
+    started: bool,
+    finished: bool,
+    exported: usize,
+}
+
+impl ShMessageExport {
+    /// `with_aircraft` adds what is known about the aircraft each message was tied to
+    #[must_use]
+    pub const fn new(query: ShMessageQuery, format: ShExportFormat, with_aircraft: bool) -> Self {
+        Self {
+            query,
+            format,
+            with_aircraft,
+            after: None,
+            started: false,
+            finished: false,
+            exported: 0,
+        }
+    }
+
+    /// How many messages have been handed out so far
+    #[must_use]
+    pub const fn exported(&self) -> usize {
+        self.exported
+    }
+
+    /// The next part of the file, or `None` once all of it has been handed out. A CSV always
+    /// starts with its header, even if nothing matched
+    ///
+    /// # Errors
+    /// The messages couldn't be read
+    pub async fn next_chunk(&mut self, database: &ShDatabase) -> io::Result<Option<Vec<u8>>> {
+        let mut chunk = Vec::new();
+        if !self.started {
+            self.started = true;
+            if self.format == ShExportFormat::Csv {
+                let mut header = MESSAGE_COLUMNS.to_vec();
+                if self.with_aircraft {
+                    header.extend(AIRCRAFT_COLUMNS);
+                }
+                write_csv_record(&mut chunk, &header)?;
+            }
+        }
+
+        // a page can come back empty if none of it could be read, so keep going until there is
+        // something to hand out
+        while !self.finished {
+            let (messages, next) = database
+                .export_messages(&self.query, self.after, PAGE_SIZE, self.with_aircraft)
+                .await
+                .map_err(io::Error::other)?;
+            self.finished = next.is_none();
+            self.after = next.or(self.after);
+
+            for message in &messages {
+                match self.format {
+                    ShExportFormat::Csv => {
+                        write_csv_record(&mut chunk, &csv_record(message, self.with_aircraft))?;
+                    }
+                    ShExportFormat::Jsonl => {
+                        serde_json::to_writer(&mut chunk, message)?;
+                        chunk.push(b'\n');
+                    }
+                }
+            }
+            self.exported += messages.len();
+
+            if !messages.is_empty() {
+                break;
+            }
+        }
+
+        Ok(Some(chunk).filter(|chunk| !chunk.is_empty()))
+    }
+}
+
+fn write_csv_record(chunk: &mut Vec<u8>, record: &[impl AsRef<[u8]>]) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(chunk);
+    writer.write_record(record)?;
+    writer.flush()
+}
+
+fn optional(value: Option<&impl ToString>) -> String {
+    value.map(ToString::to_string).unwrap_or_default()
+}
+
+/// The message as a row, in the same order as the header
+fn csv_record(exported: &ShExportedMessage, with_aircraft: bool) -> Vec<String> {
+    let message = &exported.message;
+    let mut record = vec![
+        exported.id.to_string(),
+        optional(exported.flight_id.as_ref()),
+        message.timestamp.to_string(),
+        message.message_type.to_string(),
+        optional(message.decoder.as_ref()),
+        message.source.clone(),
+        optional(message.station_id.as_ref()),
+        optional(message.frequency.as_ref()),
+        optional(message.signal_level.as_ref()),
+        format!("{:?}", message.direction),
+        optional(message.tail.as_ref()),
+        optional(message.flight.as_ref()),
+        optional(message.icao_hex.as_ref()),
+        optional(message.label.as_ref()),
+        optional(message.block_id.as_ref()),
+        optional(message.message_number.as_ref()),
+        optional(message.text.as_ref()),
+        optional(message.ground_station_id.as_ref()),
+        optional(message.ground_station_name.as_ref()),
+    ];
+
+    if with_aircraft {
+        let aircraft = exported.aircraft.as_ref();
+        record.extend([
+            optional(aircraft.map(|aircraft| &aircraft.aircraft)),
+            optional(aircraft.and_then(|aircraft| aircraft.icao_hex.as_ref())),
+            optional(aircraft.and_then(|aircraft| aircraft.tail.as_ref())),
+            optional(aircraft.and_then(|aircraft| aircraft.callsign.as_ref())),
+            optional(aircraft.map(|aircraft| &aircraft.flight_start)),
+            optional(aircraft.map(|aircraft| &aircraft.flight_end)),
+        ]);
+    }
+
+    record
+}
@@ -3,21 +3,40 @@
 // license that can be found in the LICENSE file or at
 // https://opensource.org/licenses/MIT.
 
+#[macro_use]
+extern crate log;
+
+pub mod alerts;
+pub mod consumers;
+pub mod emergency;
+pub mod export;
+pub mod lifecycle;
+pub mod metrics;
+pub mod source_data;
+pub mod sources;
+pub mod state;
+pub mod stats;
+pub mod timeline;
+pub mod weather;
+
 use async_trait::async_trait;
 use sh_common::ServerType;
 use sh_config::ShConfig;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// `ShDataUser` is anything the hub runs alongside itself. `start` runs until the consumer is
+/// stopped, and `stop` and `restart` are called while it is running, usually through a
+/// `lifecycle::Lifecycle`
 #[async_trait]
 pub trait ShDataUser {
     async fn start(
         &self,
         data: Option<Arc<Mutex<ShConfig>>>,
     ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Make `start` return. A stopped consumer isn't started again
     fn stop(&self);
+    /// Start over, picking up the current config
     fn restart(&self);
     fn get_server_type(&self) -> ServerType;
 }
-
-pub type ShDataUserList = Vec<Box<dyn ShDataUser + Send + Sync>>;
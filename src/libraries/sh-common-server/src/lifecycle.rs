@@ -0,0 +1,79 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use tokio::sync::watch;
+
+/// What a running consumer has been asked to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleSignal {
+    /// Finish up and return from `start`
+    Stop,
+    /// Drop what it is doing and start over, picking up any config changes
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Requests {
+    restarts: u64,
+    stopped: bool,
+}
+
+/// `Lifecycle` is how a consumer is told to stop or restart while its `start` is running.
+/// Stopping is for good, a stopped consumer is replaced rather than started again
+pub struct Lifecycle {
+    requests: watch::Sender<Requests>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lifecycle {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            requests: watch::Sender::new(Requests::default()),
+        }
+    }
+
+    pub fn stop(&self) {
+        self.requests
+            .send_modify(|requests| requests.stopped = true);
+    }
+
+    pub fn restart(&self) {
+        self.requests
+            .send_modify(|requests| requests.restarts = requests.restarts.wrapping_add(1));
+    }
+
+    /// Watch for signals sent from now on
+    #[must_use]
+    pub fn watch(&self) -> LifecycleWatch {
+        LifecycleWatch(self.requests.subscribe())
+    }
+}
+
+/// The side of a `Lifecycle` that `start` waits on. Clones see the same signals
+#[derive(Debug, Clone)]
+pub struct LifecycleWatch(watch::Receiver<Requests>);
+
+impl LifecycleWatch {
+    /// Wait for the next signal. Safe to use in `select!`, nothing is missed if it is dropped
+    pub async fn signalled(&mut self) -> LifecycleSignal {
+        // a stop that came in before we started watching still counts
+        if self.0.borrow().stopped {
+            return LifecycleSignal::Stop;
+        }
+
+        // nobody can signal us any more if the `Lifecycle` has gone, so we are done
+        if self.0.changed().await.is_err() || self.0.borrow_and_update().stopped {
+            LifecycleSignal::Stop
+        } else {
+            LifecycleSignal::Restart
+        }
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Counters and gauges for the API server's /metrics endpoint, in the Prometheus text format.
+// Everything that produces data holds a handle to the one `ShMetrics` and updates it as it goes
+
+use std::fmt;
+use std::sync::atomic::AtomicU64;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prometheus_client::encoding::{text::encode, EncodeLabelSet};
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::{Registry, Unit};
+
+/// Gives back how many items are waiting in a queue
+type QueueProbe = Box<dyn Fn() -> usize + Send + Sync>;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct SourceLabels {
+    source: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct MessageLabels {
+    source: String,
+    decoder: String,
+    message_type: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct OperationLabels {
+    operation: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ConsumerLabels {
+    consumer: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct QueueLabels {
+    queue: String,
+}
+
+fn database_write_histogram() -> Histogram {
+    // 0.5ms up to about a second
+    Histogram::new(exponential_buckets(0.0005, 2.0, 12))
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+fn source_labels(source: &str) -> SourceLabels {
+    SourceLabels {
+        source: source.to_string(),
+    }
+}
+
+/// `ShMetrics` is everything the hub reports on its /metrics endpoint
+pub struct ShMetrics {
+    registry: Registry,
+    messages: Family<MessageLabels, Counter>,
+    positions: Family<SourceLabels, Counter>,
+    parse_errors: Family<SourceLabels, Counter>,
+    reconnects: Family<SourceLabels, Counter>,
+    connected: Family<SourceLabels, Gauge>,
+    last_data: Family<SourceLabels, Gauge<f64, AtomicU64>>,
+    aircraft_tracked: Gauge,
+    websocket_clients: Gauge,
+    database_writes: Family<OperationLabels, Histogram, fn() -> Histogram>,
+    queue_depth: Family<QueueLabels, Gauge>,
+    consumer_restarts: Family<ConsumerLabels, Counter>,
+    queue_probes: Mutex<Vec<(String, QueueProbe)>>,
+}
+
+impl Default for ShMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShMetrics {
+    #[must_use]
+    pub fn new() -> Self {
+        let mut registry = Registry::with_prefix("sdrehub");
+
+        let messages = Family::<MessageLabels, Counter>::default();
+        registry.register(
+            "messages",
+            "ACARS messages received, by source, decoder and message type",
+            messages.clone(),
+        );
+
+        let positions = Family::<SourceLabels, Counter>::default();
+        registry.register(
+            "adsb_positions",
+            "ADS-B reports received, by source",
+            positions.clone(),
+        );
+
+        let parse_errors = Family::<SourceLabels, Counter>::default();
+        registry.register(
+            "parse_errors",
+            "Data from a source that could not be decoded",
+            parse_errors.clone(),
+        );
+
+        let reconnects = Family::<SourceLabels, Counter>::default();
+        registry.register(
+            "source_reconnects",
+            "Times the connection to a source was lost or could not be made",
+            reconnects.clone(),
+        );
+
+        let connected = Family::<SourceLabels, Gauge>::default();
+        registry.register(
+            "source_connected",
+            "1 while the hub is connected to a source",
+            connected.clone(),
+        );
+
+        let last_data = Family::<SourceLabels, Gauge<f64, AtomicU64>>::default();
+        registry.register_with_unit(
+            "source_last_data_timestamp",
+            "Unix time data was last received from a source",
+            Unit::Seconds,
+            last_data.clone(),
+        );
+
+        let aircraft_tracked = Gauge::default();
+        registry.register(
+            "aircraft_tracked",
+            "Aircraft with an ADS-B position in the last minute",
+            aircraft_tracked.clone(),
+        );
+
+        let websocket_clients = Gauge::default();
+        registry.register(
+            "websocket_clients",
+            "Users connected to the web interface",
+            websocket_clients.clone(),
+        );
+
+        let database_writes =
+            Family::<OperationLabels, Histogram, fn() -> Histogram>::new_with_constructor(
+                database_write_histogram,
+            );
+        registry.register_with_unit(
+            "database_write",
+            "Time taken to write to the database, by what was written",
+            Unit::Seconds,
+            database_writes.clone(),
+        );
+
+        let queue_depth = Family::<QueueLabels, Gauge>::default();
+        registry.register(
+            "queue_depth",
+            "Items waiting in the hub's internal queues",
+            queue_depth.clone(),
+        );
+
+        let consumer_restarts = Family::<ConsumerLabels, Counter>::default();
+        registry.register(
+            "consumer_restarts",
+            "Times a consumer stopped on its own and was started again",
+            consumer_restarts.clone(),
+        );
+
+        Self {
+            registry,
+            messages,
+            positions,
+            parse_errors,
+            reconnects,
+            connected,
+            last_data,
+            aircraft_tracked,
+            websocket_clients,
+            database_writes,
+            queue_depth,
+            consumer_restarts,
+            queue_probes: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn message_received(&self, source: &str, decoder: &str, message_type: &str) {
+        self.messages
+            .get_or_create(&MessageLabels {
+                source: source.to_string(),
+                decoder: decoder.to_string(),
+                message_type: message_type.to_string(),
+            })
+            .inc();
+        self.data_received(source);
+    }
+
+    pub fn position_received(&self, source: &str) {
+        self.positions.get_or_create(&source_labels(source)).inc();
+        self.data_received(source);
+    }
+
+    fn data_received(&self, source: &str) {
+        self.last_data
+            .get_or_create(&source_labels(source))
+            .set(now());
+    }
+
+    pub fn parse_error(&self, source: &str) {
+        self.parse_errors
+            .get_or_create(&source_labels(source))
+            .inc();
+    }
+
+    pub fn source_connected(&self, source: &str) {
+        self.connected.get_or_create(&source_labels(source)).set(1);
+    }
+
+    /// The connection to `source` was lost, or couldn't be made, and will be tried again
+    pub fn source_disconnected(&self, source: &str) {
+        let labels = source_labels(source);
+        self.connected.get_or_create(&labels).set(0);
+        self.reconnects.get_or_create(&labels).inc();
+    }
+
+    /// `source` was stopped or restarted on purpose, which doesn't count as a reconnect
+    pub fn source_stopped(&self, source: &str) {
+        self.connected.get_or_create(&source_labels(source)).set(0);
+    }
+
+    pub fn consumer_restarted(&self, consumer: &str) {
+        self.consumer_restarts
+            .get_or_create(&ConsumerLabels {
+                consumer: consumer.to_string(),
+            })
+            .inc();
+    }
+
+    pub fn set_aircraft_tracked(&self, aircraft: usize) {
+        self.aircraft_tracked
+            .set(i64::try_from(aircraft).unwrap_or(i64::MAX));
+    }
+
+    pub fn websocket_client_connected(&self) {
+        self.websocket_clients.inc();
+    }
+
+    pub fn websocket_client_disconnected(&self) {
+        self.websocket_clients.dec();
+    }
+
+    pub fn database_write(&self, operation: &str, took: Duration) {
+        self.database_writes
+            .get_or_create(&OperationLabels {
+                operation: operation.to_string(),
+            })
+            .observe(took.as_secs_f64());
+    }
+
+    /// Report the depth of a queue. `probe` is asked every time the metrics are read
+    ///
+    /// # Panics
+    /// Another thread panicked while holding the queue list
+    pub fn watch_queue(&self, queue: &str, probe: impl Fn() -> usize + Send + Sync + 'static) {
+        self.queue_probes
+            .lock()
+            .expect("queue probes should not be poisoned")
+            .push((queue.to_string(), Box::new(probe)));
+    }
+
+    /// Everything, in the Prometheus text format
+    ///
+    /// # Errors
+    /// The metrics could not be written out
+    ///
+    /// # Panics
+    /// Another thread panicked while holding the queue list
+    pub fn encode(&self) -> Result<String, fmt::Error> {
+        for (queue, probe) in self
+            .queue_probes
+            .lock()
+            .expect("queue probes should not be poisoned")
+            .iter()
+        {
+            self.queue_depth
+                .get_or_create(&QueueLabels {
+                    queue: queue.clone(),
+                })
+                .set(i64::try_from(probe()).unwrap_or(i64::MAX));
+        }
+
+        let mut body = String::new();
+        encode(&mut body, &self.registry)?;
+        Ok(body)
+    }
+}
@@ -0,0 +1,17 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::ground_station::ShGroundStation;
+
+/// `ShSourceData` is anything a data source hands to the hub to be processed
+#[derive(Debug, Clone)]
+pub enum ShSourceData {
+    AcarsMessage(Box<ShAcarsMessage>),
+    /// Information about a ground station, heard in a squitter or XID frame
+    GroundStation(ShGroundStation),
+    AdsbPosition(ShAdsbPosition),
+}
@@ -0,0 +1,192 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sh_common::source_status::{ShSourceKind, ShSourceState, ShSourceStatus};
+use sh_common::{MessageData, ServerMessageTypes, ServerWssMessage};
+use tokio::sync::broadcast;
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+/// `SourceMonitor` keeps track of how the connection to every configured source is doing. The
+/// sources report to it as they connect, receive data and drop, and users are sent each change
+pub struct SourceMonitor {
+    // In the order the sources were added
+    statuses: Mutex<Vec<ShSourceStatus>>,
+    silence_timeout: Mutex<u64>,
+    server_messages: broadcast::Sender<ServerWssMessage>,
+}
+
+impl SourceMonitor {
+    #[must_use]
+    pub fn new(server_messages: broadcast::Sender<ServerWssMessage>) -> Self {
+        Self {
+            statuses: Mutex::new(Vec::new()),
+            silence_timeout: Mutex::new(0),
+            server_messages,
+        }
+    }
+
+    fn statuses_mut(&self) -> MutexGuard<'_, Vec<ShSourceStatus>> {
+        self.statuses
+            .lock()
+            .expect("source statuses should not be poisoned")
+    }
+
+    /// Change the status of `source` and let users know. Sources nobody added are ignored
+    fn update(&self, source: &str, change: impl FnOnce(&mut ShSourceStatus) -> bool) {
+        let mut statuses = self.statuses_mut();
+        let Some(status) = statuses.iter_mut().find(|status| status.name == source) else {
+            return;
+        };
+
+        if change(status) {
+            let status = status.clone();
+            drop(statuses);
+            self.send(status);
+        }
+    }
+
+    fn send(&self, status: ShSourceStatus) {
+        if self
+            .server_messages
+            .send(ServerWssMessage::new(
+                ServerMessageTypes::ServerSourceStatus,
+                MessageData::ShSourceStatus(status),
+            ))
+            .is_err()
+        {
+            trace!("No users connected to receive the source status");
+        }
+    }
+
+    /// Start following a configured source. A source that is already followed keeps its status
+    pub fn add(&self, source: &str, kind: ShSourceKind) {
+        let mut statuses = self.statuses_mut();
+        if statuses.iter().any(|status| status.name == source) {
+            return;
+        }
+
+        let status = ShSourceStatus::new(source.to_string(), kind, now());
+        statuses.push(status.clone());
+        drop(statuses);
+        self.send(status);
+    }
+
+    /// Stop following a source that has been taken out of the config. Users get the whole list
+    /// again, as there is no message for a single source going away
+    pub fn remove(&self, source: &str) {
+        let statuses = {
+            let mut statuses = self.statuses_mut();
+            statuses.retain(|status| status.name != source);
+            statuses.clone()
+        };
+
+        if self
+            .server_messages
+            .send(ServerWssMessage::new(
+                ServerMessageTypes::ServerResponseSourceStatus,
+                MessageData::ShSourceStatuses(statuses),
+            ))
+            .is_err()
+        {
+            trace!("No users connected to receive the source statuses");
+        }
+    }
+
+    /// # Panics
+    /// Another thread panicked while holding the silence timeout
+    pub fn set_silence_timeout(&self, seconds: u64) {
+        *self
+            .silence_timeout
+            .lock()
+            .expect("silence timeout should not be poisoned") = seconds;
+    }
+
+    pub fn connected(&self, source: &str) {
+        self.update(source, |status| {
+            status.state = ShSourceState::Connected;
+            status.since = now();
+            true
+        });
+    }
+
+    /// The connection to `source` was lost, or couldn't be made, and will be tried again
+    pub fn disconnected(&self, source: &str, error: String) {
+        self.update(source, |status| {
+            status.state = ShSourceState::Disconnected;
+            status.since = now();
+            status.reconnects += 1;
+            status.last_error = Some(error);
+            true
+        });
+    }
+
+    /// Data came in from `source`. Users are only told if it had gone quiet
+    pub fn data_received(&self, source: &str) {
+        self.update(source, |status| {
+            let now = now();
+            status.last_data = Some(now);
+
+            if status.state == ShSourceState::Silent {
+                info!(
+                    "{} source {} is sending data again",
+                    status.kind, status.name
+                );
+                status.state = ShSourceState::Connected;
+                status.since = now;
+                return true;
+            }
+
+            false
+        });
+    }
+
+    /// Flag connected sources that have sent nothing for longer than the silence timeout
+    ///
+    /// # Panics
+    /// Another thread panicked while holding the silence timeout
+    #[allow(clippy::cast_precision_loss)]
+    pub fn check_silence(&self, now: f64) {
+        let timeout = *self
+            .silence_timeout
+            .lock()
+            .expect("silence timeout should not be poisoned");
+        if timeout == 0 {
+            return;
+        }
+
+        let mut silenced = Vec::new();
+        for status in self.statuses_mut().iter_mut() {
+            // a source that has never sent anything has been quiet since it connected
+            let heard = status.last_data.unwrap_or(status.since).max(status.since);
+            if status.state == ShSourceState::Connected && now - heard > timeout as f64 {
+                warn!(
+                    "{} source {} is connected but has sent nothing for {timeout} seconds",
+                    status.kind, status.name
+                );
+                status.state = ShSourceState::Silent;
+                status.since = now;
+                silenced.push(status.clone());
+            }
+        }
+
+        for status in silenced {
+            self.send(status);
+        }
+    }
+
+    /// Every source, in the order they were added
+    #[must_use]
+    pub fn statuses(&self) -> Vec<ShSourceStatus> {
+        self.statuses_mut().clone()
+    }
+}
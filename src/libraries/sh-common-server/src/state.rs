@@ -0,0 +1,150 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::ServerWssMessage;
+use sh_config::alerts::ShAlertRule;
+use sh_config::ShConfig;
+use sh_database::ShDatabase;
+use sh_lookup::ground_stations::GroundStationRegistry;
+use sh_lookup::ShLookup;
+use sh_notifications::Notifier;
+use tokio::sync::{broadcast, watch, RwLock};
+
+use crate::alerts::AlertEngine;
+use crate::consumers::ConsumerMonitor;
+use crate::emergency::EmergencyDetector;
+use crate::metrics::ShMetrics;
+use crate::sources::SourceMonitor;
+use crate::stats::StatsCollector;
+use crate::timeline::FlightTimelines;
+use crate::weather::WeatherStore;
+
+// Number of messages we keep around to send to newly connected clients
+const MAX_RECENT_MESSAGES: usize = 250;
+// Number of server messages a slow client can fall behind before it starts missing them
+const SERVER_MESSAGE_CAPACITY: usize = 512;
+
+/// `ShHubState` is the app state the hub builds from incoming data, shared with the consumers
+/// that need to read it or be told when it changes
+pub struct ShHubState {
+    pub lookup: ShLookup,
+    pub database: ShDatabase,
+    pub ground_stations: RwLock<GroundStationRegistry>,
+    pub timelines: RwLock<FlightTimelines>,
+    pub weather: RwLock<WeatherStore>,
+    pub alerts: RwLock<AlertEngine>,
+    pub emergencies: RwLock<EmergencyDetector>,
+    pub notifier: Notifier,
+    pub metrics: Arc<ShMetrics>,
+    pub sources: Arc<SourceMonitor>,
+    pub consumers: Arc<ConsumerMonitor>,
+    pub stats: RwLock<StatsCollector>,
+    recent_messages: RwLock<VecDeque<ShAcarsMessage>>,
+    server_messages: broadcast::Sender<ServerWssMessage>,
+    config_updates: watch::Sender<ShConfig>,
+}
+
+impl ShHubState {
+    #[must_use]
+    pub fn new(
+        config: ShConfig,
+        lookup: ShLookup,
+        ground_stations: GroundStationRegistry,
+        database: ShDatabase,
+        notifier: Notifier,
+        metrics: Arc<ShMetrics>,
+    ) -> Self {
+        let (server_messages, _) = broadcast::channel(SERVER_MESSAGE_CAPACITY);
+
+        let backlog = server_messages.clone();
+        metrics.watch_queue("websocket", move || backlog.len());
+
+        Self {
+            lookup,
+            database,
+            ground_stations: RwLock::new(ground_stations),
+            timelines: RwLock::new(FlightTimelines::new()),
+            weather: RwLock::new(WeatherStore::new()),
+            alerts: RwLock::new(AlertEngine::new()),
+            emergencies: RwLock::new(EmergencyDetector::new()),
+            notifier,
+            metrics,
+            sources: Arc::new(SourceMonitor::new(server_messages.clone())),
+            consumers: Arc::new(ConsumerMonitor::new(server_messages.clone())),
+            stats: RwLock::new(StatsCollector::new()),
+            recent_messages: RwLock::new(VecDeque::with_capacity(MAX_RECENT_MESSAGES)),
+            server_messages,
+            config_updates: watch::Sender::new(config),
+        }
+    }
+
+    /// Hand a changed config, once it has been saved, to the hub to apply
+    pub fn config_changed(&self, config: &ShConfig) {
+        self.config_updates.send_replace(config.clone());
+    }
+
+    /// Each config change, as it is saved
+    #[must_use]
+    pub fn watch_config(&self) -> watch::Receiver<ShConfig> {
+        self.config_updates.subscribe()
+    }
+
+    /// The key a flight's timeline is stored under. Flight numbers are normalized to the ICAO
+    /// form so ACARS and ADS-B agree, and anything that isn't a flight number is used as is
+    #[must_use]
+    pub fn flight_key(&self, flight: &str) -> String {
+        self.lookup
+            .normalize_flight(flight)
+            .unwrap_or_else(|| flight.trim().to_uppercase())
+    }
+
+    /// Start checking incoming data against `rules`, replacing any rules already in use
+    pub async fn set_alert_rules(&self, rules: &[ShAlertRule]) {
+        self.alerts
+            .write()
+            .await
+            .set_rules(rules, |flight| self.flight_key(flight));
+    }
+
+    /// Write out the statistics counted since the last time
+    pub async fn flush_stats(&self) {
+        let pending = self.stats.write().await.take();
+        if let Err(e) = self.database.add_stats(pending).await {
+            error!("Error writing statistics: {e}");
+        }
+    }
+
+    /// Get a receiver for messages that should go out to every connected user
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerWssMessage> {
+        self.server_messages.subscribe()
+    }
+
+    /// Send a message to every connected user. It is not an error for nobody to be listening
+    pub fn broadcast(&self, message: ServerWssMessage) {
+        if self.server_messages.send(message).is_err() {
+            trace!("No users connected to receive the broadcast");
+        }
+    }
+
+    pub async fn add_recent_message(&self, message: ShAcarsMessage) {
+        let mut recent_messages = self.recent_messages.write().await;
+
+        if recent_messages.len() >= MAX_RECENT_MESSAGES {
+            recent_messages.pop_front();
+        }
+
+        recent_messages.push_back(message);
+    }
+
+    /// The most recent messages, oldest first
+    pub async fn recent_messages(&self) -> Vec<ShAcarsMessage> {
+        self.recent_messages.read().await.iter().cloned().collect()
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_database::{stats_bucket, StatsDimension, StatsRollup};
+
+/// `StatsCollector` counts messages for the statistics page as they come in. The counts are
+/// written to the database every so often rather than for every message
+#[derive(Debug, Default)]
+pub struct StatsCollector {
+    pending: StatsRollup,
+}
+
+impl StatsCollector {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a message. Ground station names have to be filled in first
+    pub fn add_message(&mut self, message: &ShAcarsMessage) {
+        let bucket = stats_bucket(message.timestamp);
+
+        self.pending
+            .count(bucket, StatsDimension::Source, message.source.clone());
+        self.pending.count(
+            bucket,
+            StatsDimension::MessageType,
+            message.message_type.to_string(),
+        );
+
+        if let Some(frequency) = message.frequency {
+            self.pending
+                .count(bucket, StatsDimension::Frequency, format!("{frequency:.3}"));
+        }
+
+        if let Some(label) = message
+            .label
+            .as_deref()
+            .map(str::trim)
+            .filter(|label| !label.is_empty())
+        {
+            self.pending
+                .count(bucket, StatsDimension::Label, label.to_uppercase());
+        }
+
+        if let Some(station) = message
+            .ground_station_name
+            .as_ref()
+            .or(message.ground_station_id.as_ref())
+        {
+            self.pending
+                .count(bucket, StatsDimension::GroundStation, station.clone());
+        }
+    }
+
+    pub fn sample_aircraft(&mut self, now: f64, aircraft: usize) {
+        self.pending.sample_aircraft(
+            stats_bucket(now),
+            i64::try_from(aircraft).unwrap_or(i64::MAX),
+        );
+    }
+
+    /// Everything counted since the last call
+    pub fn take(&mut self) -> StatsRollup {
+        std::mem::take(&mut self.pending)
+    }
+}
@@ -0,0 +1,340 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Per flight history built from ACARS messages and ADS-B positions. ACARS tells us the flight
+// number and usually the ICAO address, ADS-B tells us the ICAO address and usually the callsign,
+// so the address is what lets us put the two together.
+
+use std::collections::HashMap;
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::oooi::{ShOooiEvent, ShOooiEventType};
+use sh_common::timeline::{
+    ShFlightTimeline, ShPositionMilestone, ShTimelineEntry, ShTimelineEntryKind, ShTimelineFlag,
+    ShTimelineFlagKind,
+};
+
+// Flights we have not heard from in this long are forgotten
+const FLIGHT_EXPIRY: f64 = 24.0 * 3_600.0;
+// Messages kept per flight, beyond which the oldest are dropped from the timeline
+const MAX_MESSAGES_PER_FLIGHT: usize = 200;
+// Two reports of the same event further apart than this disagree with each other
+const EVENT_TOLERANCE: f64 = 5.0 * 60.0;
+// How far the ADS-B takeoff and landing can be from the reported OFF and ON times
+const ADSB_TOLERANCE: f64 = 15.0 * 60.0;
+// How recently an aircraft has to have sent a position to count as tracked
+const AIRCRAFT_TRACKED_WINDOW: f64 = 60.0;
+
+#[derive(Debug)]
+struct FlightRecord {
+    tail: Option<String>,
+    icao_hex: Option<String>,
+    oooi: Vec<ShOooiEvent>,
+    messages: Vec<ShTimelineEntry>,
+    milestones: Vec<ShTimelineEntry>,
+    last_updated: f64,
+}
+
+impl FlightRecord {
+    const fn new(timestamp: f64) -> Self {
+        Self {
+            tail: None,
+            icao_hex: None,
+            oooi: Vec::new(),
+            messages: Vec::new(),
+            milestones: Vec::new(),
+            last_updated: timestamp,
+        }
+    }
+
+    fn add_milestone(&mut self, timestamp: f64, milestone: ShPositionMilestone) {
+        self.milestones.push(ShTimelineEntry {
+            timestamp,
+            kind: ShTimelineEntryKind::Position(milestone),
+        });
+    }
+
+    fn milestone_time(&self, milestone: ShPositionMilestone) -> Option<f64> {
+        self.milestones
+            .iter()
+            .find(|entry| entry.kind == ShTimelineEntryKind::Position(milestone))
+            .map(|entry| entry.timestamp)
+    }
+}
+
+#[derive(Debug)]
+struct AircraftTrack {
+    first_seen: f64,
+    last_seen: f64,
+    on_ground: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct FlightTimelines {
+    flights: HashMap<String, FlightRecord>,
+    flight_for_hex: HashMap<String, String>,
+    aircraft: HashMap<String, AircraftTrack>,
+}
+
+impl FlightTimelines {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The flight an aircraft is currently flying, if we have seen its flight number
+    #[must_use]
+    pub fn flight_for_hex(&self, icao_hex: &str) -> Option<&String> {
+        self.flight_for_hex.get(&icao_hex.to_uppercase())
+    }
+
+    /// Add a message to the timeline for `flight`, along with any OOOI events found in it.
+    /// Messages without a flight number are attached to the flight the aircraft is known to be on
+    pub fn add_message(
+        &mut self,
+        flight: Option<String>,
+        message: &ShAcarsMessage,
+        events: Vec<ShOooiEvent>,
+    ) {
+        let icao_hex = message.icao_hex.as_deref().map(str::to_uppercase);
+
+        let Some(flight) = flight.or_else(|| {
+            icao_hex
+                .as_deref()
+                .and_then(|hex| self.flight_for_hex(hex).cloned())
+        }) else {
+            return;
+        };
+
+        if let Some(hex) = &icao_hex {
+            self.flight_for_hex.insert(hex.clone(), flight.clone());
+        }
+
+        let record = self
+            .flights
+            .entry(flight)
+            .or_insert_with(|| FlightRecord::new(message.timestamp));
+
+        record.last_updated = record.last_updated.max(message.timestamp);
+        if message.tail.is_some() {
+            record.tail.clone_from(&message.tail);
+        }
+        if icao_hex.is_some() {
+            record.icao_hex = icao_hex;
+        }
+
+        record.messages.push(ShTimelineEntry {
+            timestamp: message.timestamp,
+            kind: ShTimelineEntryKind::Message {
+                label: message.label.clone(),
+                text: message.text.clone(),
+            },
+        });
+        if record.messages.len() > MAX_MESSAGES_PER_FLIGHT {
+            record.messages.remove(0);
+        }
+
+        record.oooi.extend(events);
+    }
+
+    /// Track an aircraft's ADS-B reports and record takeoffs and landings against its flight
+    pub fn add_position(&mut self, flight: Option<String>, position: &ShAdsbPosition) {
+        let icao_hex = position.icao_hex.to_uppercase();
+
+        if let Some(flight) = flight {
+            self.flight_for_hex.insert(icao_hex.clone(), flight);
+        }
+
+        let track = self
+            .aircraft
+            .entry(icao_hex.clone())
+            .or_insert_with(|| AircraftTrack {
+                first_seen: position.timestamp,
+                last_seen: position.timestamp,
+                on_ground: None,
+            });
+
+        track.last_seen = track.last_seen.max(position.timestamp);
+
+        let transition = match (track.on_ground, position.on_ground) {
+            (Some(true), Some(false)) => Some(ShPositionMilestone::Takeoff),
+            (Some(false), Some(true)) => Some(ShPositionMilestone::Landing),
+            _ => None,
+        };
+
+        if position.on_ground.is_some() {
+            track.on_ground = position.on_ground;
+        }
+
+        let first_seen = track.first_seen;
+
+        let Some(flight) = self.flight_for_hex.get(&icao_hex) else {
+            return;
+        };
+
+        let record = self
+            .flights
+            .entry(flight.clone())
+            .or_insert_with(|| FlightRecord::new(position.timestamp));
+
+        record.last_updated = record.last_updated.max(position.timestamp);
+        record.icao_hex = Some(icao_hex);
+
+        if record
+            .milestone_time(ShPositionMilestone::FirstSeen)
+            .is_none()
+        {
+            record.add_milestone(first_seen, ShPositionMilestone::FirstSeen);
+        }
+
+        if let Some(milestone) = transition {
+            record.add_milestone(position.timestamp, milestone);
+        }
+    }
+
+    /// Forget flights and aircraft we have not heard from in a day
+    pub fn prune(&mut self, now: f64) {
+        self.flights
+            .retain(|_, record| now - record.last_updated < FLIGHT_EXPIRY);
+        self.aircraft
+            .retain(|_, track| now - track.last_seen < FLIGHT_EXPIRY);
+
+        let flights = &self.flights;
+        self.flight_for_hex
+            .retain(|_, flight| flights.contains_key(flight));
+    }
+
+    /// Aircraft we have had a position from in the last minute
+    #[must_use]
+    pub fn aircraft_tracked(&self, now: f64) -> usize {
+        self.aircraft
+            .values()
+            .filter(|track| now - track.last_seen < AIRCRAFT_TRACKED_WINDOW)
+            .count()
+    }
+
+    #[must_use]
+    pub fn timeline(&self, flight: &str) -> Option<ShFlightTimeline> {
+        let record = self.flights.get(flight)?;
+
+        let mut timeline = ShFlightTimeline::new(flight.to_string());
+        timeline.tail.clone_from(&record.tail);
+        timeline.icao_hex.clone_from(&record.icao_hex);
+
+        timeline
+            .entries
+            .extend(record.oooi.iter().map(|event| ShTimelineEntry {
+                timestamp: event.time,
+                kind: ShTimelineEntryKind::Oooi(event.event_type),
+            }));
+        timeline.entries.extend(record.messages.iter().cloned());
+        timeline.entries.extend(record.milestones.iter().cloned());
+
+        if let Some(track) = record
+            .icao_hex
+            .as_ref()
+            .and_then(|hex| self.aircraft.get(hex))
+        {
+            timeline.entries.push(ShTimelineEntry {
+                timestamp: track.last_seen,
+                kind: ShTimelineEntryKind::Position(ShPositionMilestone::LastSeen),
+            });
+        }
+
+        timeline
+            .entries
+            .sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        timeline.flags = check_events(record);
+
+        Some(timeline)
+    }
+}
+
+/// Look for missing and contradictory OOOI events
+fn check_events(record: &FlightRecord) -> Vec<ShTimelineFlag> {
+    let mut flags = Vec::new();
+    let mut first_times: Vec<(ShOooiEventType, f64)> = Vec::new();
+
+    for event_type in ShOooiEventType::ALL {
+        let times: Vec<f64> = record
+            .oooi
+            .iter()
+            .filter(|event| event.event_type == event_type)
+            .map(|event| event.time)
+            .collect();
+
+        let Some(first) = times.first().copied() else {
+            continue;
+        };
+
+        if times
+            .iter()
+            .any(|time| (time - first).abs() > EVENT_TOLERANCE)
+        {
+            flags.push(ShTimelineFlag {
+                kind: ShTimelineFlagKind::Contradictory,
+                event_type,
+                description: format!(
+                    "{event_type} was reported more than once with different times"
+                ),
+            });
+        }
+
+        first_times.push((event_type, first));
+    }
+
+    for pair in first_times.windows(2) {
+        let ((earlier_type, earlier), (later_type, later)) = (pair[0], pair[1]);
+        if later < earlier {
+            flags.push(ShTimelineFlag {
+                kind: ShTimelineFlagKind::Contradictory,
+                event_type: later_type,
+                description: format!("{later_type} was reported before {earlier_type}"),
+            });
+        }
+    }
+
+    // Anything before the latest event we have should have been reported too
+    if let Some((latest, _)) = first_times.last() {
+        for event_type in ShOooiEventType::ALL
+            .into_iter()
+            .filter(|event_type| event_type < latest)
+        {
+            if !first_times.iter().any(|(known, _)| *known == event_type) {
+                flags.push(ShTimelineFlag {
+                    kind: ShTimelineFlagKind::Missing,
+                    event_type,
+                    description: format!("{latest} was reported but {event_type} was not"),
+                });
+            }
+        }
+    }
+
+    for (event_type, milestone) in [
+        (ShOooiEventType::Off, ShPositionMilestone::Takeoff),
+        (ShOooiEventType::On, ShPositionMilestone::Landing),
+    ] {
+        let reported = first_times
+            .iter()
+            .find(|(known, _)| *known == event_type)
+            .map(|(_, time)| *time);
+
+        if let (Some(reported), Some(seen)) = (reported, record.milestone_time(milestone)) {
+            if (reported - seen).abs() > ADSB_TOLERANCE {
+                flags.push(ShTimelineFlag {
+                    kind: ShTimelineFlagKind::Contradictory,
+                    event_type,
+                    description: format!(
+                        "{event_type} does not match the {} seen in the ADS-B data",
+                        milestone.to_string().to_lowercase()
+                    ),
+                });
+            }
+        }
+    }
+
+    flags
+}
@@ -0,0 +1,86 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+
+use sh_common::weather::{ShWeatherProduct, ShWeatherQuery};
+
+// Products kept per airport, beyond which the oldest are dropped
+const MAX_PRODUCTS_PER_AIRPORT: usize = 20;
+// Products we have not heard again in this long are forgotten
+const PRODUCT_EXPIRY: f64 = 24.0 * 3_600.0;
+
+/// `WeatherStore` holds the weather products we have heard, keyed by airport
+#[derive(Debug, Default)]
+pub struct WeatherStore {
+    airports: HashMap<String, Vec<ShWeatherProduct>>,
+}
+
+impl WeatherStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a product. Returns false if we already had the same report, in which case only the
+    /// time it was last heard is updated
+    pub fn add(&mut self, product: ShWeatherProduct) -> bool {
+        let products = self.airports.entry(product.airport.clone()).or_default();
+
+        if let Some(existing) = products
+            .iter_mut()
+            .find(|existing| existing.is_same_report(&product))
+        {
+            existing.received = existing.received.max(product.received);
+            return false;
+        }
+
+        products.push(product);
+        // newest first. Products without an issue time go by when they were heard
+        products.sort_by(|a, b| product_time(b).total_cmp(&product_time(a)));
+        products.truncate(MAX_PRODUCTS_PER_AIRPORT);
+
+        true
+    }
+
+    /// Products matching `query`, newest first
+    #[must_use]
+    pub fn query(&self, query: &ShWeatherQuery) -> Vec<ShWeatherProduct> {
+        let mut products: Vec<ShWeatherProduct> = Vec::new();
+
+        for product in self.airports.values().flatten() {
+            if !query.matches(product) {
+                continue;
+            }
+
+            // airport products are newest first, so the first of each type is the latest
+            if query.latest_only
+                && products.iter().any(|seen| {
+                    seen.airport == product.airport && seen.product_type == product.product_type
+                })
+            {
+                continue;
+            }
+
+            products.push(product.clone());
+        }
+
+        products.sort_by(|a, b| product_time(b).total_cmp(&product_time(a)));
+        products
+    }
+
+    /// Forget products that have not been heard in a day
+    pub fn prune(&mut self, now: f64) {
+        for products in self.airports.values_mut() {
+            products.retain(|product| now - product.received < PRODUCT_EXPIRY);
+        }
+
+        self.airports.retain(|_, products| !products.is_empty());
+    }
+}
+
+fn product_time(product: &ShWeatherProduct) -> f64 {
+    product.issued.unwrap_or(product.received)
+}
@@ -0,0 +1,112 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use sh_config::source::ShEnabledDataSources;
+
+/// The network a message was received on
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShMessageType {
+    Acars,
+    Vdlm2,
+    Hfdl,
+    Inmarsat,
+    Iridium,
+}
+
+impl std::fmt::Display for ShMessageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Acars => write!(f, "ACARS"),
+            Self::Vdlm2 => write!(f, "VDLM2"),
+            Self::Hfdl => write!(f, "HFDL"),
+            Self::Inmarsat => write!(f, "Inmarsat"),
+            Self::Iridium => write!(f, "Iridium"),
+        }
+    }
+}
+
+impl ShMessageType {
+    /// The source type that has to be enabled for messages of this type to be used
+    #[must_use]
+    pub const fn source_type(self) -> ShEnabledDataSources {
+        match self {
+            Self::Acars => ShEnabledDataSources::Acars,
+            Self::Vdlm2 => ShEnabledDataSources::Vdlm2,
+            Self::Hfdl => ShEnabledDataSources::Hfdl,
+            Self::Inmarsat => ShEnabledDataSources::Inmarsat,
+            Self::Iridium => ShEnabledDataSources::Iridium,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShMessageDirection {
+    /// Ground to air
+    Uplink,
+    /// Air to ground
+    Downlink,
+    #[default]
+    Unknown,
+}
+
+/// `ShAcarsMessage` is an ACARS message decoded by any of the supported decoders, normalized
+/// in to a common format
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShAcarsMessage {
+    /// Unix timestamp, in seconds, of when the message was received by the decoder
+    pub timestamp: f64,
+    pub message_type: ShMessageType,
+    /// Name of the decoder that produced the message, e.g. `dumpvdl2`
+    pub decoder: Option<String>,
+    /// The configured source the message came in from, as `address:port`
+    pub source: String,
+    /// The station name set by the person running the decoder
+    pub station_id: Option<String>,
+    /// Frequency in MHz
+    pub frequency: Option<f64>,
+    pub signal_level: Option<f64>,
+    pub direction: ShMessageDirection,
+    pub tail: Option<String>,
+    pub flight: Option<String>,
+    /// The ICAO hex address of the aircraft, when the decoder provides it
+    pub icao_hex: Option<String>,
+    pub label: Option<String>,
+    pub block_id: Option<String>,
+    pub message_number: Option<String>,
+    pub text: Option<String>,
+    /// The ground station identifier as it appears on the network, e.g. the VDL2 address or HFDL ID
+    pub ground_station_id: Option<String>,
+    /// Filled in by the hub from the ground station registry
+    pub ground_station_name: Option<String>,
+}
+
+impl ShAcarsMessage {
+    #[must_use]
+    pub const fn new(timestamp: f64, message_type: ShMessageType, source: String) -> Self {
+        Self {
+            timestamp,
+            message_type,
+            decoder: None,
+            source,
+            station_id: None,
+            frequency: None,
+            signal_level: None,
+            direction: ShMessageDirection::Unknown,
+            tail: None,
+            flight: None,
+            icao_hex: None,
+            label: None,
+            block_id: None,
+            message_number: None,
+            text: None,
+            ground_station_id: None,
+            ground_station_name: None,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+/// `ShAdsbPosition` is a single ADS-B report for an aircraft. Reports rarely carry every field,
+/// so anything not in this report is `None`
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShAdsbPosition {
+    /// The ICAO hex address of the aircraft, upper case
+    pub icao_hex: String,
+    /// Unix timestamp, in seconds, of when the report was received
+    pub timestamp: f64,
+    pub callsign: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Altitude in feet
+    pub altitude: Option<i32>,
+    pub squawk: Option<String>,
+    pub on_ground: Option<bool>,
+}
+
+impl ShAdsbPosition {
+    #[must_use]
+    pub const fn new(icao_hex: String, timestamp: f64) -> Self {
+        Self {
+            icao_hex,
+            timestamp,
+            callsign: None,
+            latitude: None,
+            longitude: None,
+            altitude: None,
+            squawk: None,
+            on_ground: None,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{acars_message::ShAcarsMessage, adsb::ShAdsbPosition};
+
+/// `ShAlert` is raised when a message or position matches one of the configured alert rules
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShAlert {
+    /// Set once the alert has been stored
+    pub id: i64,
+    /// Name of the rule that matched
+    pub rule: String,
+    pub timestamp: f64,
+    pub icao_hex: Option<String>,
+    pub tail: Option<String>,
+    pub flight: Option<String>,
+    /// What matched, e.g. `tail N123UA, label H1`
+    pub description: String,
+    /// The message that raised the alert, if it was raised by a message
+    pub message: Option<Box<ShAcarsMessage>>,
+    /// The position that raised the alert, if it was raised by ADS-B
+    pub position: Option<ShAdsbPosition>,
+}
+
+impl ShAlert {
+    /// The aircraft the alert is about, in whatever form we have it
+    #[must_use]
+    pub fn aircraft(&self) -> String {
+        self.flight
+            .as_ref()
+            .or(self.tail.as_ref())
+            .or(self.icao_hex.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Types only used by the REST API and exports. Everything else it sends is shared with the WebSocket
+
+use serde::{Deserialize, Serialize};
+use sh_config::alerts::ShAlertsConfig;
+use sh_config::map::ShMapConfig;
+use sh_config::source::{DataSources, EnabledDataSources};
+use sh_config::web::{sh_web_config::ShWebConfig, sh_web_sdrehub::ShWebSDREHub};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::acars_message::ShAcarsMessage;
+use crate::adsb::ShAdsbPosition;
+use crate::flight::ShFlightSession;
+
+/// The body of every error the REST API returns
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShApiError {
+    /// The HTTP status code, repeated for clients that only see the body
+    pub status: u16,
+    pub error: String,
+}
+
+/// What to search stored messages for. Every field that is set has to match
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "openapi",
+    derive(utoipa::IntoParams),
+    into_params(parameter_in = Query)
+)]
+pub struct ShMessageQuery {
+    /// Text the message has to contain, ignoring case
+    pub text: Option<String>,
+    /// ICAO hex address or registration of the aircraft
+    pub aircraft: Option<String>,
+    /// Flight number, in either IATA or ICAO form
+    pub flight: Option<String>,
+    /// ACARS label, e.g. `H1`
+    pub label: Option<String>,
+    /// `acars`, `vdlm2`, `hfdl`, `inmarsat` or `iridium`
+    pub message_type: Option<String>,
+    /// The configured source the message came in from, as `address:port`
+    pub source: Option<String>,
+    /// Unix timestamp, in seconds. Only messages received at or after it
+    pub since: Option<f64>,
+    /// Unix timestamp, in seconds. Only messages received before it
+    pub until: Option<f64>,
+    /// Most messages to return. Defaults to 100, and at most 1000 are returned
+    pub limit: Option<i64>,
+    /// Number of matching messages to skip, for paging
+    pub offset: Option<i64>,
+}
+
+/// A message as it is stored in the database
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShStoredMessage {
+    pub id: i64,
+    /// The flight the message was added to, if it could be tied to an aircraft
+    pub flight_id: Option<i64>,
+    pub message: ShAcarsMessage,
+}
+
+/// What is known about the aircraft and flight a message was tied to, sent along with exported
+/// messages when asked for
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShMessageAircraft {
+    /// The ICAO hex address of the aircraft, or its registration if that is all we have
+    pub aircraft: String,
+    pub icao_hex: Option<String>,
+    pub tail: Option<String>,
+    pub callsign: Option<String>,
+    /// Unix timestamps, in seconds, of the first and last thing heard from the flight
+    pub flight_start: f64,
+    pub flight_end: f64,
+}
+
+/// A line of a JSON Lines export. The message's own fields are at the top level, so the line
+/// can be imported again as it is
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShExportedMessage {
+    pub id: i64,
+    pub flight_id: Option<i64>,
+    #[serde(flatten)]
+    pub message: ShAcarsMessage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aircraft: Option<ShMessageAircraft>,
+}
+
+/// How messages are exported
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShExportFormat {
+    Csv,
+    /// One JSON object per line, the same as the command line export
+    #[default]
+    Jsonl,
+}
+
+impl ShExportFormat {
+    pub const ALL: [Self; 2] = [Self::Csv, Self::Jsonl];
+
+    #[must_use]
+    pub const fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+}
+
+impl Display for ShExportFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Csv => write!(f, "csv"),
+            Self::Jsonl => write!(f, "jsonl"),
+        }
+    }
+}
+
+impl FromStr for ShExportFormat {
+    type Err = String;
+
+    /// Takes `csv` or `jsonl`, which is also the file extension
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.to_string().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| format!("Unknown format {value}. Expected csv or jsonl"))
+    }
+}
+
+/// An aircraft we have flights for, with what we last knew about it
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShAircraftSummary {
+    /// The ICAO hex address of the aircraft, or its registration if that is all we have
+    pub aircraft: String,
+    pub icao_hex: Option<String>,
+    pub tail: Option<String>,
+    /// The callsign on the aircraft's latest flight
+    pub callsign: Option<String>,
+    /// Unix timestamps, in seconds
+    pub first_seen: f64,
+    pub last_seen: f64,
+    pub flights: i64,
+    pub messages: i64,
+    pub positions: i64,
+}
+
+/// Everything we know about one aircraft
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShAircraftDetail {
+    pub summary: ShAircraftSummary,
+    /// The most recent flights, newest first
+    pub flights: Vec<ShFlightSession>,
+    pub last_position: Option<ShAdsbPosition>,
+    /// The most recent messages, newest first
+    pub messages: Vec<ShStoredMessage>,
+}
+
+/// Parts of the config to change. Parts left out are left as they are
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShConfigUpdate {
+    pub app: Option<ShWebSDREHub>,
+    pub map: Option<ShMapConfig>,
+    pub enabled_data_sources: Option<EnabledDataSources>,
+    pub data_sources: Option<DataSources>,
+    pub alerts: Option<ShAlertsConfig>,
+}
+
+/// The config after an update, and what the update changed
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShConfigUpdateResult {
+    pub changes: Vec<String>,
+    pub config: ShWebConfig,
+}
@@ -0,0 +1,110 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Display, Formatter};
+
+/// What a user is allowed to do. Admins can do everything viewers can
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum ShUserRole {
+    /// Can see everything, but can't change the settings
+    #[default]
+    Viewer,
+    /// Can change the settings and manage users
+    Admin,
+}
+
+impl Display for ShUserRole {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Viewer => write!(f, "Viewer"),
+            Self::Admin => write!(f, "Admin"),
+        }
+    }
+}
+
+/// `ShUser` is who a connection is acting as
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShUser {
+    pub name: String,
+    pub role: ShUserRole,
+}
+
+impl ShUser {
+    #[must_use]
+    pub fn is_admin(&self) -> bool {
+        self.role == ShUserRole::Admin
+    }
+}
+
+/// A username and password, to log in with or to create the first admin with
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShLogin {
+    pub username: String,
+    pub password: String,
+}
+
+// messages are logged, passwords shouldn't be
+impl Debug for ShLogin {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("ShLogin")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A successful login. The token goes in an `Authorization: Bearer` header for the REST API,
+/// or is sent to resume the session on a new WebSocket
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShSession {
+    pub token: String,
+    pub user: ShUser,
+    /// Unix timestamp, in seconds, of when the session ends
+    pub expires: f64,
+}
+
+/// A token for one download, given as `?token=` on links that can't send a header. It works
+/// once, and only for a minute, so the session token never ends up in a URL
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShDownloadToken {
+    pub token: String,
+    /// Unix timestamp, in seconds, of when the token stops working
+    pub expires: f64,
+}
+
+/// `ShAuthStatus` tells a WebSocket connection who it is acting as
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct ShAuthStatus {
+    /// Who the connection is acting as. Nobody means the user has to log in first
+    pub user: Option<ShUser>,
+    /// The token for the session, when this is the answer to logging in or resuming one
+    pub token: Option<String>,
+    /// There are no accounts yet, so the first login creates the admin account
+    pub setup_needed: bool,
+}
+
+/// Create a user, or change an existing user's role or password
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ShUserUpdate {
+    pub name: String,
+    pub role: ShUserRole,
+    /// The new password. Required for a new user, and left alone if not given otherwise
+    pub password: Option<String>,
+}
+
+impl Debug for ShUserUpdate {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("ShUserUpdate")
+            .field("name", &self.name)
+            .field("role", &self.role)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// When a consumer that has stopped on its own is started again
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShRestartPolicy {
+    /// Whether it failed or not
+    Always,
+    /// Only if it returned an error or panicked
+    OnFailure,
+    Never,
+}
+
+impl Display for ShRestartPolicy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Always => write!(f, "Always"),
+            Self::OnFailure => write!(f, "On failure"),
+            Self::Never => write!(f, "Never"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShConsumerState {
+    Running,
+    /// Stopped, and waiting to be started again
+    Restarting,
+    /// Finished without an error, and its policy says to leave it
+    Stopped,
+    /// Failed, and its policy says to leave it
+    Failed,
+    /// Failed too many times in a short while, so it has been given up on
+    CrashLooping,
+}
+
+impl Display for ShConsumerState {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Running => write!(f, "Running"),
+            Self::Restarting => write!(f, "Restarting"),
+            Self::Stopped => write!(f, "Stopped"),
+            Self::Failed => write!(f, "Failed"),
+            Self::CrashLooping => write!(f, "Crash looping"),
+        }
+    }
+}
+
+/// `ShConsumerStatus` is how one of the things the hub runs, like the web server or a source
+/// client, is doing
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShConsumerStatus {
+    pub name: String,
+    pub policy: ShRestartPolicy,
+    pub state: ShConsumerState,
+    /// Unix timestamp, in seconds, of when the consumer went into its current state
+    pub since: f64,
+    /// Unix timestamp, in seconds, of when a restarting consumer will be started again
+    pub restart_at: Option<f64>,
+    /// Times it has been started again after stopping on its own
+    pub restarts: u64,
+    /// Why it last failed, if it has. Panics are included
+    pub last_error: Option<String>,
+}
+
+impl ShConsumerStatus {
+    #[must_use]
+    pub const fn new(name: String, policy: ShRestartPolicy, now: f64) -> Self {
+        Self {
+            name,
+            policy,
+            state: ShConsumerState::Running,
+            since: now,
+            restart_at: None,
+            restarts: 0,
+            last_error: None,
+        }
+    }
+
+    /// Running, or stopped when it was meant to
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        matches!(
+            self.state,
+            ShConsumerState::Running | ShConsumerState::Stopped
+        )
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// What kind of emergency an aircraft is in
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ShEmergencyKind {
+    /// Squawk 7500
+    Hijack,
+    /// Squawk 7600
+    RadioFailure,
+    /// Squawk 7700
+    General,
+    /// A message with distress phrasing in it, e.g. MAYDAY
+    Distress,
+}
+
+impl ShEmergencyKind {
+    /// The emergency a squawk code means, if it is one of the emergency codes
+    #[must_use]
+    pub fn from_squawk(squawk: &str) -> Option<Self> {
+        match squawk.trim() {
+            "7500" => Some(Self::Hijack),
+            "7600" => Some(Self::RadioFailure),
+            "7700" => Some(Self::General),
+            _ => None,
+        }
+    }
+
+    /// Raised by a squawk code rather than a message
+    #[must_use]
+    pub const fn is_squawk(self) -> bool {
+        !matches!(self, Self::Distress)
+    }
+
+    #[must_use]
+    pub const fn squawk(self) -> Option<&'static str> {
+        match self {
+            Self::Hijack => Some("7500"),
+            Self::RadioFailure => Some("7600"),
+            Self::General => Some("7700"),
+            Self::Distress => None,
+        }
+    }
+}
+
+impl Display for ShEmergencyKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Hijack => write!(f, "Hijack (7500)"),
+            Self::RadioFailure => write!(f, "Radio failure (7600)"),
+            Self::General => write!(f, "General emergency (7700)"),
+            Self::Distress => write!(f, "Distress message"),
+        }
+    }
+}
+
+/// A single thing that happened during an emergency
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShEmergencyEvent {
+    pub timestamp: f64,
+    pub description: String,
+}
+
+/// `ShEmergency` is raised by the built in emergency detector when an aircraft squawks an
+/// emergency code or sends a message with distress phrasing in it. It stays active until the
+/// condition clears
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShEmergency {
+    /// Set once the emergency has been stored
+    pub id: i64,
+    pub kind: ShEmergencyKind,
+    pub icao_hex: Option<String>,
+    pub tail: Option<String>,
+    pub flight: Option<String>,
+    pub started: f64,
+    /// When the emergency ended. `None` while it is still going
+    pub cleared: Option<f64>,
+    /// The last position we have for the aircraft
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Altitude in feet
+    pub altitude: Option<i32>,
+    /// Everything that happened, oldest first
+    pub events: Vec<ShEmergencyEvent>,
+}
+
+impl ShEmergency {
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.cleared.is_none()
+    }
+
+    /// The aircraft the emergency is about, in whatever form we have it
+    #[must_use]
+    pub fn aircraft(&self) -> String {
+        self.flight
+            .as_ref()
+            .or(self.tail.as_ref())
+            .or(self.icao_hex.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Where to put the aircraft on the map, if we know
+    #[must_use]
+    pub fn location(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    pub fn add_event(&mut self, timestamp: f64, description: String) {
+        self.events.push(ShEmergencyEvent {
+            timestamp,
+            description,
+        });
+    }
+}
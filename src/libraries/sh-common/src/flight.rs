@@ -0,0 +1,56 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+use crate::acars_message::ShAcarsMessage;
+use crate::adsb::ShAdsbPosition;
+
+/// `ShFlightSession` is one flight by one aircraft, built from the messages and positions we
+/// have heard from it
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShFlightSession {
+    pub id: i64,
+    /// What the aircraft is known by. The ICAO hex address if we have it, otherwise the tail
+    pub aircraft: String,
+    pub icao_hex: Option<String>,
+    pub tail: Option<String>,
+    /// The flight number or callsign the aircraft was using
+    pub callsign: Option<String>,
+    /// Unix timestamp of the first data heard for the flight
+    pub start: f64,
+    /// Unix timestamp of the last data heard for the flight
+    pub end: f64,
+    pub takeoff: Option<f64>,
+    pub landing: Option<f64>,
+    pub message_count: i64,
+    pub position_count: i64,
+}
+
+/// A flight with everything we have heard from it
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShFlightSessionDetail {
+    pub session: ShFlightSession,
+    /// Oldest first
+    pub messages: Vec<ShAcarsMessage>,
+    /// Oldest first
+    pub track: Vec<ShAdsbPosition>,
+}
+
+/// Flights change as data comes in, and late data can split or merge flights we already had,
+/// in which case the old flights are removed and replaced
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct ShFlightSessionChange {
+    pub removed: Vec<i64>,
+    pub updated: Vec<ShFlightSession>,
+}
+
+impl ShFlightSessionChange {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.updated.is_empty()
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+use crate::acars_message::ShMessageType;
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ShGroundStationNetwork {
+    Vdlm2,
+    Hfdl,
+}
+
+impl ShGroundStationNetwork {
+    #[must_use]
+    pub const fn from_message_type(message_type: ShMessageType) -> Option<Self> {
+        match message_type {
+            ShMessageType::Vdlm2 => Some(Self::Vdlm2),
+            ShMessageType::Hfdl => Some(Self::Hfdl),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ShGroundStationNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Vdlm2 => write!(f, "VDLM2"),
+            Self::Hfdl => write!(f, "HFDL"),
+        }
+    }
+}
+
+/// `ShGroundStation` is a VDL2 or HFDL ground station, either from the bundled dataset or
+/// learned from the frames the ground station sends out
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShGroundStation {
+    pub network: ShGroundStationNetwork,
+    /// The identifier as it appears on the network. VDL2 uses the hex address, HFDL the numeric ID
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub latitude: Option<f64>,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    /// Frequencies the station has been heard on or has advertised, in kHz
+    #[serde(default)]
+    pub frequencies: Vec<f64>,
+    /// ICAO codes of the airports the station covers, as advertised in VDL2 XID frames
+    #[serde(default)]
+    pub airports: Vec<String>,
+    /// Unix timestamp of the last frame heard from or about the station
+    #[serde(default)]
+    pub last_seen: Option<f64>,
+    /// Number of ACARS messages received through the station since startup
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub message_count: u64,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+impl ShGroundStation {
+    #[must_use]
+    pub const fn new(network: ShGroundStationNetwork, id: String) -> Self {
+        Self {
+            network,
+            id,
+            name: None,
+            latitude: None,
+            longitude: None,
+            frequencies: Vec::new(),
+            airports: Vec::new(),
+            last_seen: None,
+            message_count: 0,
+        }
+    }
+
+    /// A human readable name for the station, falling back to the network and ID
+    #[must_use]
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{} {}", self.network, self.id))
+    }
+}
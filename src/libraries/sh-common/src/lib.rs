@@ -14,35 +14,155 @@
 
 // This is the main loop of the SDRE Hub.
 
+pub mod acars_message;
+pub mod adsb;
+pub mod alert;
+pub mod api;
+pub mod auth;
+pub mod consumer_status;
+pub mod emergency;
+pub mod flight;
+pub mod ground_station;
+pub mod lookup;
+pub mod notification;
+pub mod oooi;
+pub mod source_status;
+pub mod stats;
+pub mod timeline;
+pub mod weather;
+
+use acars_message::ShAcarsMessage;
+use alert::ShAlert;
+use auth::{ShAuthStatus, ShLogin, ShUser, ShUserUpdate};
+use consumer_status::ShConsumerStatus;
+use emergency::ShEmergency;
+use flight::{ShFlightSession, ShFlightSessionChange, ShFlightSessionDetail};
+use ground_station::ShGroundStation;
+use lookup::ShFlightInfo;
+use notification::ShNotificationDelivery;
 use serde::{Deserialize, Serialize};
+use sh_config::alerts::ShAlertsConfig;
 use sh_config::map::ShMapConfig;
+use sh_config::source::{DataSources, EnabledDataSources};
 use sh_config::web::{sh_web_config::ShWebConfig, sh_web_sdrehub::ShWebSDREHub};
+use source_status::{ShSourceStatus, ShSourceTest, ShSourceTestResult};
+use stats::{ShStats, ShStatsRange};
+use timeline::ShFlightTimeline;
+use weather::{ShWeatherProduct, ShWeatherQuery};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum UserMessageTypes {
     UserRequestConfig,
     UserUpdateAppConfig,
     UserUpdateMapConfig,
+    UserUpdateDataSources,
+    UserUpdateEnabledDataSources,
+    UserRequestFlightInfo,
+    UserRequestRecentMessages,
+    UserRequestGroundStations,
+    UserRequestFlightTimeline,
+    UserRequestWeather,
+    UserRequestFlightSessions,
+    UserRequestFlightSession,
+    UserUpdateAlerts,
+    UserRequestAlertHistory,
+    UserRequestNotificationLog,
+    UserRequestEmergencies,
+    UserRequestStats,
+    UserRequestSourceStatus,
+    UserRequestConsumerStatus,
+    UserTestSource,
+    UserLogin,
+    UserResumeSession,
+    UserLogout,
+    UserSetupAdmin,
+    UserRequestUsers,
+    UserUpdateUser,
+    UserDeleteUser,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum ServerMessageTypes {
     ServerResponseConfig,
     ServerWriteConfigSuccess,
     ServerWriteConfigFailure,
+    ServerResponseFlightInfo,
+    ServerResponseRecentMessages,
+    ServerNewAcarsMessage,
+    ServerResponseGroundStations,
+    ServerGroundStationUpdate,
+    ServerResponseFlightTimeline,
+    ServerResponseWeather,
+    ServerNewWeather,
+    ServerResponseFlightSessions,
+    ServerResponseFlightSession,
+    ServerFlightSessionUpdate,
+    ServerAlert,
+    ServerResponseAlertHistory,
+    ServerNotificationDelivery,
+    ServerResponseNotificationLog,
+    ServerEmergency,
+    ServerResponseEmergencies,
+    ServerResponseStats,
+    ServerSourceStatus,
+    ServerResponseSourceStatus,
+    ServerConsumerStatus,
+    ServerResponseConsumerStatus,
+    ServerResponseSourceTest,
+    ServerAuthStatus,
+    ServerAuthFailure,
+    ServerResponseUsers,
 }
 
-#[derive(Deserialize, Serialize, PartialEq, Debug)]
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub enum MessageData {
     ShConfig(ShWebConfig),
     ShAppConfig(ShWebSDREHub),
     ShMapConfig(ShMapConfig),
+    ShDataSources(DataSources),
+    ShEnabledDataSources(EnabledDataSources),
     ShConfigSuccess(String),
     ShConfigFailure(String),
+    ShFlightLookup(String),
+    ShFlightInfo(ShFlightInfo),
+    ShAcarsMessage(Box<ShAcarsMessage>),
+    ShAcarsMessages(Vec<ShAcarsMessage>),
+    ShGroundStation(ShGroundStation),
+    ShGroundStations(Vec<ShGroundStation>),
+    ShFlightTimeline(Box<ShFlightTimeline>),
+    ShWeatherQuery(ShWeatherQuery),
+    ShWeatherProduct(ShWeatherProduct),
+    ShWeatherProducts(Vec<ShWeatherProduct>),
+    ShFlightSessionId(i64),
+    ShFlightSessions(Vec<ShFlightSession>),
+    ShFlightSessionDetail(Box<ShFlightSessionDetail>),
+    ShFlightSessionChange(ShFlightSessionChange),
+    ShAlertsConfig(ShAlertsConfig),
+    ShAlert(Box<ShAlert>),
+    ShAlerts(Vec<ShAlert>),
+    ShNotificationDelivery(ShNotificationDelivery),
+    ShNotificationDeliveries(Vec<ShNotificationDelivery>),
+    ShEmergency(Box<ShEmergency>),
+    ShEmergencies(Vec<ShEmergency>),
+    ShStatsRange(ShStatsRange),
+    ShStats(Box<ShStats>),
+    ShSourceStatus(ShSourceStatus),
+    ShSourceStatuses(Vec<ShSourceStatus>),
+    ShConsumerStatus(ShConsumerStatus),
+    ShConsumerStatuses(Vec<ShConsumerStatus>),
+    ShSourceTest(ShSourceTest),
+    ShSourceTestResult(Box<ShSourceTestResult>),
+    ShLogin(ShLogin),
+    ShSessionToken(String),
+    ShAuthStatus(ShAuthStatus),
+    ShAuthFailure(String),
+    ShUsers(Vec<ShUser>),
+    ShUserUpdate(ShUserUpdate),
+    ShUserName(String),
     NoData,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ServerWssMessage {
     message_type: ServerMessageTypes,
     data: MessageData,
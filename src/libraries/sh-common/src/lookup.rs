@@ -0,0 +1,38 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+/// `ShFlightInfo` is the result of looking up a flight number in the airline and route tables
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct ShFlightInfo {
+    /// The flight number as it was requested
+    pub flight: String,
+    /// The flight number in ICAO form, e.g. `UAL1234`. `None` if the airline could not be found
+    pub flight_icao: Option<String>,
+    /// The flight number in IATA form, e.g. `UA1234`. `None` if the airline has no IATA designator
+    pub flight_iata: Option<String>,
+    pub airline_name: Option<String>,
+    pub airline_callsign: Option<String>,
+    /// ICAO code of the origin airport
+    pub origin: Option<String>,
+    /// ICAO code of the destination airport
+    pub destination: Option<String>,
+}
+
+impl ShFlightInfo {
+    #[must_use]
+    pub fn new(flight: &str) -> Self {
+        Self {
+            flight: flight.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub const fn has_route(&self) -> bool {
+        self.origin.is_some() && self.destination.is_some()
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+/// `ShNotificationDelivery` is how sending one alert to one notification target went
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShNotificationDelivery {
+    /// Set once the delivery has been stored
+    pub id: i64,
+    pub alert_id: i64,
+    /// Name of the rule that raised the alert
+    pub rule: String,
+    /// Name of the notification target
+    pub target: String,
+    /// Unix timestamp of the last attempt
+    pub timestamp: f64,
+    pub attempts: u32,
+    pub delivered: bool,
+    /// The error from the last attempt, if it failed
+    pub error: Option<String>,
+}
@@ -0,0 +1,48 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+/// The four gate and runway events an aircraft reports over ACARS. The variants are in the
+/// order they happen during a flight
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub enum ShOooiEventType {
+    /// Pushed back from the gate
+    Out,
+    /// Wheels off the runway
+    Off,
+    /// Wheels on the runway
+    On,
+    /// Arrived at the gate
+    In,
+}
+
+impl ShOooiEventType {
+    pub const ALL: [Self; 4] = [Self::Out, Self::Off, Self::On, Self::In];
+}
+
+impl std::fmt::Display for ShOooiEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Out => write!(f, "OUT"),
+            Self::Off => write!(f, "OFF"),
+            Self::On => write!(f, "ON"),
+            Self::In => write!(f, "IN"),
+        }
+    }
+}
+
+/// An OOOI event pulled out of a message
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShOooiEvent {
+    pub event_type: ShOooiEventType,
+    /// Unix timestamp of the event. Reports only carry the time of day, so the date comes from
+    /// the message the event was reported in
+    pub time: f64,
+    /// Unix timestamp of the message that reported the event
+    pub message_timestamp: f64,
+    /// Label of the message that reported the event
+    pub label: Option<String>,
+}
@@ -0,0 +1,182 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// What kind of feeder a source is
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShSourceKind {
+    AcarsRouter,
+    Adsb,
+}
+
+impl Display for ShSourceKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::AcarsRouter => write!(f, "acars_router"),
+            Self::Adsb => write!(f, "ADS-B"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShSourceState {
+    /// Not connected yet, or trying again after losing the connection
+    Connecting,
+    Connected,
+    /// Connected, but nothing has been received for longer than the silence timeout
+    Silent,
+    /// The connection was lost or could not be made. It will be tried again
+    Disconnected,
+}
+
+impl Display for ShSourceState {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Connecting => write!(f, "Connecting"),
+            Self::Connected => write!(f, "Connected"),
+            Self::Silent => write!(f, "Silent"),
+            Self::Disconnected => write!(f, "Disconnected"),
+        }
+    }
+}
+
+/// `ShSourceStatus` is how the connection to one configured source is doing
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShSourceStatus {
+    /// The address of the source, as `host:port`
+    pub name: String,
+    pub kind: ShSourceKind,
+    pub state: ShSourceState,
+    /// Unix timestamp, in seconds, of when the source went into its current state
+    pub since: f64,
+    /// Unix timestamp, in seconds, of when data was last received from the source
+    pub last_data: Option<f64>,
+    /// Times the connection has been lost or could not be made
+    pub reconnects: u64,
+    /// Why the connection was last lost, if it was
+    pub last_error: Option<String>,
+}
+
+impl ShSourceStatus {
+    #[must_use]
+    pub const fn new(name: String, kind: ShSourceKind, now: f64) -> Self {
+        Self {
+            name,
+            kind,
+            state: ShSourceState::Connecting,
+            since: now,
+            last_data: None,
+            reconnects: 0,
+            last_error: None,
+        }
+    }
+
+    /// Connected and sending data
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.state == ShSourceState::Connected
+    }
+}
+
+/// A source to try connecting to, before it is added to the config
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShSourceTest {
+    pub kind: ShSourceKind,
+    pub address: String,
+    pub port: u32,
+}
+
+impl Display for ShSourceTest {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.address, self.port)
+    }
+}
+
+/// What a source turned out to be sending
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShSourceFormat {
+    /// Decoder JSON, like `acars_router` sends
+    Json,
+    /// SBS (BaseStation) CSV
+    Sbs,
+    /// Beast binary
+    Beast,
+    /// Something was received, but not anything we know
+    Unknown,
+}
+
+impl ShSourceFormat {
+    /// Whether the hub's client for `kind` can read this
+    #[must_use]
+    pub const fn readable_by(self, kind: ShSourceKind) -> bool {
+        matches!(
+            (self, kind),
+            (Self::Json, ShSourceKind::AcarsRouter) | (Self::Sbs, ShSourceKind::Adsb)
+        )
+    }
+}
+
+impl Display for ShSourceFormat {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "JSON"),
+            Self::Sbs => write!(f, "SBS"),
+            Self::Beast => write!(f, "Beast"),
+            Self::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// `ShSourceTestResult` is what a short test connection to a source found
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShSourceTestResult {
+    /// The source tested, as `host:port`
+    pub name: String,
+    pub kind: ShSourceKind,
+    /// The connection could be made
+    pub reachable: bool,
+    /// What the source sent. `None` if it sent nothing while it was listened to
+    pub format: Option<ShSourceFormat>,
+    /// The decoder that produced JSON, like `dumpvdl2` or `acarsdec`
+    pub decoder: Option<String>,
+    /// The first message that could be read, or the first thing received if none could
+    pub sample: Option<String>,
+    pub bytes_received: usize,
+    /// Why the source can't be used as it is, if it can't
+    pub error: Option<String>,
+}
+
+impl ShSourceTestResult {
+    #[must_use]
+    pub fn new(test: &ShSourceTest) -> Self {
+        Self {
+            name: test.to_string(),
+            kind: test.kind,
+            reachable: false,
+            format: None,
+            decoder: None,
+            sample: None,
+            bytes_received: 0,
+            error: None,
+        }
+    }
+
+    /// Connected, and sent something the hub can read
+    #[must_use]
+    pub fn is_usable(&self) -> bool {
+        self.error.is_none()
+            && self
+                .format
+                .is_some_and(|format| format.readable_by(self.kind))
+    }
+}
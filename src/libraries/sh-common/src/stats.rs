@@ -0,0 +1,107 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// How far back the statistics page looks
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShStatsRange {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl ShStatsRange {
+    pub const ALL: [Self; 4] = [Self::Hour, Self::Day, Self::Week, Self::Month];
+
+    /// Length of the range in seconds
+    #[must_use]
+    pub const fn seconds(self) -> i64 {
+        match self {
+            Self::Hour => 60 * 60,
+            Self::Day => 24 * 60 * 60,
+            Self::Week => 7 * 24 * 60 * 60,
+            Self::Month => 30 * 24 * 60 * 60,
+        }
+    }
+
+    /// Length of each point on the time series, in seconds. Keeps every range to a few dozen
+    /// points
+    #[must_use]
+    pub const fn period(self) -> i64 {
+        match self {
+            Self::Hour => 5 * 60,
+            Self::Day => 60 * 60,
+            Self::Week => 6 * 60 * 60,
+            Self::Month => 24 * 60 * 60,
+        }
+    }
+}
+
+impl Display for ShStatsRange {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Hour => write!(f, "1h"),
+            Self::Day => write!(f, "24h"),
+            Self::Week => write!(f, "7d"),
+            Self::Month => write!(f, "30d"),
+        }
+    }
+}
+
+impl FromStr for ShStatsRange {
+    type Err = String;
+
+    /// Takes the same `1h`, `24h`, `7d` and `30d` the range is shown as
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|range| range.to_string().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| format!("Unknown range {value}. Expected one of 1h, 24h, 7d or 30d"))
+    }
+}
+
+/// One point on the time series
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShStatsPoint {
+    /// Unix timestamp, in seconds, of the start of the period
+    pub timestamp: i64,
+    pub messages: i64,
+    /// The most aircraft tracked at once during the period
+    pub aircraft: i64,
+}
+
+/// How many messages had a particular source, label and so on
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShStatsCount {
+    pub key: String,
+    pub count: i64,
+}
+
+/// `ShStats` is everything on the statistics page for one range
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShStats {
+    pub range: ShStatsRange,
+    /// Oldest first, with a point for every period in the range
+    pub series: Vec<ShStatsPoint>,
+    pub total_messages: i64,
+    /// The breakdowns are largest first
+    pub sources: Vec<ShStatsCount>,
+    pub message_types: Vec<ShStatsCount>,
+    pub frequencies: Vec<ShStatsCount>,
+    pub labels: Vec<ShStatsCount>,
+    pub ground_stations: Vec<ShStatsCount>,
+    /// Messages by hour of the day, UTC. The key is the hour, `00` to `23`, and every hour is
+    /// there, in order
+    pub hours: Vec<ShStatsCount>,
+}
@@ -0,0 +1,85 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+use crate::oooi::ShOooiEventType;
+
+/// Points in a flight we can pick out of the ADS-B data
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ShPositionMilestone {
+    FirstSeen,
+    Takeoff,
+    Landing,
+    LastSeen,
+}
+
+impl std::fmt::Display for ShPositionMilestone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FirstSeen => write!(f, "First seen"),
+            Self::Takeoff => write!(f, "Takeoff"),
+            Self::Landing => write!(f, "Landing"),
+            Self::LastSeen => write!(f, "Last seen"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub enum ShTimelineEntryKind {
+    Oooi(ShOooiEventType),
+    Message {
+        label: Option<String>,
+        text: Option<String>,
+    },
+    Position(ShPositionMilestone),
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShTimelineEntry {
+    pub timestamp: f64,
+    pub kind: ShTimelineEntryKind,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ShTimelineFlagKind {
+    /// An event that should have been reported was not
+    Missing,
+    /// Events that cannot all be true, such as two different OUT times
+    Contradictory,
+}
+
+/// A problem with the OOOI events for a flight, shown to the user instead of being resolved
+/// behind their back
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShTimelineFlag {
+    pub kind: ShTimelineFlagKind,
+    pub event_type: ShOooiEventType,
+    pub description: String,
+}
+
+/// `ShFlightTimeline` is everything we know happened to a flight, in time order
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShFlightTimeline {
+    /// The flight number the timeline is for, in ICAO form when it could be normalized
+    pub flight: String,
+    pub tail: Option<String>,
+    pub icao_hex: Option<String>,
+    pub entries: Vec<ShTimelineEntry>,
+    pub flags: Vec<ShTimelineFlag>,
+}
+
+impl ShFlightTimeline {
+    #[must_use]
+    pub const fn new(flight: String) -> Self {
+        Self {
+            flight,
+            tail: None,
+            icao_hex: None,
+            entries: Vec::new(),
+            flags: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum ShWeatherProductType {
+    Metar,
+    /// An unscheduled METAR issued when conditions change
+    Speci,
+    Taf,
+    Atis,
+}
+
+impl std::fmt::Display for ShWeatherProductType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Metar => write!(f, "METAR"),
+            Self::Speci => write!(f, "SPECI"),
+            Self::Taf => write!(f, "TAF"),
+            Self::Atis => write!(f, "ATIS"),
+        }
+    }
+}
+
+/// `ShWeatherProduct` is a single METAR, TAF or ATIS for one airport, pulled out of a message
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ShWeatherProduct {
+    pub product_type: ShWeatherProductType,
+    /// The airport as it appeared in the product. Usually the ICAO code, but some ATIS uplinks
+    /// use the IATA code
+    pub airport: String,
+    /// Unix timestamp the product was issued at, if it had an issue time we could read
+    pub issued: Option<f64>,
+    /// The ATIS information letter
+    pub atis_code: Option<String>,
+    /// The product text as it was received
+    pub raw: String,
+    /// Unix timestamp of the message the product was heard in
+    pub received: f64,
+    /// The aircraft the product was sent to
+    pub tail: Option<String>,
+}
+
+impl ShWeatherProduct {
+    /// Two products are the same report if they are for the same airport, of the same type
+    /// and were issued at the same time. Airlines often uplink the same report many times
+    #[must_use]
+    pub fn is_same_report(&self, other: &Self) -> bool {
+        self.product_type == other.product_type
+            && self.airport == other.airport
+            && self.issued.map(f64::to_bits) == other.issued.map(f64::to_bits)
+            && self.atis_code == other.atis_code
+    }
+}
+
+/// A request for the weather we have heard. Every field is optional, so an empty query returns
+/// everything
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct ShWeatherQuery {
+    pub airport: Option<String>,
+    pub product_type: Option<ShWeatherProductType>,
+    /// Only return the most recent product of each type for each airport
+    #[serde(default)]
+    pub latest_only: bool,
+}
+
+impl ShWeatherQuery {
+    /// Does `product` match the airport and type asked for. `latest_only` is up to the caller,
+    /// as that depends on what else is being returned
+    #[must_use]
+    pub fn matches(&self, product: &ShWeatherProduct) -> bool {
+        self.airport.as_ref().map_or(true, |airport| {
+            airport.trim().eq_ignore_ascii_case(&product.airport)
+        }) && self
+            .product_type
+            .map_or(true, |product_type| product_type == product.product_type)
+    }
+}
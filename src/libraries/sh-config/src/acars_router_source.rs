@@ -11,15 +11,42 @@ use serde::{
 };
 use void::Void;
 
-use crate::address::ShAcarsRouterConfig;
+use crate::address::{ShAcarsRouterConfig, ShRejectedAddress};
 
 pub trait SourceTrait {
     fn new() -> Self;
     fn insert(&mut self, value: ShAcarsRouterConfig);
+    /// Keep an entry that couldn't be used, so it can be reported
+    fn reject(&mut self, value: String, reason: String);
 }
 #[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct AcarsRouterSource {
     addresses: Vec<ShAcarsRouterConfig>,
+    /// Entries that were in the config but couldn't be used, and were left out
+    #[serde(skip)]
+    rejected: Vec<ShRejectedAddress>,
+}
+
+impl AcarsRouterSource {
+    #[must_use]
+    pub fn addresses(&self) -> &[ShAcarsRouterConfig] {
+        &self.addresses
+    }
+
+    #[must_use]
+    pub fn rejected(&self) -> &[ShRejectedAddress] {
+        &self.rejected
+    }
+}
+
+impl From<Vec<ShAcarsRouterConfig>> for AcarsRouterSource {
+    fn from(addresses: Vec<ShAcarsRouterConfig>) -> Self {
+        Self {
+            addresses,
+            rejected: Vec::new(),
+        }
+    }
 }
 
 impl FromStr for AcarsRouterSource {
@@ -28,9 +55,10 @@ impl FromStr for AcarsRouterSource {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut output = Self::new();
 
-        for address in s.split(',') {
-            if let Some(address) = ShAcarsRouterConfig::new(address) {
-                output.insert(address);
+        for address in s.split(',').filter(|address| !address.trim().is_empty()) {
+            match ShAcarsRouterConfig::parse(address) {
+                Ok(address) => output.insert(address),
+                Err(e) => output.reject(address.trim().to_string(), e),
             }
         }
 
@@ -42,12 +70,17 @@ impl SourceTrait for AcarsRouterSource {
     fn new() -> Self {
         Self {
             addresses: Vec::new(),
+            rejected: Vec::new(),
         }
     }
 
     fn insert(&mut self, value: ShAcarsRouterConfig) {
         self.addresses.push(value);
     }
+
+    fn reject(&mut self, value: String, reason: String) {
+        self.rejected.push(ShRejectedAddress { value, reason });
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Hash, Clone)]
@@ -59,6 +92,25 @@ enum FieldTypes {
     Port(u32),
 }
 
+impl fmt::Display for FieldTypes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "\"{address}\""),
+            Self::Port(port) => write!(f, "{port}"),
+        }
+    }
+}
+
+/// An entry as it was written, for saying which one couldn't be used
+fn describe(item: &HashMap<String, FieldTypes>) -> String {
+    let mut fields: Vec<String> = item
+        .iter()
+        .map(|(key, value)| format!("{key} = {value}"))
+        .collect();
+    fields.sort();
+    format!("{{ {} }}", fields.join(", "))
+}
+
 /// # Errors
 /// Returns an error if the input fails to deserialize
 pub fn string_or_struct<'de, T, D>(deserializer: D) -> Result<T, D::Error>
@@ -111,29 +163,22 @@ where
                         }
 
                         for item in &value {
-                            let port = match item.get("port").unwrap().to_owned() {
-                                FieldTypes::Port(port) => {
-                                    if (0..=65535).contains(&port) {
-                                        port
-                                    } else {
-                                        return Err(de::Error::custom("Port out of range"));
-                                    }
-                                }
-                                FieldTypes::Address(_) => {
-                                    return Err(de::Error::custom("Port not found"));
-                                }
+                            let (Some(FieldTypes::Address(address)), Some(FieldTypes::Port(port))) =
+                                (item.get("address"), item.get("port"))
+                            else {
+                                source.reject(
+                                    describe(item),
+                                    "Needs an address and a port".to_string(),
+                                );
+                                continue;
                             };
 
-                            let address = match item.get("address").unwrap().to_owned() {
-                                FieldTypes::Address(address) => address,
-                                FieldTypes::Port(_) => {
-                                    return Err(de::Error::custom("Address not found"));
-                                }
-                            };
-
-                            let address = ShAcarsRouterConfig::new_from_parts(address, port);
-
-                            source.insert(address);
+                            let address =
+                                ShAcarsRouterConfig::new_from_parts(address.clone(), *port);
+                            match address.validate() {
+                                Ok(()) => source.insert(address),
+                                Err(e) => source.reject(address.to_string(), e),
+                            }
                         }
                     }
                     Err(e) => {
@@ -160,8 +205,9 @@ where
             let mut source = T::new();
 
             while let Some(value) = seq.next_element::<String>()? {
-                if let Some(address) = ShAcarsRouterConfig::new(&value) {
-                    source.insert(address);
+                match ShAcarsRouterConfig::parse(&value) {
+                    Ok(address) => source.insert(address),
+                    Err(e) => source.reject(value, e),
                 }
             }
 
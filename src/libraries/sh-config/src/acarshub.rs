@@ -0,0 +1,325 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Carrying a docker-acarshub setup over to the hub. acarshub is set up entirely with environment
+// variables, so this works from a map of them. acarshub is usually fed by acars_router, which
+// it listens to over UDP, where the hub connects to acars_router over TCP instead
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use crate::acars_router_source::AcarsRouterSource;
+use crate::address::{SHAdsbConfig, ShAcarsRouterConfig};
+use crate::adsb_source::AdsbSource;
+use crate::lookup::ShAirlineOverride;
+use crate::source::ShEnabledDataSources;
+use crate::ShConfig;
+
+// Where acars_router is found in the usual docker compose setup
+const ACARS_ROUTER_HOST: &str = "acars_router";
+// readsb and dump1090 serve SBS on this port
+const SBS_PORT: u16 = 30003;
+
+/// One of the kinds of message acarshub can be turned on for
+struct AcarshubDecoder {
+    /// Turns the kind of message on
+    enable: &'static str,
+    /// Where acarshub gets the messages from, like `udp` or `tcp://host:port`
+    connections: &'static str,
+    source: ShEnabledDataSources,
+    /// The port `acars_router` serves these messages on over TCP, by default
+    router_port: u16,
+}
+
+const DECODERS: [AcarshubDecoder; 5] = [
+    AcarshubDecoder {
+        enable: "ENABLE_ACARS",
+        connections: "ACARS_CONNECTIONS",
+        source: ShEnabledDataSources::Acars,
+        router_port: 15550,
+    },
+    AcarshubDecoder {
+        enable: "ENABLE_VDLM",
+        connections: "VDLM_CONNECTIONS",
+        source: ShEnabledDataSources::Vdlm2,
+        router_port: 15555,
+    },
+    AcarshubDecoder {
+        enable: "ENABLE_HFDL",
+        connections: "HFDL_CONNECTIONS",
+        source: ShEnabledDataSources::Hfdl,
+        router_port: 15556,
+    },
+    AcarshubDecoder {
+        enable: "ENABLE_IMSL",
+        connections: "IMSL_CONNECTIONS",
+        source: ShEnabledDataSources::Inmarsat,
+        router_port: 15557,
+    },
+    AcarshubDecoder {
+        enable: "ENABLE_IRDM",
+        connections: "IRDM_CONNECTIONS",
+        source: ShEnabledDataSources::Iridium,
+        router_port: 15558,
+    },
+];
+
+// acarshub settings the hub has nothing like, and why
+const UNSUPPORTED: [(&str, &str); 9] = [
+    ("FEED", "The hub doesn't feed other sites. acars_router can"),
+    ("DB_SAVEALL", "The hub keeps every message"),
+    ("DB_SAVE_DAYS", "The hub keeps every message"),
+    ("DB_ALERT_SAVE_DAYS", "The hub keeps every alert"),
+    (
+        "ALLOW_REMOTE_UPDATES",
+        "Settings can be changed by admin accounts",
+    ),
+    ("TAR1090_URL", "The hub doesn't link to tar1090"),
+    (
+        "ADSB_BYPASS_URL",
+        "The hub reads SBS from the ADS-B decoder itself",
+    ),
+    ("ENABLE_RANGE_RINGS", "The hub's map has no range rings"),
+    ("QUIET_MESSAGES", "The hub always shows every message"),
+];
+
+/// What came of reading acarshub's settings
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ShAcarshubReport {
+    /// The settings that were changed, and what from
+    pub changes: Vec<String>,
+    /// acarshub settings that weren't carried over, and why
+    pub skipped: Vec<String>,
+}
+
+/// Change `config` to do what acarshub was set up to do by `env`. Sources and airline overrides
+/// are added to the ones already there
+#[must_use]
+pub fn apply_acarshub_env<S: BuildHasher>(
+    config: &mut ShConfig,
+    env: &HashMap<String, String, S>,
+) -> ShAcarshubReport {
+    let mut report = ShAcarshubReport::default();
+    let get = |name: &str| {
+        env.get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+
+    let mut routers = config.data_sources.acars_routers.addresses().to_vec();
+    for decoder in &DECODERS {
+        let Some(enable) = get(decoder.enable) else {
+            continue;
+        };
+        if !is_enabled(enable) {
+            continue;
+        }
+
+        if !config.enabled_data_sources.is_enabled(&decoder.source) {
+            config
+                .enabled_data_sources
+                .enabled_sources
+                .push(decoder.source.clone());
+            report.changes.push(format!(
+                "enabled_data_sources: turned on {:?}, from {}={enable}",
+                decoder.source, decoder.enable
+            ));
+        }
+
+        for address in router_addresses(decoder, get(decoder.connections), &mut report) {
+            if routers.contains(&address) {
+                continue;
+            }
+            report
+                .changes
+                .push(format!("data_sources.acars_routers: added {address}"));
+            routers.push(address);
+        }
+    }
+    config.data_sources.acars_routers = AcarsRouterSource::from(routers);
+
+    apply_adsb(config, &get, &mut report);
+    apply_iata_overrides(config, get("IATA_OVERRIDE"), &mut report);
+
+    for (name, reason) in UNSUPPORTED {
+        if let Some(value) = get(name) {
+            report.skipped.push(format!("{name}={value}: {reason}"));
+        }
+    }
+
+    report
+}
+
+fn is_enabled(value: &str) -> bool {
+    matches!(
+        value.to_lowercase().as_str(),
+        "true" | "external" | "yes" | "on" | "1"
+    )
+}
+
+/// The `acars_router` sources for one kind of message. acarshub connecting out over TCP is what
+/// the hub does, so those are kept. Anything it listened for is taken to be from `acars_router`
+fn router_addresses(
+    decoder: &AcarshubDecoder,
+    connections: Option<&str>,
+    report: &mut ShAcarshubReport,
+) -> Vec<ShAcarsRouterConfig> {
+    let mut addresses = Vec::new();
+    let mut listened = connections.is_none();
+
+    for connection in connections
+        .unwrap_or_default()
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|connection| !connection.is_empty())
+    {
+        if let Some(address) = connection.strip_prefix("tcp://") {
+            match ShAcarsRouterConfig::parse(address) {
+                Ok(address) => addresses.push(address),
+                Err(e) => report
+                    .skipped
+                    .push(format!("{}={connection}: {e}", decoder.connections)),
+            }
+        } else if connection.starts_with("zmq://") {
+            report.skipped.push(format!(
+                "{}={connection}: The hub can't read ZMQ. Send it through acars_router",
+                decoder.connections
+            ));
+        } else {
+            listened = true;
+        }
+    }
+
+    if listened {
+        let address = ShAcarsRouterConfig::new_from_parts(
+            ACARS_ROUTER_HOST.to_string(),
+            u32::from(decoder.router_port),
+        );
+        report.changes.push(format!(
+            "data_sources.acars_routers: acarshub listened for {:?} messages, so it is assumed \
+             acars_router is serving them at {address}. Change it if acars_router is somewhere \
+             else",
+            decoder.source
+        ));
+        addresses.push(address);
+    }
+
+    addresses
+}
+
+/// acarshub reads aircraft.json from tar1090, where the hub reads SBS from the decoder. The
+/// decoder is taken to be on the same host as tar1090
+fn apply_adsb<'a>(
+    config: &mut ShConfig,
+    get: &impl Fn(&str) -> Option<&'a str>,
+    report: &mut ShAcarshubReport,
+) {
+    let latitude = get("ADSB_LAT").and_then(|value| value.parse::<f64>().ok());
+    let longitude = get("ADSB_LON").and_then(|value| value.parse::<f64>().ok());
+
+    if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+        if config.map.center_latitude == 0.0 && config.map.center_longitude == 0.0 {
+            config.map.center_latitude = latitude;
+            config.map.center_longitude = longitude;
+            report.changes.push(format!(
+                "map: centered on {latitude}, {longitude}, from ADSB_LAT and ADSB_LON"
+            ));
+        }
+    }
+
+    if !get("ENABLE_ADSB").is_some_and(is_enabled) {
+        return;
+    }
+    let Some(url) = get("ADSB_URL") else {
+        report
+            .skipped
+            .push("ENABLE_ADSB: ADSB_URL isn't set, so there is no ADS-B source".to_string());
+        return;
+    };
+    let (Some(latitude), Some(longitude)) = (latitude, longitude) else {
+        report.skipped.push(format!(
+            "ADSB_URL={url}: ADS-B sources need ADSB_LAT and ADSB_LON for where they are"
+        ));
+        return;
+    };
+
+    let host = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    let source = match SHAdsbConfig::parse(&format!("{host}:{SBS_PORT}:{latitude}:{longitude}")) {
+        Ok(source) => source,
+        Err(e) => {
+            report.skipped.push(format!("ADSB_URL={url}: {e}"));
+            return;
+        }
+    };
+
+    if !config
+        .enabled_data_sources
+        .is_enabled(&ShEnabledDataSources::Adsb)
+    {
+        config
+            .enabled_data_sources
+            .enabled_sources
+            .push(ShEnabledDataSources::Adsb);
+        report
+            .changes
+            .push("enabled_data_sources: turned on Adsb, from ENABLE_ADSB".to_string());
+    }
+
+    let mut sources = config.data_sources.adsb_sources.addresses().to_vec();
+    if !sources.contains(&source) {
+        report.changes.push(format!(
+            "data_sources.adsb_sources: added {source}. It is assumed the decoder behind \
+             {url} serves SBS on port {SBS_PORT}"
+        ));
+        sources.push(source);
+        config.data_sources.adsb_sources = AdsbSource::from(sources);
+    }
+}
+
+/// `IATA_OVERRIDE` is `IATA|ICAO|Name` entries, split by `;`
+fn apply_iata_overrides(
+    config: &mut ShConfig,
+    overrides: Option<&str>,
+    report: &mut ShAcarshubReport,
+) {
+    let Some(overrides) = overrides else {
+        return;
+    };
+
+    for entry in overrides
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let parts: Vec<&str> = entry.split('|').map(str::trim).collect();
+        let [iata, icao, name] = parts[..] else {
+            report.skipped.push(format!(
+                "IATA_OVERRIDE entry {entry}: Has to be IATA|ICAO|Name"
+            ));
+            continue;
+        };
+
+        let icao = icao.to_uppercase();
+        let overrides = &mut config.lookup.airline_overrides;
+        if overrides.iter().any(|known| known.icao == icao) {
+            continue;
+        }
+
+        overrides.push(ShAirlineOverride {
+            iata: Some(iata.to_uppercase()).filter(|iata| !iata.is_empty()),
+            icao,
+            name: name.to_string(),
+            callsign: String::new(),
+        });
+        report
+            .changes
+            .push(format!("lookup.airline_overrides: added {entry}"));
+    }
+}
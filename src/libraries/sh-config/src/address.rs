@@ -5,8 +5,69 @@
 
 use serde::{Deserialize, Serialize};
 
+// The rules every source address follows, shared by parsing, the config file and the web
+// interface's editor so they all agree
+
+/// # Errors
+/// The address is empty, or has a space or `:` in it
+pub fn validate_address(address: &str) -> Result<(), String> {
+    if address.trim().is_empty() {
+        return Err("The address can't be empty".to_string());
+    }
+
+    if address
+        .trim()
+        .contains(|c: char| c == ':' || c.is_whitespace())
+    {
+        return Err(format!("{address} is not a host name or IPv4 address"));
+    }
+
+    Ok(())
+}
+
+/// # Errors
+/// The port isn't between 1 and 65535
+pub fn validate_port(port: u32) -> Result<(), String> {
+    if port == 0 || port > 65535 {
+        return Err(format!("Port {port} has to be between 1 and 65535"));
+    }
+
+    Ok(())
+}
+
+/// # Errors
+/// The latitude isn't between -90 and 90
+pub fn validate_latitude(latitude: f64) -> Result<(), String> {
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(format!("Latitude {latitude} has to be between -90 and 90"));
+    }
+
+    Ok(())
+}
+
+/// # Errors
+/// The longitude isn't between -180 and 180
+pub fn validate_longitude(longitude: f64) -> Result<(), String> {
+    if !(-180.0..=180.0).contains(&longitude) {
+        return Err(format!(
+            "Longitude {longitude} has to be between -180 and 180"
+        ));
+    }
+
+    Ok(())
+}
+
+/// A source in the config that couldn't be used, kept so it can be reported rather than
+/// quietly dropped
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ShRejectedAddress {
+    pub value: String,
+    pub reason: String,
+}
+
 /// Struct to store the address of an ACARS router
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ShAcarsRouterConfig {
     address: String,
     port: u32,
@@ -18,29 +79,63 @@ impl ShAcarsRouterConfig {
     /// Returns an Option containing the `AcarsRouterAddress` if successful
     #[must_use]
     pub fn new(input: &str) -> Option<Self> {
+        Self::parse(input).ok()
+    }
+
+    /// # Errors
+    /// The input isn't `address:port`, or the address or port can't be used
+    pub fn parse(input: &str) -> Result<Self, String> {
         let parts: Vec<&str> = input.split(':').collect();
 
         if parts.len() != 2 {
-            return None;
+            return Err("Has to be address:port".to_string());
         }
 
-        let Ok(port) = parts[1].parse::<u32>() else {
-            return None;
-        };
+        let port = parts[1]
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Port {} is not a number", parts[1].trim()))?;
 
-        Some(Self {
+        let address = Self {
             address: parts[0].trim().to_string(),
             port,
-        })
+        };
+        address.validate()?;
+
+        Ok(address)
+    }
+
+    /// # Errors
+    /// The address or port can't be used
+    pub fn validate(&self) -> Result<(), String> {
+        validate_address(&self.address)?;
+        validate_port(self.port)
     }
 
     #[must_use]
     pub const fn new_from_parts(address: String, port: u32) -> Self {
         Self { address, port }
     }
+
+    #[must_use]
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    #[must_use]
+    pub const fn port(&self) -> u32 {
+        self.port
+    }
+}
+
+impl std::fmt::Display for ShAcarsRouterConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.address, self.port)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct SHAdsbConfig {
     address: String,
     port: u32,
@@ -49,68 +144,46 @@ pub struct SHAdsbConfig {
 }
 
 impl SHAdsbConfig {
-    /// Create a new `AcarsRouterAddress` from a string
-    /// Input should be in the format "address:port"
-    /// Returns an Option containing the `AcarsRouterAddress` if successful
+    /// Create a new `SHAdsbConfig` from a string
+    /// Input should be in the format "address:port:latitude:longitude"
+    /// Returns an Option containing the `SHAdsbConfig` if successful
     #[must_use]
     pub fn new(input: &str) -> Option<Self> {
-        let parts: Vec<&str> = input.split(':').collect();
+        Self::parse(input).ok()
+    }
+
+    /// # Errors
+    /// The input isn't `address:port:latitude:longitude`, or one of them can't be used
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = input.split(':').map(str::trim).collect();
 
         if parts.len() != 4 {
-            return None;
+            return Err("Has to be address:port:latitude:longitude".to_string());
         }
 
-        let port = match parts[1].parse::<u32>() {
-            Ok(port) => {
-                // verify that the port is in the correct range
-
-                if port == 0 || port > 65535 {
-                    return None;
-                }
-
-                port
-            }
-            Err(_) => {
-                return None;
-            }
-        };
-
-        let latitude = match parts[2].parse::<f64>() {
-            Ok(latitude) => {
-                // verify that the latitude is in the correct range
-
-                if !(-90.0..=90.0).contains(&latitude) {
-                    return None;
-                }
-
-                latitude
-            }
-            Err(_) => {
-                return None;
-            }
-        };
+        let port = parts[1]
+            .parse::<u32>()
+            .map_err(|_| format!("Port {} is not a number", parts[1]))?;
+        let latitude = parts[2]
+            .parse::<f64>()
+            .map_err(|_| format!("Latitude {} is not a number", parts[2]))?;
+        let longitude = parts[3]
+            .parse::<f64>()
+            .map_err(|_| format!("Longitude {} is not a number", parts[3]))?;
 
-        let longitude = match parts[3].parse::<f64>() {
-            Ok(longitude) => {
-                // verify that the longitude is in the correct range
+        let address = Self::new_from_parts(parts[0].to_string(), port, latitude, longitude);
+        address.validate()?;
 
-                if !(-180.0..=180.0).contains(&longitude) {
-                    return None;
-                }
-
-                longitude
-            }
-            Err(_) => {
-                return None;
-            }
-        };
+        Ok(address)
+    }
 
-        Some(Self::new_from_parts(
-            parts[0].trim().to_string(),
-            port,
-            latitude,
-            longitude,
-        ))
+    /// # Errors
+    /// The address, port or position can't be used
+    pub fn validate(&self) -> Result<(), String> {
+        validate_address(&self.address)?;
+        validate_port(self.port)?;
+        validate_latitude(self.latitude)?;
+        validate_longitude(self.longitude)
     }
 
     #[must_use]
@@ -122,4 +195,30 @@ impl SHAdsbConfig {
             longitude,
         }
     }
+
+    #[must_use]
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    #[must_use]
+    pub const fn port(&self) -> u32 {
+        self.port
+    }
+
+    #[must_use]
+    pub const fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    #[must_use]
+    pub const fn longitude(&self) -> f64 {
+        self.longitude
+    }
+}
+
+impl std::fmt::Display for SHAdsbConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.address, self.port)
+    }
 }
@@ -11,16 +11,43 @@ use serde::{
 };
 use void::Void;
 
-use crate::address::SHAdsbConfig;
+use crate::address::{SHAdsbConfig, ShRejectedAddress};
 
 pub trait SourceTrait {
     fn new() -> Self;
     fn insert(&mut self, value: SHAdsbConfig);
+    /// Keep an entry that couldn't be used, so it can be reported
+    fn reject(&mut self, value: String, reason: String);
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct AdsbSource {
     addresses: Vec<SHAdsbConfig>,
+    /// Entries that were in the config but couldn't be used, and were left out
+    #[serde(skip)]
+    rejected: Vec<ShRejectedAddress>,
+}
+
+impl AdsbSource {
+    #[must_use]
+    pub fn addresses(&self) -> &[SHAdsbConfig] {
+        &self.addresses
+    }
+
+    #[must_use]
+    pub fn rejected(&self) -> &[ShRejectedAddress] {
+        &self.rejected
+    }
+}
+
+impl From<Vec<SHAdsbConfig>> for AdsbSource {
+    fn from(addresses: Vec<SHAdsbConfig>) -> Self {
+        Self {
+            addresses,
+            rejected: Vec::new(),
+        }
+    }
 }
 
 impl FromStr for AdsbSource {
@@ -29,9 +56,10 @@ impl FromStr for AdsbSource {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut output = Self::new();
 
-        for address in s.split(',') {
-            if let Some(address) = SHAdsbConfig::new(address) {
-                output.insert(address);
+        for address in s.split(',').filter(|address| !address.trim().is_empty()) {
+            match SHAdsbConfig::parse(address) {
+                Ok(address) => output.insert(address),
+                Err(e) => output.reject(address.trim().to_string(), e),
             }
         }
 
@@ -43,12 +71,17 @@ impl SourceTrait for AdsbSource {
     fn new() -> Self {
         Self {
             addresses: Vec::new(),
+            rejected: Vec::new(),
         }
     }
 
     fn insert(&mut self, value: SHAdsbConfig) {
         self.addresses.push(value);
     }
+
+    fn reject(&mut self, value: String, reason: String) {
+        self.rejected.push(ShRejectedAddress { value, reason });
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -59,6 +92,37 @@ enum FieldTypes {
     Position(f64),
 }
 
+impl FieldTypes {
+    /// Whole numbers come through as ports, but are fine as a position
+    fn position(&self) -> Option<f64> {
+        match self {
+            Self::Address(_) => None,
+            Self::Port(port) => Some(f64::from(*port)),
+            Self::Position(position) => Some(*position),
+        }
+    }
+}
+
+impl fmt::Display for FieldTypes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "\"{address}\""),
+            Self::Port(port) => write!(f, "{port}"),
+            Self::Position(position) => write!(f, "{position}"),
+        }
+    }
+}
+
+/// An entry as it was written, for saying which one couldn't be used
+fn describe(item: &HashMap<String, FieldTypes>) -> String {
+    let mut fields: Vec<String> = item
+        .iter()
+        .map(|(key, value)| format!("{key} = {value}"))
+        .collect();
+    fields.sort();
+    format!("{{ {} }}", fields.join(", "))
+}
+
 ///# Errors
 /// will return an error if the input fails to deserialize
 pub fn string_or_struct<'de, T, D>(deserializer: D) -> Result<T, D::Error>
@@ -111,55 +175,35 @@ where
                         }
 
                         for item in &value {
-                            let port = match item.get("port").unwrap().to_owned() {
-                                FieldTypes::Port(port) => {
-                                    if (0..=65535).contains(&port) {
-                                        port
-                                    } else {
-                                        return Err(de::Error::custom("Port out of range"));
-                                    }
-                                }
-                                _ => {
-                                    return Err(de::Error::custom("Port not found"));
-                                }
-                            };
-
-                            let FieldTypes::Address(address) =
-                                item.get("address").unwrap().to_owned()
+                            let (
+                                Some(FieldTypes::Address(address)),
+                                Some(FieldTypes::Port(port)),
+                                Some(latitude),
+                                Some(longitude),
+                            ) = (
+                                item.get("address"),
+                                item.get("port"),
+                                item.get("latitude").and_then(FieldTypes::position),
+                                item.get("longitude").and_then(FieldTypes::position),
+                            )
                             else {
-                                return Err(de::Error::custom("Address not found"));
-                            };
-
-                            let latitude = match item.get("latitude").unwrap().to_owned() {
-                                FieldTypes::Position(latitutde) => {
-                                    if (-90.0..=90.0).contains(&latitutde) {
-                                        latitutde
-                                    } else {
-                                        return Err(de::Error::custom("Latitude out of range"));
-                                    }
-                                }
-                                _ => {
-                                    return Err(de::Error::custom("Latitude not found"));
-                                }
+                                source.reject(
+                                    describe(item),
+                                    "Needs an address, port, latitude and longitude".to_string(),
+                                );
+                                continue;
                             };
 
-                            let longitude = match item.get("longitude").unwrap().to_owned() {
-                                FieldTypes::Position(longitude) => {
-                                    if (-180.0..=180.0).contains(&longitude) {
-                                        longitude
-                                    } else {
-                                        return Err(de::Error::custom("Longitude out of range"));
-                                    }
-                                }
-                                _ => {
-                                    return Err(de::Error::custom("Longitude not found"));
-                                }
-                            };
-
-                            let address =
-                                SHAdsbConfig::new_from_parts(address, port, latitude, longitude);
-
-                            source.insert(address);
+                            let address = SHAdsbConfig::new_from_parts(
+                                address.clone(),
+                                *port,
+                                latitude,
+                                longitude,
+                            );
+                            match address.validate() {
+                                Ok(()) => source.insert(address),
+                                Err(e) => source.reject(address.to_string(), e),
+                            }
                         }
                     }
                     Err(e) => {
@@ -186,8 +230,9 @@ where
             let mut source = T::new();
 
             while let Some(value) = seq.next_element::<String>()? {
-                if let Some(address) = SHAdsbConfig::new(&value) {
-                    source.insert(address);
+                match SHAdsbConfig::parse(&value) {
+                    Ok(address) => source.insert(address),
+                    Err(e) => source.reject(value, e),
                 }
             }
 
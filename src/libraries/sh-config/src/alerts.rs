@@ -0,0 +1,77 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use serde_inline_default::serde_inline_default;
+
+use crate::source::ShEnabledDataSources;
+
+/// `ShAlertsConfig` holds the rules the hub checks every message and position against
+#[serde_inline_default]
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShAlertsConfig {
+    #[serde_inline_default(Vec::new())]
+    pub rules: Vec<ShAlertRule>,
+}
+
+/// `ShAlertRule` is a single alert rule. Every field that is set has to match for the rule to
+/// fire, and a list matches if any of its entries do. Empty fields match anything
+#[serde_inline_default]
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShAlertRule {
+    /// Name of the rule, shown with every alert it raises. Must be unique
+    pub name: String,
+    #[serde_inline_default(true)]
+    pub enabled: bool,
+    /// Aircraft registrations, e.g. `N123UA`
+    #[serde_inline_default(Vec::new())]
+    pub tails: Vec<String>,
+    /// ICAO hex addresses, e.g. `A1B2C3`
+    #[serde_inline_default(Vec::new())]
+    pub icao_hexes: Vec<String>,
+    /// Flight numbers or callsigns, in either IATA or ICAO form
+    #[serde_inline_default(Vec::new())]
+    pub flights: Vec<String>,
+    /// ACARS labels, e.g. `H1`. Only messages can match
+    #[serde_inline_default(Vec::new())]
+    pub labels: Vec<String>,
+    /// Squawk codes, e.g. `7700`. Only ADS-B positions can match
+    #[serde_inline_default(Vec::new())]
+    pub squawks: Vec<String>,
+    /// The kinds of source the data has to come from
+    #[serde_inline_default(Vec::new())]
+    pub source_types: Vec<ShEnabledDataSources>,
+    /// Regular expression checked against the message text. Only messages can match
+    pub text_pattern: Option<String>,
+    /// Names of the notification targets the alert is sent to, on top of the web interface
+    #[serde_inline_default(Vec::new())]
+    pub notify: Vec<String>,
+}
+
+impl ShAlertRule {
+    /// Does the rule check anything only messages have
+    #[must_use]
+    pub fn wants_messages(&self) -> bool {
+        !self.labels.is_empty() || self.text_pattern.is_some()
+    }
+
+    /// Does the rule check anything only ADS-B positions have
+    #[must_use]
+    pub fn wants_positions(&self) -> bool {
+        !self.squawks.is_empty()
+    }
+
+    #[must_use]
+    pub fn has_criteria(&self) -> bool {
+        !self.tails.is_empty()
+            || !self.icao_hexes.is_empty()
+            || !self.flights.is_empty()
+            || !self.source_types.is_empty()
+            || self.wants_messages()
+            || self.wants_positions()
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use serde_inline_default::serde_inline_default;
+
+/// `ShAuthConfig` decides who has to log in. The accounts themselves are kept in
+/// `sh_users.toml` in the data path, not here
+#[serde_inline_default]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ShAuthConfig {
+    /// Users have to log in. With this off, anyone who can reach the hub is an admin
+    #[serde_inline_default(true)]
+    pub enabled: bool,
+    /// Connections from this machine don't have to log in, and are admins. Off unless asked
+    /// for, as a reverse proxy on the same machine that doesn't say it is one makes everyone
+    /// look local
+    #[serde_inline_default(false)]
+    pub trust_localhost: bool,
+    /// Hours a login lasts before the user has to log in again
+    #[serde_inline_default(168)]
+    pub session_hours: u64,
+}
+
+impl Default for ShAuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            trust_localhost: false,
+            session_hours: 168,
+        }
+    }
+}
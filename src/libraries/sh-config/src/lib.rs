@@ -13,35 +13,65 @@
 )]
 
 use std::env;
+use std::net::IpAddr;
 
+use alerts::ShAlertsConfig;
+use auth::ShAuthConfig;
 use directories::ProjectDirs;
 use figment::{
-    providers::{Env, Format, Toml},
+    providers::{Env, Format, Serialized, Toml},
     Figment,
 };
 #[macro_use]
 extern crate log;
+use lookup::ShLookupConfig;
 use map::ShMapConfig;
+use migration::{ShConfigMigration, CONFIG_VERSION};
+use notifications::ShNotificationsConfig;
+use overridden::ShOverriddenSetting;
 use sdre_rust_logging::SetupLogging;
 use sdrehub::SDREHub;
 use serde::{Deserialize, Serialize};
 use serde_inline_default::serde_inline_default;
-use source::{DataSources, EnabledDataSources};
+use server::ShServerConfig;
+use source::{DataSources, EnabledDataSources, ShEnabledDataSources};
+use validation::ShConfigIssue;
 use web::sh_web_config::ShWebConfig;
 
 pub mod acars_router_source;
+pub mod acarshub;
 pub mod address;
 pub mod adsb_source;
+pub mod alerts;
+pub mod auth;
+pub mod lookup;
 pub mod map;
+pub mod migration;
+pub mod notifications;
+mod overridden;
 pub mod sdrehub;
+pub mod server;
 pub mod source;
+pub mod validation;
 pub mod web;
 
-// FIXME: env variables require a dot between the prefix and the variable name. This is not ideal. Should be able to use underscores
+// The log levels `app.log_level` can be set to
+const LOG_LEVELS: [&str; 5] = ["error", "warn", "info", "debug", "trace"];
+
+// Environment variables that set a whole setting on their own, as `SH_` and the first part.
+// `SH_ACARS_ROUTERS=host:port,host:port` is easier to give a container than one variable per key
+const ENV_SHORTCUTS: [(&str, &str); 2] = [
+    ("acars_routers", "data_sources.acars_routers"),
+    ("adsb_sources", "data_sources.adsb_sources"),
+];
 
 #[serde_inline_default]
-#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ShConfig {
+    /// Always `CONFIG_VERSION` once read, as older files are upgraded as they are read
+    #[serde_inline_default(CONFIG_VERSION)]
+    #[serde(skip_deserializing)]
+    pub config_version: u32,
     #[serde_inline_default(SDREHub::default())]
     pub app: SDREHub,
     #[serde_inline_default(EnabledDataSources::default())]
@@ -50,12 +80,157 @@ pub struct ShConfig {
     pub data_sources: DataSources,
     #[serde_inline_default(ShMapConfig::default())]
     pub map: ShMapConfig,
+    #[serde_inline_default(ShLookupConfig::default())]
+    pub lookup: ShLookupConfig,
+    #[serde_inline_default(ShAlertsConfig::default())]
+    pub alerts: ShAlertsConfig,
+    #[serde_inline_default(ShNotificationsConfig::default())]
+    pub notifications: ShNotificationsConfig,
+    #[serde_inline_default(ShAuthConfig::default())]
+    pub auth: ShAuthConfig,
+    #[serde_inline_default(ShServerConfig::default())]
+    pub server: ShServerConfig,
+    /// What was done to bring an older config file up to date, if anything. The file itself is
+    /// upgraded the next time the config is written
+    #[serde(skip)]
+    pub migration: Option<ShConfigMigration>,
+    /// Settings the environment or command line has changed from what the file says
+    #[serde(skip)]
+    overridden: Vec<ShOverriddenSetting>,
+}
+
+impl Default for ShConfig {
+    fn default() -> Self {
+        Self {
+            config_version: CONFIG_VERSION,
+            app: SDREHub::default(),
+            enabled_data_sources: EnabledDataSources::default(),
+            data_sources: DataSources::default(),
+            map: ShMapConfig::default(),
+            lookup: ShLookupConfig::default(),
+            alerts: ShAlertsConfig::default(),
+            notifications: ShNotificationsConfig::default(),
+            auth: ShAuthConfig::default(),
+            server: ShServerConfig::default(),
+            migration: None,
+            overridden: Vec::new(),
+        }
+    }
+}
+
+/// Settings given on the command line. They win over the config file and `SH_` environment
+/// variables, and are never written to the file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ShConfigOverrides {
+    /// Read this config file instead of `sh_config.toml` in the data path
+    pub config_file: Option<String>,
+    pub data_path: Option<String>,
+    pub log_level: Option<String>,
+    pub bind: Option<IpAddr>,
 }
 
 impl ShConfig {
-    #[must_use]
-    pub fn new() -> Self {
-        Self::get_and_validate_config()
+    /// Read the config file, with `SH_` environment variables over the top of it. See
+    /// `environment` for how the variables are named
+    ///
+    /// # Errors
+    /// The data path can't be used, or the config can't be read. Every setting that couldn't
+    /// be read is in the list
+    pub fn load() -> Result<Self, Vec<ShConfigIssue>> {
+        Self::load_with(&ShConfigOverrides::default())
+    }
+
+    /// Like `load`, with `overrides` over the top of everything else
+    ///
+    /// # Errors
+    /// The data path can't be used, or the config can't be read. Every setting that couldn't
+    /// be read is in the list
+    pub fn load_with(overrides: &ShConfigOverrides) -> Result<Self, Vec<ShConfigIssue>> {
+        let environment = Figment::new()
+            .merge(Self::environment())
+            .merge(Self::environment_shortcuts());
+        Self::load_from(overrides, environment)
+    }
+
+    /// Like `load_with`, with `environment` standing in for the `SH_` variables
+    fn load_from(
+        overrides: &ShConfigOverrides,
+        environment: Figment,
+    ) -> Result<Self, Vec<ShConfigIssue>> {
+        let data_path =
+            Self::get_application_data_path(overrides.data_path.as_deref()).map_err(|e| {
+                let value = overrides
+                    .data_path
+                    .clone()
+                    .or_else(|| env::var("SH_DATA_PATH").ok())
+                    .unwrap_or_default();
+                vec![ShConfigIssue::error("app.data_path", value, e)]
+            })?;
+        let file_path = overrides
+            .config_file
+            .clone()
+            .unwrap_or_else(|| format!("{data_path}/sh_config.toml"));
+
+        let upgraded = migration::read_upgraded(&file_path).map_err(|issue| vec![issue])?;
+        let file = match &upgraded {
+            Some((contents, _)) => Toml::string(contents),
+            None => Toml::file(&file_path),
+        };
+
+        let stored = Figment::new()
+            .merge(Serialized::default("app.data_path", &data_path))
+            .merge(Serialized::default("app.config_file", &file_path))
+            .merge(Serialized::default(
+                "app.database_url",
+                format!("sqlite://{data_path}/sdre-hub.db"),
+            ))
+            .merge(file);
+
+        // kept apart from the file, so they are never written back to it
+        let mut overriding = environment;
+        if overrides.data_path.is_some() {
+            overriding = overriding.merge(Serialized::default("app.data_path", &data_path));
+        }
+        if overrides.config_file.is_some() {
+            overriding = overriding.merge(Serialized::default("app.config_file", &file_path));
+        }
+        if let Some(log_level) = &overrides.log_level {
+            overriding = overriding.merge(Serialized::default("app.log_level", log_level));
+        }
+        if let Some(bind) = overrides.bind {
+            overriding = overriding.merge(Serialized::default("server.bind", bind));
+        }
+
+        let mut config: Self = stored
+            .clone()
+            .merge(overriding.clone())
+            .extract()
+            .map_err(ShConfigIssue::from_figment)?;
+        config.migration = upgraded.map(|(_, migration)| migration);
+        config.overridden = ShOverriddenSetting::find(&stored, &overriding);
+
+        Ok(config)
+    }
+
+    /// `SH_` variables for single settings. Parts of the name can be split with `__` or `.`, so
+    /// `SH_APP__LOG_LEVEL` and `SH_APP.LOG_LEVEL` both set `app.log_level`. Docker Compose and
+    /// most shells can't take the dotted ones
+    fn environment() -> Env {
+        let shortcuts = ENV_SHORTCUTS.map(|(name, _)| name);
+        Env::prefixed("SH_").ignore(&shortcuts).split("__")
+    }
+
+    /// The `ENV_SHORTCUTS` variables. They are merged after `environment`, so they win over the
+    /// same setting given the long way
+    fn environment_shortcuts() -> Env {
+        let shortcuts = ENV_SHORTCUTS.map(|(name, _)| name);
+        Env::prefixed("SH_").only(&shortcuts).map(|name| {
+            let key = ENV_SHORTCUTS
+                .iter()
+                .find(|(shortcut, _)| name == *shortcut)
+                .map_or(name.as_str(), |(_, key)| key);
+            key.into()
+        })
     }
 
     #[must_use]
@@ -65,112 +240,233 @@ impl ShConfig {
             enabled_data_sources: self.enabled_data_sources.clone(),
             data_sources: self.data_sources.clone(),
             map: self.map.clone(),
+            alerts: self.alerts.clone(),
+            // targets can hold passwords, so users only get to see what they are called
+            notification_targets: self.notifications.target_names(),
+            issues: self.issues(),
+            overridden: self.overridden(),
         }
     }
 
-    fn get_application_data_path() -> String {
-        if env::var("SH_DATA_PATH").is_ok() {
-            let path = env::var("SH_DATA_PATH").unwrap();
-            if std::path::Path::new(&path).exists() {
-                // canonicalize the path
-                match std::fs::canonicalize(&path) {
-                    Ok(canonical_path) => {
-                        if let Some(canonical_path_str) = canonical_path.to_str() {
-                            return canonical_path_str.to_string();
-                        }
-                    }
-                    Err(e) => {
-                        println!("Error getting config file path: {e}");
-                        println!("Exiting");
-                        std::process::exit(1);
-                    }
-                }
-            }
+    /// Where the settings `SH_` variables or the command line set are, like `server.port`. The
+    /// file doesn't keep what they are set to, so they can't be changed while the hub runs
+    #[must_use]
+    pub fn overridden(&self) -> Vec<String> {
+        self.overridden
+            .iter()
+            .map(ShOverriddenSetting::path)
+            .collect()
+    }
 
-            println!("Error getting config file path");
-            println!("Exiting");
-            std::process::exit(1);
+    /// The settings in [`Self::overridden`] that `changed` has something else for
+    ///
+    /// # Panics
+    /// The config has something toml can't hold. Everything in it is plain data, so it won't
+    #[must_use]
+    pub fn overridden_changes(&self, changed: &Self) -> Vec<String> {
+        if self.overridden.is_empty() {
+            return Vec::new();
         }
 
-        // Otherwise, use the OS default pathing
-        ProjectDirs::from("org", "sdre-e", "sdr-e-hub").map_or_else(
-            || {
-                println!("Error getting config file path");
-                println!("Exiting");
-                std::process::exit(1);
-            },
-            |proj_dirs| {
-                proj_dirs.config_dir().to_str().map_or_else(
-                    || {
-                        println!("Error getting config file path");
-                        println!("Exiting");
-                        std::process::exit(1);
-                    },
-                    |path| {
-                        // make the directory if it doesn't exist
-                        if std::path::Path::new(&path).exists() {
-                            path.to_string()
-                        } else {
-                            match std::fs::create_dir_all(path) {
-                                Ok(()) => path.to_string(),
-                                Err(e) => {
-                                    println!("Error creating config directory: {e}");
-                                    println!("Exiting");
-                                    std::process::exit(1);
-                                }
-                            }
-                        }
-                    },
-                )
-            },
-        )
-    }
-
-    fn get_config_file_path() -> String {
-        let path = Self::get_application_data_path();
-        format!("{path}/sh_config.toml")
-    }
-
-    fn get_config(file_path: &str) -> Self {
-        match Figment::new()
-            .merge(Toml::file(file_path))
-            .merge(Env::prefixed("SH_"))
-            .extract()
+        let before = toml::Table::try_from(self).unwrap();
+        let after = toml::Table::try_from(changed).unwrap();
+        self.overridden
+            .iter()
+            .filter(|setting| setting.changed(&before, &after))
+            .map(ShOverriddenSetting::path)
+            .collect()
+    }
+
+    /// Everything wrong with the config that can be found without starting anything. Alert
+    /// rules are checked by the alert engine, as it is what compiles their patterns
+    #[must_use]
+    pub fn issues(&self) -> Vec<ShConfigIssue> {
+        let mut issues = Vec::new();
+
+        if !LOG_LEVELS.contains(&self.app.log_level.to_lowercase().as_str()) {
+            issues.push(ShConfigIssue::warning(
+                "app.log_level",
+                &self.app.log_level,
+                format!(
+                    "Has to be one of {}, so info is used",
+                    LOG_LEVELS.join(", ")
+                ),
+            ));
+        }
+
+        if !std::path::Path::new(&self.app.data_path).is_dir() {
+            issues.push(ShConfigIssue::error(
+                "app.data_path",
+                &self.app.data_path,
+                "The directory does not exist",
+            ));
+        }
+
+        issues.extend(self.data_sources.issues());
+        issues.extend(self.unused_source_issues());
+        issues.extend(self.server.issues());
+        issues.extend(self.lookup.issues());
+        issues.extend(self.notifications.issues());
+
+        issues
+    }
+
+    /// Sources that are configured, but everything they send would be ignored
+    fn unused_source_issues(&self) -> Vec<ShConfigIssue> {
+        let enabled = &self.enabled_data_sources;
+        let mut issues = Vec::new();
+
+        if !self.data_sources.acars_routers.addresses().is_empty()
+            && ![
+                ShEnabledDataSources::Acars,
+                ShEnabledDataSources::Vdlm2,
+                ShEnabledDataSources::Hfdl,
+            ]
+            .iter()
+            .any(|source| enabled.is_enabled(source))
         {
-            Ok(config) => config,
-            Err(e) => {
-                println!("Error reading config file: {e}");
-                println!("Exiting");
-                std::process::exit(1);
+            issues.push(ShConfigIssue::warning(
+                "enabled_data_sources.enabled_sources",
+                "",
+                "acars_router sources are configured but ACARS, VDLM2 and HFDL are all disabled. Their messages will be ignored",
+            ));
+        }
+
+        if !self.data_sources.adsb_sources.addresses().is_empty()
+            && !enabled.is_enabled(&ShEnabledDataSources::Adsb)
+        {
+            issues.push(ShConfigIssue::warning(
+                "enabled_data_sources.enabled_sources",
+                "",
+                "ADS-B sources are configured but ADS-B is disabled. Their positions will be ignored",
+            ));
+        }
+
+        issues
+    }
+
+    /// `path` if it was given, then `SH_DATA_PATH`, then the OS config directory
+    fn get_application_data_path(path: Option<&str>) -> Result<String, String> {
+        let given = path
+            .map(|path| (path.to_string(), "--data-path"))
+            .or_else(|| {
+                env::var("SH_DATA_PATH")
+                    .ok()
+                    .map(|path| (path, "SH_DATA_PATH"))
+            });
+
+        if let Some((path, from)) = given {
+            if !std::path::Path::new(&path).exists() {
+                return Err(format!("{from} is set to {path}, which does not exist"));
             }
+
+            // canonicalize the path
+            let canonical_path = std::fs::canonicalize(&path)
+                .map_err(|e| format!("Error getting the full path of {path}: {e}"))?;
+            return canonical_path
+                .to_str()
+                .map(ToString::to_string)
+                .ok_or_else(|| format!("{path} is not a UTF-8 path"));
         }
+
+        // Otherwise, use the OS default pathing
+        let proj_dirs = ProjectDirs::from("org", "sdre-e", "sdr-e-hub").ok_or_else(|| {
+            "Couldn't work out where to keep the config. Set SH_DATA_PATH".to_string()
+        })?;
+        let path = proj_dirs.config_dir().to_str().ok_or_else(|| {
+            "The config directory is not a UTF-8 path. Set SH_DATA_PATH".to_string()
+        })?;
+
+        // make the directory if it doesn't exist
+        if !std::path::Path::new(&path).exists() {
+            std::fs::create_dir_all(path)
+                .map_err(|e| format!("Error creating config directory {path}: {e}"))?;
+        }
+
+        Ok(path.to_string())
+    }
+
+    /// The data path for settings that weren't read with `load`, which always sets it. `.` if
+    /// there isn't one to be had
+    fn default_data_path() -> String {
+        Self::get_application_data_path(None).unwrap_or_else(|_| ".".to_string())
     }
 
-    fn get_and_validate_config() -> Self {
-        let file_path = Self::get_config_file_path();
-        Self::get_config(&file_path)
+    fn default_config_file() -> String {
+        format!("{}/sh_config.toml", Self::default_data_path())
     }
 
     pub fn show_config(&self) {
-        debug!("Config: {self:#?}");
+        debug!("Config: {:#?}", self.redacted());
+    }
+
+    /// A copy that is safe to log, without passwords or tokens
+    #[must_use]
+    pub fn redacted(&self) -> Self {
+        let mut redacted = self.clone();
+        redacted.notifications = self.notifications.redacted();
+        redacted
     }
 
     pub fn enable_logging(&self) {
         println!("Enabling logging with level: {}", self.app.log_level);
-        self.app.log_level.enable_logging();
+        // the logger lets everything through and the level does the filtering, so it can be
+        // changed while running
+        "trace".enable_logging();
+        self.set_log_level();
+    }
+
+    pub fn set_log_level(&self) {
+        log::set_max_level(self.app.log_level.as_str().set_logging_level());
     }
 
-    fn get_config_as_toml_string(&self) -> String {
-        toml::to_string(&self).unwrap()
+    /// The config as it is written to the config file. Settings given in `SH_` variables or on
+    /// the command line are left as the file had them
+    ///
+    /// # Panics
+    /// The config has something toml can't hold. Everything in it is plain data, so it won't
+    #[must_use]
+    pub fn to_toml_string(&self) -> String {
+        if self.overridden.is_empty() {
+            return toml::to_string(&self).unwrap();
+        }
+
+        let mut table = toml::Table::try_from(self).unwrap();
+        for setting in &self.overridden {
+            setting.restore(&mut table);
+        }
+        // back through `ShConfig`, so the settings are written in the usual order
+        let stored: Self = table.try_into().unwrap();
+        toml::to_string(&stored).unwrap()
     }
 
+    /// The config as the file has it, without `SH_` variables or the command line. Changes made
+    /// to it can be written without taking those along
+    ///
+    /// # Panics
+    /// The config has something toml can't hold. Everything in it is plain data, so it won't
+    #[must_use]
+    pub fn stored(&self) -> Self {
+        let mut stored: Self = toml::from_str(&self.to_toml_string()).unwrap();
+        // still written to wherever this one would be
+        stored.app.config_file.clone_from(&self.app.config_file);
+        stored.migration.clone_from(&self.migration);
+        stored
+    }
+
+    /// Always written as the current `config_version`. A file from an older version is backed
+    /// up first
+    ///
     /// # Errors
-    /// Will return error if there is an issue writing the config file
+    /// Will return error if there is an issue writing the config file or its backup
     pub fn write_config(&self) -> Result<(), std::io::Error> {
-        let file_path = Self::get_config_file_path();
-        let config = self.get_config_as_toml_string();
+        let file_path = &self.app.config_file;
+        if let Some(backup) = migration::back_up(file_path)? {
+            println!("Upgrading the config file. The old one is kept as {backup}");
+        }
+        // not printed, as it has passwords and tokens in it
+        let config = self.to_toml_string();
         println!("Writing config file to: {file_path}");
-        println!("Config: {config}");
 
         match std::fs::write(file_path, config) {
             Ok(()) => Ok(()),
@@ -181,3 +477,143 @@ impl ShConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    /// A data path of its own for a test, with `contents` as its config file
+    fn data_path(name: &str, contents: &str) -> String {
+        let path = env::temp_dir().join(format!("sh-config-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("sh_config.toml"), contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn command_line_overrides_are_not_written() {
+        let data_path = data_path(
+            "overrides",
+            "config_version = 1\n[app]\nlog_level = \"warn\"\n",
+        );
+        let overrides = ShConfigOverrides {
+            data_path: Some(data_path.clone()),
+            log_level: Some("debug".to_string()),
+            bind: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            ..ShConfigOverrides::default()
+        };
+
+        let mut config = ShConfig::load_with(&overrides).unwrap();
+        assert_eq!(config.app.log_level, "debug");
+        assert_eq!(config.server.bind, IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        // changed from the web interface while running
+        config.map.center_latitude = 51.5;
+        config.write_config().unwrap();
+
+        let written: toml::Table =
+            toml::from_str(&std::fs::read_to_string(&config.app.config_file).unwrap()).unwrap();
+        assert_eq!(written["app"]["log_level"].as_str(), Some("warn"));
+        assert_eq!(written["server"]["bind"].as_str(), Some("0.0.0.0"));
+        assert_eq!(written["map"]["center_latitude"].as_float(), Some(51.5));
+
+        // and the overrides still win when it is read again
+        let config = ShConfig::load_with(&overrides).unwrap();
+        assert_eq!(config.app.log_level, "debug");
+        assert!((config.map.center_latitude - 51.5).abs() < f64::EPSILON);
+
+        std::fs::remove_dir_all(data_path).unwrap();
+    }
+
+    #[test]
+    fn environment_variables_are_not_written() {
+        let data_path = data_path(
+            "environment",
+            "config_version = 1\n[map]\ncenter_latitude = 0.0\ncenter_longitude = 0.0\n",
+        );
+        // what SH_ACARS_ROUTERS and SH_MAP__CENTER_LONGITUDE come through as
+        let environment = Figment::new()
+            .merge(Serialized::default(
+                "data_sources.acars_routers",
+                "acars-router:15550",
+            ))
+            .merge(Serialized::default("map.center_longitude", -0.12));
+
+        let overrides = ShConfigOverrides {
+            data_path: Some(data_path.clone()),
+            ..ShConfigOverrides::default()
+        };
+        let config = ShConfig::load_from(&overrides, environment).unwrap();
+        assert_eq!(config.data_sources.acars_routers.addresses().len(), 1);
+        assert!((config.map.center_longitude + 0.12).abs() < f64::EPSILON);
+        config.write_config().unwrap();
+
+        let written: toml::Table =
+            toml::from_str(&std::fs::read_to_string(&config.app.config_file).unwrap()).unwrap();
+        let stored: ShConfig = written.try_into().unwrap();
+        assert!(stored.data_sources.acars_routers.addresses().is_empty());
+        assert!(stored.map.center_longitude.abs() < f64::EPSILON);
+
+        std::fs::remove_dir_all(data_path).unwrap();
+    }
+
+    #[test]
+    fn stored_is_the_config_without_overrides() {
+        let data_path = data_path(
+            "stored",
+            "config_version = 1\n[app]\nlog_level = \"warn\"\n",
+        );
+        let config_file = format!("{data_path}/sh_config.toml");
+        let config = ShConfig::load_with(&ShConfigOverrides {
+            config_file: Some(config_file.clone()),
+            data_path: Some(data_path.clone()),
+            log_level: Some("debug".to_string()),
+            ..ShConfigOverrides::default()
+        })
+        .unwrap();
+
+        let mut stored = config.stored();
+        assert_eq!(stored.app.log_level, "warn");
+        assert_eq!(stored.app.config_file, config_file);
+
+        stored.map.center_latitude = 51.5;
+        stored.write_config().unwrap();
+        let written: toml::Table =
+            toml::from_str(&std::fs::read_to_string(&config_file).unwrap()).unwrap();
+        assert_eq!(written["app"]["log_level"].as_str(), Some("warn"));
+        assert_eq!(written["map"]["center_latitude"].as_float(), Some(51.5));
+
+        std::fs::remove_dir_all(data_path).unwrap();
+    }
+
+    #[test]
+    fn changes_to_overridden_settings_are_found() {
+        let data_path = data_path("changes", "config_version = 1\n");
+        let config = ShConfig::load_with(&ShConfigOverrides {
+            data_path: Some(data_path.clone()),
+            log_level: Some("debug".to_string()),
+            ..ShConfigOverrides::default()
+        })
+        .unwrap();
+        assert!(config.overridden().contains(&"app.log_level".to_string()));
+        let web = config.to_web_config();
+        assert_eq!(web.overridden, config.overridden());
+        assert!(web.is_overridden("app.log_level"));
+        assert!(!web.is_overridden("app.log"));
+
+        let mut changed = config.clone();
+        changed.map.center_latitude = 51.5;
+        assert!(config.overridden_changes(&changed).is_empty());
+
+        changed.app.log_level = "trace".to_string();
+        assert_eq!(
+            config.overridden_changes(&changed),
+            vec!["app.log_level".to_string()]
+        );
+
+        std::fs::remove_dir_all(data_path).unwrap();
+    }
+}
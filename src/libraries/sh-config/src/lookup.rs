@@ -0,0 +1,79 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde::{Deserialize, Serialize};
+use serde_inline_default::serde_inline_default;
+
+use crate::validation::ShConfigIssue;
+
+/// `ShLookupConfig` is a struct for storing the location of the airline and route lookup tables,
+/// as well as any entries the admin wants to add or correct
+#[serde_inline_default]
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, Clone)]
+pub struct ShLookupConfig {
+    /// `airlines_file` is the path to a CSV file with the columns `iata,icao,name,callsign`
+    /// If not set, `airlines.csv` in the data path is used if it exists
+    pub airlines_file: Option<String>,
+    /// `routes_file` is the path to a CSV file with the columns `flight,origin,destination`
+    /// If not set, `routes.csv` in the data path is used if it exists
+    pub routes_file: Option<String>,
+    /// `ground_stations_file` is the path to a JSON file of VDL2/HFDL ground stations, in the same
+    /// format as the bundled dataset. Entries are merged over the bundled ones
+    /// If not set, `ground_stations.json` in the data path is used if it exists
+    pub ground_stations_file: Option<String>,
+    /// `airline_overrides` are applied on top of the airlines file. An override with the same
+    /// ICAO code as an existing entry replaces it
+    #[serde_inline_default(Vec::new())]
+    pub airline_overrides: Vec<ShAirlineOverride>,
+    /// `route_overrides` are applied on top of the routes file. An override for a flight that
+    /// already has a route replaces it
+    #[serde_inline_default(Vec::new())]
+    pub route_overrides: Vec<ShRouteOverride>,
+}
+
+impl ShLookupConfig {
+    /// Files that were set but aren't there. The lookups carry on without them
+    #[must_use]
+    pub fn issues(&self) -> Vec<ShConfigIssue> {
+        [
+            ("lookup.airlines_file", &self.airlines_file),
+            ("lookup.routes_file", &self.routes_file),
+            ("lookup.ground_stations_file", &self.ground_stations_file),
+        ]
+        .into_iter()
+        .filter_map(|(path, file)| Some((path, file.as_ref()?)))
+        .filter(|(_, file)| !std::path::Path::new(file).is_file())
+        .map(|(path, file)| {
+            ShConfigIssue::warning(path, file, "The file does not exist, so it is not used")
+        })
+        .collect()
+    }
+}
+
+/// `ShAirlineOverride` is a single airline entry supplied in the config file
+#[serde_inline_default]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ShAirlineOverride {
+    /// Two character IATA designator, e.g. `UA`. Some airlines do not have one
+    pub iata: Option<String>,
+    /// Three letter ICAO designator, e.g. `UAL`
+    pub icao: String,
+    /// Name of the airline, e.g. `United Airlines`
+    pub name: String,
+    /// Radio telephony callsign, e.g. `UNITED`
+    #[serde_inline_default(String::new())]
+    pub callsign: String,
+}
+
+/// `ShRouteOverride` is a single route entry supplied in the config file
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ShRouteOverride {
+    /// Flight number, in either IATA (`UA1234`) or ICAO (`UAL1234`) form
+    pub flight: String,
+    /// ICAO code of the origin airport, e.g. `KSFO`
+    pub origin: String,
+    /// ICAO code of the destination airport, e.g. `KEWR`
+    pub destination: String,
+}
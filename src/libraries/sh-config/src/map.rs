@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize};
 
 /// `MapConfig` is a struct for storing global map values
 #[derive(Debug, Serialize, Deserialize, Default, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ShMapConfig {
     /// `center_latitude` is the latitude of the center of the map
     /// This value will be used to center the map on the web interface
@@ -0,0 +1,320 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Bringing config files from older versions of the hub up to date. Each migration takes the
+// file from one version to the next, so a file from any version is upgraded by running the
+// ones after it, in order. A setting that is renamed or moved gets a migration and a bump of
+// `CONFIG_VERSION`, rather than a serde alias
+
+use std::path::Path;
+use std::str::FromStr;
+
+use toml::{Table, Value};
+use void::ResultVoidExt;
+
+use crate::acars_router_source::AcarsRouterSource;
+use crate::address::ShRejectedAddress;
+use crate::adsb_source::AdsbSource;
+use crate::validation::ShConfigIssue;
+
+/// The version of the config files this hub writes
+pub const CONFIG_VERSION: u32 = 1;
+
+// Takes a file up one version, returning what it changed
+type Migration = fn(&mut Table) -> Vec<String>;
+
+// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [sources_as_tables];
+
+/// What upgrading a config file did
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ShConfigMigration {
+    pub from: u32,
+    pub to: u32,
+    /// One line for each thing that changed, oldest migration first
+    pub changes: Vec<String>,
+}
+
+/// The version a config file was written as. Files from before there were versions are 0
+///
+/// # Errors
+/// `config_version` isn't a version number
+pub fn file_version(config: &Table) -> Result<u32, String> {
+    match config.get("config_version") {
+        None => Ok(0),
+        Some(Value::Integer(version)) => {
+            u32::try_from(*version).map_err(|_| format!("{version} is not a config version"))
+        }
+        Some(value) => Err(format!("{value} is not a config version")),
+    }
+}
+
+/// Run the migrations the config needs, and mark it as the current version. `None` if it
+/// already was
+///
+/// # Errors
+/// The version can't be read, or is newer than this hub knows about
+pub fn upgrade(config: &mut Table) -> Result<Option<ShConfigMigration>, String> {
+    let from = file_version(config)?;
+    if from > CONFIG_VERSION {
+        return Err(format!(
+            "Written by a newer version of the hub. This one only understands up to version \
+             {CONFIG_VERSION}"
+        ));
+    }
+    if from == CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let mut changes = Vec::new();
+    for (version, migration) in (from..).zip(&MIGRATIONS[from as usize..]) {
+        changes.extend(
+            migration(config)
+                .into_iter()
+                .map(|change| format!("version {} to {}: {change}", version, version + 1)),
+        );
+    }
+    config.insert(
+        "config_version".to_string(),
+        Value::Integer(i64::from(CONFIG_VERSION)),
+    );
+
+    Ok(Some(ShConfigMigration {
+        from,
+        to: CONFIG_VERSION,
+        changes,
+    }))
+}
+
+/// The config file brought up to date, as TOML, without changing the file. `None` if there is
+/// no file to upgrade, or it isn't TOML, which figment explains better when it reads it
+///
+/// # Errors
+/// The file can't be upgraded
+pub fn read_upgraded(
+    file_path: &str,
+) -> Result<Option<(String, ShConfigMigration)>, ShConfigIssue> {
+    let Ok(contents) = std::fs::read_to_string(file_path) else {
+        return Ok(None);
+    };
+    let Ok(mut config) = contents.parse::<Table>() else {
+        return Ok(None);
+    };
+
+    let value = config
+        .get("config_version")
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let migration =
+        upgrade(&mut config).map_err(|e| ShConfigIssue::error("config_version", value, e))?;
+
+    Ok(migration.map(|migration| (config.to_string(), migration)))
+}
+
+/// Copy a config file older than `CONFIG_VERSION` to `<file>.v<version>.bak`
+///
+/// Done before it is written over as the current version. An existing backup is kept, as it is
+/// the closest to the original. Returns where the backup is, if one was needed
+///
+/// # Errors
+/// The backup couldn't be written
+pub fn back_up(file_path: &str) -> Result<Option<String>, std::io::Error> {
+    let Ok(contents) = std::fs::read_to_string(file_path) else {
+        return Ok(None);
+    };
+    let Ok(version) = contents
+        .parse::<Table>()
+        .map_err(|e| e.to_string())
+        .and_then(|config| file_version(&config))
+    else {
+        return Ok(None);
+    };
+    if version >= CONFIG_VERSION {
+        return Ok(None);
+    }
+
+    let backup = format!("{file_path}.v{version}.bak");
+    if !Path::new(&backup).exists() {
+        std::fs::write(&backup, contents)?;
+    }
+
+    Ok(Some(backup))
+}
+
+/// Version 0 to 1. Sources could be given as `"host:port,host:port"`, or a list of those. They
+/// are written out as lists of addresses now, so the shorthand is turned in to that
+fn sources_as_tables(config: &mut Table) -> Vec<String> {
+    let Some(Value::Table(sources)) = config.get_mut("data_sources") else {
+        return Vec::new();
+    };
+
+    [
+        source_as_table(sources, "acars_routers", |list| {
+            let source = AcarsRouterSource::from_str(list).void_unwrap();
+            (Value::try_from(&source).ok(), source.rejected().to_vec())
+        }),
+        source_as_table(sources, "adsb_sources", |list| {
+            let source = AdsbSource::from_str(list).void_unwrap();
+            (Value::try_from(&source).ok(), source.rejected().to_vec())
+        }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Swap the shorthand for a source in `sources` for the table `parse` makes of it. It is left
+/// alone if any of it can't be read, so nothing is lost
+fn source_as_table(
+    sources: &mut Table,
+    key: &str,
+    parse: fn(&str) -> (Option<Value>, Vec<ShRejectedAddress>),
+) -> Option<String> {
+    let list = match sources.get(key)? {
+        Value::String(list) => list.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(Value::as_str)
+            .collect::<Option<Vec<_>>>()?
+            .join(","),
+        _ => return None,
+    };
+
+    let (table, rejected) = parse(&list);
+    if let Some(rejected) = rejected.first() {
+        return Some(format!(
+            "data_sources.{key} was left as it is, as {} can't be used: {}",
+            rejected.value, rejected.reason
+        ));
+    }
+
+    sources.insert(key.to_string(), table?);
+    Some(format!(
+        "data_sources.{key} was changed from \"{list}\" to a list of addresses"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ShConfig, ShConfigOverrides};
+
+    const UNVERSIONED: &str = r#"
+[app]
+log_level = "debug"
+
+[data_sources]
+acars_routers = "acars_router:15550,192.168.1.10:15555"
+adsb_sources = ["readsb:30003:51.47:-0.45"]
+"#;
+
+    const UPGRADED: &str = r#"
+config_version = 1
+
+[app]
+log_level = "debug"
+
+[data_sources.acars_routers]
+addresses = [
+    { address = "acars_router", port = 15550 },
+    { address = "192.168.1.10", port = 15555 },
+]
+
+[data_sources.adsb_sources]
+addresses = [{ address = "readsb", port = 30003, latitude = 51.47, longitude = -0.45 }]
+"#;
+
+    #[test]
+    fn upgrades_an_unversioned_config() {
+        let mut config: Table = UNVERSIONED.parse().unwrap();
+        let migration = upgrade(&mut config).unwrap().unwrap();
+
+        assert_eq!(config, UPGRADED.parse::<Table>().unwrap());
+        assert_eq!(
+            migration,
+            ShConfigMigration {
+                from: 0,
+                to: CONFIG_VERSION,
+                changes: vec![
+                    "version 0 to 1: data_sources.acars_routers was changed from \
+                     \"acars_router:15550,192.168.1.10:15555\" to a list of addresses"
+                        .to_string(),
+                    "version 0 to 1: data_sources.adsb_sources was changed from \
+                     \"readsb:30003:51.47:-0.45\" to a list of addresses"
+                        .to_string(),
+                ],
+            }
+        );
+
+        // and there is nothing more to do after that
+        assert_eq!(upgrade(&mut config), Ok(None));
+    }
+
+    #[test]
+    fn leaves_sources_it_cant_read() {
+        let mut config: Table = "[data_sources]\nacars_routers = \"acars_router:15550,nonsense\""
+            .parse()
+            .unwrap();
+        let migration = upgrade(&mut config).unwrap().unwrap();
+
+        assert_eq!(
+            config["data_sources"]["acars_routers"].as_str(),
+            Some("acars_router:15550,nonsense")
+        );
+        assert_eq!(migration.changes.len(), 1);
+        assert!(migration.changes[0].starts_with(
+            "version 0 to 1: data_sources.acars_routers was left as it is, as nonsense can't be used"
+        ));
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let mut config: Table = "config_version = 99".parse().unwrap();
+        assert!(upgrade(&mut config).is_err());
+
+        let mut config: Table = "config_version = \"one\"".parse().unwrap();
+        assert!(upgrade(&mut config).is_err());
+    }
+
+    #[test]
+    fn writing_an_upgraded_config_backs_up_the_old_one() {
+        let data_path =
+            std::env::temp_dir().join(format!("sh-config-migration-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&data_path);
+        std::fs::create_dir_all(&data_path).unwrap();
+        let file_path = data_path.join("sh_config.toml");
+        std::fs::write(&file_path, UNVERSIONED).unwrap();
+
+        let config = ShConfig::load_with(&ShConfigOverrides {
+            data_path: Some(data_path.to_str().unwrap().to_string()),
+            ..ShConfigOverrides::default()
+        })
+        .unwrap();
+        let migration = config.migration.clone().unwrap();
+        assert_eq!((migration.from, migration.to), (0, CONFIG_VERSION));
+        assert_eq!(migration.changes.len(), 2);
+
+        // nothing is touched until the config is written
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), UNVERSIONED);
+        config.write_config().unwrap();
+
+        let backup = data_path.join("sh_config.toml.v0.bak");
+        assert_eq!(std::fs::read_to_string(backup).unwrap(), UNVERSIONED);
+        let written: Table = std::fs::read_to_string(&file_path)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(file_version(&written), Ok(CONFIG_VERSION));
+        assert_eq!(
+            written["data_sources"]["acars_routers"],
+            UPGRADED.parse::<Table>().unwrap()["data_sources"]["acars_routers"]
+        );
+
+        // a second write has nothing to back up
+        assert_eq!(back_up(file_path.to_str().unwrap()).unwrap(), None);
+
+        std::fs::remove_dir_all(data_path).unwrap();
+    }
+}
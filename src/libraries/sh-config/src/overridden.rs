@@ -0,0 +1,106 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Settings that only last as long as the process does. Writing the config puts back what the
+// file had for each of them, so a one-off flag or a container's environment doesn't end up in
+// the file for good
+
+use figment::Figment;
+use toml::{Table, Value};
+
+use crate::ShConfig;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShOverriddenSetting {
+    /// Where the setting is, down to the first part the file holds as a single value
+    path: Vec<String>,
+    /// `None` if the file didn't have it, in which case it is left out
+    file_value: Option<Value>,
+}
+
+impl ShOverriddenSetting {
+    /// Everything `overriding` sets, with what `stored` has for each
+    pub fn find(stored: &Figment, overriding: &Figment) -> Vec<Self> {
+        let Ok(overriding) = overriding.extract::<Table>() else {
+            return Vec::new();
+        };
+        // if the file can't be read without the overrides, they are all left out when written
+        let stored = stored
+            .extract::<ShConfig>()
+            .ok()
+            .and_then(|config| Table::try_from(config).ok());
+
+        let mut settings = Vec::new();
+        Self::find_in(&overriding, stored.as_ref(), &mut Vec::new(), &mut settings);
+        settings
+    }
+
+    fn find_in(
+        overriding: &Table,
+        stored: Option<&Table>,
+        path: &mut Vec<String>,
+        settings: &mut Vec<Self>,
+    ) {
+        for (key, value) in overriding {
+            path.push(key.clone());
+            let file_value = stored.and_then(|stored| stored.get(key));
+            match (value, file_value) {
+                (Value::Table(overriding), None | Some(Value::Table(_))) => {
+                    Self::find_in(
+                        overriding,
+                        file_value.and_then(Value::as_table),
+                        path,
+                        settings,
+                    );
+                }
+                _ => settings.push(Self {
+                    path: path.clone(),
+                    file_value: file_value.cloned(),
+                }),
+            }
+            path.pop();
+        }
+    }
+
+    /// Where the setting is, written the way the config file would have it
+    pub fn path(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Whether `before` and `after` have something different for the setting
+    pub fn changed(&self, before: &Table, after: &Table) -> bool {
+        self.get(before) != self.get(after)
+    }
+
+    fn get<'a>(&self, config: &'a Table) -> Option<&'a Value> {
+        let (key, parents) = self.path.split_last()?;
+
+        let mut table = config;
+        for parent in parents {
+            table = table.get(parent)?.as_table()?;
+        }
+        table.get(key)
+    }
+
+    /// Put the file's value back into `config`
+    pub fn restore(&self, config: &mut Table) {
+        let Some((key, parents)) = self.path.split_last() else {
+            return;
+        };
+
+        let mut table = config;
+        for parent in parents {
+            let Some(inner) = table.get_mut(parent).and_then(Value::as_table_mut) else {
+                return;
+            };
+            table = inner;
+        }
+
+        match &self.file_value {
+            Some(value) => table.insert(key.clone(), value.clone()),
+            None => table.remove(key),
+        };
+    }
+}
@@ -15,9 +15,9 @@ pub struct SDREHub {
     pub database_url: String,
     #[serde_inline_default("info".to_string())]
     pub log_level: String,
-    #[serde_inline_default(ShConfig::get_application_data_path())]
+    #[serde_inline_default(ShConfig::default_data_path())]
     pub data_path: String,
-    #[serde_inline_default(ShConfig::get_config_file_path())]
+    #[serde_inline_default(ShConfig::default_config_file())]
     #[serde(skip_serializing)]
     pub config_file: String,
 }
@@ -50,12 +50,12 @@ impl SDREHub {
 
 impl Default for SDREHub {
     fn default() -> Self {
-        let path = ShConfig::get_application_data_path();
+        let path = ShConfig::default_data_path();
         Self {
-            database_url: format!("sqlite://{path}sdre-hub.db"),
+            database_url: format!("sqlite://{path}/sdre-hub.db"),
             data_path: path,
             log_level: "info".to_string(),
-            config_file: ShConfig::get_config_file_path(),
+            config_file: ShConfig::default_config_file(),
         }
     }
 }
@@ -0,0 +1,101 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+use serde_inline_default::serde_inline_default;
+
+use crate::validation::ShConfigIssue;
+
+/// `ShServerConfig` is where the web interface and API listen. Changes need a restart
+#[serde_inline_default]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ShServerConfig {
+    /// Address to listen on. `::` listens on IPv6, and on IPv4 as well on most systems
+    #[serde_inline_default(IpAddr::V4(Ipv4Addr::UNSPECIFIED))]
+    pub bind: IpAddr,
+    #[serde_inline_default(3000)]
+    pub port: u16,
+    /// PEM certificate chain. With `tls_key` set as well, the hub serves https and wss
+    pub tls_cert: Option<String>,
+    /// PEM private key for `tls_cert`
+    pub tls_key: Option<String>,
+    /// Listen on this Unix socket instead of `bind` and `port`, for a reverse proxy on the
+    /// same machine to connect to. Connections on it are never trusted as local
+    pub unix_socket: Option<String>,
+    /// Serve the web interface from this directory, where `trunk build` put it. Without it,
+    /// the copy built into the hub is served, if it was built with one
+    pub frontend_path: Option<String>,
+}
+
+impl Default for ShServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: 3000,
+            tls_cert: None,
+            tls_key: None,
+            unix_socket: None,
+            frontend_path: None,
+        }
+    }
+}
+
+impl ShServerConfig {
+    #[must_use]
+    pub const fn address(&self) -> SocketAddr {
+        SocketAddr::new(self.bind, self.port)
+    }
+
+    /// The certificate and key, if TLS is turned on
+    ///
+    /// # Errors
+    /// Only one of the certificate and key has been set
+    pub fn tls(&self) -> Result<Option<(&str, &str)>, String> {
+        match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            _ => Err("Both server.tls_cert and server.tls_key are needed for TLS".to_string()),
+        }
+    }
+
+    #[must_use]
+    pub fn issues(&self) -> Vec<ShConfigIssue> {
+        let mut issues = Vec::new();
+        let files = [
+            ("server.tls_cert", &self.tls_cert),
+            ("server.tls_key", &self.tls_key),
+        ];
+
+        for (path, file) in files {
+            match file {
+                Some(file) if !std::path::Path::new(file).is_file() => {
+                    issues.push(ShConfigIssue::error(path, file, "The file does not exist"));
+                }
+                None if self.tls_cert.is_some() || self.tls_key.is_some() => {
+                    issues.push(ShConfigIssue::error(
+                        path,
+                        "",
+                        "Both server.tls_cert and server.tls_key are needed for TLS",
+                    ));
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(frontend_path) = &self.frontend_path {
+            if !std::path::Path::new(frontend_path).is_dir() {
+                issues.push(ShConfigIssue::warning(
+                    "server.frontend_path",
+                    frontend_path,
+                    "The directory does not exist, so the built in web interface is served if there is one",
+                ));
+            }
+        }
+
+        issues
+    }
+}
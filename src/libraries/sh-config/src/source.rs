@@ -6,9 +6,12 @@
 use serde::{Deserialize, Serialize};
 use serde_inline_default::serde_inline_default;
 
-use crate::{acars_router_source::AcarsRouterSource, adsb_source::AdsbSource};
+use crate::{
+    acars_router_source::AcarsRouterSource, adsb_source::AdsbSource, validation::ShConfigIssue,
+};
 
 #[derive(Debug, Deserialize, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 #[serde(rename_all = "snake_case", try_from = "String")]
 pub enum ShEnabledDataSources {
     Acars,
@@ -56,12 +59,21 @@ impl Serialize for ShEnabledDataSources {
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct EnabledDataSources {
     pub enabled_sources: Vec<ShEnabledDataSources>,
 }
 
+impl EnabledDataSources {
+    #[must_use]
+    pub fn is_enabled(&self, source: &ShEnabledDataSources) -> bool {
+        self.enabled_sources.contains(source)
+    }
+}
+
 #[serde_inline_default]
-#[derive(Debug, Serialize, Default, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct DataSources {
     #[serde_inline_default(AcarsRouterSource::default())]
     #[serde(deserialize_with = "crate::acars_router_source::string_or_struct")]
@@ -69,4 +81,105 @@ pub struct DataSources {
     #[serde_inline_default(AdsbSource::default())]
     #[serde(deserialize_with = "crate::adsb_source::string_or_struct")]
     pub adsb_sources: AdsbSource,
+    /// Seconds a connected source can go without sending anything before it is flagged as
+    /// silent. 0 turns the check off
+    #[serde_inline_default(300)]
+    pub silence_timeout: u64,
+}
+
+impl DataSources {
+    /// Every source has to be usable, and each `address:port` can only be used once
+    ///
+    /// # Errors
+    /// Says which source is the problem and why
+    pub fn validate(&self) -> Result<(), String> {
+        let rejected = self
+            .acars_routers
+            .rejected()
+            .iter()
+            .chain(self.adsb_sources.rejected());
+        if let Some(rejected) = rejected.into_iter().next() {
+            return Err(format!("{}: {}", rejected.value, rejected.reason));
+        }
+
+        match self.issues().into_iter().find(ShConfigIssue::is_error) {
+            Some(issue) => Err(format!("{}: {}", issue.value, issue.reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Sources that can't be used are errors, and ones that were left out when the config was
+    /// read are warnings
+    #[must_use]
+    pub fn issues(&self) -> Vec<ShConfigIssue> {
+        let mut issues = Vec::new();
+        let mut seen = Vec::new();
+
+        let acars_routers =
+            self.acars_routers
+                .addresses()
+                .iter()
+                .enumerate()
+                .map(|(index, address)| {
+                    (
+                        format!("data_sources.acars_routers.addresses[{index}]"),
+                        address.to_string(),
+                        address.validate(),
+                    )
+                });
+        let adsb_sources =
+            self.adsb_sources
+                .addresses()
+                .iter()
+                .enumerate()
+                .map(|(index, address)| {
+                    (
+                        format!("data_sources.adsb_sources.addresses[{index}]"),
+                        address.to_string(),
+                        address.validate(),
+                    )
+                });
+
+        for (path, name, valid) in acars_routers.chain(adsb_sources) {
+            if let Err(e) = valid {
+                issues.push(ShConfigIssue::error(path, name, e));
+                continue;
+            }
+
+            if seen.contains(&name) {
+                issues.push(ShConfigIssue::error(
+                    path,
+                    name.clone(),
+                    "This address and port is already used by another source",
+                ));
+            }
+            seen.push(name);
+        }
+
+        let rejected = [
+            ("data_sources.acars_routers", self.acars_routers.rejected()),
+            ("data_sources.adsb_sources", self.adsb_sources.rejected()),
+        ];
+        for (path, rejected) in rejected {
+            for rejected in rejected {
+                issues.push(ShConfigIssue::warning(
+                    path,
+                    rejected.value.clone(),
+                    format!("{}, so it has been left out", rejected.reason),
+                ));
+            }
+        }
+
+        issues
+    }
+}
+
+impl Default for DataSources {
+    fn default() -> Self {
+        Self {
+            acars_routers: AcarsRouterSource::default(),
+            adsb_sources: AdsbSource::default(),
+            silence_timeout: 300,
+        }
+    }
 }
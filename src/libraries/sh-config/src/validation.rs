@@ -0,0 +1,114 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Problems found with the config, as a list rather than the first one found, so they can all
+// be fixed in one go. Errors stop something working, warnings are things that are ignored or
+// probably not what was meant
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ShConfigSeverity {
+    Error,
+    Warning,
+}
+
+impl Display for ShConfigSeverity {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// `ShConfigIssue` is one problem with one setting
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ShConfigIssue {
+    pub severity: ShConfigSeverity,
+    /// Where the setting is, like `data_sources.adsb_sources.addresses[0]`
+    pub path: String,
+    /// The value that is the problem, as it was given. Empty if there isn't one
+    pub value: String,
+    pub reason: String,
+}
+
+impl ShConfigIssue {
+    #[must_use]
+    pub fn error(
+        path: impl Into<String>,
+        value: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: ShConfigSeverity::Error,
+            path: path.into(),
+            value: value.into(),
+            reason: reason.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn warning(
+        path: impl Into<String>,
+        value: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: ShConfigSeverity::Warning,
+            ..Self::error(path, value, reason)
+        }
+    }
+
+    #[must_use]
+    pub fn is_error(&self) -> bool {
+        self.severity == ShConfigSeverity::Error
+    }
+
+    /// Turn whatever figment couldn't read in to issues, one for each setting
+    #[must_use]
+    pub fn from_figment(error: figment::Error) -> Vec<Self> {
+        error
+            .into_iter()
+            .map(|error| {
+                let value = match &error.kind {
+                    figment::error::Kind::InvalidType(actual, _)
+                    | figment::error::Kind::InvalidValue(actual, _) => actual.to_string(),
+                    _ => String::new(),
+                };
+                let reason = match error.metadata.as_ref().and_then(|m| m.source.as_ref()) {
+                    Some(source) => format!("{} (from {source})", error.kind),
+                    None => error.kind.to_string(),
+                };
+
+                Self::error(error.path.join("."), value, reason)
+            })
+            .collect()
+    }
+}
+
+impl Display for ShConfigIssue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.value.is_empty() {
+            write!(f, "{}: {}: {}", self.severity, self.path, self.reason)
+        } else {
+            write!(
+                f,
+                "{}: {} = {}: {}",
+                self.severity, self.path, self.value, self.reason
+            )
+        }
+    }
+}
+
+/// Any of the issues stop something working
+#[must_use]
+pub fn has_errors(issues: &[ShConfigIssue]) -> bool {
+    issues.iter().any(ShConfigIssue::is_error)
+}
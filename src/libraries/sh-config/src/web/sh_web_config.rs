@@ -6,16 +6,40 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    alerts::ShAlertsConfig,
     map::ShMapConfig,
     source::{DataSources, EnabledDataSources},
+    validation::ShConfigIssue,
 };
 
 use super::sh_web_sdrehub::ShWebSDREHub;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct ShWebConfig {
     pub app: ShWebSDREHub,
     pub enabled_data_sources: EnabledDataSources,
     pub data_sources: DataSources,
     pub map: ShMapConfig,
+    pub alerts: ShAlertsConfig,
+    pub notification_targets: Vec<String>,
+    /// What is wrong with the config, worked out when this was sent
+    pub issues: Vec<ShConfigIssue>,
+    /// Settings set by `SH_` variables or the command line, like `server.port`. They can't be
+    /// changed here
+    #[serde(default)]
+    pub overridden: Vec<String>,
+}
+
+impl ShWebConfig {
+    /// Whether the setting at `path`, or something it is part of, is in `overridden`
+    #[must_use]
+    pub fn is_overridden(&self, path: &str) -> bool {
+        self.overridden.iter().any(|overridden| {
+            path == overridden
+                || path
+                    .strip_prefix(overridden.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
 }
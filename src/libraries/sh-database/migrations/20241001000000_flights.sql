@@ -0,0 +1,52 @@
+-- Flights are built from the messages and positions below. A message or position with no
+-- flight is one we couldn't tie to an aircraft.
+
+CREATE TABLE flights (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    aircraft TEXT NOT NULL,
+    icao_hex TEXT,
+    tail TEXT,
+    callsign TEXT,
+    callsign_from_adsb INTEGER NOT NULL DEFAULT 0,
+    start_time REAL NOT NULL,
+    end_time REAL NOT NULL,
+    takeoff REAL,
+    landing REAL,
+    on_ground INTEGER,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    position_count INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX flights_aircraft_end_time ON flights (aircraft, end_time);
+CREATE INDEX flights_end_time ON flights (end_time);
+
+CREATE TABLE messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    flight_id INTEGER REFERENCES flights (id) ON DELETE SET NULL,
+    aircraft TEXT,
+    icao_hex TEXT,
+    tail TEXT,
+    timestamp REAL NOT NULL,
+    callsign TEXT,
+    message TEXT NOT NULL
+);
+
+CREATE INDEX messages_flight_id ON messages (flight_id);
+CREATE INDEX messages_aircraft_timestamp ON messages (aircraft, timestamp);
+CREATE INDEX messages_timestamp ON messages (timestamp);
+
+CREATE TABLE positions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    flight_id INTEGER REFERENCES flights (id) ON DELETE SET NULL,
+    aircraft TEXT NOT NULL,
+    timestamp REAL NOT NULL,
+    callsign TEXT,
+    latitude REAL,
+    longitude REAL,
+    altitude INTEGER,
+    squawk TEXT,
+    on_ground INTEGER
+);
+
+CREATE INDEX positions_flight_id ON positions (flight_id);
+CREATE INDEX positions_aircraft_timestamp ON positions (aircraft, timestamp);
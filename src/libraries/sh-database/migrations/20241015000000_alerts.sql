@@ -0,0 +1,15 @@
+-- Every alert raised by the alert rules. The whole alert, including the message or position
+-- that raised it, is kept as JSON in `alert`
+
+CREATE TABLE alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule TEXT NOT NULL,
+    timestamp REAL NOT NULL,
+    icao_hex TEXT,
+    tail TEXT,
+    flight TEXT,
+    alert TEXT NOT NULL
+);
+
+CREATE INDEX alerts_timestamp ON alerts (timestamp);
+CREATE INDEX alerts_rule ON alerts (rule);
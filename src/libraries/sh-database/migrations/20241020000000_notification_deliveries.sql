@@ -0,0 +1,14 @@
+-- The outcome of sending each alert to each notification target it was routed to
+
+CREATE TABLE notification_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    alert_id INTEGER REFERENCES alerts (id) ON DELETE CASCADE,
+    rule TEXT NOT NULL,
+    target TEXT NOT NULL,
+    timestamp REAL NOT NULL,
+    attempts INTEGER NOT NULL,
+    delivered INTEGER NOT NULL,
+    error TEXT
+);
+
+CREATE INDEX notification_deliveries_timestamp ON notification_deliveries (timestamp);
@@ -0,0 +1,16 @@
+-- Emergencies raised by the built in detector. The whole emergency, including its timeline,
+-- is kept as JSON in `emergency` and rewritten every time it changes
+
+CREATE TABLE emergencies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    icao_hex TEXT,
+    tail TEXT,
+    flight TEXT,
+    started REAL NOT NULL,
+    cleared REAL,
+    emergency TEXT NOT NULL
+);
+
+CREATE INDEX emergencies_started ON emergencies (started);
+CREATE INDEX emergencies_cleared ON emergencies (cleared);
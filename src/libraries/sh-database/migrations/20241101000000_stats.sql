@@ -0,0 +1,20 @@
+-- Rollups for the statistics page. Counts are kept in five minute buckets, `bucket` being the
+-- Unix time the bucket starts, and anything older than the longest range the page shows is
+-- thrown away
+
+-- Messages by what they came in on. `dimension` is what `key` is, e.g. `label`
+CREATE TABLE stats_messages (
+    bucket INTEGER NOT NULL,
+    dimension TEXT NOT NULL,
+    key TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (bucket, dimension, key)
+);
+
+CREATE INDEX stats_messages_dimension_bucket ON stats_messages (dimension, bucket);
+
+-- The most aircraft tracked at once in each bucket
+CREATE TABLE stats_aircraft (
+    bucket INTEGER PRIMARY KEY,
+    aircraft INTEGER NOT NULL
+);
@@ -0,0 +1,146 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Reading the message history out of a docker-acarshub `messages.db`, to move it in to the hub.
+// acarshub keeps every field as text, with an empty string for anything the message didn't
+// have, and doesn't keep which way a message went or which decoder it came from
+
+use sh_common::acars_message::{ShAcarsMessage, ShMessageType};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::Row;
+
+// What imported messages say they came in from
+const SOURCE: &str = "acarshub";
+
+// acarshub's columns, as text so numbers stored by older versions read the same way
+const COLUMNS: &str = "id, CAST(message_type AS TEXT) AS message_type, \
+    CAST(msg_time AS REAL) AS msg_time, CAST(station_id AS TEXT) AS station_id, \
+    CAST(freq AS TEXT) AS freq, CAST(level AS TEXT) AS level, CAST(tail AS TEXT) AS tail, \
+    CAST(flight AS TEXT) AS flight, CAST(icao AS TEXT) AS icao, CAST(label AS TEXT) AS label, \
+    CAST(block_id AS TEXT) AS block_id, CAST(msgno AS TEXT) AS msgno, \
+    CAST(msg_text AS TEXT) AS msg_text";
+
+pub struct AcarshubDatabase {
+    pool: SqlitePool,
+}
+
+impl AcarshubDatabase {
+    /// Open acarshub's database at `path`. It is only read from
+    ///
+    /// # Errors
+    /// - The database doesn't exist or could not be opened
+    pub async fn open(path: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::new().filename(path).read_only(true);
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// How many messages acarshub has
+    ///
+    /// # Errors
+    /// - The database isn't an acarshub one
+    pub async fn message_count(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Up to `limit` messages with an id after `after`, oldest first, and the id to carry on
+    /// after. Messages that can't be turned in to hub messages are the reason why, with their id.
+    /// The id is `None` once there are no more
+    ///
+    /// # Errors
+    /// - The messages could not be read, or the database isn't an acarshub one
+    pub async fn messages_after(
+        &self,
+        after: i64,
+        limit: i64,
+    ) -> Result<(Vec<Result<ShAcarsMessage, String>>, Option<i64>), sqlx::Error> {
+        let rows = sqlx::query(&format!(
+            "SELECT {COLUMNS} FROM messages WHERE id > ? ORDER BY id LIMIT ?"
+        ))
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut messages = Vec::with_capacity(rows.len());
+        let mut next = None;
+        for row in &rows {
+            let id: i64 = row.try_get("id")?;
+            messages.push(to_message(row).map_err(|e| format!("message {id}: {e}")));
+            next = Some(id);
+        }
+
+        Ok((messages, next))
+    }
+}
+
+/// The text in `column`, or `None` if acarshub left it empty
+fn text(row: &SqliteRow, column: &str) -> Option<String> {
+    row.try_get::<Option<String>, _>(column)
+        .ok()
+        .flatten()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn number(row: &SqliteRow, column: &str) -> Option<f64> {
+    text(row, column).and_then(|value| value.parse().ok())
+}
+
+/// acarshub's names for each kind of message
+fn message_type(name: &str) -> Option<ShMessageType> {
+    match name.to_uppercase().as_str() {
+        "ACARS" => Some(ShMessageType::Acars),
+        "VDL-M2" | "VDLM2" | "VDL2" => Some(ShMessageType::Vdlm2),
+        "HFDL" => Some(ShMessageType::Hfdl),
+        "IMS-L" | "IMSL" | "SATCOM" => Some(ShMessageType::Inmarsat),
+        "IRDM" => Some(ShMessageType::Iridium),
+        _ => None,
+    }
+}
+
+/// acarshub keeps the ICAO address as hex for VDL2 and HFDL, but as the decimal number
+/// acarsdec sends for ACARS. Six characters is always taken as hex
+fn icao_hex(icao: &str) -> Option<String> {
+    if icao.len() == 6 && icao.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(icao.to_uppercase());
+    }
+
+    icao.parse::<u32>()
+        .ok()
+        .filter(|icao| *icao <= 0x00FF_FFFF)
+        .map(|icao| format!("{icao:06X}"))
+}
+
+fn to_message(row: &SqliteRow) -> Result<ShAcarsMessage, String> {
+    let name = text(row, "message_type").unwrap_or_default();
+    let message_type = message_type(&name)
+        .ok_or_else(|| format!("\"{name}\" isn't a kind of message the hub knows"))?;
+    let timestamp = row
+        .try_get::<Option<f64>, _>("msg_time")
+        .ok()
+        .flatten()
+        .ok_or_else(|| "It has no time".to_string())?;
+
+    let mut message = ShAcarsMessage::new(timestamp, message_type, SOURCE.to_string());
+    message.station_id = text(row, "station_id");
+    message.frequency = number(row, "freq");
+    message.signal_level = number(row, "level");
+    // the decoders send ACARS tails with the dots they are padded with
+    message.tail = text(row, "tail")
+        .map(|tail| tail.trim_start_matches('.').to_string())
+        .filter(|tail| !tail.is_empty());
+    message.flight = text(row, "flight");
+    message.icao_hex = text(row, "icao").as_deref().and_then(icao_hex);
+    message.label = text(row, "label");
+    message.block_id = text(row, "block_id");
+    message.message_number = text(row, "msgno");
+    message.text = text(row, "msg_text");
+
+    Ok(message)
+}
@@ -0,0 +1,125 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::api::{ShAircraftDetail, ShAircraftSummary};
+use sqlx::FromRow;
+
+use crate::flights::{FlightRow, PositionRow};
+use crate::messages::{stored_messages, MessageRow};
+use crate::sessions::Session;
+use crate::ShDatabase;
+
+// Flights and messages sent with an aircraft's details
+const AIRCRAFT_FLIGHTS: i64 = 50;
+const AIRCRAFT_MESSAGES: i64 = 50;
+
+// Totals for each aircraft, along with what its latest flight knew about it
+const SUMMARY_QUERY: &str = "SELECT f.aircraft, f.icao_hex, f.tail, f.callsign, totals.first_seen, totals.last_seen, totals.flights, totals.messages, totals.positions
+     FROM (SELECT aircraft, MIN(start_time) AS first_seen, MAX(end_time) AS last_seen, COUNT(*) AS flights, SUM(message_count) AS messages, SUM(position_count) AS positions FROM flights GROUP BY aircraft) totals
+     JOIN flights f ON f.aircraft = totals.aircraft AND f.end_time = totals.last_seen";
+
+#[derive(FromRow)]
+struct SummaryRow {
+    aircraft: String,
+    icao_hex: Option<String>,
+    tail: Option<String>,
+    callsign: Option<String>,
+    first_seen: f64,
+    last_seen: f64,
+    flights: i64,
+    messages: i64,
+    positions: i64,
+}
+
+impl From<SummaryRow> for ShAircraftSummary {
+    fn from(row: SummaryRow) -> Self {
+        Self {
+            aircraft: row.aircraft,
+            icao_hex: row.icao_hex,
+            tail: row.tail,
+            callsign: row.callsign,
+            first_seen: row.first_seen,
+            last_seen: row.last_seen,
+            flights: row.flights,
+            messages: row.messages,
+            positions: row.positions,
+        }
+    }
+}
+
+impl ShDatabase {
+    /// Aircraft we have flights for, most recently seen first
+    ///
+    /// # Errors
+    /// - The aircraft could not be read
+    pub async fn aircraft_list(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ShAircraftSummary>, sqlx::Error> {
+        let rows: Vec<SummaryRow> = sqlx::query_as(&format!(
+            "{SUMMARY_QUERY} GROUP BY f.aircraft ORDER BY totals.last_seen DESC LIMIT ? OFFSET ?"
+        ))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(ShAircraftSummary::from).collect())
+    }
+
+    /// Everything stored for `aircraft`, its ICAO hex address or registration as the flights
+    /// have it, if we have seen it
+    ///
+    /// # Errors
+    /// - The aircraft could not be read
+    pub async fn aircraft(&self, aircraft: &str) -> Result<Option<ShAircraftDetail>, sqlx::Error> {
+        let aircraft = aircraft.trim().to_uppercase();
+
+        let summary: Option<SummaryRow> = sqlx::query_as(&format!(
+            "{SUMMARY_QUERY} WHERE f.aircraft = ? GROUP BY f.aircraft"
+        ))
+        .bind(&aircraft)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(summary) = summary else {
+            return Ok(None);
+        };
+
+        let flights: Vec<FlightRow> = sqlx::query_as(
+            "SELECT * FROM flights WHERE aircraft = ? ORDER BY end_time DESC LIMIT ?",
+        )
+        .bind(&aircraft)
+        .bind(AIRCRAFT_FLIGHTS)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let last_position: Option<PositionRow> = sqlx::query_as(
+            "SELECT aircraft, timestamp, callsign, latitude, longitude, altitude, squawk, on_ground FROM positions WHERE aircraft = ? ORDER BY timestamp DESC LIMIT 1",
+        )
+        .bind(&aircraft)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let messages: Vec<MessageRow> = sqlx::query_as(
+            "SELECT id, flight_id, message FROM messages WHERE aircraft = ? ORDER BY timestamp DESC, id DESC LIMIT ?",
+        )
+        .bind(&aircraft)
+        .bind(AIRCRAFT_MESSAGES)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(ShAircraftDetail {
+            summary: summary.into(),
+            flights: flights
+                .into_iter()
+                .filter_map(|row| Session::from(row).to_shared())
+                .collect(),
+            last_position: last_position.map(ShAdsbPosition::from),
+            messages: stored_messages(messages),
+        }))
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use sh_common::alert::ShAlert;
+
+use crate::ShDatabase;
+
+// Number of alerts sent when users ask for the history
+const RECENT_ALERTS: i64 = 200;
+
+impl ShDatabase {
+    /// Store an alert, setting its id
+    ///
+    /// # Errors
+    /// - The alert could not be stored
+    pub async fn add_alert(&self, alert: &mut ShAlert) -> Result<(), sqlx::Error> {
+        let mut transaction = self.pool.begin().await?;
+
+        alert.id = sqlx::query(
+            "INSERT INTO alerts (rule, timestamp, icao_hex, tail, flight, alert) VALUES (?, ?, ?, ?, ?, '')",
+        )
+        .bind(&alert.rule)
+        .bind(alert.timestamp)
+        .bind(&alert.icao_hex)
+        .bind(&alert.tail)
+        .bind(&alert.flight)
+        .execute(&mut *transaction)
+        .await?
+        .last_insert_rowid();
+
+        // the stored copy carries its id too
+        let serialized =
+            serde_json::to_string(alert).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        sqlx::query("UPDATE alerts SET alert = ? WHERE id = ?")
+            .bind(serialized)
+            .bind(alert.id)
+            .execute(&mut *transaction)
+            .await?;
+
+        transaction.commit().await
+    }
+
+    /// The most recent alerts, newest first
+    ///
+    /// # Errors
+    /// - The alerts could not be read
+    pub async fn recent_alerts(&self) -> Result<Vec<ShAlert>, sqlx::Error> {
+        let stored: Vec<(String,)> =
+            sqlx::query_as("SELECT alert FROM alerts ORDER BY timestamp DESC, id DESC LIMIT ?")
+                .bind(RECENT_ALERTS)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(stored
+            .into_iter()
+            .filter_map(|(alert,)| match serde_json::from_str::<ShAlert>(&alert) {
+                Ok(alert) => Some(alert),
+                Err(e) => {
+                    warn!("Skipping a stored alert that could not be read: {e}");
+                    None
+                }
+            })
+            .collect())
+    }
+}
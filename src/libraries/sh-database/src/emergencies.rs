@@ -0,0 +1,116 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use sh_common::emergency::ShEmergency;
+
+use crate::ShDatabase;
+
+// Number of emergencies sent when users ask for them. Ones still going are always sent
+const RECENT_EMERGENCIES: i64 = 100;
+
+fn serialize(emergency: &ShEmergency) -> Result<String, sqlx::Error> {
+    serde_json::to_string(emergency).map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+fn deserialize(stored: Vec<(String,)>) -> Vec<ShEmergency> {
+    stored
+        .into_iter()
+        .filter_map(
+            |(emergency,)| match serde_json::from_str::<ShEmergency>(&emergency) {
+                Ok(emergency) => Some(emergency),
+                Err(e) => {
+                    warn!("Skipping a stored emergency that could not be read: {e}");
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+impl ShDatabase {
+    /// Store an emergency. New emergencies have their id set, ones already stored are updated
+    ///
+    /// # Errors
+    /// - The emergency could not be stored
+    pub async fn save_emergency(&self, emergency: &mut ShEmergency) -> Result<(), sqlx::Error> {
+        let mut transaction = self.pool.begin().await?;
+
+        if emergency.id == 0 {
+            emergency.id = sqlx::query(
+                "INSERT INTO emergencies (kind, icao_hex, tail, flight, started, emergency) VALUES (?, ?, ?, ?, ?, '')",
+            )
+            .bind(emergency.kind.to_string())
+            .bind(&emergency.icao_hex)
+            .bind(&emergency.tail)
+            .bind(&emergency.flight)
+            .bind(emergency.started)
+            .execute(&mut *transaction)
+            .await?
+            .last_insert_rowid();
+        }
+
+        sqlx::query(
+            "UPDATE emergencies SET kind = ?, tail = ?, flight = ?, cleared = ?, emergency = ? WHERE id = ?",
+        )
+        .bind(emergency.kind.to_string())
+        .bind(&emergency.tail)
+        .bind(&emergency.flight)
+        .bind(emergency.cleared)
+        .bind(serialize(emergency)?)
+        .bind(emergency.id)
+        .execute(&mut *transaction)
+        .await?;
+
+        transaction.commit().await
+    }
+
+    /// End every emergency still marked as going. Used at start up, as nothing from the last
+    /// run is being followed any more
+    ///
+    /// # Errors
+    /// - The emergencies could not be read or updated
+    pub async fn close_open_emergencies(&self, now: f64) -> Result<usize, sqlx::Error> {
+        let stored: Vec<(String,)> =
+            sqlx::query_as("SELECT emergency FROM emergencies WHERE cleared IS NULL")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let mut emergencies = deserialize(stored);
+        for emergency in &mut emergencies {
+            emergency.add_event(
+                now,
+                "SDRE Hub restarted, emergency no longer followed".to_string(),
+            );
+            emergency.cleared = Some(now);
+            self.save_emergency(emergency).await?;
+        }
+
+        Ok(emergencies.len())
+    }
+
+    /// Emergencies still going, then the most recent ones that have ended. Newest first
+    ///
+    /// # Errors
+    /// - The emergencies could not be read
+    pub async fn recent_emergencies(&self) -> Result<Vec<ShEmergency>, sqlx::Error> {
+        let stored: Vec<(String,)> = sqlx::query_as(
+            "SELECT emergency FROM emergencies WHERE cleared IS NULL
+             UNION ALL
+             SELECT emergency FROM (SELECT emergency FROM emergencies WHERE cleared IS NOT NULL ORDER BY started DESC, id DESC LIMIT ?)",
+        )
+        .bind(RECENT_EMERGENCIES)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut emergencies = deserialize(stored);
+        emergencies.sort_by(|a, b| {
+            b.is_active()
+                .cmp(&a.is_active())
+                .then(b.started.total_cmp(&a.started))
+        });
+
+        Ok(emergencies)
+    }
+}
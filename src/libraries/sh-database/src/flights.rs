@@ -0,0 +1,719 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Storing messages and positions, and keeping the flights they belong to up to date. Aircraft
+// are known by their ICAO hex address where we have it. Messages that only carry a tail are
+// matched to an address we have seen alongside that tail, and failing that go by the tail.
+
+use std::collections::HashMap;
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::flight::{ShFlightSession, ShFlightSessionChange, ShFlightSessionDetail};
+use sqlx::FromRow;
+
+use crate::sessions::{group, PointKind, Session, SessionPoint, SESSION_GAP};
+use crate::ShDatabase;
+
+// Positions are kept at most this often per aircraft, unless something interesting changed
+const TRACK_INTERVAL: f64 = 15.0;
+// Data this far before the end of an aircraft's latest flight is late, and the flights for
+// the aircraft are worked out again with it included
+const LATE_THRESHOLD: f64 = 5.0 * 60.0;
+// Number of flights sent when users ask for the recent ones
+const RECENT_FLIGHTS: i64 = 200;
+
+#[derive(Debug, Default)]
+struct AircraftState {
+    /// The latest flight for the aircraft
+    session: Option<Session>,
+    /// Most position reports don't carry the callsign, so we remember the last one we saw
+    callsign: Option<String>,
+    last_track: Option<f64>,
+    track_on_ground: Option<bool>,
+    last_seen: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Tracker {
+    aircraft: HashMap<String, AircraftState>,
+    hex_for_tail: HashMap<String, String>,
+}
+
+impl Tracker {
+    fn aircraft_for_message(&mut self, message: &ShAcarsMessage) -> Option<String> {
+        let tail = normalize(message.tail.as_deref());
+
+        match (normalize(message.icao_hex.as_deref()), tail) {
+            (Some(icao_hex), tail) => {
+                if let Some(tail) = tail {
+                    self.hex_for_tail.insert(tail, icao_hex.clone());
+                }
+                Some(icao_hex)
+            }
+            (None, Some(tail)) => Some(self.hex_for_tail.get(&tail).cloned().unwrap_or(tail)),
+            (None, None) => None,
+        }
+    }
+}
+
+fn normalize(value: Option<&str>) -> Option<String> {
+    value
+        .map(|value| value.trim().trim_start_matches('.').to_uppercase())
+        .filter(|value| !value.is_empty())
+}
+
+#[derive(FromRow)]
+pub struct FlightRow {
+    id: i64,
+    aircraft: String,
+    icao_hex: Option<String>,
+    tail: Option<String>,
+    callsign: Option<String>,
+    callsign_from_adsb: bool,
+    start_time: f64,
+    end_time: f64,
+    takeoff: Option<f64>,
+    landing: Option<f64>,
+    on_ground: Option<bool>,
+    message_count: i64,
+    position_count: i64,
+}
+
+impl From<FlightRow> for Session {
+    fn from(row: FlightRow) -> Self {
+        let callsign_kind = row.callsign.as_ref().map(|_| {
+            if row.callsign_from_adsb {
+                PointKind::Position
+            } else {
+                PointKind::Message
+            }
+        });
+
+        Self {
+            id: Some(row.id),
+            aircraft: row.aircraft,
+            icao_hex: row.icao_hex,
+            tail: row.tail,
+            callsign: row.callsign,
+            callsign_kind,
+            start: row.start_time,
+            end: row.end_time,
+            takeoff: row.takeoff,
+            landing: row.landing,
+            on_ground: row.on_ground,
+            message_count: row.message_count,
+            position_count: row.position_count,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct PointRow {
+    id: i64,
+    timestamp: f64,
+    callsign: Option<String>,
+    icao_hex: Option<String>,
+    tail: Option<String>,
+    on_ground: Option<bool>,
+}
+
+impl PointRow {
+    fn into_point(self, kind: PointKind) -> SessionPoint {
+        SessionPoint {
+            kind,
+            id: self.id,
+            timestamp: self.timestamp,
+            callsign: self.callsign,
+            icao_hex: self.icao_hex,
+            tail: self.tail,
+            on_ground: self.on_ground,
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub struct PositionRow {
+    aircraft: String,
+    timestamp: f64,
+    callsign: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    altitude: Option<i32>,
+    squawk: Option<String>,
+    on_ground: Option<bool>,
+}
+
+impl From<PositionRow> for ShAdsbPosition {
+    fn from(row: PositionRow) -> Self {
+        Self {
+            icao_hex: row.aircraft,
+            timestamp: row.timestamp,
+            callsign: row.callsign,
+            latitude: row.latitude,
+            longitude: row.longitude,
+            altitude: row.altitude,
+            squawk: row.squawk,
+            on_ground: row.on_ground,
+        }
+    }
+}
+
+impl ShDatabase {
+    /// Store a message and add it to the flight it belongs to. `callsign` is the flight number
+    /// normalized so it matches ADS-B callsigns
+    ///
+    /// # Errors
+    /// - The message could not be stored
+    pub async fn add_message(
+        &self,
+        message: &ShAcarsMessage,
+        callsign: Option<String>,
+    ) -> Result<ShFlightSessionChange, sqlx::Error> {
+        let serialized =
+            serde_json::to_string(message).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        let mut tracker = self.tracker.lock().await;
+        let aircraft = tracker.aircraft_for_message(message);
+        let icao_hex = normalize(message.icao_hex.as_deref());
+        let tail = normalize(message.tail.as_deref());
+
+        let id = sqlx::query(
+            "INSERT INTO messages (aircraft, icao_hex, tail, timestamp, callsign, message) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&aircraft)
+        .bind(&icao_hex)
+        .bind(&tail)
+        .bind(message.timestamp)
+        .bind(&callsign)
+        .bind(serialized)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        // Nothing to tie it to a flight, but it is still worth keeping
+        let Some(aircraft) = aircraft else {
+            return Ok(ShFlightSessionChange::default());
+        };
+
+        let point = SessionPoint {
+            kind: PointKind::Message,
+            id,
+            timestamp: message.timestamp,
+            callsign,
+            icao_hex,
+            tail,
+            on_ground: None,
+        };
+
+        let change = self.add_point(&mut tracker, aircraft, point).await;
+        drop(tracker);
+
+        change
+    }
+
+    /// Add a position to the track of the flight it belongs to. Positions come in far faster
+    /// than we need them, so most are only used to keep the callsign up to date
+    ///
+    /// # Errors
+    /// - The position could not be stored
+    pub async fn add_position(
+        &self,
+        position: &ShAdsbPosition,
+        callsign: Option<String>,
+    ) -> Result<ShFlightSessionChange, sqlx::Error> {
+        let aircraft = position.icao_hex.to_uppercase();
+        let mut tracker = self.tracker.lock().await;
+        let state = tracker.aircraft.entry(aircraft.clone()).or_default();
+        state.last_seen = state.last_seen.max(position.timestamp);
+
+        let callsign_changed = callsign.is_some() && callsign != state.callsign;
+        if callsign.is_some() {
+            state.callsign = callsign;
+        }
+
+        let ground_changed =
+            position.on_ground.is_some() && position.on_ground != state.track_on_ground;
+        let track_due = position.latitude.is_some()
+            && position.longitude.is_some()
+            && state.last_track.map_or(true, |last| {
+                (position.timestamp - last).abs() >= TRACK_INTERVAL
+            });
+
+        if !(callsign_changed || ground_changed || track_due) {
+            return Ok(ShFlightSessionChange::default());
+        }
+
+        state.last_track = Some(position.timestamp);
+        if ground_changed {
+            state.track_on_ground = position.on_ground;
+        }
+
+        let callsign = state.callsign.clone();
+
+        let id = sqlx::query(
+            "INSERT INTO positions (aircraft, timestamp, callsign, latitude, longitude, altitude, squawk, on_ground) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&aircraft)
+        .bind(position.timestamp)
+        .bind(&callsign)
+        .bind(position.latitude)
+        .bind(position.longitude)
+        .bind(position.altitude)
+        .bind(&position.squawk)
+        .bind(position.on_ground)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        let point = SessionPoint {
+            kind: PointKind::Position,
+            id,
+            timestamp: position.timestamp,
+            callsign,
+            icao_hex: Some(aircraft.clone()),
+            tail: None,
+            on_ground: position.on_ground,
+        };
+
+        let change = self.add_point(&mut tracker, aircraft, point).await;
+        drop(tracker);
+
+        change
+    }
+
+    async fn add_point(
+        &self,
+        tracker: &mut Tracker,
+        aircraft: String,
+        point: SessionPoint,
+    ) -> Result<ShFlightSessionChange, sqlx::Error> {
+        let known = tracker
+            .aircraft
+            .get(&aircraft)
+            .and_then(|state| state.session.clone());
+        let session = match known {
+            Some(session) => Some(session),
+            // first time we have heard from it since starting up
+            None => self.latest_session(&aircraft).await?,
+        };
+
+        let state = tracker.aircraft.entry(aircraft.clone()).or_default();
+        state.last_seen = state.last_seen.max(point.timestamp);
+
+        let mut change = ShFlightSessionChange::default();
+
+        match session {
+            Some(session) if point.timestamp < session.end - LATE_THRESHOLD => {
+                debug!("Late data for {aircraft}, working out its flights again");
+                let (regrouped, latest) = self.regroup(&aircraft, point.timestamp).await?;
+                change = regrouped;
+                state.session = latest.or(Some(session));
+            }
+            Some(mut session) if session.continues(&point) => {
+                let before = (
+                    session.callsign.clone(),
+                    session.tail.clone(),
+                    session.takeoff,
+                    session.landing,
+                );
+
+                session.extend(&point);
+                self.update_flight(&session).await?;
+                self.set_flight(point.kind, point.id, session.id).await?;
+
+                if before
+                    != (
+                        session.callsign.clone(),
+                        session.tail.clone(),
+                        session.takeoff,
+                        session.landing,
+                    )
+                {
+                    change.updated.extend(session.to_shared());
+                }
+
+                state.session = Some(session);
+            }
+            _ => {
+                let mut session = Session::new(aircraft, &point);
+                session.id = Some(self.insert_flight(&session).await?);
+                self.set_flight(point.kind, point.id, session.id).await?;
+
+                change.updated.extend(session.to_shared());
+                state.session = Some(session);
+            }
+        }
+
+        Ok(change)
+    }
+
+    async fn latest_session(&self, aircraft: &str) -> Result<Option<Session>, sqlx::Error> {
+        let row: Option<FlightRow> = sqlx::query_as(
+            "SELECT * FROM flights WHERE aircraft = ? ORDER BY end_time DESC LIMIT 1",
+        )
+        .bind(aircraft)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(Session::from))
+    }
+
+    /// Work out the flights for an aircraft again from everything stored since `from`. Returns
+    /// the change along with the aircraft's latest flight
+    async fn regroup(
+        &self,
+        aircraft: &str,
+        from: f64,
+    ) -> Result<(ShFlightSessionChange, Option<Session>), sqlx::Error> {
+        let mut transaction = self.pool.begin().await?;
+
+        // any flight that ended close enough to `from` could gain or lose data
+        let old: Vec<FlightRow> = sqlx::query_as(
+            "SELECT * FROM flights WHERE aircraft = ? AND end_time >= ? ORDER BY start_time",
+        )
+        .bind(aircraft)
+        .bind(from - SESSION_GAP)
+        .fetch_all(&mut *transaction)
+        .await?;
+        let window_start = old.iter().map(|row| row.start_time).fold(from, f64::min);
+
+        let messages: Vec<PointRow> = sqlx::query_as(
+            "SELECT id, timestamp, callsign, icao_hex, tail, NULL AS on_ground FROM messages WHERE aircraft = ? AND timestamp >= ?",
+        )
+        .bind(aircraft)
+        .bind(window_start)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let positions: Vec<PointRow> = sqlx::query_as(
+            "SELECT id, timestamp, callsign, aircraft AS icao_hex, NULL AS tail, on_ground FROM positions WHERE aircraft = ? AND timestamp >= ?",
+        )
+        .bind(aircraft)
+        .bind(window_start)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        let mut points: Vec<SessionPoint> = messages
+            .into_iter()
+            .map(|row| row.into_point(PointKind::Message))
+            .chain(
+                positions
+                    .into_iter()
+                    .map(|row| row.into_point(PointKind::Position)),
+            )
+            .collect();
+        points.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+
+        let mut change = ShFlightSessionChange::default();
+
+        for row in &old {
+            sqlx::query("DELETE FROM flights WHERE id = ?")
+                .bind(row.id)
+                .execute(&mut *transaction)
+                .await?;
+            change.removed.push(row.id);
+        }
+
+        let mut latest = None;
+
+        for (mut session, session_points) in group(aircraft, points) {
+            let id = insert_flight(&mut transaction, &session).await?;
+            session.id = Some(id);
+
+            for point in &session_points {
+                set_flight(&mut transaction, point.kind, point.id, Some(id)).await?;
+            }
+
+            change.updated.extend(session.to_shared());
+            latest = Some(session);
+        }
+
+        transaction.commit().await?;
+
+        Ok((change, latest))
+    }
+
+    async fn insert_flight(&self, session: &Session) -> Result<i64, sqlx::Error> {
+        let mut connection = self.pool.acquire().await?;
+        insert_flight(&mut connection, session).await
+    }
+
+    async fn update_flight(&self, session: &Session) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE flights SET icao_hex = ?, tail = ?, callsign = ?, callsign_from_adsb = ?, start_time = ?, end_time = ?, takeoff = ?, landing = ?, on_ground = ?, message_count = ?, position_count = ? WHERE id = ?",
+        )
+        .bind(&session.icao_hex)
+        .bind(&session.tail)
+        .bind(&session.callsign)
+        .bind(session.callsign_kind == Some(PointKind::Position))
+        .bind(session.start)
+        .bind(session.end)
+        .bind(session.takeoff)
+        .bind(session.landing)
+        .bind(session.on_ground)
+        .bind(session.message_count)
+        .bind(session.position_count)
+        .bind(session.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_flight(
+        &self,
+        kind: PointKind,
+        id: i64,
+        flight_id: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        let mut connection = self.pool.acquire().await?;
+        set_flight(&mut connection, kind, id, flight_id).await
+    }
+
+    /// The most recently active flights, newest first
+    ///
+    /// # Errors
+    /// - The flights could not be read
+    pub async fn recent_flights(&self) -> Result<Vec<ShFlightSession>, sqlx::Error> {
+        let rows: Vec<FlightRow> =
+            sqlx::query_as("SELECT * FROM flights ORDER BY end_time DESC LIMIT ?")
+                .bind(RECENT_FLIGHTS)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| Session::from(row).to_shared())
+            .collect())
+    }
+
+    /// A flight with all of its messages and its track, if there is a flight with that id
+    ///
+    /// # Errors
+    /// - The flight could not be read
+    pub async fn flight(&self, id: i64) -> Result<Option<ShFlightSessionDetail>, sqlx::Error> {
+        let row: Option<FlightRow> = sqlx::query_as("SELECT * FROM flights WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let Some(session) = row.and_then(|row| Session::from(row).to_shared()) else {
+            return Ok(None);
+        };
+
+        let stored: Vec<(String,)> =
+            sqlx::query_as("SELECT message FROM messages WHERE flight_id = ? ORDER BY timestamp")
+                .bind(id)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let messages = stored
+            .into_iter()
+            .filter_map(
+                |(message,)| match serde_json::from_str::<ShAcarsMessage>(&message) {
+                    Ok(message) => Some(message),
+                    Err(e) => {
+                        warn!("Skipping a stored message that could not be read: {e}");
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        let track: Vec<PositionRow> = sqlx::query_as(
+            "SELECT aircraft, timestamp, callsign, latitude, longitude, altitude, squawk, on_ground FROM positions WHERE flight_id = ? ORDER BY timestamp",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(ShFlightSessionDetail {
+            session,
+            messages,
+            track: track.into_iter().map(ShAdsbPosition::from).collect(),
+        }))
+    }
+
+    /// Forget what we know about aircraft we have not heard from in a while. Their flights are
+    /// read back from the database if they turn up again
+    pub async fn forget_idle_aircraft(&self, now: f64) {
+        self.tracker
+            .lock()
+            .await
+            .aircraft
+            .retain(|_, state| now - state.last_seen < SESSION_GAP);
+    }
+}
+
+async fn insert_flight(
+    connection: &mut sqlx::SqliteConnection,
+    session: &Session,
+) -> Result<i64, sqlx::Error> {
+    let id = sqlx::query(
+        "INSERT INTO flights (aircraft, icao_hex, tail, callsign, callsign_from_adsb, start_time, end_time, takeoff, landing, on_ground, message_count, position_count) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&session.aircraft)
+    .bind(&session.icao_hex)
+    .bind(&session.tail)
+    .bind(&session.callsign)
+    .bind(session.callsign_kind == Some(PointKind::Position))
+    .bind(session.start)
+    .bind(session.end)
+    .bind(session.takeoff)
+    .bind(session.landing)
+    .bind(session.on_ground)
+    .bind(session.message_count)
+    .bind(session.position_count)
+    .execute(connection)
+    .await?
+    .last_insert_rowid();
+
+    Ok(id)
+}
+
+async fn set_flight(
+    connection: &mut sqlx::SqliteConnection,
+    kind: PointKind,
+    id: i64,
+    flight_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    let query = match kind {
+        PointKind::Message => "UPDATE messages SET flight_id = ? WHERE id = ?",
+        PointKind::Position => "UPDATE positions SET flight_id = ? WHERE id = ?",
+    };
+
+    sqlx::query(query)
+        .bind(flight_id)
+        .bind(id)
+        .execute(connection)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sh_common::acars_message::ShMessageType;
+
+    use super::*;
+
+    const HEX: &str = "A1B2C3";
+
+    async fn position(database: &ShDatabase, timestamp: f64, callsign: Option<&str>) {
+        let mut position = ShAdsbPosition::new(HEX.to_string(), timestamp);
+        position.latitude = Some(51.47);
+        position.longitude = Some(-0.45);
+        database
+            .add_position(&position, callsign.map(ToString::to_string))
+            .await
+            .unwrap();
+    }
+
+    async fn message(database: &ShDatabase, timestamp: f64) -> ShFlightSessionChange {
+        let mut message = ShAcarsMessage::new(timestamp, ShMessageType::Acars, String::new());
+        message.icao_hex = Some(HEX.to_string());
+        database.add_message(&message, None).await.unwrap()
+    }
+
+    /// Start, end, callsign and number of messages of each flight, oldest first
+    async fn flights(database: &ShDatabase) -> Vec<(f64, f64, Option<String>, i64)> {
+        let mut flights: Vec<_> = database
+            .recent_flights()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|flight| {
+                (
+                    flight.start,
+                    flight.end,
+                    flight.callsign,
+                    flight.message_count,
+                )
+            })
+            .collect();
+        flights.reverse();
+        flights
+    }
+
+    #[tokio::test]
+    async fn silence_ends_a_flight() {
+        let database = ShDatabase::in_memory().await;
+        position(&database, 0.0, Some("UAL1")).await;
+        position(&database, 600.0, None).await;
+        position(&database, 601.0 + SESSION_GAP, None).await;
+
+        assert_eq!(
+            flights(&database).await,
+            [
+                (0.0, 600.0, Some("UAL1".to_string()), 0),
+                // positions without a callsign keep the last one heard
+                (
+                    601.0 + SESSION_GAP,
+                    601.0 + SESSION_GAP,
+                    Some("UAL1".to_string()),
+                    0
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_new_callsign_is_a_new_flight() {
+        let database = ShDatabase::in_memory().await;
+        position(&database, 0.0, Some("UAL1")).await;
+        position(&database, 60.0, None).await;
+        position(&database, 120.0, Some("UAL2")).await;
+        position(&database, 180.0, None).await;
+
+        assert_eq!(
+            flights(&database).await,
+            [
+                (0.0, 60.0, Some("UAL1".to_string()), 0),
+                (120.0, 180.0, Some("UAL2".to_string()), 0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn late_data_goes_in_the_flight_it_belongs_to() {
+        const LATER: f64 = 600.0 + SESSION_GAP * 2.0;
+        let database = ShDatabase::in_memory().await;
+        position(&database, 0.0, Some("UAL1")).await;
+        position(&database, 600.0, None).await;
+        position(&database, LATER, None).await;
+        position(&database, LATER + 600.0, None).await;
+
+        // heard long after the first flight ended, but sent during it
+        let change = message(&database, 300.0).await;
+        assert!(!change.removed.is_empty());
+
+        assert_eq!(
+            flights(&database).await,
+            [
+                (0.0, 600.0, Some("UAL1".to_string()), 1),
+                (LATER, LATER + 600.0, Some("UAL1".to_string()), 0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn late_data_can_join_flights() {
+        let database = ShDatabase::in_memory().await;
+        position(&database, 0.0, Some("UAL1")).await;
+        let later = SESSION_GAP + 600.0;
+        position(&database, later, None).await;
+        position(&database, later + 600.0, None).await;
+        assert_eq!(flights(&database).await.len(), 2);
+
+        // fills the silence that split them
+        message(&database, SESSION_GAP / 2.0).await;
+
+        assert_eq!(
+            flights(&database).await,
+            [(0.0, later + 600.0, Some("UAL1".to_string()), 1)]
+        );
+    }
+}
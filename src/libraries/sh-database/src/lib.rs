@@ -0,0 +1,103 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Everything we keep past a restart lives in here. The hub writes messages and positions as
+// they come in and the API reads them back out.
+
+#![deny(
+    clippy::pedantic,
+//    clippy::cargo,
+    clippy::nursery,
+    clippy::style,
+    clippy::correctness,
+    clippy::all
+)]
+
+#[macro_use]
+extern crate log;
+
+pub mod acarshub;
+mod aircraft;
+mod alerts;
+mod emergencies;
+mod flights;
+mod maintenance;
+mod messages;
+mod notifications;
+mod sessions;
+mod stats;
+
+use std::str::FromStr;
+
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions};
+use tokio::sync::Mutex;
+
+use crate::flights::Tracker;
+
+pub use crate::messages::ExportCursor;
+pub use crate::stats::{stats_bucket, StatsDimension, StatsRollup};
+
+pub struct ShDatabase {
+    pool: SqlitePool,
+    tracker: Mutex<Tracker>,
+}
+
+impl ShDatabase {
+    /// Open the database at `url`, creating it if it doesn't exist, and bring the schema up
+    /// to date
+    ///
+    /// # Errors
+    /// - The url is not a valid sqlite url
+    /// - The database could not be opened or created
+    /// - A migration failed
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let database = Self::open(url).await?;
+        database.migrate().await?;
+        info!("Database ready at {url}");
+
+        Ok(database)
+    }
+
+    /// Open the database at `url`, creating it if it doesn't exist, without touching the
+    /// schema. Only `migrate` is safe to use until the schema is up to date
+    ///
+    /// # Errors
+    /// - The url is not a valid sqlite url
+    /// - The database could not be opened or created
+    pub async fn open(url: &str) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal);
+
+        let pool = SqlitePoolOptions::new().connect_with(options).await?;
+
+        Ok(Self {
+            pool,
+            tracker: Mutex::new(Tracker::default()),
+        })
+    }
+
+    /// A database that only lives as long as it is used, for tests. Kept to one connection, as
+    /// each connection to `:memory:` gets a database of its own
+    #[cfg(test)]
+    pub(crate) async fn in_memory() -> Self {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        let database = Self {
+            pool,
+            tracker: Mutex::new(Tracker::default()),
+        };
+        database.migrate().await.unwrap();
+
+        database
+    }
+}
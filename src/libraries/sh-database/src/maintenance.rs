@@ -0,0 +1,76 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Looking after the database file itself, for the `db` commands
+
+use std::collections::HashSet;
+
+use sqlx::migrate::Migrate;
+
+use crate::ShDatabase;
+
+impl ShDatabase {
+    /// Bring the schema up to date. Returns the migrations that were applied, as
+    /// `version description`. `connect` already does this, so it is for databases from `open`
+    ///
+    /// # Errors
+    /// - A migration failed, or the ones already applied could not be read
+    pub async fn migrate(&self) -> Result<Vec<String>, sqlx::Error> {
+        let migrator = sqlx::migrate!();
+
+        let mut connection = self.pool.acquire().await?;
+        connection.ensure_migrations_table().await?;
+        let applied: HashSet<i64> = connection
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|migration| migration.version)
+            .collect();
+        drop(connection);
+
+        migrator.run(&self.pool).await?;
+
+        Ok(migrator
+            .iter()
+            .filter(|migration| !applied.contains(&migration.version))
+            .map(|migration| format!("{} {}", migration.version, migration.description))
+            .collect())
+    }
+
+    /// The newest migration the schema has had, if it has had any
+    ///
+    /// # Errors
+    /// - The migrations could not be read
+    pub async fn schema_version(&self) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Rebuild the database file without the space left by deleted rows. Returns the size of
+    /// the file before and after, in bytes. Nothing else can write while it runs
+    ///
+    /// # Errors
+    /// - The database could not be vacuumed
+    pub async fn vacuum(&self) -> Result<(i64, i64), sqlx::Error> {
+        let before = self.size().await?;
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        // otherwise the rebuilt pages sit in the write-ahead log
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        let after = self.size().await?;
+
+        Ok((before, after))
+    }
+
+    async fn size(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::api::{ShExportedMessage, ShMessageAircraft, ShMessageQuery, ShStoredMessage};
+use sqlx::{FromRow, QueryBuilder, Sqlite};
+
+use crate::ShDatabase;
+
+// Messages returned by a search when the query doesn't say
+const DEFAULT_SEARCH_LIMIT: i64 = 100;
+// Most messages a single search returns
+const MAX_SEARCH_LIMIT: i64 = 1000;
+
+pub type MessageRow = (i64, Option<i64>, String);
+
+/// Where an export got up to, as the timestamp and id of the last message
+pub type ExportCursor = (f64, i64);
+
+#[derive(FromRow)]
+struct ExportRow {
+    id: i64,
+    flight_id: Option<i64>,
+    timestamp: f64,
+    message: String,
+    aircraft: Option<String>,
+    icao_hex: Option<String>,
+    tail: Option<String>,
+    callsign: Option<String>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+}
+
+impl ExportRow {
+    fn into_exported(self, with_aircraft: bool) -> Option<ShExportedMessage> {
+        let message = match serde_json::from_str::<ShAcarsMessage>(&self.message) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!(
+                    "Skipping stored message {} that could not be read: {e}",
+                    self.id
+                );
+                return None;
+            }
+        };
+        let aircraft = match (with_aircraft, self.aircraft, self.start_time, self.end_time) {
+            (true, Some(aircraft), Some(flight_start), Some(flight_end)) => {
+                Some(ShMessageAircraft {
+                    aircraft,
+                    icao_hex: self.icao_hex,
+                    tail: self.tail,
+                    callsign: self.callsign,
+                    flight_start,
+                    flight_end,
+                })
+            }
+            _ => None,
+        };
+
+        Some(ShExportedMessage {
+            id: self.id,
+            flight_id: self.flight_id,
+            message,
+            aircraft,
+        })
+    }
+}
+
+/// Turn stored rows back in to messages, skipping any that can't be read
+pub fn stored_messages(rows: Vec<MessageRow>) -> Vec<ShStoredMessage> {
+    rows.into_iter()
+        .filter_map(|(id, flight_id, message)| {
+            match serde_json::from_str::<ShAcarsMessage>(&message) {
+                Ok(message) => Some(ShStoredMessage {
+                    id,
+                    flight_id,
+                    message,
+                }),
+                Err(e) => {
+                    warn!("Skipping stored message {id} that could not be read: {e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn normalize(value: Option<&str>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_uppercase())
+        .filter(|value| !value.is_empty())
+}
+
+/// Add what `query` matches on to a query that already has a `WHERE`. `limit` and `offset`
+/// are left to the caller
+fn push_filters(builder: &mut QueryBuilder<Sqlite>, query: &ShMessageQuery) {
+    if let Some(aircraft) = normalize(query.aircraft.as_deref()) {
+        builder
+            .push(" AND (aircraft = ")
+            .push_bind(aircraft.clone())
+            .push(" OR icao_hex = ")
+            .push_bind(aircraft.clone())
+            .push(" OR tail = ")
+            .push_bind(aircraft)
+            .push(")");
+    }
+    if let Some(flight) = normalize(query.flight.as_deref()) {
+        builder.push(" AND callsign = ").push_bind(flight);
+    }
+    if let Some(text) = query.text.as_deref().filter(|text| !text.is_empty()) {
+        builder
+            .push(" AND instr(upper(json_extract(message, '$.text')), upper(")
+            .push_bind(text.to_string())
+            .push(")) > 0");
+    }
+    if let Some(label) = normalize(query.label.as_deref()) {
+        builder
+            .push(" AND upper(json_extract(message, '$.label')) = ")
+            .push_bind(label);
+    }
+    if let Some(message_type) = normalize(query.message_type.as_deref()) {
+        builder
+            .push(" AND upper(json_extract(message, '$.message_type')) = ")
+            .push_bind(message_type);
+    }
+    if let Some(source) = query.source.as_deref().filter(|source| !source.is_empty()) {
+        builder
+            .push(" AND json_extract(message, '$.source') = ")
+            .push_bind(source.to_string());
+    }
+    if let Some(since) = query.since {
+        builder.push(" AND timestamp >= ").push_bind(since);
+    }
+    if let Some(until) = query.until {
+        builder.push(" AND timestamp < ").push_bind(until);
+    }
+}
+
+impl ShDatabase {
+    /// Stored messages matching `query`, newest first. `flight` has to be normalized the same
+    /// way as when the messages were added
+    ///
+    /// # Errors
+    /// - The messages could not be read
+    pub async fn search_messages(
+        &self,
+        query: &ShMessageQuery,
+    ) -> Result<Vec<ShStoredMessage>, sqlx::Error> {
+        let mut builder: QueryBuilder<Sqlite> =
+            QueryBuilder::new("SELECT id, flight_id, message FROM messages WHERE 1 = 1");
+
+        push_filters(&mut builder, query);
+
+        builder
+            .push(" ORDER BY timestamp DESC, id DESC LIMIT ")
+            .push_bind(
+                query
+                    .limit
+                    .unwrap_or(DEFAULT_SEARCH_LIMIT)
+                    .clamp(1, MAX_SEARCH_LIMIT),
+            )
+            .push(" OFFSET ")
+            .push_bind(query.offset.unwrap_or_default().max(0));
+
+        let rows: Vec<MessageRow> = builder.build_query_as().fetch_all(&self.pool).await?;
+        Ok(stored_messages(rows))
+    }
+
+    /// Up to `limit` messages matching `query` that come after `after`, oldest first, and where
+    /// to carry on from. The query's own `limit` and `offset` are ignored, so an export can go
+    /// through every match a page at a time. The cursor is `None` once there are no more
+    ///
+    /// # Errors
+    /// - The messages could not be read
+    pub async fn export_messages(
+        &self,
+        query: &ShMessageQuery,
+        after: Option<ExportCursor>,
+        limit: i64,
+        with_aircraft: bool,
+    ) -> Result<(Vec<ShExportedMessage>, Option<ExportCursor>), sqlx::Error> {
+        // filtered on its own first, as the flight has columns with the same names
+        let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "SELECT m.id, m.flight_id, m.timestamp, m.message, f.aircraft, f.icao_hex, f.tail, \
+             f.callsign, f.start_time, f.end_time FROM (SELECT id, flight_id, timestamp, message \
+             FROM messages WHERE 1 = 1",
+        );
+        push_filters(&mut builder, query);
+        if let Some((timestamp, id)) = after {
+            builder
+                .push(" AND (timestamp > ")
+                .push_bind(timestamp)
+                .push(" OR (timestamp = ")
+                .push_bind(timestamp)
+                .push(" AND id > ")
+                .push_bind(id)
+                .push("))");
+        }
+        builder
+            .push(" ORDER BY timestamp, id LIMIT ")
+            .push_bind(limit)
+            .push(") m LEFT JOIN flights f ON f.id = m.flight_id ORDER BY m.timestamp, m.id");
+
+        let rows: Vec<ExportRow> = builder.build_query_as().fetch_all(&self.pool).await?;
+        // messages that can't be read are skipped, so the page can be short of them
+        let next = rows.last().map(|row| (row.timestamp, row.id));
+        let messages = rows
+            .into_iter()
+            .filter_map(|row| row.into_exported(with_aircraft))
+            .collect();
+
+        Ok((messages, next))
+    }
+
+    /// The message stored with `id`, if there is one
+    ///
+    /// # Errors
+    /// - The message could not be read
+    pub async fn message(&self, id: i64) -> Result<Option<ShStoredMessage>, sqlx::Error> {
+        let row: Option<MessageRow> =
+            sqlx::query_as("SELECT id, flight_id, message FROM messages WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(stored_messages(row.into_iter().collect()).pop())
+    }
+
+    /// The exact same message is already stored, so adding it again would double it up
+    ///
+    /// # Errors
+    /// - The messages could not be read
+    pub async fn has_message(&self, message: &ShAcarsMessage) -> Result<bool, sqlx::Error> {
+        let serialized =
+            serde_json::to_string(message).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+
+        sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM messages WHERE timestamp = ? AND message = ?)",
+        )
+        .bind(message.timestamp)
+        .bind(serialized)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
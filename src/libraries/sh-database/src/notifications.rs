@@ -0,0 +1,133 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use sh_common::notification::ShNotificationDelivery;
+use sqlx::FromRow;
+
+use crate::ShDatabase;
+
+// Number of deliveries sent when users ask for the log
+const RECENT_DELIVERIES: i64 = 200;
+
+#[derive(FromRow)]
+struct DeliveryRow {
+    id: i64,
+    alert_id: Option<i64>,
+    rule: String,
+    target: String,
+    timestamp: f64,
+    attempts: i64,
+    delivered: bool,
+    error: Option<String>,
+}
+
+impl From<DeliveryRow> for ShNotificationDelivery {
+    fn from(row: DeliveryRow) -> Self {
+        Self {
+            id: row.id,
+            alert_id: row.alert_id.unwrap_or_default(),
+            rule: row.rule,
+            target: row.target,
+            timestamp: row.timestamp,
+            attempts: u32::try_from(row.attempts).unwrap_or_default(),
+            delivered: row.delivered,
+            error: row.error,
+        }
+    }
+}
+
+impl ShDatabase {
+    /// Store the outcome of a delivery, setting its id
+    ///
+    /// # Errors
+    /// - The delivery could not be stored
+    pub async fn add_delivery(
+        &self,
+        delivery: &mut ShNotificationDelivery,
+    ) -> Result<(), sqlx::Error> {
+        // alerts that failed to store have no id to point at
+        let alert_id = Some(delivery.alert_id).filter(|id| *id > 0);
+
+        delivery.id = sqlx::query(
+            "INSERT INTO notification_deliveries (alert_id, rule, target, timestamp, attempts, delivered, error) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(alert_id)
+        .bind(&delivery.rule)
+        .bind(&delivery.target)
+        .bind(delivery.timestamp)
+        .bind(delivery.attempts)
+        .bind(delivery.delivered)
+        .bind(&delivery.error)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(())
+    }
+
+    /// The most recent deliveries, newest first
+    ///
+    /// # Errors
+    /// - The deliveries could not be read
+    pub async fn recent_deliveries(&self) -> Result<Vec<ShNotificationDelivery>, sqlx::Error> {
+        let rows: Vec<DeliveryRow> = sqlx::query_as(
+            "SELECT * FROM notification_deliveries ORDER BY timestamp DESC, id DESC LIMIT ?",
+        )
+        .bind(RECENT_DELIVERIES)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(ShNotificationDelivery::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sh_common::alert::ShAlert;
+
+    fn delivery(alert_id: i64, timestamp: f64, delivered: bool) -> ShNotificationDelivery {
+        ShNotificationDelivery {
+            id: 0,
+            alert_id,
+            rule: "emergency".to_string(),
+            target: "hook".to_string(),
+            timestamp,
+            attempts: if delivered { 1 } else { 5 },
+            delivered,
+            error: (!delivered).then(|| "HTTP status server error (500)".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_logged_newest_first() {
+        let database = ShDatabase::in_memory().await;
+        let mut alert = ShAlert {
+            id: 0,
+            rule: "emergency".to_string(),
+            timestamp: 100.0,
+            icao_hex: None,
+            tail: None,
+            flight: None,
+            description: "squawk 7700".to_string(),
+            message: None,
+            position: None,
+        };
+        database.add_alert(&mut alert).await.unwrap();
+
+        let mut sent = delivery(alert.id, 100.0, true);
+        // an alert that couldn't be stored has no id, and is logged without one
+        let mut failed = delivery(0, 200.0, false);
+        database.add_delivery(&mut sent).await.unwrap();
+        database.add_delivery(&mut failed).await.unwrap();
+        assert!(sent.id > 0);
+        assert!(failed.id > sent.id);
+
+        assert_eq!(
+            database.recent_deliveries().await.unwrap(),
+            vec![failed, sent]
+        );
+    }
+}
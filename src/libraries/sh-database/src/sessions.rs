@@ -0,0 +1,268 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// How messages and positions are split in to flights. Everything heard from an aircraft is one
+// flight until one of these happens:
+//   - we hear nothing from it for SESSION_GAP
+//   - it takes off again after landing
+//   - the callsign changes. ACARS flight numbers and ADS-B callsigns don't always normalize to
+//     the same thing, so only a change from the same kind of source counts. An ADS-B callsign
+//     takes over from an ACARS one as it is the more reliable of the two
+// The same rules are used for data as it arrives and when late data means the flights for an
+// aircraft have to be worked out again from what is stored.
+
+use sh_common::flight::ShFlightSession;
+
+// Silence longer than this ends a flight. Long enough to cover an aircraft out of ADS-B range
+// that only talks over ACARS every so often in cruise
+pub const SESSION_GAP: f64 = 90.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointKind {
+    Message,
+    Position,
+}
+
+/// A single message or position, reduced to what decides which flight it belongs to
+#[derive(Debug, Clone)]
+pub struct SessionPoint {
+    pub kind: PointKind,
+    /// The row the point is stored in
+    pub id: i64,
+    pub timestamp: f64,
+    pub callsign: Option<String>,
+    pub icao_hex: Option<String>,
+    pub tail: Option<String>,
+    pub on_ground: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// `None` until the session has been stored
+    pub id: Option<i64>,
+    pub aircraft: String,
+    pub icao_hex: Option<String>,
+    pub tail: Option<String>,
+    pub callsign: Option<String>,
+    pub callsign_kind: Option<PointKind>,
+    pub start: f64,
+    pub end: f64,
+    pub takeoff: Option<f64>,
+    pub landing: Option<f64>,
+    pub on_ground: Option<bool>,
+    pub message_count: i64,
+    pub position_count: i64,
+}
+
+impl Session {
+    pub fn new(aircraft: String, point: &SessionPoint) -> Self {
+        let mut session = Self {
+            id: None,
+            aircraft,
+            icao_hex: None,
+            tail: None,
+            callsign: None,
+            callsign_kind: None,
+            start: point.timestamp,
+            end: point.timestamp,
+            takeoff: None,
+            landing: None,
+            on_ground: None,
+            message_count: 0,
+            position_count: 0,
+        };
+
+        session.extend(point);
+        session
+    }
+
+    /// Does `point` belong to this flight, or is it the start of the next one
+    pub fn continues(&self, point: &SessionPoint) -> bool {
+        if point.timestamp - self.end > SESSION_GAP {
+            return false;
+        }
+
+        // a second takeoff is a second flight
+        if self.landing.is_some() && self.on_ground == Some(true) && point.on_ground == Some(false)
+        {
+            return false;
+        }
+
+        match (&self.callsign, &point.callsign) {
+            (Some(current), Some(callsign)) => {
+                current == callsign || self.callsign_kind != Some(point.kind)
+            }
+            _ => true,
+        }
+    }
+
+    pub fn extend(&mut self, point: &SessionPoint) {
+        self.start = self.start.min(point.timestamp);
+        self.end = self.end.max(point.timestamp);
+
+        if point.icao_hex.is_some() {
+            self.icao_hex.clone_from(&point.icao_hex);
+        }
+
+        if point.tail.is_some() {
+            self.tail.clone_from(&point.tail);
+        }
+
+        if let Some(callsign) = &point.callsign {
+            let replace = self.callsign_kind.map_or(true, |kind| {
+                kind == point.kind || point.kind == PointKind::Position
+            });
+
+            if replace {
+                self.callsign = Some(callsign.clone());
+                self.callsign_kind = Some(point.kind);
+            }
+        }
+
+        if let Some(on_ground) = point.on_ground {
+            match (self.on_ground, on_ground) {
+                (Some(true), false) if self.takeoff.is_none() => {
+                    self.takeoff = Some(point.timestamp);
+                }
+                (Some(false), true) => self.landing = Some(point.timestamp),
+                _ => {}
+            }
+            self.on_ground = Some(on_ground);
+        }
+
+        match point.kind {
+            PointKind::Message => self.message_count += 1,
+            PointKind::Position => self.position_count += 1,
+        }
+    }
+
+    /// The session as sent to users. Only stored sessions have an id to send
+    pub fn to_shared(&self) -> Option<ShFlightSession> {
+        Some(ShFlightSession {
+            id: self.id?,
+            aircraft: self.aircraft.clone(),
+            icao_hex: self.icao_hex.clone(),
+            tail: self.tail.clone(),
+            callsign: self.callsign.clone(),
+            start: self.start,
+            end: self.end,
+            takeoff: self.takeoff,
+            landing: self.landing,
+            message_count: self.message_count,
+            position_count: self.position_count,
+        })
+    }
+}
+
+/// Split the points for one aircraft in to flights. `points` must be oldest first. Returns each
+/// session along with the points in it
+pub fn group(aircraft: &str, points: Vec<SessionPoint>) -> Vec<(Session, Vec<SessionPoint>)> {
+    let mut sessions: Vec<(Session, Vec<SessionPoint>)> = Vec::new();
+
+    for point in points {
+        match sessions.last_mut() {
+            Some((session, session_points)) if session.continues(&point) => {
+                session.extend(&point);
+                session_points.push(point);
+            }
+            _ => sessions.push((Session::new(aircraft.to_string(), &point), vec![point])),
+        }
+    }
+
+    sessions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(kind: PointKind, timestamp: f64, callsign: Option<&str>) -> SessionPoint {
+        SessionPoint {
+            kind,
+            id: 0,
+            timestamp,
+            callsign: callsign.map(ToString::to_string),
+            icao_hex: Some("A1B2C3".to_string()),
+            tail: None,
+            on_ground: None,
+        }
+    }
+
+    fn grouped(points: Vec<SessionPoint>) -> Vec<(f64, f64, Option<String>)> {
+        group("A1B2C3", points)
+            .into_iter()
+            .map(|(session, _)| (session.start, session.end, session.callsign))
+            .collect()
+    }
+
+    #[test]
+    fn silence_ends_a_flight() {
+        const NEXT: f64 = 601.0 + SESSION_GAP * 2.0;
+        let points = vec![
+            point(PointKind::Position, 0.0, Some("UAL1")),
+            point(PointKind::Message, 600.0, None),
+            // right on the gap still counts
+            point(PointKind::Position, 600.0 + SESSION_GAP, None),
+            point(PointKind::Position, NEXT, None),
+        ];
+
+        assert_eq!(
+            grouped(points),
+            [
+                (0.0, 600.0 + SESSION_GAP, Some("UAL1".to_string())),
+                (NEXT, NEXT, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_new_callsign_is_a_new_flight() {
+        let points = vec![
+            point(PointKind::Position, 0.0, Some("UAL1")),
+            point(PointKind::Position, 60.0, Some("UAL1")),
+            point(PointKind::Position, 120.0, Some("UAL2")),
+        ];
+
+        assert_eq!(
+            grouped(points),
+            [
+                (0.0, 60.0, Some("UAL1".to_string())),
+                (120.0, 120.0, Some("UAL2".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn acars_and_adsb_callsigns_dont_split_a_flight() {
+        // the flight number from ACARS didn't normalize to the ADS-B callsign
+        let points = vec![
+            point(PointKind::Message, 0.0, Some("XY123")),
+            point(PointKind::Position, 60.0, Some("UAL123")),
+            point(PointKind::Message, 120.0, Some("XY123")),
+        ];
+
+        // and the ADS-B one takes over
+        assert_eq!(grouped(points), [(0.0, 120.0, Some("UAL123".to_string()))]);
+    }
+
+    #[test]
+    fn taking_off_again_is_a_new_flight() {
+        let mut points = vec![
+            point(PointKind::Position, 0.0, None),
+            point(PointKind::Position, 60.0, None),
+            point(PointKind::Position, 3_600.0, None),
+            point(PointKind::Position, 4_200.0, None),
+        ];
+        for (point, on_ground) in points.iter_mut().zip([true, false, true, false]) {
+            point.on_ground = Some(on_ground);
+        }
+
+        let sessions = group("A1B2C3", points);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].0.takeoff, Some(60.0));
+        assert_eq!(sessions[0].0.landing, Some(3_600.0));
+        assert!((sessions[1].0.start - 4_200.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,221 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+
+use sh_common::stats::{ShStats, ShStatsCount, ShStatsPoint, ShStatsRange};
+
+use crate::ShDatabase;
+
+// Length of a rollup bucket, in seconds
+const STATS_BUCKET: i64 = 5 * 60;
+// Rollups are kept a little past the longest range the statistics page shows
+const STATS_RETENTION: i64 = ShStatsRange::Month.seconds() + 24 * 60 * 60;
+// Most entries sent for each breakdown
+const MAX_BREAKDOWN: i64 = 25;
+
+/// What a message count is broken down by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatsDimension {
+    /// The source the message came in from
+    Source,
+    /// ACARS, VDLM2, HFDL and so on
+    MessageType,
+    Frequency,
+    Label,
+    GroundStation,
+}
+
+impl StatsDimension {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Source => "source",
+            Self::MessageType => "type",
+            Self::Frequency => "frequency",
+            Self::Label => "label",
+            Self::GroundStation => "ground_station",
+        }
+    }
+}
+
+/// The bucket `timestamp` falls in
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn stats_bucket(timestamp: f64) -> i64 {
+    let seconds = timestamp.floor() as i64;
+    seconds - seconds.rem_euclid(STATS_BUCKET)
+}
+
+/// `StatsRollup` is counts gathered since they were last written out
+#[derive(Debug, Default)]
+pub struct StatsRollup {
+    messages: HashMap<(i64, StatsDimension, String), i64>,
+    aircraft: HashMap<i64, i64>,
+}
+
+impl StatsRollup {
+    pub fn count(&mut self, bucket: i64, dimension: StatsDimension, key: String) {
+        *self.messages.entry((bucket, dimension, key)).or_default() += 1;
+    }
+
+    /// Note how many aircraft are being tracked. Each bucket keeps the most it has seen
+    pub fn sample_aircraft(&mut self, bucket: i64, aircraft: i64) {
+        let most = self.aircraft.entry(bucket).or_default();
+        *most = (*most).max(aircraft);
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty() && self.aircraft.is_empty()
+    }
+}
+
+async fn breakdown(
+    database: &ShDatabase,
+    dimension: StatsDimension,
+    since: i64,
+) -> Result<Vec<ShStatsCount>, sqlx::Error> {
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT key, SUM(count) AS total FROM stats_messages WHERE dimension = ? AND bucket >= ? GROUP BY key ORDER BY total DESC, key LIMIT ?",
+    )
+    .bind(dimension.as_str())
+    .bind(since)
+    .bind(MAX_BREAKDOWN)
+    .fetch_all(&database.pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(key, count)| ShStatsCount { key, count })
+        .collect())
+}
+
+impl ShDatabase {
+    /// Add counts to the rollups
+    ///
+    /// # Errors
+    /// - The counts could not be written. None of them are
+    pub async fn add_stats(&self, rollup: StatsRollup) -> Result<(), sqlx::Error> {
+        if rollup.is_empty() {
+            return Ok(());
+        }
+
+        let mut transaction = self.pool.begin().await?;
+
+        for ((bucket, dimension, key), count) in rollup.messages {
+            sqlx::query(
+                "INSERT INTO stats_messages (bucket, dimension, key, count) VALUES (?, ?, ?, ?)
+                 ON CONFLICT (bucket, dimension, key) DO UPDATE SET count = count + excluded.count",
+            )
+            .bind(bucket)
+            .bind(dimension.as_str())
+            .bind(key)
+            .bind(count)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        for (bucket, aircraft) in rollup.aircraft {
+            sqlx::query(
+                "INSERT INTO stats_aircraft (bucket, aircraft) VALUES (?, ?)
+                 ON CONFLICT (bucket) DO UPDATE SET aircraft = MAX(aircraft, excluded.aircraft)",
+            )
+            .bind(bucket)
+            .bind(aircraft)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await
+    }
+
+    /// Throw away rollups too old for the statistics page to show
+    ///
+    /// # Errors
+    /// - The rollups could not be deleted
+    pub async fn prune_stats(&self, now: f64) -> Result<(), sqlx::Error> {
+        let cutoff = stats_bucket(now) - STATS_RETENTION;
+
+        sqlx::query("DELETE FROM stats_messages WHERE bucket < ?")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM stats_aircraft WHERE bucket < ?")
+            .bind(cutoff)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Everything on the statistics page for the `range` up to `now`
+    ///
+    /// # Errors
+    /// - The rollups could not be read
+    pub async fn stats(&self, range: ShStatsRange, now: f64) -> Result<ShStats, sqlx::Error> {
+        let period = range.period();
+        let current = stats_bucket(now);
+        let last_period = current - current.rem_euclid(period);
+        // whole periods only, so the breakdowns agree with the series
+        let since = last_period - range.seconds() + period;
+
+        // every message has a type, so those add up to the total
+        let messages: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT bucket - (bucket % ?1) AS period, SUM(count) FROM stats_messages WHERE dimension = ?2 AND bucket >= ?3 GROUP BY period",
+        )
+        .bind(period)
+        .bind(StatsDimension::MessageType.as_str())
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let aircraft: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT bucket - (bucket % ?1) AS period, MAX(aircraft) FROM stats_aircraft WHERE bucket >= ?2 GROUP BY period",
+        )
+        .bind(period)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let messages: HashMap<i64, i64> = messages.into_iter().collect();
+        let aircraft: HashMap<i64, i64> = aircraft.into_iter().collect();
+        let series: Vec<ShStatsPoint> = (0..range.seconds() / period)
+            .map(|index| {
+                let timestamp = since + index * period;
+                ShStatsPoint {
+                    timestamp,
+                    messages: messages.get(&timestamp).copied().unwrap_or_default(),
+                    aircraft: aircraft.get(&timestamp).copied().unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        let hours: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT (bucket / 3600) % 24 AS hour, SUM(count) FROM stats_messages WHERE dimension = ? AND bucket >= ? GROUP BY hour",
+        )
+        .bind(StatsDimension::MessageType.as_str())
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        let hours: HashMap<i64, i64> = hours.into_iter().collect();
+
+        Ok(ShStats {
+            range,
+            total_messages: series.iter().map(|point| point.messages).sum(),
+            series,
+            sources: breakdown(self, StatsDimension::Source, since).await?,
+            message_types: breakdown(self, StatsDimension::MessageType, since).await?,
+            frequencies: breakdown(self, StatsDimension::Frequency, since).await?,
+            labels: breakdown(self, StatsDimension::Label, since).await?,
+            ground_stations: breakdown(self, StatsDimension::GroundStation, since).await?,
+            hours: (0..24)
+                .map(|hour| ShStatsCount {
+                    key: format!("{hour:02}"),
+                    count: hours.get(&hour).copied().unwrap_or_default(),
+                })
+                .collect(),
+        })
+    }
+}
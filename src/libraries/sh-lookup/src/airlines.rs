@@ -0,0 +1,236 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use sh_config::lookup::ShAirlineOverride;
+
+use crate::error::LookupError;
+use crate::flight_number::{DesignatorType, FlightNumber};
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+pub struct Airline {
+    pub iata: Option<String>,
+    pub icao: String,
+    pub name: String,
+    #[serde(default)]
+    pub callsign: String,
+}
+
+impl From<&ShAirlineOverride> for Airline {
+    fn from(value: &ShAirlineOverride) -> Self {
+        Self {
+            iata: value.iata.clone(),
+            icao: value.icao.clone(),
+            name: value.name.clone(),
+            callsign: value.callsign.clone(),
+        }
+    }
+}
+
+/// `AirlineTable` maps airline designators to airlines, and is used to convert flight numbers
+/// between the IATA form used in ACARS and the ICAO form used in ADS-B
+#[derive(Debug, Default)]
+pub struct AirlineTable {
+    by_icao: HashMap<String, Airline>,
+    iata_to_icao: HashMap<String, String>,
+}
+
+impl AirlineTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the airline table from a CSV file with the header `iata,icao,name,callsign`
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not valid CSV
+    pub fn from_file(path: &str) -> Result<Self, LookupError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|source| LookupError::Csv {
+                path: path.to_string(),
+                source,
+            })?;
+
+        let mut table = Self::new();
+
+        for record in reader.deserialize::<Airline>() {
+            match record {
+                Ok(airline) => table.insert(airline),
+                Err(e) => warn!("Skipping invalid airline entry in {path}: {e}"),
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Add an airline to the table, replacing any existing airline with the same ICAO designator.
+    /// IATA designators get reused between airlines, so the most recently inserted airline wins
+    pub fn insert(&mut self, airline: Airline) {
+        let Airline {
+            iata,
+            icao,
+            name,
+            callsign,
+        } = airline;
+        let icao = icao.trim().to_uppercase();
+
+        if icao.len() != 3 {
+            warn!("Skipping airline {name} with invalid ICAO code {icao}");
+            return;
+        }
+
+        let iata = iata
+            .map(|iata| iata.trim().to_uppercase())
+            .filter(|iata| iata.len() == 2);
+
+        // drop the IATA mapping of the entry we are replacing, if it still points at it
+        if let Some(old_iata) = self.by_icao.get(&icao).and_then(|old| old.iata.clone()) {
+            if self.iata_to_icao.get(&old_iata) == Some(&icao) {
+                self.iata_to_icao.remove(&old_iata);
+            }
+        }
+
+        if let Some(iata) = &iata {
+            self.iata_to_icao.insert(iata.clone(), icao.clone());
+        }
+
+        self.by_icao.insert(
+            icao.clone(),
+            Airline {
+                iata,
+                icao,
+                name: name.trim().to_string(),
+                callsign: callsign.trim().to_uppercase(),
+            },
+        );
+    }
+
+    pub fn apply_overrides(&mut self, overrides: &[ShAirlineOverride]) {
+        for airline in overrides {
+            self.insert(airline.into());
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.by_icao.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.by_icao.is_empty()
+    }
+
+    #[must_use]
+    pub fn get_by_icao(&self, icao: &str) -> Option<&Airline> {
+        self.by_icao.get(&icao.to_uppercase())
+    }
+
+    #[must_use]
+    pub fn get_by_iata(&self, iata: &str) -> Option<&Airline> {
+        self.iata_to_icao
+            .get(&iata.to_uppercase())
+            .and_then(|icao| self.by_icao.get(icao))
+    }
+
+    /// Find the airline operating a flight, regardless of which form the flight number is in
+    #[must_use]
+    pub fn airline_for_flight(&self, flight: &FlightNumber) -> Option<&Airline> {
+        match flight.designator_type {
+            DesignatorType::Icao => self.get_by_icao(&flight.designator),
+            DesignatorType::Iata => self.get_by_iata(&flight.designator),
+        }
+    }
+
+    /// Convert a flight number to the ICAO form used in ADS-B callsigns, e.g. `UA0123` -> `UAL123`
+    /// Flights already in ICAO form are returned without leading zeros even if the airline is unknown
+    #[must_use]
+    pub fn to_icao_flight(&self, flight: &str) -> Option<String> {
+        let flight = FlightNumber::parse(flight)?;
+
+        match flight.designator_type {
+            DesignatorType::Icao => Some(flight.to_string()),
+            DesignatorType::Iata => self
+                .get_by_iata(&flight.designator)
+                .map(|airline| flight.with_designator(&airline.icao)),
+        }
+    }
+
+    /// Convert a flight number to the IATA form used in ACARS, e.g. `UAL123` -> `UA123`
+    #[must_use]
+    pub fn to_iata_flight(&self, flight: &str) -> Option<String> {
+        let flight = FlightNumber::parse(flight)?;
+
+        match flight.designator_type {
+            DesignatorType::Iata => Some(flight.to_string()),
+            DesignatorType::Icao => self
+                .get_by_icao(&flight.designator)
+                .and_then(|airline| airline.iata.as_ref())
+                .map(|iata| flight.with_designator(iata)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> AirlineTable {
+        let mut table = AirlineTable::new();
+        for (iata, icao, name) in [
+            (Some("UA"), "UAL", "United Airlines"),
+            (Some("BA"), "BAW", "British Airways"),
+            (None, "XAX", "No IATA Air"),
+        ] {
+            table.insert(Airline {
+                iata: iata.map(ToString::to_string),
+                icao: icao.to_string(),
+                name: name.to_string(),
+                callsign: String::new(),
+            });
+        }
+        table
+    }
+
+    #[test]
+    fn to_icao_flight() {
+        let table = table();
+        let flights = [
+            ("UA0123", Some("UAL123")),
+            ("UAL123", Some("UAL123")),
+            ("ba 12a", Some("BAW12A")),
+            // ICAO form is kept even if the airline is unknown
+            ("ZZZ0042", Some("ZZZ42")),
+            // but an unknown IATA designator can't be converted
+            ("ZZ42", None),
+            ("N12345", None),
+            ("G-ABCD", None),
+        ];
+
+        for (flight, icao) in flights {
+            assert_eq!(table.to_icao_flight(flight).as_deref(), icao, "{flight}");
+        }
+    }
+
+    #[test]
+    fn to_iata_flight() {
+        let table = table();
+        let flights = [
+            ("UAL123", Some("UA123")),
+            ("UA0123", Some("UA123")),
+            ("BAW12A", Some("BA12A")),
+            ("XAX1", None),
+            ("N123AB", None),
+        ];
+
+        for (flight, iata) in flights {
+            assert_eq!(table.to_iata_flight(flight).as_deref(), iata, "{flight}");
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum LookupError {
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    Csv {
+        path: String,
+        source: csv::Error,
+    },
+    Json {
+        path: String,
+        source: serde_json::Error,
+    },
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "Error reading {path}: {source}"),
+            Self::Csv { path, source } => write!(f, "Error parsing {path}: {source}"),
+            Self::Json { path, source } => write!(f, "Error parsing {path}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for LookupError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Csv { source, .. } => Some(source),
+            Self::Json { source, .. } => Some(source),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+/// The kind of airline designator a flight number was written with
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DesignatorType {
+    /// Two character designator, as seen in ACARS, e.g. `UA`
+    Iata,
+    /// Three letter designator, as seen in ADS-B callsigns, e.g. `UAL`
+    Icao,
+}
+
+/// `FlightNumber` is a flight number broken up in to the airline designator and the number
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FlightNumber {
+    pub designator: String,
+    pub designator_type: DesignatorType,
+    /// The numeric part with any leading zeros removed, plus an optional letter suffix
+    pub number: String,
+}
+
+impl FlightNumber {
+    /// Parse a flight number such as `UA1234`, `UAL1234`, `UA 0123` or `BA123A`
+    /// Returns None if the input does not look like an airline flight number, which includes US
+    /// registrations like `N12345` that would otherwise pass for a flight of `N1`
+    #[must_use]
+    pub fn parse(input: &str) -> Option<Self> {
+        let cleaned: String = input
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .collect::<String>()
+            .to_uppercase();
+
+        if !cleaned.is_ascii() || cleaned.len() < 3 || is_us_registration(&cleaned) {
+            return None;
+        }
+
+        let (designator, designator_type, rest) =
+            if cleaned[..3].chars().all(|c| c.is_ascii_alphabetic())
+                && cleaned[3..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                (&cleaned[..3], DesignatorType::Icao, &cleaned[3..])
+            } else if cleaned[..2].chars().all(|c| c.is_ascii_alphanumeric())
+                && cleaned[..2].chars().any(|c| c.is_ascii_alphabetic())
+                && cleaned[2..].starts_with(|c: char| c.is_ascii_digit())
+            {
+                (&cleaned[..2], DesignatorType::Iata, &cleaned[2..])
+            } else {
+                return None;
+            };
+
+        let digits_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (digits, suffix) = rest.split_at(digits_end);
+
+        // flight numbers are at most four digits, with at most two letters tacked on the end
+        if digits.len() > 4 || suffix.len() > 2 || !suffix.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return None;
+        }
+
+        let digits = digits.trim_start_matches('0');
+        let digits = if digits.is_empty() { "0" } else { digits };
+
+        Some(Self {
+            designator: designator.to_string(),
+            designator_type,
+            number: format!("{digits}{suffix}"),
+        })
+    }
+
+    #[must_use]
+    pub fn with_designator(&self, designator: &str) -> String {
+        format!("{designator}{}", self.number)
+    }
+}
+
+/// `N`, a number that doesn't start with 0 and up to two letters, five characters at most. The
+/// few airlines with a designator like `N8` lose out, as private aircraft use their registration
+/// as their callsign far more often
+fn is_us_registration(cleaned: &str) -> bool {
+    let Some(rest) = cleaned.strip_prefix('N') else {
+        return false;
+    };
+    let digits_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (digits, letters) = rest.split_at(digits_end);
+
+    !digits.is_empty()
+        && !digits.starts_with('0')
+        && rest.len() <= 5
+        && letters.len() <= 2
+        && letters.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+impl std::fmt::Display for FlightNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.designator, self.number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(input: &str) -> Option<(String, DesignatorType, String)> {
+        FlightNumber::parse(input)
+            .map(|flight| (flight.designator, flight.designator_type, flight.number))
+    }
+
+    #[test]
+    fn parses_flight_numbers() {
+        let flights = [
+            ("UA1234", "UA", DesignatorType::Iata, "1234"),
+            ("ua 0123", "UA", DesignatorType::Iata, "123"),
+            ("B6-0007", "B6", DesignatorType::Iata, "7"),
+            ("UAL1234", "UAL", DesignatorType::Icao, "1234"),
+            ("BAW 012", "BAW", DesignatorType::Icao, "12"),
+            ("BA123A", "BA", DesignatorType::Iata, "123A"),
+            ("EZY12TB", "EZY", DesignatorType::Icao, "12TB"),
+            ("KL000", "KL", DesignatorType::Iata, "0"),
+            // an airline, not a registration
+            ("NKS123", "NKS", DesignatorType::Icao, "123"),
+        ];
+
+        for (input, designator, designator_type, number) in flights {
+            assert_eq!(
+                parsed(input),
+                Some((designator.to_string(), designator_type, number.to_string())),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_flight_number() {
+        let inputs = [
+            // registrations
+            "N12345", "N123AB", "N1234Z", "N8", "G-ABCD", "D-AIBC", "GABCD",
+            // too long, or not a number
+            "UA12345", "BA123ABC", "UAL", "12345", "AB", "", "UÄ123",
+        ];
+
+        for input in inputs {
+            assert_eq!(parsed(input), None, "{input}");
+        }
+    }
+
+    #[test]
+    fn displays_without_leading_zeros() {
+        let flight = FlightNumber::parse("UA 0123").unwrap();
+        assert_eq!(flight.to_string(), "UA123");
+        assert_eq!(flight.with_designator("UAL"), "UAL123");
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::ground_station::{ShGroundStation, ShGroundStationNetwork};
+use sh_config::lookup::ShLookupConfig;
+
+use crate::error::LookupError;
+
+// The HFDL network is small and fixed, so we ship it. VDL2 ground stations are learned from XID frames
+const BUNDLED_GROUND_STATIONS: &str = include_str!("../data/ground_stations.json");
+const DEFAULT_GROUND_STATIONS_FILE: &str = "ground_stations.json";
+const LEARNED_GROUND_STATIONS_FILE: &str = "ground_stations_learned.json";
+
+// Two frequencies closer than this, in kHz, are considered the same
+const FREQUENCY_TOLERANCE: f64 = 0.5;
+
+type StationKey = (ShGroundStationNetwork, String);
+
+/// `GroundStationRegistry` keeps track of every VDL2 and HFDL ground station we know about
+///
+/// It starts with the bundled dataset and any file the admin has supplied, and fills in the
+/// blanks from HFDL squitters and VDL2 XID frames as they are heard.
+#[derive(Debug, Default)]
+pub struct GroundStationRegistry {
+    stations: HashMap<StationKey, ShGroundStation>,
+    learned: HashSet<StationKey>,
+    learned_file: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl GroundStationRegistry {
+    #[must_use]
+    pub fn new(config: &ShLookupConfig, data_path: &str) -> Self {
+        let mut registry = Self {
+            learned_file: Some(Path::new(data_path).join(LEARNED_GROUND_STATIONS_FILE)),
+            ..Default::default()
+        };
+
+        match serde_json::from_str::<Vec<ShGroundStation>>(BUNDLED_GROUND_STATIONS) {
+            Ok(stations) => {
+                for station in stations {
+                    registry.insert(station);
+                }
+            }
+            Err(e) => error!("Error parsing bundled ground stations: {e}"),
+        }
+
+        let user_file = config.ground_stations_file.as_ref().map_or_else(
+            || {
+                let path = Path::new(data_path).join(DEFAULT_GROUND_STATIONS_FILE);
+                path.exists().then_some(path)
+            },
+            |path| Some(PathBuf::from(path)),
+        );
+
+        if let Some(path) = user_file {
+            match Self::read_file(&path) {
+                Ok(stations) => {
+                    for station in stations {
+                        registry.insert(station);
+                    }
+                }
+                Err(e) => error!("{e}"),
+            }
+        }
+
+        if let Some(path) = registry.learned_file.clone() {
+            if path.exists() {
+                match Self::read_file(&path) {
+                    Ok(stations) => {
+                        for station in stations {
+                            registry.learn(station);
+                        }
+                    }
+                    Err(e) => error!("{e}"),
+                }
+            }
+        }
+
+        // loading the learned file is not a change that needs to be written back out
+        registry.dirty = false;
+
+        info!("Loaded {} ground stations", registry.stations.len());
+
+        registry
+    }
+
+    fn read_file(path: &Path) -> Result<Vec<ShGroundStation>, LookupError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| LookupError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+
+        serde_json::from_str(&contents).map_err(|source| LookupError::Json {
+            path: path.display().to_string(),
+            source,
+        })
+    }
+
+    /// Add a station, replacing any fields the new entry has values for
+    fn insert(&mut self, station: ShGroundStation) {
+        let key = (station.network, station.id.to_uppercase());
+
+        if let Some(existing) = self.stations.get_mut(&key) {
+            if station.name.is_some() {
+                existing.name = station.name;
+            }
+            if station.latitude.is_some() && station.longitude.is_some() {
+                existing.latitude = station.latitude;
+                existing.longitude = station.longitude;
+            }
+            if !station.frequencies.is_empty() {
+                existing.frequencies = station.frequencies;
+            }
+            if !station.airports.is_empty() {
+                existing.airports = station.airports;
+            }
+        } else {
+            let mut station = station;
+            station.id.clone_from(&key.1);
+            self.stations.insert(key, station);
+        }
+    }
+
+    /// Merge what we heard about a station in to the registry. Learned data only fills in the
+    /// blanks and never replaces a name or location from the dataset.
+    /// Returns the updated station if anything other than the last seen time changed
+    pub fn learn(&mut self, report: ShGroundStation) -> Option<ShGroundStation> {
+        let key = (report.network, report.id.to_uppercase());
+        let mut changed = false;
+
+        let station = self.stations.entry(key.clone()).or_insert_with(|| {
+            changed = true;
+            ShGroundStation::new(key.0, key.1.clone())
+        });
+
+        if station.name.is_none() && report.name.is_some() {
+            station.name = report.name;
+            changed = true;
+        }
+
+        if station.latitude.is_none() && report.latitude.is_some() && report.longitude.is_some() {
+            station.latitude = report.latitude;
+            station.longitude = report.longitude;
+            changed = true;
+        }
+
+        for frequency in report.frequencies {
+            if !station
+                .frequencies
+                .iter()
+                .any(|known| (known - frequency).abs() < FREQUENCY_TOLERANCE)
+            {
+                station.frequencies.push(frequency);
+                station.frequencies.sort_by(f64::total_cmp);
+                changed = true;
+            }
+        }
+
+        for airport in report.airports {
+            let airport = airport.to_uppercase();
+            if !station.airports.contains(&airport) {
+                station.airports.push(airport);
+                station.airports.sort();
+                changed = true;
+            }
+        }
+
+        if report.last_seen > station.last_seen {
+            station.last_seen = report.last_seen;
+        }
+
+        if changed {
+            self.learned.insert(key);
+            self.dirty = true;
+            Some(station.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Count a message against the ground station it went through, and fill in the station name.
+    /// A station we have never heard of before is added to the registry and returned
+    pub fn observe_message(&mut self, message: &mut ShAcarsMessage) -> Option<ShGroundStation> {
+        let network = ShGroundStationNetwork::from_message_type(message.message_type)?;
+        let id = message.ground_station_id.clone()?;
+
+        let mut report = ShGroundStation::new(network, id.clone());
+        report.last_seen = Some(message.timestamp);
+        if let Some(frequency) = message.frequency {
+            report.frequencies.push(frequency * 1000.0);
+        }
+
+        let updated = self.learn(report);
+
+        if let Some(station) = self.stations.get_mut(&(network, id.to_uppercase())) {
+            station.message_count += 1;
+            message.ground_station_name.clone_from(&station.name);
+        }
+
+        updated
+    }
+
+    #[must_use]
+    pub fn get(&self, network: ShGroundStationNetwork, id: &str) -> Option<&ShGroundStation> {
+        self.stations.get(&(network, id.to_uppercase()))
+    }
+
+    #[must_use]
+    pub fn stations(&self) -> Vec<ShGroundStation> {
+        let mut stations: Vec<ShGroundStation> = self.stations.values().cloned().collect();
+        stations.sort_by(|a, b| (a.network as u8, &a.id).cmp(&(b.network as u8, &b.id)));
+        stations
+    }
+
+    /// Write the learned stations out to the data path so they survive a restart
+    /// # Errors
+    /// Returns an error if the file cannot be written
+    pub fn save_learned(&mut self) -> Result<(), LookupError> {
+        let Some(path) = &self.learned_file else {
+            return Ok(());
+        };
+
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let stations: Vec<ShGroundStation> = self
+            .learned
+            .iter()
+            .filter_map(|key| self.stations.get(key))
+            .map(|station| ShGroundStation {
+                message_count: 0,
+                ..station.clone()
+            })
+            .collect();
+
+        let contents =
+            serde_json::to_string_pretty(&stations).map_err(|source| LookupError::Json {
+                path: path.display().to_string(),
+                source,
+            })?;
+
+        std::fs::write(path, contents).map_err(|source| LookupError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+
+        debug!("Saved {} learned ground stations", stations.len());
+        self.dirty = false;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Lookup tables for turning the identifiers we see in messages in to something a human can read.
+// Flight numbers show up as IATA (UA1234) in ACARS and as ICAO (UAL1234) in ADS-B, so the airline
+// table is also what lets us match the two up.
+
+#![deny(
+    clippy::pedantic,
+//    clippy::cargo,
+    clippy::nursery,
+    clippy::style,
+    clippy::correctness,
+    clippy::all
+)]
+
+#[macro_use]
+extern crate log;
+
+pub mod airlines;
+pub mod error;
+pub mod flight_number;
+pub mod ground_stations;
+pub mod routes;
+
+use airlines::AirlineTable;
+use routes::RouteTable;
+use sh_common::lookup::ShFlightInfo;
+use sh_config::lookup::ShLookupConfig;
+
+const DEFAULT_AIRLINES_FILE: &str = "airlines.csv";
+const DEFAULT_ROUTES_FILE: &str = "routes.csv";
+
+#[derive(Debug, Default)]
+pub struct ShLookup {
+    airlines: AirlineTable,
+    routes: RouteTable,
+}
+
+impl ShLookup {
+    /// Build the lookup tables from the files and overrides in the config.
+    /// Missing or broken files are logged and result in empty tables, so the hub can still run.
+    #[must_use]
+    pub fn new(config: &ShLookupConfig, data_path: &str) -> Self {
+        let airlines_file = Self::resolve_path(
+            config.airlines_file.as_ref(),
+            data_path,
+            DEFAULT_AIRLINES_FILE,
+        );
+        let routes_file =
+            Self::resolve_path(config.routes_file.as_ref(), data_path, DEFAULT_ROUTES_FILE);
+
+        let mut airlines = airlines_file.map_or_else(AirlineTable::new, |path| {
+            AirlineTable::from_file(&path).unwrap_or_else(|e| {
+                error!("{e}");
+                AirlineTable::new()
+            })
+        });
+        airlines.apply_overrides(&config.airline_overrides);
+
+        let mut routes = routes_file.map_or_else(RouteTable::new, |path| {
+            RouteTable::from_file(&path, &airlines).unwrap_or_else(|e| {
+                error!("{e}");
+                RouteTable::new()
+            })
+        });
+        routes.apply_overrides(&config.route_overrides, &airlines);
+
+        info!(
+            "Loaded {} airlines and {} routes",
+            airlines.len(),
+            routes.len()
+        );
+
+        Self { airlines, routes }
+    }
+
+    /// Returns the file to load. A file set in the config is always returned so a typo gets
+    /// reported, while the default file in the data path is optional.
+    fn resolve_path(configured: Option<&String>, data_path: &str, default: &str) -> Option<String> {
+        if let Some(path) = configured {
+            return Some(path.clone());
+        }
+
+        let path = std::path::Path::new(data_path).join(default);
+
+        if path.exists() {
+            path.to_str().map(ToString::to_string)
+        } else {
+            debug!("No lookup file found at {}", path.display());
+            None
+        }
+    }
+
+    #[must_use]
+    pub const fn airlines(&self) -> &AirlineTable {
+        &self.airlines
+    }
+
+    #[must_use]
+    pub const fn routes(&self) -> &RouteTable {
+        &self.routes
+    }
+
+    /// Normalize a flight number to the ICAO form so ACARS and ADS-B flights can be compared
+    #[must_use]
+    pub fn normalize_flight(&self, flight: &str) -> Option<String> {
+        self.airlines.to_icao_flight(flight)
+    }
+
+    #[must_use]
+    pub fn flight_info(&self, flight: &str) -> ShFlightInfo {
+        let mut info = ShFlightInfo::new(flight);
+
+        let Some(flight_number) = flight_number::FlightNumber::parse(flight) else {
+            return info;
+        };
+
+        if let Some(airline) = self.airlines.airline_for_flight(&flight_number) {
+            info.flight_icao = Some(flight_number.with_designator(&airline.icao));
+            info.flight_iata = airline
+                .iata
+                .as_ref()
+                .map(|iata| flight_number.with_designator(iata));
+            info.airline_name = Some(airline.name.clone());
+            info.airline_callsign = Some(airline.callsign.clone()).filter(|c| !c.is_empty());
+        }
+
+        if let Some(route) = self.routes.get(flight, &self.airlines) {
+            info.origin = Some(route.origin.clone());
+            info.destination = Some(route.destination.clone());
+        }
+
+        info
+    }
+}
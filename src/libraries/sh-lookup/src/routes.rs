@@ -0,0 +1,112 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use sh_config::lookup::ShRouteOverride;
+
+use crate::airlines::AirlineTable;
+use crate::error::LookupError;
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+pub struct Route {
+    pub flight: String,
+    pub origin: String,
+    pub destination: String,
+}
+
+impl From<&ShRouteOverride> for Route {
+    fn from(value: &ShRouteOverride) -> Self {
+        Self {
+            flight: value.flight.clone(),
+            origin: value.origin.clone(),
+            destination: value.destination.clone(),
+        }
+    }
+}
+
+/// `RouteTable` maps flight numbers to the airports they fly between. Flights are stored under
+/// their ICAO form where the airline is known, so a route can be found with either form
+#[derive(Debug, Default)]
+pub struct RouteTable {
+    routes: HashMap<String, Route>,
+}
+
+impl RouteTable {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the route table from a CSV file with the header `flight,origin,destination`
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not valid CSV
+    pub fn from_file(path: &str, airlines: &AirlineTable) -> Result<Self, LookupError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_path(path)
+            .map_err(|source| LookupError::Csv {
+                path: path.to_string(),
+                source,
+            })?;
+
+        let mut table = Self::new();
+
+        for record in reader.deserialize::<Route>() {
+            match record {
+                Ok(route) => table.insert(route, airlines),
+                Err(e) => warn!("Skipping invalid route entry in {path}: {e}"),
+            }
+        }
+
+        Ok(table)
+    }
+
+    fn key(flight: &str, airlines: &AirlineTable) -> String {
+        airlines
+            .to_icao_flight(flight)
+            .unwrap_or_else(|| flight.trim().to_uppercase())
+    }
+
+    pub fn insert(&mut self, route: Route, airlines: &AirlineTable) {
+        let Route {
+            flight,
+            origin,
+            destination,
+        } = route;
+        let key = Self::key(&flight, airlines);
+
+        self.routes.insert(
+            key.clone(),
+            Route {
+                flight: key,
+                origin: origin.trim().to_uppercase(),
+                destination: destination.trim().to_uppercase(),
+            },
+        );
+    }
+
+    pub fn apply_overrides(&mut self, overrides: &[ShRouteOverride], airlines: &AirlineTable) {
+        for route in overrides {
+            self.insert(route.into(), airlines);
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    #[must_use]
+    pub fn get(&self, flight: &str, airlines: &AirlineTable) -> Option<&Route> {
+        self.routes.get(&Self::key(flight, airlines))
+    }
+}
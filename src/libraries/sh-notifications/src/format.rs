@@ -0,0 +1,98 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// The human readable forms of an alert, for targets that aren't sent JSON
+
+use sh_common::alert::ShAlert;
+
+/// A unix timestamp as `YYYY-MM-DD HH:MM:SSZ`
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_possible_wrap
+)]
+fn format_utc(timestamp: f64) -> String {
+    let seconds = timestamp.max(0.0) as u64;
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+
+    // days since the epoch to a civil date, from Howard Hinnant's date algorithms
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+/// One line, for email subjects and chat titles
+pub fn title(alert: &ShAlert) -> String {
+    format!("SDR-E Hub alert: {} - {}", alert.rule, alert.aircraft())
+}
+
+/// Everything worth knowing about the alert, as plain text
+pub fn body(alert: &ShAlert) -> String {
+    let mut lines = vec![
+        format!("Rule: {}", alert.rule),
+        format!("Time: {}", format_utc(alert.timestamp)),
+    ];
+
+    if let Some(flight) = &alert.flight {
+        lines.push(format!("Flight: {flight}"));
+    }
+    if let Some(tail) = &alert.tail {
+        lines.push(format!("Tail: {tail}"));
+    }
+    if let Some(icao_hex) = &alert.icao_hex {
+        lines.push(format!("ICAO: {icao_hex}"));
+    }
+    lines.push(format!("Matched: {}", alert.description));
+
+    if let Some(position) = &alert.position {
+        if let (Some(latitude), Some(longitude)) = (position.latitude, position.longitude) {
+            lines.push(format!("Position: {latitude:.4}, {longitude:.4}"));
+        }
+        if let Some(altitude) = position.altitude {
+            lines.push(format!("Altitude: {altitude} ft"));
+        }
+    }
+
+    if let Some(text) = alert
+        .message
+        .as_ref()
+        .and_then(|message| message.text.as_ref())
+    {
+        lines.push(String::new());
+        lines.push(text.clone());
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_utc() {
+        assert_eq!(format_utc(0.0), "1970-01-01 00:00:00Z");
+        assert_eq!(format_utc(951_782_400.0), "2000-02-29 00:00:00Z");
+        assert_eq!(format_utc(1_700_000_000.5), "2023-11-14 22:13:20Z");
+    }
+}
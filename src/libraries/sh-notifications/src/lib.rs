@@ -0,0 +1,387 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Sends alerts to the notification targets their rules are routed to. Every delivery runs on
+// its own task, retrying with backoff, and reports how it went back to the hub so it can be
+// logged.
+
+#![deny(
+    clippy::pedantic,
+//    clippy::cargo,
+    clippy::nursery,
+    clippy::style,
+    clippy::correctness,
+    clippy::all
+)]
+
+#[macro_use]
+extern crate log;
+
+mod format;
+mod mqtt;
+mod smtp;
+#[cfg(test)]
+mod stand_in;
+mod webhook;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sh_common::alert::ShAlert;
+use sh_common::notification::ShNotificationDelivery;
+use sh_config::notifications::{ShNotificationTarget, ShNotificationsConfig};
+use tokio::sync::{mpsc, RwLock};
+
+// However many attempts are left, we never wait longer than this between them
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+// How long a single attempt gets before it counts as failed
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct Notifier {
+    config: RwLock<ShNotificationsConfig>,
+    http: reqwest::Client,
+    deliveries: mpsc::Sender<ShNotificationDelivery>,
+}
+
+impl Notifier {
+    /// The outcome of every delivery is sent to `deliveries` once it has succeeded or run out
+    /// of attempts
+    #[must_use]
+    pub fn new(
+        config: ShNotificationsConfig,
+        deliveries: mpsc::Sender<ShNotificationDelivery>,
+    ) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(ATTEMPT_TIMEOUT)
+            .user_agent(concat!("sdre-hub/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config: RwLock::new(config),
+            http,
+            deliveries,
+        }
+    }
+
+    /// Use new targets and retry settings. Deliveries already under way carry on as they were
+    pub async fn set_config(&self, config: ShNotificationsConfig) {
+        *self.config.write().await = config;
+    }
+
+    /// Send `alert` to each of the targets named in `targets`
+    pub async fn notify(&self, alert: &ShAlert, targets: &[String]) {
+        let config = self.config.read().await;
+
+        for name in targets {
+            let Some(target) = config.target(name).cloned() else {
+                warn!(
+                    "Alert rule \"{}\" sends to \"{name}\", but there is no notification target with that name",
+                    alert.rule
+                );
+                continue;
+            };
+
+            tokio::spawn(deliver(
+                self.http.clone(),
+                target,
+                alert.clone(),
+                config.max_attempts.max(1),
+                Duration::from_secs(config.retry_delay),
+                self.deliveries.clone(),
+            ));
+        }
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+async fn send(
+    http: &reqwest::Client,
+    target: &ShNotificationTarget,
+    alert: &ShAlert,
+) -> Result<(), String> {
+    match target {
+        ShNotificationTarget::Webhook(target) => webhook::send_json(http, target, alert).await,
+        ShNotificationTarget::Discord(target) => webhook::send_discord(http, target, alert).await,
+        ShNotificationTarget::Slack(target) => webhook::send_slack(http, target, alert).await,
+        ShNotificationTarget::Mqtt(target) => mqtt::send(target, alert).await,
+        ShNotificationTarget::Smtp(target) => smtp::send(target, alert).await,
+    }
+}
+
+async fn deliver(
+    http: reqwest::Client,
+    target: ShNotificationTarget,
+    alert: ShAlert,
+    max_attempts: u32,
+    retry_delay: Duration,
+    deliveries: mpsc::Sender<ShNotificationDelivery>,
+) {
+    let mut delivery = ShNotificationDelivery {
+        id: 0,
+        alert_id: alert.id,
+        rule: alert.rule.clone(),
+        target: target.name().to_string(),
+        timestamp: now(),
+        attempts: 0,
+        delivered: false,
+        error: None,
+    };
+    let mut delay = retry_delay;
+
+    loop {
+        delivery.attempts += 1;
+        delivery.timestamp = now();
+
+        let result = tokio::time::timeout(ATTEMPT_TIMEOUT, send(&http, &target, &alert))
+            .await
+            .unwrap_or_else(|_| Err("Timed out".to_string()));
+
+        match result {
+            Ok(()) => {
+                debug!("Sent alert {} to {}", alert.id, delivery.target);
+                delivery.delivered = true;
+                delivery.error = None;
+                break;
+            }
+            Err(e) if delivery.attempts >= max_attempts => {
+                error!(
+                    "Giving up sending alert {} to {} after {} attempts: {e}",
+                    alert.id, delivery.target, delivery.attempts
+                );
+                delivery.error = Some(e);
+                break;
+            }
+            Err(e) => {
+                warn!(
+                    "Error sending alert {} to {}, trying again in {}s: {e}",
+                    alert.id,
+                    delivery.target,
+                    delay.as_secs()
+                );
+                delivery.error = Some(e);
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+
+    if deliveries.send(delivery).await.is_err() {
+        error!("Could not log the delivery of alert {}", alert.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sh_common::acars_message::{ShAcarsMessage, ShMessageType};
+    use sh_config::notifications::{ShMqttTarget, ShSmtpSecurity, ShSmtpTarget, ShWebhookTarget};
+
+    // Short, so the retry tests don't hold things up
+    const TEST_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+    fn alert() -> ShAlert {
+        ShAlert {
+            id: 7,
+            rule: "emergency".to_string(),
+            timestamp: 1_700_000_000.0,
+            icao_hex: Some("A1B2C3".to_string()),
+            tail: Some("N123AB".to_string()),
+            flight: Some("UAL123".to_string()),
+            description: "squawk 7700".to_string(),
+            message: None,
+            position: None,
+        }
+    }
+
+    fn webhook(url: &str) -> ShWebhookTarget {
+        ShWebhookTarget {
+            name: "hook".to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    /// Deliver `alert` to `target`, and how it went
+    async fn delivered(
+        target: ShNotificationTarget,
+        alert: &ShAlert,
+        max_attempts: u32,
+    ) -> ShNotificationDelivery {
+        let (deliveries_tx, mut deliveries) = mpsc::channel(1);
+        deliver(
+            reqwest::Client::new(),
+            target,
+            alert.clone(),
+            max_attempts,
+            TEST_RETRY_DELAY,
+            deliveries_tx,
+        )
+        .await;
+        deliveries.recv().await.unwrap()
+    }
+
+    fn json(body: &str) -> serde_json::Value {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn webhook_gets_the_alert_as_json() {
+        let (url, mut bodies) = stand_in::http(vec![200]).await;
+
+        let delivery = delivered(ShNotificationTarget::Webhook(webhook(&url)), &alert(), 1).await;
+
+        assert!(delivery.delivered);
+        assert_eq!(delivery.attempts, 1);
+        let sent: ShAlert = serde_json::from_str(&bodies.recv().await.unwrap()).unwrap();
+        assert_eq!(sent, alert());
+    }
+
+    #[tokio::test]
+    async fn discord_gets_content() {
+        let (url, mut bodies) = stand_in::http(vec![204]).await;
+
+        let delivery = delivered(ShNotificationTarget::Discord(webhook(&url)), &alert(), 1).await;
+
+        assert!(delivery.delivered);
+        let content = json(&bodies.recv().await.unwrap())["content"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(content.starts_with("**SDR-E Hub alert: emergency - UAL123**\nRule: emergency"));
+        assert!(content.contains("Matched: squawk 7700"));
+    }
+
+    #[tokio::test]
+    async fn discord_content_is_cut_to_fit() {
+        let (url, mut bodies) = stand_in::http(vec![204]).await;
+        let mut message = ShAcarsMessage::new(1_700_000_000.0, ShMessageType::Acars, String::new());
+        message.text = Some("X".repeat(5_000));
+        let alert = ShAlert {
+            message: Some(Box::new(message)),
+            ..alert()
+        };
+
+        delivered(ShNotificationTarget::Discord(webhook(&url)), &alert, 1).await;
+
+        let body = json(&bodies.recv().await.unwrap());
+        let content = body["content"].as_str().unwrap();
+        assert_eq!(content.chars().count(), 2_000);
+        assert!(content.ends_with("XXX..."));
+    }
+
+    #[tokio::test]
+    async fn slack_gets_text() {
+        let (url, mut bodies) = stand_in::http(vec![200]).await;
+
+        let delivery = delivered(ShNotificationTarget::Slack(webhook(&url)), &alert(), 1).await;
+
+        assert!(delivery.delivered);
+        let body = json(&bodies.recv().await.unwrap());
+        let text = body["text"].as_str().unwrap();
+        assert!(text.starts_with("*SDR-E Hub alert: emergency - UAL123*\nRule: emergency"));
+        assert!(text.contains("Time: 2023-11-14 22:13:20Z"));
+    }
+
+    #[tokio::test]
+    async fn mqtt_publishes_the_alert() {
+        let (port, mut published) = stand_in::mqtt().await;
+        let target = ShMqttTarget {
+            name: "broker".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            topic: "sdre-hub/alerts".to_string(),
+            username: None,
+            password: None,
+            client_id: "sdre-hub".to_string(),
+        };
+
+        let delivery = delivered(ShNotificationTarget::Mqtt(target), &alert(), 1).await;
+
+        assert!(delivery.delivered, "{:?}", delivery.error);
+        let (topic, payload) = published.recv().await.unwrap();
+        assert_eq!(topic, "sdre-hub/alerts");
+        assert_eq!(
+            serde_json::from_slice::<ShAlert>(&payload).unwrap(),
+            alert()
+        );
+    }
+
+    #[tokio::test]
+    async fn smtp_sends_an_email() {
+        let (port, mut emails) = stand_in::smtp().await;
+        let target = ShSmtpTarget {
+            name: "email".to_string(),
+            host: "127.0.0.1".to_string(),
+            port,
+            security: ShSmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "hub@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+        };
+
+        let delivery = delivered(ShNotificationTarget::Smtp(target), &alert(), 1).await;
+
+        assert!(delivery.delivered, "{:?}", delivery.error);
+        let (recipients, data) = emails.recv().await.unwrap();
+        assert_eq!(recipients, vec!["<ops@example.com>".to_string()]);
+        assert!(data.contains("Subject: SDR-E Hub alert: emergency - UAL123"));
+        assert!(data.contains("Matched: squawk 7700"));
+    }
+
+    #[tokio::test]
+    async fn failed_attempts_are_retried() {
+        let (url, mut bodies) = stand_in::http(vec![500, 503, 200]).await;
+
+        let delivery = delivered(ShNotificationTarget::Webhook(webhook(&url)), &alert(), 5).await;
+
+        assert!(delivery.delivered);
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(delivery.error, None);
+        for _ in 0..3 {
+            assert!(bodies.recv().await.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_the_last_attempt() {
+        let (url, _bodies) = stand_in::http(vec![500]).await;
+
+        let delivery = delivered(ShNotificationTarget::Webhook(webhook(&url)), &alert(), 2).await;
+
+        assert!(!delivery.delivered);
+        assert_eq!(delivery.attempts, 2);
+        assert!(delivery.error.unwrap().contains("500"));
+    }
+
+    #[tokio::test]
+    async fn notify_logs_a_delivery_for_each_named_target() {
+        let (url, _bodies) = stand_in::http(vec![200]).await;
+        let config = ShNotificationsConfig {
+            retry_delay: 0,
+            targets: vec![ShNotificationTarget::Webhook(webhook(&url))],
+            ..ShNotificationsConfig::default()
+        };
+        let (deliveries_tx, mut deliveries) = mpsc::channel(4);
+        let notifier = Notifier::new(config, deliveries_tx);
+
+        notifier
+            .notify(&alert(), &["hook".to_string(), "missing".to_string()])
+            .await;
+
+        let delivery = deliveries.recv().await.unwrap();
+        assert_eq!(delivery.alert_id, 7);
+        assert_eq!(delivery.rule, "emergency");
+        assert_eq!(delivery.target, "hook");
+        assert!(delivery.delivered);
+        // there is no target called missing, so nothing else is logged
+        drop(notifier);
+        assert!(deliveries.recv().await.is_none());
+    }
+}
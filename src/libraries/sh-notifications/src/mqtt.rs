@@ -0,0 +1,51 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, Packet, QoS};
+use sh_common::alert::ShAlert;
+use sh_config::notifications::ShMqttTarget;
+
+/// Publish the alert as JSON. Alerts are rare enough that connecting for each one is simpler
+/// than keeping a connection open
+pub async fn send(target: &ShMqttTarget, alert: &ShAlert) -> Result<(), String> {
+    let payload = serde_json::to_vec(alert).map_err(|e| e.to_string())?;
+
+    // brokers drop the older of two connections with the same id, and deliveries can overlap
+    let client_id = format!("{}-{}", target.client_id, alert.id);
+    let mut options = MqttOptions::new(client_id, &target.host, target.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if let (Some(username), Some(password)) = (&target.username, &target.password) {
+        options.set_credentials(username, password);
+    }
+
+    let (client, mut event_loop) = AsyncClient::new(options, 10);
+    client
+        .publish(&target.topic, QoS::AtLeastOnce, false, payload)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // nothing goes out until the event loop is polled, and the publish isn't done until the
+    // broker acknowledges it
+    loop {
+        match event_loop.poll().await {
+            Ok(Event::Incoming(Packet::PubAck(_))) => break,
+            Ok(_) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    // let the disconnect go out before the connection is dropped
+    if client.disconnect().await.is_ok() {
+        while let Ok(event) = event_loop.poll().await {
+            if matches!(event, Event::Outgoing(Outgoing::Disconnect)) {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sh_common::alert::ShAlert;
+use sh_config::notifications::{ShSmtpSecurity, ShSmtpTarget};
+
+use crate::format;
+
+pub async fn send(target: &ShSmtpTarget, alert: &ShAlert) -> Result<(), String> {
+    let mut email = Message::builder()
+        .from(
+            target
+                .from
+                .parse()
+                .map_err(|e| format!("Invalid from address: {e}"))?,
+        )
+        .subject(format::title(alert))
+        .header(ContentType::TEXT_PLAIN);
+
+    for to in &target.to {
+        email = email.to(to
+            .parse()
+            .map_err(|e| format!("Invalid to address {to}: {e}"))?);
+    }
+
+    let email = email.body(format::body(alert)).map_err(|e| e.to_string())?;
+
+    let builder = match target.security {
+        ShSmtpSecurity::StartTls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&target.host)
+                .map_err(|e| e.to_string())?
+        }
+        ShSmtpSecurity::Tls => {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&target.host).map_err(|e| e.to_string())?
+        }
+        ShSmtpSecurity::None => {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&target.host)
+        }
+    };
+
+    let mut builder = builder.port(target.port);
+    if let (Some(username), Some(password)) = (&target.username, &target.password) {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    builder
+        .build()
+        .send(email)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
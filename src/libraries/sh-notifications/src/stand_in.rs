@@ -0,0 +1,183 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Local servers that stand in for the real targets in tests. Each one does just enough of its
+// protocol for a target to deliver to it, and passes on what it was sent
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+async fn listen() -> (TcpListener, u16) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    (listener, port)
+}
+
+/// An HTTP server answering with each of `statuses` in turn, then the last one from then on.
+/// Passes on the body of each request
+pub async fn http(statuses: Vec<u16>) -> (String, mpsc::UnboundedReceiver<String>) {
+    let (listener, port) = listen().await;
+    let (bodies_tx, bodies) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        for request in 0.. {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let status = statuses
+                .get(request)
+                .or_else(|| statuses.last())
+                .copied()
+                .unwrap_or(200);
+            let body = answer_http(stream, status).await;
+            let _ = bodies_tx.send(body);
+        }
+    });
+
+    (format!("http://127.0.0.1:{port}/hook"), bodies)
+}
+
+async fn answer_http(stream: TcpStream, status: u16) -> String {
+    let mut reader = BufReader::new(stream);
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await.unwrap();
+    let response =
+        format!("HTTP/1.1 {status} Stand In\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    reader
+        .get_mut()
+        .write_all(response.as_bytes())
+        .await
+        .unwrap();
+
+    String::from_utf8(body).unwrap()
+}
+
+/// An MQTT broker that takes one connection at a time. Passes on the topic and payload of each
+/// publish
+pub async fn mqtt() -> (u16, mpsc::UnboundedReceiver<(String, Vec<u8>)>) {
+    let (listener, port) = listen().await;
+    let (published_tx, published) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            while let Some((kind, packet)) = read_mqtt_packet(&mut stream).await {
+                match kind >> 4 {
+                    // CONNECT, accepted
+                    1 => stream.write_all(&[0x20, 2, 0, 0]).await.unwrap(),
+                    // PUBLISH, acknowledged if it was sent at least once
+                    3 => {
+                        let topic_length = usize::from(u16::from_be_bytes([packet[0], packet[1]]));
+                        let topic = String::from_utf8(packet[2..2 + topic_length].to_vec());
+                        let mut rest = &packet[2 + topic_length..];
+                        if kind & 0b0110 != 0 {
+                            stream
+                                .write_all(&[0x40, 2, rest[0], rest[1]])
+                                .await
+                                .unwrap();
+                            rest = &rest[2..];
+                        }
+                        let _ = published_tx.send((topic.unwrap(), rest.to_vec()));
+                    }
+                    // PINGREQ
+                    12 => stream.write_all(&[0xD0, 0]).await.unwrap(),
+                    // DISCONNECT
+                    14 => break,
+                    _ => {}
+                }
+            }
+        }
+    });
+
+    (port, published)
+}
+
+async fn read_mqtt_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+    let kind = stream.read_u8().await.ok()?;
+    let mut length = 0;
+    for shift in (0..28).step_by(7) {
+        let byte = stream.read_u8().await.ok()?;
+        length |= usize::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let mut packet = vec![0; length];
+    stream.read_exact(&mut packet).await.ok()?;
+    Some((kind, packet))
+}
+
+/// An SMTP server, without TLS or authentication. Passes on the recipients and data of each
+/// email
+pub async fn smtp() -> (u16, mpsc::UnboundedReceiver<(Vec<String>, String)>) {
+    let (listener, port) = listen().await;
+    let (emails_tx, emails) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut stream = BufReader::new(stream);
+            stream
+                .get_mut()
+                .write_all(b"220 stand-in\r\n")
+                .await
+                .unwrap();
+            let mut recipients = Vec::new();
+
+            loop {
+                let mut line = String::new();
+                if stream.read_line(&mut line).await.unwrap_or_default() == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_uppercase();
+
+                let reply: &[u8] = if command.starts_with("RCPT TO:") {
+                    recipients.push(line.trim_end()[8..].to_string());
+                    b"250 OK\r\n"
+                } else if command == "DATA" {
+                    stream
+                        .get_mut()
+                        .write_all(b"354 Go ahead\r\n")
+                        .await
+                        .unwrap();
+                    let mut data = String::new();
+                    loop {
+                        let mut line = String::new();
+                        stream.read_line(&mut line).await.unwrap();
+                        if line == ".\r\n" {
+                            break;
+                        }
+                        data.push_str(&line);
+                    }
+                    let _ = emails_tx.send((std::mem::take(&mut recipients), data));
+                    b"250 Queued\r\n"
+                } else if command == "QUIT" {
+                    stream.get_mut().write_all(b"221 Bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 OK\r\n"
+                };
+                stream.get_mut().write_all(reply).await.unwrap();
+            }
+        }
+    });
+
+    (port, emails)
+}
@@ -0,0 +1,59 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde_json::json;
+use sh_common::alert::ShAlert;
+use sh_config::notifications::ShWebhookTarget;
+
+use crate::format;
+
+// Discord turns away messages longer than this
+const DISCORD_MAX_LENGTH: usize = 2_000;
+
+async fn post(
+    http: &reqwest::Client,
+    target: &ShWebhookTarget,
+    payload: &(impl serde::Serialize + Sync),
+) -> Result<(), String> {
+    http.post(&target.url)
+        .json(payload)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// The alert as it is sent to the web interface
+pub async fn send_json(
+    http: &reqwest::Client,
+    target: &ShWebhookTarget,
+    alert: &ShAlert,
+) -> Result<(), String> {
+    post(http, target, alert).await
+}
+
+pub async fn send_discord(
+    http: &reqwest::Client,
+    target: &ShWebhookTarget,
+    alert: &ShAlert,
+) -> Result<(), String> {
+    let mut content = format!("**{}**\n{}", format::title(alert), format::body(alert));
+    if content.chars().count() > DISCORD_MAX_LENGTH {
+        content = content.chars().take(DISCORD_MAX_LENGTH - 3).collect();
+        content.push_str("...");
+    }
+
+    post(http, target, &json!({ "content": content })).await
+}
+
+pub async fn send_slack(
+    http: &reqwest::Client,
+    target: &ShWebhookTarget,
+    alert: &ShAlert,
+) -> Result<(), String> {
+    let text = format!("*{}*\n{}", format::title(alert), format::body(alert));
+    post(http, target, &json!({ "text": text })).await
+}
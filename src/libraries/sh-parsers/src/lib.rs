@@ -0,0 +1,21 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Parsers that pull structured data out of free text message bodies. Airlines and avionics
+// vendors all format their reports a little differently, so these aim to handle the common
+// formats and ignore anything they are not sure about.
+
+#![deny(
+    clippy::pedantic,
+//    clippy::cargo,
+    clippy::nursery,
+    clippy::style,
+    clippy::correctness,
+    clippy::all
+)]
+
+pub mod oooi;
+mod time;
+pub mod weather;
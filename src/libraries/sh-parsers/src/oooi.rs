@@ -0,0 +1,255 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// OOOI reports come in two flavours. The ARINC 620 Q labels each carry a single event with the
+// time somewhere in the text, and airline free text reports spell the events out, e.g.
+// `OUT 1205 OFF 1214` or `OUT/1205`.
+
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::oooi::{ShOooiEvent, ShOooiEventType};
+
+use crate::time::{parse_hhmm, resolve_time_of_day};
+
+// Separators we accept between a keyword and its time
+const SEPARATORS: &[u8] = b" /-=:.";
+const MAX_SEPARATORS: usize = 3;
+
+const fn event_for_label(label: &str) -> Option<ShOooiEventType> {
+    match label.as_bytes() {
+        b"QA" | b"QE" | b"QH" | b"QP" => Some(ShOooiEventType::Out),
+        b"QB" | b"QF" | b"QQ" => Some(ShOooiEventType::Off),
+        b"QC" | b"QK" | b"QR" => Some(ShOooiEventType::On),
+        b"QD" | b"QL" | b"QS" => Some(ShOooiEventType::In),
+        _ => None,
+    }
+}
+
+fn event_for_keyword(keyword: &str) -> Option<ShOooiEventType> {
+    match keyword {
+        "OUT" => Some(ShOooiEventType::Out),
+        "OFF" => Some(ShOooiEventType::Off),
+        "ON" => Some(ShOooiEventType::On),
+        "IN" => Some(ShOooiEventType::In),
+        _ => None,
+    }
+}
+
+/// Pull any OOOI events out of a message. Each event type is reported at most once per message
+#[must_use]
+pub fn extract(message: &ShAcarsMessage) -> Vec<ShOooiEvent> {
+    let Some(text) = message.text.as_deref() else {
+        return Vec::new();
+    };
+
+    let text = text.to_uppercase();
+    let label_event = message.label.as_deref().and_then(event_for_label);
+    let mut found = find_keyword_times(&text);
+
+    if let Some(event_type) = label_event {
+        // A Q label report that doesn't spell out the event still has the time in it
+        if found.is_empty() {
+            if let Some((hours, minutes)) = find_standalone_time(&text) {
+                found.push((event_type, hours, minutes));
+            }
+        }
+    } else if found.len() < 2 {
+        // Outside of the OOOI labels a single "ON 1234" is as likely to be free text as a
+        // report, so we want to see at least two events before believing it
+        return Vec::new();
+    }
+
+    found
+        .into_iter()
+        .map(|(event_type, hours, minutes)| ShOooiEvent {
+            event_type,
+            time: resolve_time_of_day(message.timestamp, hours, minutes),
+            message_timestamp: message.timestamp,
+            label: message.label.clone(),
+        })
+        .collect()
+}
+
+/// Find `KEYWORD[sep]HHMM` pairs in upper case text
+fn find_keyword_times(text: &str) -> Vec<(ShOooiEventType, u32, u32)> {
+    let bytes = text.as_bytes();
+    let mut found: Vec<(ShOooiEventType, u32, u32)> = Vec::new();
+    let mut position = 0;
+
+    while position < bytes.len() {
+        // keywords have to start a word
+        if !bytes[position].is_ascii_alphabetic()
+            || (position > 0 && bytes[position - 1].is_ascii_alphanumeric())
+        {
+            position += 1;
+            continue;
+        }
+
+        let word_end = bytes[position..]
+            .iter()
+            .position(|c| !c.is_ascii_alphabetic())
+            .map_or(bytes.len(), |offset| position + offset);
+
+        if let Some(event_type) = event_for_keyword(&text[position..word_end]) {
+            if let Some((hours, minutes)) = time_after_keyword(&text[word_end..]) {
+                if !found.iter().any(|(known, _, _)| *known == event_type) {
+                    found.push((event_type, hours, minutes));
+                }
+            }
+        }
+
+        position = word_end;
+    }
+
+    found
+}
+
+fn time_after_keyword(rest: &str) -> Option<(u32, u32)> {
+    let separators = rest
+        .bytes()
+        .take(MAX_SEPARATORS + 1)
+        .take_while(|c| SEPARATORS.contains(c))
+        .count();
+
+    if separators > MAX_SEPARATORS {
+        return None;
+    }
+
+    parse_hhmm(&rest[separators..]).map(|(hours, minutes, _)| (hours, minutes))
+}
+
+/// The first whitespace separated four digit time in the text
+fn find_standalone_time(text: &str) -> Option<(u32, u32)> {
+    text.split_whitespace()
+        .filter(|word| word.len() == 4 || word.len() == 5)
+        .find_map(|word| match parse_hhmm(word) {
+            Some((hours, minutes, consumed)) if consumed == word.len() => Some((hours, minutes)),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use sh_common::acars_message::ShMessageType;
+
+    use super::*;
+
+    // 2024-03-10 12:30 UTC
+    const RECEIVED: f64 = 1_710_073_800.0;
+    const DAY_START: f64 = 1_710_028_800.0;
+
+    fn message(label: Option<&str>, text: Option<&str>, timestamp: f64) -> ShAcarsMessage {
+        let mut message = ShAcarsMessage::new(timestamp, ShMessageType::Acars, String::new());
+        message.label = label.map(ToString::to_string);
+        message.text = text.map(ToString::to_string);
+        message
+    }
+
+    // label, text and the events with their time of day
+    type Sample = (
+        &'static str,
+        &'static str,
+        &'static [(ShOooiEventType, u32, u32)],
+    );
+
+    fn at(hours: u32, minutes: u32) -> f64 {
+        DAY_START + f64::from(hours * 3_600 + minutes * 60)
+    }
+
+    #[test]
+    fn extracts_events() {
+        use ShOooiEventType::{In, Off, On, Out};
+
+        let samples: [Sample; 10] = [
+            // Q labels, with the event given by the label
+            ("QA", "KJFKKBOS 1205 FOB 0123", &[(Out, 12, 5)]),
+            ("QB", "KJFKKBOS 12:14", &[(Off, 12, 14)]),
+            ("QC", "KBOSKJFK 1152 FOB 0048", &[(On, 11, 52)]),
+            ("QD", "KBOSKJFK 1158", &[(In, 11, 58)]),
+            // a Q label that spells the event out uses what it says
+            ("QA", "OUT 1205", &[(Out, 12, 5)]),
+            // airline free text labels
+            ("10", "OUT 1205 OFF 1214", &[(Out, 12, 5), (Off, 12, 14)]),
+            (
+                "11",
+                "OUT/1105 OFF/1114 ON/1225",
+                &[(Out, 11, 5), (Off, 11, 14), (On, 12, 25)],
+            ),
+            (
+                "12",
+                "FLT 123 KJFK-KBOS OUT=1205 OFF=1214 ON=1150 IN=1158",
+                &[(Out, 12, 5), (Off, 12, 14), (On, 11, 50), (In, 11, 58)],
+            ),
+            ("14", "on: 11:50 in - 11:58", &[(On, 11, 50), (In, 11, 58)]),
+            // only the first of each event counts
+            (
+                "10",
+                "OUT 1205 OUT 1210 OFF 1214",
+                &[(Out, 12, 5), (Off, 12, 14)],
+            ),
+        ];
+
+        for (label, text, expected) in samples {
+            let events = extract(&message(Some(label), Some(text), RECEIVED));
+            let found: Vec<_> = events
+                .iter()
+                .map(|event| (event.event_type, event.time))
+                .collect();
+            let expected: Vec<_> = expected
+                .iter()
+                .map(|(event_type, hours, minutes)| {
+                    // later than the message, so it was the day before
+                    let time = at(*hours, *minutes);
+                    let time = if time > RECEIVED + 3_600.0 {
+                        time - 86_400.0
+                    } else {
+                        time
+                    };
+                    (*event_type, time)
+                })
+                .collect();
+            assert_eq!(found, expected, "{label} {text}");
+            assert!(events
+                .iter()
+                .all(|event| event.label.as_deref() == Some(label)
+                    && (event.message_timestamp - RECEIVED).abs() < f64::EPSILON));
+        }
+    }
+
+    #[test]
+    fn ignores_malformed_reports() {
+        let samples = [
+            // a single event outside of the Q labels could be anything
+            (Some("10"), Some("ON 1150")),
+            (Some("H1"), Some("TURN ON 1150 WHEN READY")),
+            // times that can't be
+            (Some("10"), Some("OUT 2560 OFF 1214")),
+            (Some("QA"), Some("KJFKKBOS 2405")),
+            (Some("11"), Some("OUT 12051 OFF 121")),
+            // longer words that start with a keyword
+            (Some("10"), Some("OUTPUT 1205 OFFSET 1214")),
+            // too far from the keyword
+            (Some("10"), Some("OUT - / 1205 OFF :-/. 1214")),
+            (Some("QB"), Some("NO TIME HERE")),
+            (Some("QA"), Some("")),
+            (Some("QA"), None),
+            (None, Some("OUT 1205")),
+        ];
+
+        for (label, text) in samples {
+            let events = extract(&message(label, text, RECEIVED));
+            assert!(events.is_empty(), "{label:?} {text:?} gave {events:?}");
+        }
+    }
+
+    #[test]
+    fn times_after_the_message_are_from_the_day_before() {
+        // received 00:10 on the 10th
+        let received = DAY_START + 600.0;
+        let events = extract(&message(Some("QD"), Some("KBOSKJFK 2350"), received));
+
+        assert_eq!(events.len(), 1);
+        assert!((events[0].time - (DAY_START - 600.0)).abs() < f64::EPSILON);
+    }
+}
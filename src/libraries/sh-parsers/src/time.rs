@@ -0,0 +1,134 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+// Reports are about things that have already happened, but the aircraft clock and the
+// decoder clock do not always agree
+const CLOCK_SKEW: f64 = 3_600.0;
+// A day of the month this far before the message is really early next month
+const MONTH_ROLLOVER: f64 = 27.0 * SECONDS_PER_DAY;
+
+/// Turn a UTC time of day in to a Unix timestamp, using the date of the message it was
+/// reported in. A time later than the message is assumed to be from the day before
+pub fn resolve_time_of_day(message_timestamp: f64, hours: u32, minutes: u32) -> f64 {
+    let day_start = (message_timestamp / SECONDS_PER_DAY).floor() * SECONDS_PER_DAY;
+    let time = day_start + f64::from(hours * 3_600 + minutes * 60);
+
+    if time > message_timestamp + CLOCK_SKEW {
+        time - SECONDS_PER_DAY
+    } else {
+        time
+    }
+}
+
+/// Parse `HHMM` or `HH:MM` from the start of `input`. Returns the hours, minutes and the
+/// number of bytes consumed. The time must not be followed by another digit
+pub fn parse_hhmm(input: &str) -> Option<(u32, u32, usize)> {
+    let bytes = input.as_bytes();
+
+    let (digits, consumed) = match bytes {
+        [a, b, b':', c, d, ..] => ([*a, *b, *c, *d], 5),
+        [a, b, c, d, ..] => ([*a, *b, *c, *d], 4),
+        _ => return None,
+    };
+
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    if bytes.get(consumed).is_some_and(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let hours = u32::from(digits[0] - b'0') * 10 + u32::from(digits[1] - b'0');
+    let minutes = u32::from(digits[2] - b'0') * 10 + u32::from(digits[3] - b'0');
+
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    Some((hours, minutes, consumed))
+}
+
+/// Turn a `DDHHMM` UTC time in to a Unix timestamp, using the month of the message it was
+/// reported in. Reports can be a day ahead of the message near midnight, but a day of the
+/// month much later than the message is from the month before
+pub fn resolve_day_time(message_timestamp: f64, day: u32, hours: u32, minutes: u32) -> f64 {
+    #[allow(clippy::cast_possible_truncation)]
+    let message_day = (message_timestamp / SECONDS_PER_DAY).floor() as i64;
+    let (year, month, _) = civil_from_days(message_day);
+
+    let time_in = |year: i64, month: i64| {
+        let (year, month) = match month {
+            0 => (year - 1, 12),
+            13 => (year + 1, 1),
+            month => (year, month),
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        let day_start = days_from_civil(year, month, i64::from(day)) as f64 * SECONDS_PER_DAY;
+        day_start + f64::from(hours * 3_600 + minutes * 60)
+    };
+
+    let time = time_in(year, month);
+
+    if time > message_timestamp + SECONDS_PER_DAY {
+        time_in(year, month - 1)
+    } else if time < message_timestamp - MONTH_ROLLOVER {
+        time_in(year, month + 1)
+    } else {
+        time
+    }
+}
+
+/// Parse `DDHHMMZ` from the start of `input`, returning the day, hours and minutes
+pub fn parse_ddhhmmz(input: &str) -> Option<(u32, u32, u32)> {
+    let bytes = input.as_bytes();
+
+    if bytes.len() < 7 || bytes[6] != b'Z' || !bytes[..6].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let day = u32::from(bytes[0] - b'0') * 10 + u32::from(bytes[1] - b'0');
+    let (hours, minutes, _) = parse_hhmm(&input[2..6])?;
+
+    if !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some((day, hours, minutes))
+}
+
+// Howard Hinnant's civil calendar algorithms, which save us pulling in a date library for
+// the one thing we need one for
+const fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+const fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
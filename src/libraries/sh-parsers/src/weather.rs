@@ -0,0 +1,490 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Weather uplinks are usually a request reply with several airports packed in one after the
+// other, so each product runs from where it starts until the next one starts, or until the
+// `=` that ends it if the sender used one. Products are recognised by:
+//   METAR/SPECI KSEA 121853Z ...
+//   TAF [AMD|COR] KSEA 121130Z ...
+//   KSEA 121853Z 18010KT ...       a METAR without the keyword
+//   KSEA 121130Z 1212/1312 ...     a TAF without the keyword
+//   KSEA [ARR|DEP] ATIS INFO K 1853Z ...
+
+use sh_common::acars_message::{ShAcarsMessage, ShMessageDirection};
+use sh_common::weather::{ShWeatherProduct, ShWeatherProductType};
+
+use crate::time::{parse_ddhhmmz, parse_hhmm, resolve_day_time, resolve_time_of_day};
+
+const ATIS_KEYWORDS: &[&str] = &["ATIS", "D-ATIS", "ARR", "DEP", "ARRIVAL", "DEPARTURE"];
+// How far past the information letter we look for the ATIS time
+const ATIS_TIME_SEARCH: usize = 3;
+
+struct Token<'a> {
+    text: &'a str,
+    start: usize,
+}
+
+struct ProductStart {
+    product_type: ShWeatherProductType,
+    airport: String,
+    issued: Option<f64>,
+    atis_code: Option<String>,
+    start: usize,
+    /// Index of the first token after the header
+    next_token: usize,
+}
+
+/// Pull every METAR, TAF and ATIS out of a message. Aircraft don't send weather, so downlinks
+/// are skipped
+#[must_use]
+pub fn extract(message: &ShAcarsMessage) -> Vec<ShWeatherProduct> {
+    if message.direction == ShMessageDirection::Downlink {
+        return Vec::new();
+    }
+
+    let Some(text) = message.text.as_deref() else {
+        return Vec::new();
+    };
+
+    // ASCII upper casing keeps the byte offsets the same as the original text
+    let text = text.to_ascii_uppercase();
+    let tokens = tokenize(&text);
+
+    let mut starts: Vec<ProductStart> = Vec::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        match find_start(&tokens, index, message.timestamp) {
+            Some(start) => {
+                index = start.next_token;
+                starts.push(start);
+            }
+            None => index += 1,
+        }
+    }
+
+    let mut products = Vec::with_capacity(starts.len());
+
+    for (position, start) in starts.iter().enumerate() {
+        let next_start = starts
+            .get(position + 1)
+            .map_or(text.len(), |next| next.start);
+        let end = text[start.start..next_start]
+            .find('=')
+            .map_or(next_start, |offset| start.start + offset);
+
+        let raw = text[start.start..end].trim();
+        if raw.is_empty() {
+            continue;
+        }
+
+        products.push(ShWeatherProduct {
+            product_type: start.product_type,
+            airport: start.airport.clone(),
+            issued: start.issued,
+            atis_code: start.atis_code.clone(),
+            raw: raw.to_string(),
+            received: message.timestamp,
+            tail: message.tail.clone(),
+        });
+    }
+
+    products
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (position, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(token_start)) => {
+                tokens.push(Token {
+                    text: &text[token_start..position],
+                    start: token_start,
+                });
+                start = None;
+            }
+            (false, None) => start = Some(position),
+            _ => {}
+        }
+    }
+
+    if let Some(token_start) = start {
+        tokens.push(Token {
+            text: &text[token_start..],
+            start: token_start,
+        });
+    }
+
+    tokens
+}
+
+fn is_airport(token: &str) -> bool {
+    token.len() == 4
+        && token.starts_with(|c: char| c.is_ascii_alphabetic())
+        && token.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// ATIS uplinks sometimes use the three letter IATA code
+fn is_atis_airport(token: &str) -> bool {
+    (token.len() == 3 || token.len() == 4) && token.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn issue_time(token: Option<&Token>, message_timestamp: f64) -> Option<f64> {
+    let (day, hours, minutes) = parse_ddhhmmz(token?.text)?;
+    Some(resolve_day_time(message_timestamp, day, hours, minutes))
+}
+
+fn find_start(tokens: &[Token], index: usize, message_timestamp: f64) -> Option<ProductStart> {
+    let token = tokens[index].text;
+
+    match token {
+        "METAR" | "SPECI" | "TAF" => keyword_start(tokens, index, message_timestamp),
+        "INFO" | "INFORMATION" => atis_start(tokens, index, message_timestamp),
+        _ => bare_start(tokens, index, message_timestamp),
+    }
+}
+
+fn keyword_start(tokens: &[Token], index: usize, message_timestamp: f64) -> Option<ProductStart> {
+    let product_type = match tokens[index].text {
+        "METAR" => ShWeatherProductType::Metar,
+        "SPECI" => ShWeatherProductType::Speci,
+        _ => ShWeatherProductType::Taf,
+    };
+
+    let mut next = index + 1;
+    while tokens
+        .get(next)
+        .is_some_and(|token| matches!(token.text, "AMD" | "COR"))
+    {
+        next += 1;
+    }
+
+    // Without an issue time this is more likely to be something like "NO TAF AVBL"
+    let airport = tokens.get(next).filter(|token| is_airport(token.text))?;
+    let issued = issue_time(tokens.get(next + 1), message_timestamp)?;
+
+    Some(ProductStart {
+        product_type,
+        airport: airport.text.to_string(),
+        issued: Some(issued),
+        atis_code: None,
+        start: tokens[index].start,
+        next_token: next + 2,
+    })
+}
+
+fn bare_start(tokens: &[Token], index: usize, message_timestamp: f64) -> Option<ProductStart> {
+    let airport = tokens[index].text;
+    if !is_airport(airport) || !airport.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let issued = issue_time(tokens.get(index + 1), message_timestamp)?;
+    let following = tokens.get(index + 2)?.text;
+
+    // a validity period straight after the issue time means a TAF, and a METAR goes
+    // straight in to the wind
+    let product_type = if is_validity_period(following) {
+        ShWeatherProductType::Taf
+    } else if following.ends_with("KT")
+        || following.ends_with("MPS")
+        || matches!(following, "AUTO" | "COR" | "NIL")
+    {
+        ShWeatherProductType::Metar
+    } else {
+        return None;
+    };
+
+    Some(ProductStart {
+        product_type,
+        airport: airport.to_string(),
+        issued: Some(issued),
+        atis_code: None,
+        start: tokens[index].start,
+        next_token: index + 2,
+    })
+}
+
+/// `DDHH/DDHH`
+fn is_validity_period(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    bytes.len() == 9
+        && bytes[4] == b'/'
+        && bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[5..].iter().all(u8::is_ascii_digit)
+}
+
+fn atis_start(tokens: &[Token], index: usize, message_timestamp: f64) -> Option<ProductStart> {
+    // walk back over the ATIS/ARR/DEP keywords to the airport
+    let mut airport_index = index;
+    while airport_index > 0 && ATIS_KEYWORDS.contains(&tokens[airport_index - 1].text) {
+        airport_index -= 1;
+    }
+
+    if airport_index == index || airport_index == 0 {
+        return None;
+    }
+    airport_index -= 1;
+
+    let airport = tokens[airport_index]
+        .text
+        .trim_start_matches('/')
+        .trim_end_matches(',');
+    if !is_atis_airport(airport) {
+        return None;
+    }
+
+    let code = tokens
+        .get(index + 1)?
+        .text
+        .trim_end_matches(|c: char| c.is_ascii_punctuation());
+    if code.len() != 1 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let issued = tokens
+        .iter()
+        .skip(index + 2)
+        .take(ATIS_TIME_SEARCH)
+        .find_map(|token| {
+            let time = token
+                .text
+                .trim_end_matches(|c: char| c.is_ascii_punctuation());
+            match (time.strip_suffix('Z'), parse_hhmm(time)) {
+                (Some(_), Some((hours, minutes, 4))) => {
+                    Some(resolve_time_of_day(message_timestamp, hours, minutes))
+                }
+                _ => None,
+            }
+        });
+
+    Some(ProductStart {
+        product_type: ShWeatherProductType::Atis,
+        airport: airport.to_string(),
+        issued,
+        atis_code: Some(code.to_string()),
+        start: tokens[airport_index].start,
+        next_token: index + 2,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sh_common::acars_message::ShMessageType;
+
+    use super::*;
+
+    // 2024-03-12 19:00 UTC
+    const RECEIVED: f64 = 1_710_270_000.0;
+    // 18:53 and 11:30 that day
+    const ISSUED_1853: f64 = 1_710_269_580.0;
+    const ISSUED_1130: f64 = 1_710_243_000.0;
+
+    fn uplink(text: &str, timestamp: f64) -> ShAcarsMessage {
+        let mut message = ShAcarsMessage::new(timestamp, ShMessageType::Acars, String::new());
+        message.direction = ShMessageDirection::Uplink;
+        message.tail = Some("N12345".to_string());
+        message.text = Some(text.to_string());
+        message
+    }
+
+    fn products(text: &str) -> Vec<(ShWeatherProductType, String, Option<f64>, String)> {
+        extract(&uplink(text, RECEIVED))
+            .into_iter()
+            .map(|product| {
+                (
+                    product.product_type,
+                    product.airport,
+                    product.issued,
+                    product.raw,
+                )
+            })
+            .collect()
+    }
+
+    fn product(
+        product_type: ShWeatherProductType,
+        airport: &str,
+        issued: Option<f64>,
+        raw: &str,
+    ) -> (ShWeatherProductType, String, Option<f64>, String) {
+        (product_type, airport.to_string(), issued, raw.to_string())
+    }
+
+    #[test]
+    fn metars_and_tafs() {
+        use ShWeatherProductType::{Metar, Speci, Taf};
+
+        let samples = [
+            (
+                "METAR KSEA 121853Z 18010KT 10SM FEW040 12/05 A3001=",
+                vec![product(
+                    Metar,
+                    "KSEA",
+                    Some(ISSUED_1853),
+                    "METAR KSEA 121853Z 18010KT 10SM FEW040 12/05 A3001",
+                )],
+            ),
+            (
+                "speci ksea 121853z 18010g25kt 3sm -ra bkn008",
+                vec![product(
+                    Speci,
+                    "KSEA",
+                    Some(ISSUED_1853),
+                    "SPECI KSEA 121853Z 18010G25KT 3SM -RA BKN008",
+                )],
+            ),
+            (
+                "TAF AMD KSEA 121130Z 1212/1312 18010KT P6SM BKN040",
+                vec![product(
+                    Taf,
+                    "KSEA",
+                    Some(ISSUED_1130),
+                    "TAF AMD KSEA 121130Z 1212/1312 18010KT P6SM BKN040",
+                )],
+            ),
+            // without the keywords
+            (
+                "KPDX 121853Z AUTO 20008KT 10SM CLR 11/04 A3002",
+                vec![product(
+                    Metar,
+                    "KPDX",
+                    Some(ISSUED_1853),
+                    "KPDX 121853Z AUTO 20008KT 10SM CLR 11/04 A3002",
+                )],
+            ),
+            (
+                "KBOS 121130Z 1212/1318 27015KT P6SM SCT250",
+                vec![product(
+                    Taf,
+                    "KBOS",
+                    Some(ISSUED_1130),
+                    "KBOS 121130Z 1212/1318 27015KT P6SM SCT250",
+                )],
+            ),
+        ];
+
+        for (text, expected) in samples {
+            assert_eq!(products(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn atis() {
+        let found = extract(&uplink(
+            "/SEA ARR ATIS INFO K 1853Z. 18010KT 10SM FEW040 ILS RWY 16R IN USE",
+            RECEIVED,
+        ));
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].product_type, ShWeatherProductType::Atis);
+        assert_eq!(found[0].airport, "SEA");
+        assert_eq!(found[0].atis_code.as_deref(), Some("K"));
+        assert_eq!(found[0].issued, Some(ISSUED_1853));
+        assert_eq!(found[0].tail.as_deref(), Some("N12345"));
+        assert!(found[0].raw.starts_with("/SEA ARR ATIS INFO K"));
+    }
+
+    #[test]
+    fn several_products_in_one_message() {
+        use ShWeatherProductType::{Atis, Metar, Taf};
+
+        let text = "REQUESTED WX\n\
+            METAR KSEA 121853Z 18010KT 10SM FEW040 12/05 A3001=\n\
+            KPDX 121853Z 20008KT 10SM BKN050 11/04 A3002\n\
+            TAF KSEA 121130Z 1212/1312 18010KT P6SM BKN040\n  FM122000 20012KT P6SM OVC030=\n\
+            KSEA DEP ATIS INFO L 1853Z RWY 16L";
+        let found: Vec<_> = products(text)
+            .into_iter()
+            .map(|(product_type, airport, _, raw)| (product_type, airport, raw))
+            .collect();
+
+        assert_eq!(
+            found,
+            [
+                (
+                    Metar,
+                    "KSEA".to_string(),
+                    "METAR KSEA 121853Z 18010KT 10SM FEW040 12/05 A3001".to_string()
+                ),
+                (
+                    Metar,
+                    "KPDX".to_string(),
+                    "KPDX 121853Z 20008KT 10SM BKN050 11/04 A3002".to_string()
+                ),
+                (
+                    Taf,
+                    "KSEA".to_string(),
+                    "TAF KSEA 121130Z 1212/1312 18010KT P6SM BKN040\n  FM122000 20012KT P6SM OVC030"
+                        .to_string()
+                ),
+                (
+                    Atis,
+                    "KSEA".to_string(),
+                    "KSEA DEP ATIS INFO L 1853Z RWY 16L".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_messages() {
+        // cut off part way through, so only what made it is kept
+        assert_eq!(
+            products("METAR KSEA 121853Z 18010KT 10SM FE"),
+            [product(
+                ShWeatherProductType::Metar,
+                "KSEA",
+                Some(ISSUED_1853),
+                "METAR KSEA 121853Z 18010KT 10SM FE"
+            )]
+        );
+
+        // cut off before there is enough to tell what it is
+        let samples: [(&str, &[&str]); 5] = [
+            ("METAR KSEA 1218", &[]),
+            ("TAF KSEA", &[]),
+            ("KSEA 121853Z", &[]),
+            ("KSEA ATIS INFO", &[]),
+            ("METAR KSEA 121853Z 18010KT 10SM=\nTAF KBOS 1211", &["KSEA"]),
+        ];
+        for (text, airports) in samples {
+            let found: Vec<_> = products(text)
+                .into_iter()
+                .map(|(_, airport, _, _)| airport)
+                .collect();
+            assert_eq!(found, airports, "{text}");
+        }
+    }
+
+    #[test]
+    fn ignores_what_isnt_weather() {
+        let samples = [
+            "NO TAF AVBL FOR KSEA",
+            "REQUEST METAR KSEA KPDX",
+            "SEA INFO",
+            "POS N47123W122456 1853 FL350",
+            "",
+        ];
+        for text in samples {
+            assert!(products(text).is_empty(), "{text}");
+        }
+
+        // aircraft don't send weather, they ask for it
+        let mut downlink = uplink("METAR KSEA 121853Z 18010KT 10SM", RECEIVED);
+        downlink.direction = ShMessageDirection::Downlink;
+        assert!(extract(&downlink).is_empty());
+    }
+
+    #[test]
+    fn issue_times_at_the_end_of_the_month() {
+        // received 00:10 on the 1st of March, issued 23:50 on the 29th of February
+        let found = extract(&uplink("KSEA 292350Z 18010KT 10SM", 1_709_251_800.0));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].issued, Some(1_709_250_600.0));
+    }
+}
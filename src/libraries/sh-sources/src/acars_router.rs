@@ -0,0 +1,166 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use sh_common::ServerType;
+use sh_common_server::lifecycle::{Lifecycle, LifecycleSignal};
+use sh_common_server::metrics::ShMetrics;
+use sh_common_server::source_data::ShSourceData;
+use sh_common_server::sources::SourceMonitor;
+use sh_common_server::ShDataUser;
+use sh_config::address::ShAcarsRouterConfig;
+use sh_config::ShConfig;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::decoders::decode_frame;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// `ShAcarsRouterClient` connects to an `acars_router` TCP JSON output and hands everything it
+/// decodes to the hub. It reconnects forever if the connection drops
+pub struct ShAcarsRouterClient {
+    config: ShAcarsRouterConfig,
+    sender: mpsc::Sender<ShSourceData>,
+    metrics: Arc<ShMetrics>,
+    monitor: Arc<SourceMonitor>,
+    lifecycle: Lifecycle,
+}
+
+impl ShAcarsRouterClient {
+    #[must_use]
+    pub fn new(
+        config: ShAcarsRouterConfig,
+        sender: mpsc::Sender<ShSourceData>,
+        metrics: Arc<ShMetrics>,
+        monitor: Arc<SourceMonitor>,
+    ) -> Self {
+        Self {
+            config,
+            sender,
+            metrics,
+            monitor,
+            lifecycle: Lifecycle::new(),
+        }
+    }
+
+    async fn connect_and_read(&self, source: &str) -> Result<(), std::io::Error> {
+        let stream = TcpStream::connect(source).await?;
+        info!("Connected to acars_router at {source}");
+        self.metrics.source_connected(source);
+        self.monitor.connected(source);
+
+        let mut lines = BufReader::new(stream).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            self.monitor.data_received(source);
+
+            let frame = match decode_frame(&line, source) {
+                Ok(frame) => frame,
+                Err(e) => {
+                    warn!("Error decoding message from {source}: {e}");
+                    self.metrics.parse_error(source);
+                    debug!("Offending message: {line}");
+                    continue;
+                }
+            };
+
+            for station in frame.ground_stations {
+                self.send(ShSourceData::GroundStation(station)).await?;
+            }
+
+            if let Some(message) = frame.message {
+                self.metrics.message_received(
+                    source,
+                    message.decoder.as_deref().unwrap_or("unknown"),
+                    &message.message_type.to_string(),
+                );
+                self.send(ShSourceData::AcarsMessage(Box::new(message)))
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send(&self, data: ShSourceData) -> Result<(), std::io::Error> {
+        self.sender
+            .send(data)
+            .await
+            .map_err(|_| std::io::Error::other("The hub is no longer accepting messages"))
+    }
+}
+
+#[async_trait]
+impl ShDataUser for ShAcarsRouterClient {
+    async fn start(
+        &self,
+        _data: Option<Arc<Mutex<ShConfig>>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let source = self.config.to_string();
+        let mut lifecycle = self.lifecycle.watch();
+
+        loop {
+            let result = tokio::select! {
+                result = self.connect_and_read(&source) => result,
+                signal = lifecycle.signalled() => {
+                    self.metrics.source_stopped(&source);
+                    if signal == LifecycleSignal::Stop {
+                        info!("Stopped reading from acars_router at {source}");
+                        return Ok(());
+                    }
+                    info!("Reconnecting to acars_router at {source}");
+                    continue;
+                }
+            };
+
+            let error = match result {
+                Ok(()) => {
+                    warn!("Connection to acars_router at {source} closed");
+                    "Connection closed".to_string()
+                }
+                Err(e) => {
+                    error!("Error reading from acars_router at {source}: {e}");
+                    e.to_string()
+                }
+            };
+            self.metrics.source_disconnected(&source);
+            self.monitor.disconnected(&source, error);
+
+            if self.sender.is_closed() {
+                return Ok(());
+            }
+
+            // a restart tries again straight away
+            tokio::select! {
+                () = tokio::time::sleep(RECONNECT_DELAY) => (),
+                signal = lifecycle.signalled() => {
+                    if signal == LifecycleSignal::Stop {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop(&self) {
+        self.lifecycle.stop();
+    }
+
+    fn restart(&self) {
+        self.lifecycle.restart();
+    }
+
+    fn get_server_type(&self) -> ServerType {
+        ServerType::Other
+    }
+}
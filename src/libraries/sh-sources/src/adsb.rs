@@ -0,0 +1,193 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// ADS-B comes in as SBS (BaseStation) CSV, which readsb and dump1090 serve on port 30003
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use sh_common::adsb::ShAdsbPosition;
+use sh_common::ServerType;
+use sh_common_server::lifecycle::{Lifecycle, LifecycleSignal};
+use sh_common_server::metrics::ShMetrics;
+use sh_common_server::source_data::ShSourceData;
+use sh_common_server::sources::SourceMonitor;
+use sh_common_server::ShDataUser;
+use sh_config::address::SHAdsbConfig;
+use sh_config::ShConfig;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// Field positions in an SBS MSG line
+const FIELD_ICAO_HEX: usize = 4;
+const FIELD_CALLSIGN: usize = 10;
+const FIELD_ALTITUDE: usize = 11;
+const FIELD_LATITUDE: usize = 14;
+const FIELD_LONGITUDE: usize = 15;
+const FIELD_SQUAWK: usize = 17;
+const FIELD_ON_GROUND: usize = 21;
+
+/// `ShAdsbClient` connects to an SBS output and hands every aircraft report to the hub.
+/// It reconnects forever if the connection drops
+pub struct ShAdsbClient {
+    config: SHAdsbConfig,
+    sender: mpsc::Sender<ShSourceData>,
+    metrics: Arc<ShMetrics>,
+    monitor: Arc<SourceMonitor>,
+    lifecycle: Lifecycle,
+}
+
+impl ShAdsbClient {
+    #[must_use]
+    pub fn new(
+        config: SHAdsbConfig,
+        sender: mpsc::Sender<ShSourceData>,
+        metrics: Arc<ShMetrics>,
+        monitor: Arc<SourceMonitor>,
+    ) -> Self {
+        Self {
+            config,
+            sender,
+            metrics,
+            monitor,
+            lifecycle: Lifecycle::new(),
+        }
+    }
+
+    async fn connect_and_read(&self, source: &str) -> Result<(), std::io::Error> {
+        let stream = TcpStream::connect(source).await?;
+        info!("Connected to ADS-B source at {source}");
+        self.metrics.source_connected(source);
+        self.monitor.connected(source);
+
+        let mut lines = BufReader::new(stream).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            self.monitor.data_received(source);
+
+            let Some(position) = parse_sbs_line(&line, now()) else {
+                // the other SBS line types are expected, a MSG line we can't use isn't
+                if line.trim_start().starts_with("MSG") {
+                    self.metrics.parse_error(source);
+                }
+                continue;
+            };
+            self.metrics.position_received(source);
+
+            self.sender
+                .send(ShSourceData::AdsbPosition(position))
+                .await
+                .map_err(|_| std::io::Error::other("The hub is no longer accepting messages"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ShDataUser for ShAdsbClient {
+    async fn start(
+        &self,
+        _data: Option<Arc<Mutex<ShConfig>>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let source = self.config.to_string();
+        let mut lifecycle = self.lifecycle.watch();
+
+        loop {
+            let result = tokio::select! {
+                result = self.connect_and_read(&source) => result,
+                signal = lifecycle.signalled() => {
+                    self.metrics.source_stopped(&source);
+                    if signal == LifecycleSignal::Stop {
+                        info!("Stopped reading from ADS-B source at {source}");
+                        return Ok(());
+                    }
+                    info!("Reconnecting to ADS-B source at {source}");
+                    continue;
+                }
+            };
+
+            let error = match result {
+                Ok(()) => {
+                    warn!("Connection to ADS-B source at {source} closed");
+                    "Connection closed".to_string()
+                }
+                Err(e) => {
+                    error!("Error reading from ADS-B source at {source}: {e}");
+                    e.to_string()
+                }
+            };
+            self.metrics.source_disconnected(&source);
+            self.monitor.disconnected(&source, error);
+
+            if self.sender.is_closed() {
+                return Ok(());
+            }
+
+            // a restart tries again straight away
+            tokio::select! {
+                () = tokio::time::sleep(RECONNECT_DELAY) => (),
+                signal = lifecycle.signalled() => {
+                    if signal == LifecycleSignal::Stop {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop(&self) {
+        self.lifecycle.stop();
+    }
+
+    fn restart(&self) {
+        self.lifecycle.restart();
+    }
+
+    fn get_server_type(&self) -> ServerType {
+        ServerType::Other
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+/// Parse a single SBS line. Only `MSG` lines carry aircraft data, and we use the time we
+/// received the line rather than the one in it, as SBS times have no time zone
+#[must_use]
+pub fn parse_sbs_line(line: &str, timestamp: f64) -> Option<ShAdsbPosition> {
+    let fields: Vec<&str> = line.trim().split(',').map(str::trim).collect();
+
+    if fields.first() != Some(&"MSG") {
+        return None;
+    }
+
+    let field = |index: usize| fields.get(index).copied().filter(|value| !value.is_empty());
+
+    let icao_hex = field(FIELD_ICAO_HEX)?
+        .trim_start_matches('~')
+        .to_uppercase();
+
+    let mut position = ShAdsbPosition::new(icao_hex, timestamp);
+    position.callsign = field(FIELD_CALLSIGN).map(ToString::to_string);
+    position.altitude = field(FIELD_ALTITUDE).and_then(|altitude| altitude.parse().ok());
+    position.latitude = field(FIELD_LATITUDE).and_then(|latitude| latitude.parse().ok());
+    position.longitude = field(FIELD_LONGITUDE).and_then(|longitude| longitude.parse().ok());
+    position.squawk = field(FIELD_SQUAWK).map(ToString::to_string);
+    // SBS flags are -1 for set and 0 for not set
+    position.on_ground = field(FIELD_ON_GROUND).map(|on_ground| on_ground != "0");
+
+    Some(position)
+}
@@ -0,0 +1,108 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// acarsdec, and vdlm2dec which shares its output format
+
+use serde_json::Value;
+use sh_common::acars_message::{ShAcarsMessage, ShMessageDirection, ShMessageType};
+
+use super::{clean_tail, get_f64, get_string, DecodedFrame};
+
+#[must_use]
+pub fn decode(value: &Value, source: &str) -> DecodedFrame {
+    let decoder = value.get("app").and_then(|app| get_string(app, "name"));
+
+    // vdlm2dec adds the aircraft address, which acarsdec has no way of knowing
+    let message_type = if decoder.as_deref() == Some("vdlm2dec") || value.get("icao").is_some() {
+        ShMessageType::Vdlm2
+    } else {
+        ShMessageType::Acars
+    };
+
+    let mut message = ShAcarsMessage::new(
+        get_f64(value, "timestamp").unwrap_or_default(),
+        message_type,
+        source.to_string(),
+    );
+
+    message.decoder = decoder;
+    message.station_id = get_string(value, "station_id");
+    message.frequency = get_f64(value, "freq");
+    message.signal_level = get_f64(value, "level");
+    message.tail = clean_tail(get_string(value, "tail"));
+    message.flight = get_string(value, "flight");
+    message.label = get_string(value, "label");
+    message.block_id = get_string(value, "block_id");
+    message.message_number = get_string(value, "msgno");
+    message.text = get_string(value, "text");
+    message.icao_hex = value
+        .get("icao")
+        .and_then(Value::as_u64)
+        .map(|icao| format!("{icao:06X}"));
+
+    // Downlinks use a numeric block ID, uplinks a letter
+    message.direction = match message.block_id.as_deref().and_then(|id| id.chars().next()) {
+        Some(c) if c.is_ascii_digit() => ShMessageDirection::Downlink,
+        Some(c) if c.is_ascii_alphabetic() => ShMessageDirection::Uplink,
+        _ => ShMessageDirection::Unknown,
+    };
+
+    // vdlm2dec gives us the address a downlink was sent to, which is the ground station
+    if message_type == ShMessageType::Vdlm2 && message.direction == ShMessageDirection::Downlink {
+        message.ground_station_id = value
+            .get("toaddr")
+            .and_then(Value::as_u64)
+            .map(|address| format!("{address:06X}"));
+    }
+
+    DecodedFrame {
+        message: Some(message),
+        ground_stations: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::decode_frame;
+
+    #[test]
+    fn acarsdec_downlink() {
+        let line = r##"{"timestamp":1718806735.123456,"station_id":"XX-EGLL-ACARS","channel":2,"freq":131.550,"level":-24.1,"error":0,"mode":"2","label":"H1","block_id":"5","ack":false,"tail":".N12345","flight":"UA0123","msgno":"D21A","text":"#DFB/PIREP","end":true,"app":{"name":"acarsdec","ver":"3.7"}}"##;
+        let frame = decode_frame(line, "acars_router:15550").unwrap();
+        let message = frame.message.unwrap();
+
+        assert_eq!(message.message_type, ShMessageType::Acars);
+        assert_eq!(message.source, "acars_router:15550");
+        assert_eq!(message.decoder.as_deref(), Some("acarsdec"));
+        assert_eq!(message.station_id.as_deref(), Some("XX-EGLL-ACARS"));
+        assert_eq!(message.frequency, Some(131.55));
+        assert_eq!(message.signal_level, Some(-24.1));
+        assert_eq!(message.tail.as_deref(), Some("N12345"));
+        assert_eq!(message.flight.as_deref(), Some("UA0123"));
+        assert_eq!(message.label.as_deref(), Some("H1"));
+        assert_eq!(message.message_number.as_deref(), Some("D21A"));
+        assert_eq!(message.text.as_deref(), Some("#DFB/PIREP"));
+        assert_eq!(message.direction, ShMessageDirection::Downlink);
+        assert_eq!(message.icao_hex, None);
+        assert_eq!(message.ground_station_id, None);
+        // plain ACARS says nothing about ground stations
+        assert!(frame.ground_stations.is_empty());
+    }
+
+    #[test]
+    fn vdlm2dec_learns_the_ground_station_address() {
+        let line = r#"{"timestamp":1718806736.5,"station_id":"XX-EGLL-VDLM2","channel":0,"freq":136.975,"icao":10624451,"toaddr":1087853,"mode":"2","label":"H1","block_id":"5","ack":false,"tail":".N12345","flight":"UA0123","msgno":"D22A","text":"POS","end":true,"app":{"name":"vdlm2dec","ver":"2.3"}}"#;
+        let frame = decode_frame(line, "acars_router:15555").unwrap();
+        let message = frame.message.unwrap();
+
+        assert_eq!(message.message_type, ShMessageType::Vdlm2);
+        assert_eq!(message.decoder.as_deref(), Some("vdlm2dec"));
+        assert_eq!(message.icao_hex.as_deref(), Some("A21DC3"));
+        assert_eq!(message.direction, ShMessageDirection::Downlink);
+        assert_eq!(message.ground_station_id.as_deref(), Some("10996D"));
+        assert!(frame.ground_stations.is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde_json::Value;
+use sh_common::acars_message::{ShAcarsMessage, ShMessageDirection, ShMessageType};
+use sh_common::ground_station::{ShGroundStation, ShGroundStationNetwork};
+
+use super::{apply_acars_fields, get_f64, get_string, get_timestamp, DecodedFrame};
+
+const GROUND_STATION: &str = "Ground station";
+const AIRCRAFT: &str = "Aircraft";
+
+#[must_use]
+pub fn decode(hfdl: &Value, source: &str) -> DecodedFrame {
+    let timestamp = get_timestamp(hfdl);
+    // dumphfdl reports the frequency in Hz
+    let frequency = get_f64(hfdl, "freq").map(|freq| freq / 1_000_000.0);
+
+    let mut frame = DecodedFrame::default();
+
+    // Squitters are broadcast by every ground station and list the status and frequencies
+    // of all of the stations in the network
+    if let Some(spdu) = hfdl.get("spdu") {
+        if let Some(src) = spdu.get("src") {
+            if let Some(mut station) = ground_station_from_entity(src, timestamp) {
+                if let Some(frequency) = frequency {
+                    station.frequencies.push(frequency * 1000.0);
+                }
+                frame.ground_stations.push(station);
+            }
+        }
+
+        for status in spdu
+            .get("gs_status")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(mut station) = status
+                .get("gs")
+                .and_then(|gs| ground_station_from_entity(gs, timestamp))
+            else {
+                continue;
+            };
+
+            // squitter frequencies are in kHz already
+            station.frequencies = status
+                .get("freqs")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|freq| get_f64(freq, "freq"))
+                .collect();
+
+            frame.ground_stations.push(station);
+        }
+    }
+
+    if let Some(lpdu) = hfdl.get("lpdu") {
+        let src = lpdu.get("src").unwrap_or(&Value::Null);
+        let dst = lpdu.get("dst").unwrap_or(&Value::Null);
+        let src_type = get_string(src, "type");
+
+        let ground_station = if src_type.as_deref() == Some(GROUND_STATION) {
+            ground_station_from_entity(src, timestamp)
+        } else {
+            ground_station_from_entity(dst, timestamp)
+        };
+
+        if let Some(acars) = lpdu.get("hfnpdu").and_then(|hfnpdu| hfnpdu.get("acars")) {
+            let mut message =
+                ShAcarsMessage::new(timestamp, ShMessageType::Hfdl, source.to_string());
+            message.decoder = hfdl.get("app").and_then(|app| get_string(app, "name"));
+            message.station_id = get_string(hfdl, "station");
+            message.frequency = frequency;
+            message.signal_level = get_f64(hfdl, "sig_level");
+            message.ground_station_id = ground_station.as_ref().map(|gs| gs.id.clone());
+            message.icao_hex = lpdu
+                .get("ac_info")
+                .and_then(|ac_info| get_string(ac_info, "icao"));
+
+            message.direction = match src_type.as_deref() {
+                Some(GROUND_STATION) => ShMessageDirection::Uplink,
+                Some(AIRCRAFT) => ShMessageDirection::Downlink,
+                _ => ShMessageDirection::Unknown,
+            };
+
+            apply_acars_fields(&mut message, acars);
+            frame.message = Some(message);
+        }
+
+        // dumphfdl only knows the station name if it was given a system table, but if it
+        // was we might as well learn it
+        if let Some(station) = ground_station.filter(|gs| gs.name.is_some()) {
+            frame.ground_stations.push(station);
+        }
+    }
+
+    frame
+}
+
+/// dumphfdl describes both ends of a link as `{"type": ..., "id": ..., "name": ...}`
+fn ground_station_from_entity(entity: &Value, timestamp: f64) -> Option<ShGroundStation> {
+    if get_string(entity, "type").as_deref() != Some(GROUND_STATION) {
+        return None;
+    }
+
+    let id = entity.get("id").and_then(Value::as_u64)?;
+    let mut station = ShGroundStation::new(ShGroundStationNetwork::Hfdl, id.to_string());
+    station.name = get_string(entity, "name");
+    station.last_seen = Some(timestamp);
+
+    Some(station)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::decode_frame;
+
+    #[test]
+    fn squitter_lists_the_ground_stations() {
+        let line = r#"{"hfdl":{"app":{"name":"dumphfdl","ver":"1.6.1"},"station":"XX-EGLL-HFDL","t":{"sec":1718806800,"usec":0},"freq":8927000,"bit_rate":1800,"sig_level":-22.3,"noise_level":-40.1,"freq_skew":0.1,"slot":"S","spdu":{"err":false,"src":{"type":"Ground station","id":17,"name":"Reykjavik, Iceland"},"spdu_version":0,"rls":true,"iso":false,"change_note":"None","frame_index":1234,"frame_offset":0,"min_priority":0,"systable_version":51,"gs_status":[{"gs":{"type":"Ground station","id":1,"name":"San Francisco, California"},"utc_sync":true,"freqs":[{"id":0,"freq":21934.0},{"id":1,"freq":13276.0}]},{"gs":{"type":"Ground station","id":3,"name":"Reykjavik, Iceland"},"utc_sync":true,"freqs":[{"id":0,"freq":8927.0}]}]}}}"#;
+        let frame = decode_frame(line, "acars_router:15556").unwrap();
+
+        assert!(frame.message.is_none());
+        let ids: Vec<&str> = frame
+            .ground_stations
+            .iter()
+            .map(|station| station.id.as_str())
+            .collect();
+        assert_eq!(ids, ["17", "1", "3"]);
+
+        // the station that sent it was heard on this frequency
+        let sender = &frame.ground_stations[0];
+        assert_eq!(sender.network, ShGroundStationNetwork::Hfdl);
+        assert_eq!(sender.name.as_deref(), Some("Reykjavik, Iceland"));
+        assert_eq!(sender.last_seen, Some(1_718_806_800.0));
+        assert_eq!(sender.frequencies.len(), 1);
+        assert!((sender.frequencies[0] - 8927.0).abs() < 1e-6);
+
+        let listed = &frame.ground_stations[1];
+        assert_eq!(listed.name.as_deref(), Some("San Francisco, California"));
+        assert_eq!(listed.frequencies, vec![21934.0, 13276.0]);
+    }
+
+    #[test]
+    fn downlink_to_a_named_ground_station() {
+        let line = r#"{"hfdl":{"app":{"name":"dumphfdl","ver":"1.6.1"},"station":"XX-EGLL-HFDL","t":{"sec":1718806810,"usec":500000},"freq":8927000,"bit_rate":1800,"sig_level":-25.0,"noise_level":-41.3,"freq_skew":0.2,"slot":"-","lpdu":{"err":false,"src":{"type":"Aircraft","id":12},"dst":{"type":"Ground station","id":17,"name":"Reykjavik, Iceland"},"type":{"id":13,"name":"Long Unnumbered Data"},"ac_info":{"icao":"4CA7B5"},"hfnpdu":{"err":false,"type":{"id":255,"name":"Enveloped data"},"acars":{"err":false,"crc_ok":true,"more":false,"reg":".EI-FNH","mode":"2","label":"H1","blk_id":"7","ack":"!","flight":"RYR12AB","msg_num":"M21","msg_num_seq":"A","msg_text":"POSN52.123W015.456"}}}}}"#;
+        let frame = decode_frame(line, "acars_router:15556").unwrap();
+        let message = frame.message.unwrap();
+
+        assert_eq!(message.message_type, ShMessageType::Hfdl);
+        assert!((message.timestamp - 1_718_806_810.5).abs() < 1e-6);
+        assert_eq!(message.decoder.as_deref(), Some("dumphfdl"));
+        assert_eq!(message.frequency, Some(8.927));
+        assert_eq!(message.direction, ShMessageDirection::Downlink);
+        assert_eq!(message.icao_hex.as_deref(), Some("4CA7B5"));
+        assert_eq!(message.ground_station_id.as_deref(), Some("17"));
+        assert_eq!(message.tail.as_deref(), Some("EI-FNH"));
+        assert_eq!(message.flight.as_deref(), Some("RYR12AB"));
+        assert_eq!(message.text.as_deref(), Some("POSN52.123W015.456"));
+
+        // its name came along with it
+        let [station] = frame.ground_stations.as_slice() else {
+            panic!(
+                "expected one ground station, got {:?}",
+                frame.ground_stations
+            );
+        };
+        assert_eq!(station.id, "17");
+        assert_eq!(station.name.as_deref(), Some("Reykjavik, Iceland"));
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+use serde_json::Value;
+use sh_common::acars_message::{ShAcarsMessage, ShMessageDirection, ShMessageType};
+use sh_common::ground_station::{ShGroundStation, ShGroundStationNetwork};
+
+use super::{apply_acars_fields, get_f64, get_string, get_timestamp, DecodedFrame};
+
+const GROUND_STATION: &str = "Ground station";
+const AIRCRAFT: &str = "Aircraft";
+
+#[must_use]
+pub fn decode(vdl2: &Value, source: &str) -> DecodedFrame {
+    let timestamp = get_timestamp(vdl2);
+    // dumpvdl2 reports the frequency in Hz
+    let frequency = get_f64(vdl2, "freq").map(|freq| freq / 1_000_000.0);
+
+    let avlc = vdl2.get("avlc").unwrap_or(&Value::Null);
+    let src = avlc.get("src").unwrap_or(&Value::Null);
+    let dst = avlc.get("dst").unwrap_or(&Value::Null);
+    let src_type = get_string(src, "type");
+    let dst_type = get_string(dst, "type");
+
+    let mut frame = DecodedFrame::default();
+
+    let ground_station_id = if src_type.as_deref() == Some(GROUND_STATION) {
+        get_string(src, "addr")
+    } else if dst_type.as_deref() == Some(GROUND_STATION) {
+        get_string(dst, "addr")
+    } else {
+        None
+    };
+
+    // Anything a ground station sends tells us it is on this frequency, and XID frames
+    // can also tell us where it is and what airports it covers
+    if src_type.as_deref() == Some(GROUND_STATION) {
+        if let Some(id) = &ground_station_id {
+            let mut station = ShGroundStation::new(ShGroundStationNetwork::Vdlm2, id.clone());
+            station.last_seen = Some(timestamp);
+            if let Some(frequency) = frequency {
+                station.frequencies.push(frequency * 1000.0);
+            }
+            if let Some(xid) = avlc.get("xid") {
+                apply_xid_params(&mut station, xid);
+            }
+            frame.ground_stations.push(station);
+        }
+    }
+
+    if let Some(acars) = avlc.get("acars") {
+        let mut message = ShAcarsMessage::new(timestamp, ShMessageType::Vdlm2, source.to_string());
+        message.decoder = vdl2.get("app").and_then(|app| get_string(app, "name"));
+        message.station_id = get_string(vdl2, "station");
+        message.frequency = frequency;
+        message.signal_level = get_f64(vdl2, "sig_level");
+        message.ground_station_id = ground_station_id;
+
+        message.direction = match (src_type.as_deref(), dst_type.as_deref()) {
+            (Some(GROUND_STATION), _) => ShMessageDirection::Uplink,
+            (Some(AIRCRAFT), _) => ShMessageDirection::Downlink,
+            _ => ShMessageDirection::Unknown,
+        };
+
+        message.icao_hex = match message.direction {
+            ShMessageDirection::Uplink => get_string(dst, "addr"),
+            ShMessageDirection::Downlink => get_string(src, "addr"),
+            ShMessageDirection::Unknown => None,
+        };
+
+        apply_acars_fields(&mut message, acars);
+        frame.message = Some(message);
+    }
+
+    frame
+}
+
+fn apply_xid_params(station: &mut ShGroundStation, xid: &Value) {
+    let Some(params) = xid.get("vdl_params").and_then(Value::as_array) else {
+        return;
+    };
+
+    for param in params {
+        let Some(value) = param.get("value") else {
+            continue;
+        };
+
+        match param.get("name").and_then(Value::as_str) {
+            Some("gs_location") => {
+                if let (Some(lat), Some(lon)) = (get_f64(value, "lat"), get_f64(value, "lon")) {
+                    station.latitude = Some(lat);
+                    station.longitude = Some(lon);
+                }
+            }
+            Some("airport_coverage") => {
+                let airports: Vec<String> = match value {
+                    Value::Array(airports) => airports
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(ToString::to_string)
+                        .collect(),
+                    Value::String(airports) => airports
+                        .split_whitespace()
+                        .map(ToString::to_string)
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                station.airports.extend(airports);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::decode_frame;
+
+    #[test]
+    fn downlink_to_a_ground_station() {
+        let line = r##"{"vdl2":{"app":{"name":"dumpvdl2","ver":"2.3.0"},"station":"XX-EGLL-VDLM2","t":{"sec":1718806735,"usec":250000},"freq":136975000,"burst_len_octets":58,"hdr_bits_fixed":0,"octets_corrected_by_fec":0,"idx":0,"sig_level":-28.5,"noise_level":-47.9,"freq_skew":1.2,"avlc":{"src":{"addr":"A1B2C3","type":"Aircraft","status":"Airborne"},"dst":{"addr":"10916D","type":"Ground station"},"cr":"Command","frame_type":"I","rseq":3,"sseq":1,"poll":false,"acars":{"err":false,"crc_ok":true,"more":false,"reg":".N12345","mode":"2","label":"H1","blk_id":"5","ack":"!","flight":"UA0123","msg_num":"D21","msg_num_seq":"A","msg_text":"#DFB/PIREP"}}}}"##;
+        let frame = decode_frame(line, "acars_router:15555").unwrap();
+        let message = frame.message.unwrap();
+
+        assert_eq!(message.message_type, ShMessageType::Vdlm2);
+        assert!((message.timestamp - 1_718_806_735.25).abs() < 1e-6);
+        assert_eq!(message.decoder.as_deref(), Some("dumpvdl2"));
+        assert_eq!(message.station_id.as_deref(), Some("XX-EGLL-VDLM2"));
+        assert_eq!(message.frequency, Some(136.975));
+        assert_eq!(message.signal_level, Some(-28.5));
+        assert_eq!(message.direction, ShMessageDirection::Downlink);
+        assert_eq!(message.icao_hex.as_deref(), Some("A1B2C3"));
+        assert_eq!(message.ground_station_id.as_deref(), Some("10916D"));
+        assert_eq!(message.tail.as_deref(), Some("N12345"));
+        assert_eq!(message.flight.as_deref(), Some("UA0123"));
+        assert_eq!(message.block_id.as_deref(), Some("5"));
+        assert_eq!(message.message_number.as_deref(), Some("D21"));
+        assert_eq!(message.text.as_deref(), Some("#DFB/PIREP"));
+        // the aircraft sent it, so it says nothing about the station
+        assert!(frame.ground_stations.is_empty());
+    }
+
+    #[test]
+    fn xid_from_a_ground_station() {
+        let line = r#"{"vdl2":{"app":{"name":"dumpvdl2","ver":"2.3.0"},"station":"XX-EGLL-VDLM2","t":{"sec":1718806740,"usec":0},"freq":136975000,"burst_len_octets":62,"hdr_bits_fixed":0,"octets_corrected_by_fec":0,"idx":0,"sig_level":-30.1,"noise_level":-48.2,"freq_skew":-0.4,"avlc":{"src":{"addr":"10916D","type":"Ground station","status":"On ground"},"dst":{"addr":"FFFFFF","type":"All stations"},"cr":"Response","frame_type":"XID","poll":false,"xid":{"err":false,"type":"GSIF","type_descr":"Ground Station Information Frame","pub_params":[{"name":"param_set_id","value":"8885:1993"},{"name":"proto_version","value":1}],"vdl_params":[{"name":"param_set_id","value":"V"},{"name":"avlc_n1","value":2088},{"name":"gs_location","value":{"lat":51.5,"lon":-0.4}},{"name":"airport_coverage","value":["EGLL","EGLC"]},{"name":"freqs","value":[136975]}]}}}}"#;
+        let frame = decode_frame(line, "acars_router:15555").unwrap();
+
+        assert!(frame.message.is_none());
+        let [station] = frame.ground_stations.as_slice() else {
+            panic!(
+                "expected one ground station, got {:?}",
+                frame.ground_stations
+            );
+        };
+        assert_eq!(station.network, ShGroundStationNetwork::Vdlm2);
+        assert_eq!(station.id, "10916D");
+        assert_eq!(station.latitude, Some(51.5));
+        assert_eq!(station.longitude, Some(-0.4));
+        assert_eq!(station.airports, vec!["EGLL", "EGLC"]);
+        assert_eq!(station.last_seen, Some(1_718_806_740.0));
+        assert_eq!(station.frequencies.len(), 1);
+        assert!((station.frequencies[0] - 136_975.0).abs() < 1e-6);
+    }
+}
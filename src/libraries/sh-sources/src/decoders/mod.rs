@@ -0,0 +1,146 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// acars_router passes along the JSON the decoders produce as-is, so each decoder's format
+// has to be turned in to our common message format here.
+
+pub mod acarsdec;
+pub mod dumphfdl;
+pub mod dumpvdl2;
+
+use std::fmt;
+
+use serde_json::Value;
+use sh_common::acars_message::ShAcarsMessage;
+use sh_common::ground_station::ShGroundStation;
+
+/// `DecodedFrame` is everything we could get out of a single line of decoder output
+#[derive(Debug, Default)]
+pub struct DecodedFrame {
+    /// The ACARS message carried in the frame. Frames such as squitters do not carry one
+    pub message: Option<ShAcarsMessage>,
+    /// Ground stations the frame told us about
+    pub ground_stations: Vec<ShGroundStation>,
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Json(serde_json::Error),
+    UnknownFormat,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "Invalid JSON: {e}"),
+            Self::UnknownFormat => write!(f, "Unknown decoder format"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// The decoders whose JSON we understand. acarsdec's format is shared by vdlm2dec
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecoderFormat {
+    Acarsdec,
+    Dumpvdl2,
+    Dumphfdl,
+}
+
+impl DecoderFormat {
+    /// Work out which decoder produced a line of JSON
+    #[must_use]
+    pub fn detect(value: &Value) -> Option<Self> {
+        if value.get("vdl2").is_some() {
+            Some(Self::Dumpvdl2)
+        } else if value.get("hfdl").is_some() {
+            Some(Self::Dumphfdl)
+        } else if value.get("timestamp").is_some() && value.get("freq").is_some() {
+            Some(Self::Acarsdec)
+        } else {
+            None
+        }
+    }
+
+    /// The name the decoder gives itself in the JSON, falling back to the format's name
+    #[must_use]
+    pub fn app_name(self, value: &Value) -> String {
+        let app = match self {
+            Self::Acarsdec => value.get("app"),
+            Self::Dumpvdl2 => value.get("vdl2").and_then(|vdl2| vdl2.get("app")),
+            Self::Dumphfdl => value.get("hfdl").and_then(|hfdl| hfdl.get("app")),
+        };
+
+        app.and_then(|app| get_string(app, "name"))
+            .unwrap_or_else(|| self.to_string())
+    }
+}
+
+impl fmt::Display for DecoderFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Acarsdec => write!(f, "acarsdec"),
+            Self::Dumpvdl2 => write!(f, "dumpvdl2"),
+            Self::Dumphfdl => write!(f, "dumphfdl"),
+        }
+    }
+}
+
+/// Decode a single line of JSON from `acars_router`, working out which decoder produced it
+/// # Errors
+/// Returns an error if the line is not JSON or is not in a format we understand
+pub fn decode_frame(line: &str, source: &str) -> Result<DecodedFrame, DecodeError> {
+    let value: Value = serde_json::from_str(line).map_err(DecodeError::Json)?;
+
+    match DecoderFormat::detect(&value) {
+        Some(DecoderFormat::Dumpvdl2) => Ok(dumpvdl2::decode(&value["vdl2"], source)),
+        Some(DecoderFormat::Dumphfdl) => Ok(dumphfdl::decode(&value["hfdl"], source)),
+        Some(DecoderFormat::Acarsdec) => Ok(acarsdec::decode(&value, source)),
+        None => Err(DecodeError::UnknownFormat),
+    }
+}
+
+/// Get a string field, ignoring empty strings
+pub(crate) fn get_string(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(ToString::to_string)
+}
+
+pub(crate) fn get_f64(value: &Value, key: &str) -> Option<f64> {
+    value.get(key).and_then(Value::as_f64)
+}
+
+/// Decoders pad the registration with leading dots, e.g. `.N123AB`
+pub(crate) fn clean_tail(tail: Option<String>) -> Option<String> {
+    tail.map(|tail| tail.trim_start_matches('.').to_string())
+        .filter(|tail| !tail.is_empty())
+}
+
+/// The `t` object dumpvdl2 and dumphfdl use for timestamps
+pub(crate) fn get_timestamp(value: &Value) -> f64 {
+    let Some(t) = value.get("t") else {
+        return 0.0;
+    };
+
+    let seconds = get_f64(t, "sec").unwrap_or_default();
+    let microseconds = get_f64(t, "usec").unwrap_or_default();
+
+    seconds + microseconds / 1_000_000.0
+}
+
+/// Pull the ACARS fields out of the `acars` object that dumpvdl2 and dumphfdl share
+pub(crate) fn apply_acars_fields(message: &mut ShAcarsMessage, acars: &Value) {
+    message.tail = clean_tail(get_string(acars, "reg"));
+    message.flight = get_string(acars, "flight");
+    message.label = get_string(acars, "label");
+    message.block_id = get_string(acars, "blk_id");
+    message.message_number = get_string(acars, "msg_num");
+    message.text = get_string(acars, "msg_text");
+}
@@ -0,0 +1,24 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// The data sources. Each configured source gets a client that connects to it, decodes what it
+// receives and passes the result on to the hub.
+
+#![deny(
+    clippy::pedantic,
+//    clippy::cargo,
+    clippy::nursery,
+    clippy::style,
+    clippy::correctness,
+    clippy::all
+)]
+
+#[macro_use]
+extern crate log;
+
+pub mod acars_router;
+pub mod adsb;
+pub mod decoders;
+pub mod probe;
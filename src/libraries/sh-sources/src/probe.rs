@@ -0,0 +1,428 @@
+// Copyright (C) 2024 Fred Clausen
+// Use of this source code is governed by an MIT-style
+// license that can be found in the LICENSE file or at
+// https://opensource.org/licenses/MIT.
+
+// Trying out a source before it is added to the config. The test makes its own connection and
+// only looks at what comes back, so the clients for the configured sources never see it
+
+use std::time::Duration;
+
+use serde_json::Value;
+use sh_common::source_status::{ShSourceFormat, ShSourceKind, ShSourceTest, ShSourceTestResult};
+use sh_config::address::{validate_address, validate_port};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, timeout_at, Instant};
+
+use crate::adsb::parse_sbs_line;
+use crate::decoders::DecoderFormat;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const LISTEN_TIME: Duration = Duration::from_secs(5);
+// How much is read looking for a message before giving up
+const MAX_BYTES: usize = 64 * 1024;
+// Longest sample sent back, in characters
+const SAMPLE_LENGTH: usize = 500;
+// Beast frames start with this, followed by the frame type
+const BEAST_ESCAPE: u8 = 0x1a;
+// The line types an SBS output sends
+const SBS_TYPES: [&str; 6] = ["MSG", "SEL", "ID", "AIR", "STA", "CLK"];
+
+/// What has been made of the data received so far
+struct Findings {
+    format: ShSourceFormat,
+    decoder: Option<String>,
+    sample: String,
+    /// A whole message was found, so there is no need to keep listening
+    complete: bool,
+}
+
+impl Findings {
+    const fn new(format: ShSourceFormat, sample: String, complete: bool) -> Self {
+        Self {
+            format,
+            decoder: None,
+            sample,
+            complete,
+        }
+    }
+}
+
+/// Connect to a source, listen for a few seconds and report what it sent. It stops listening
+/// as soon as it has a whole message
+pub async fn test_source(test: &ShSourceTest) -> ShSourceTestResult {
+    let mut result = ShSourceTestResult::new(test);
+
+    if let Err(e) = validate_address(&test.address).and_then(|()| validate_port(test.port)) {
+        result.error = Some(e);
+        return result;
+    }
+
+    let source = test.to_string();
+    let mut stream = match timeout(CONNECT_TIMEOUT, TcpStream::connect(&source)).await {
+        Ok(Ok(stream)) => stream,
+        Ok(Err(e)) => {
+            result.error = Some(format!("Could not connect: {e}"));
+            return result;
+        }
+        Err(_) => {
+            result.error = Some(format!(
+                "Could not connect within {} seconds",
+                CONNECT_TIMEOUT.as_secs()
+            ));
+            return result;
+        }
+    };
+    result.reachable = true;
+    debug!("Testing {} source at {source}", test.kind);
+
+    let deadline = Instant::now() + LISTEN_TIME;
+    let mut received = Vec::new();
+    let mut buffer = [0u8; 4096];
+    let mut closed = false;
+    let mut findings = None;
+
+    loop {
+        let read = match timeout_at(deadline, stream.read(&mut buffer)).await {
+            // listened long enough
+            Err(_) => break,
+            Ok(Ok(0)) => {
+                closed = true;
+                break;
+            }
+            Ok(Ok(read)) => read,
+            Ok(Err(e)) => {
+                result.error = Some(format!("Error reading from the source: {e}"));
+                break;
+            }
+        };
+
+        received.extend_from_slice(&buffer[..read]);
+        findings = inspect(&received);
+        if findings.as_ref().is_some_and(|findings| findings.complete)
+            || received.len() >= MAX_BYTES
+        {
+            break;
+        }
+    }
+
+    result.bytes_received = received.len();
+    if let Some(findings) = findings {
+        result.format = Some(findings.format);
+        result.decoder = findings.decoder;
+        result.sample = Some(findings.sample);
+    }
+
+    if result.error.is_none() {
+        result.error = problem(&result, closed);
+    }
+
+    result
+}
+
+/// Why the hub couldn't use what the source sent, if it couldn't
+fn problem(result: &ShSourceTestResult, closed: bool) -> Option<String> {
+    let expected = match result.kind {
+        ShSourceKind::AcarsRouter => ShSourceFormat::Json,
+        ShSourceKind::Adsb => ShSourceFormat::Sbs,
+    };
+
+    match result.format {
+        None if closed => {
+            Some("The source closed the connection without sending anything".to_string())
+        }
+        None => Some(format!(
+            "Connected, but nothing was received in {} seconds. The source may just be quiet",
+            LISTEN_TIME.as_secs()
+        )),
+        Some(ShSourceFormat::Beast) if result.kind == ShSourceKind::Adsb => Some(
+            "The source is sending Beast, but the hub reads SBS. readsb and dump1090 serve SBS \
+             on port 30003"
+                .to_string(),
+        ),
+        Some(ShSourceFormat::Unknown) => Some(format!(
+            "The source is sending something the hub doesn't recognise. {} sources have to send \
+             {expected}",
+            result.kind
+        )),
+        Some(format) if !format.readable_by(result.kind) => Some(format!(
+            "The source is sending {format}, but {} sources have to send {expected}",
+            result.kind
+        )),
+        Some(ShSourceFormat::Json) if result.decoder.is_none() => {
+            Some("The source is sending JSON, but not from a decoder the hub knows".to_string())
+        }
+        Some(_) => None,
+    }
+}
+
+/// Work out what the data received so far is, preferring the first whole message in it
+fn inspect(received: &[u8]) -> Option<Findings> {
+    if received.is_empty() {
+        return None;
+    }
+
+    if let Some(findings) = inspect_beast(received) {
+        return Some(findings);
+    }
+
+    // the last line may not have all arrived yet
+    let text = String::from_utf8_lossy(received);
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    if !text.ends_with('\n') {
+        lines.pop();
+    }
+
+    let mut first = None;
+    for line in lines
+        .into_iter()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let findings = inspect_line(line);
+        if findings.complete {
+            return Some(findings);
+        }
+        first.get_or_insert(findings);
+    }
+
+    Some(first.unwrap_or_else(|| Findings::new(ShSourceFormat::Unknown, sample(&text), false)))
+}
+
+fn inspect_line(line: &str) -> Findings {
+    if line.starts_with('{') {
+        if let Ok(value) = serde_json::from_str::<Value>(line) {
+            let decoder = DecoderFormat::detect(&value);
+            return Findings {
+                decoder: decoder.map(|decoder| decoder.app_name(&value)),
+                ..Findings::new(ShSourceFormat::Json, sample(line), decoder.is_some())
+            };
+        }
+    }
+
+    if parse_sbs_line(line, 0.0).is_some() {
+        return Findings::new(ShSourceFormat::Sbs, sample(line), true);
+    }
+
+    let line_type = line.split(',').next().unwrap_or_default().trim();
+    if line.contains(',') && SBS_TYPES.contains(&line_type) {
+        return Findings::new(ShSourceFormat::Sbs, sample(line), false);
+    }
+
+    Findings::new(ShSourceFormat::Unknown, sample(line), false)
+}
+
+/// Beast is binary, so it is spotted by its frame markers rather than by lines. The sample is
+/// the first frame, in hex
+fn inspect_beast(received: &[u8]) -> Option<Findings> {
+    let is_frame_start = |index: usize| {
+        received[index] == BEAST_ESCAPE
+            && received
+                .get(index + 1)
+                .is_some_and(|frame_type| (b'1'..=b'4').contains(frame_type))
+            // a doubled escape is an escaped byte inside a frame
+            && (index == 0 || received[index - 1] != BEAST_ESCAPE)
+    };
+
+    let is_text = received
+        .iter()
+        .all(|byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace());
+    if is_text {
+        return None;
+    }
+
+    let start = (0..received.len()).find(|&index| is_frame_start(index))?;
+    let end = (start + 2..received.len()).find(|&index| is_frame_start(index));
+
+    let frame = &received[start..end.unwrap_or(received.len())];
+    let hex: Vec<String> = frame.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    Some(Findings::new(
+        ShSourceFormat::Beast,
+        sample(&hex.join(" ")),
+        end.is_some(),
+    ))
+}
+
+fn sample(text: &str) -> String {
+    text.trim().chars().take(SAMPLE_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const ACARSDEC: &str = r##"{"timestamp":1718806735.123456,"station_id":"XX-EGLL-ACARS","channel":2,"freq":131.550,"level":-24.1,"error":0,"mode":"2","label":"H1","block_id":"5","ack":false,"tail":".N12345","flight":"UA0123","msgno":"D21A","text":"#DFB/PIREP","end":true,"app":{"name":"acarsdec","ver":"3.7"}}"##;
+    const DUMPVDL2: &str = r##"{"vdl2":{"app":{"name":"dumpvdl2","ver":"2.3.0"},"station":"XX-EGLL-VDLM2","t":{"sec":1718806735,"usec":250000},"freq":136975000,"sig_level":-28.5,"noise_level":-47.9,"avlc":{"src":{"addr":"A1B2C3","type":"Aircraft","status":"Airborne"},"dst":{"addr":"10916D","type":"Ground station"},"cr":"Command","frame_type":"I","acars":{"err":false,"crc_ok":true,"more":false,"reg":".N12345","mode":"2","label":"H1","blk_id":"5","ack":"!","flight":"UA0123","msg_num":"D21","msg_num_seq":"A","msg_text":"#DFB/PIREP"}}}}"##;
+    const DUMPHFDL: &str = r#"{"hfdl":{"app":{"name":"dumphfdl","ver":"1.6.1"},"station":"XX-EGLL-HFDL","t":{"sec":1718806800,"usec":0},"freq":8927000,"bit_rate":1800,"sig_level":-22.3,"noise_level":-40.1,"slot":"S","spdu":{"err":false,"src":{"type":"Ground station","id":17,"name":"Reykjavik, Iceland"},"systable_version":51,"gs_status":[{"gs":{"type":"Ground station","id":1,"name":"San Francisco, California"},"utc_sync":true,"freqs":[{"id":0,"freq":21934.0}]}]}}}"#;
+    const SBS: &str = "MSG,3,1,1,4CA7B5,1,2024/06/19,14:58:55.123,2024/06/19,14:58:55.123,RYR12AB,37000,,,52.12345,-15.45678,,,0,0,0,0";
+
+    /// A Beast Mode S long frame: escape, type, 6 byte timestamp, signal and 14 bytes of message
+    fn beast_frame() -> Vec<u8> {
+        let mut frame = vec![BEAST_ESCAPE, b'3', 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0xc8];
+        frame.extend_from_slice(&[
+            0x8d, 0x4c, 0xa7, 0xb5, 0x58, 0x99, 0x41, 0x4f, 0x2e, 0x8e, 0x1b, 0x7f, 0x10, 0x6b,
+        ]);
+        frame
+    }
+
+    fn inspect_text(text: &str) -> Findings {
+        inspect(format!("{text}\n").as_bytes()).unwrap()
+    }
+
+    fn result(kind: ShSourceKind, findings: Option<Findings>) -> ShSourceTestResult {
+        let mut result = ShSourceTestResult::new(&ShSourceTest {
+            kind,
+            address: "127.0.0.1".to_string(),
+            port: 15550,
+        });
+        if let Some(findings) = findings {
+            result.format = Some(findings.format);
+            result.decoder = findings.decoder;
+            result.sample = Some(findings.sample);
+        }
+        result
+    }
+
+    #[test]
+    fn decoder_json_is_recognised() {
+        for (line, decoder) in [
+            (ACARSDEC, "acarsdec"),
+            (DUMPVDL2, "dumpvdl2"),
+            (DUMPHFDL, "dumphfdl"),
+        ] {
+            let findings = inspect_text(line);
+            assert_eq!(findings.format, ShSourceFormat::Json, "{decoder}");
+            assert_eq!(findings.decoder.as_deref(), Some(decoder));
+            assert!(findings.complete, "{decoder}");
+
+            let result = result(ShSourceKind::AcarsRouter, Some(findings));
+            assert_eq!(problem(&result, false), None, "{decoder}");
+        }
+    }
+
+    #[test]
+    fn json_from_something_else_is_not_enough() {
+        let findings = inspect_text(r#"{"hello":"there"}"#);
+        assert_eq!(findings.format, ShSourceFormat::Json);
+        assert_eq!(findings.decoder, None);
+        assert!(!findings.complete);
+
+        let result = result(ShSourceKind::AcarsRouter, Some(findings));
+        assert!(problem(&result, false)
+            .unwrap()
+            .contains("not from a decoder"));
+    }
+
+    #[test]
+    fn sbs_is_recognised() {
+        let findings = inspect_text(SBS);
+        assert_eq!(findings.format, ShSourceFormat::Sbs);
+        assert_eq!(findings.sample, SBS);
+        assert!(findings.complete);
+        assert_eq!(
+            problem(&result(ShSourceKind::Adsb, Some(findings)), false),
+            None
+        );
+
+        // SBS sent to where acars_router was expected
+        let findings = inspect_text(SBS);
+        let problem = problem(&result(ShSourceKind::AcarsRouter, Some(findings)), false);
+        assert_eq!(
+            problem.as_deref(),
+            Some("The source is sending SBS, but acars_router sources have to send JSON")
+        );
+    }
+
+    #[test]
+    fn beast_is_spotted_by_its_frames() {
+        let mut received = beast_frame();
+        let findings = inspect_beast(&received).unwrap();
+        assert_eq!(findings.format, ShSourceFormat::Beast);
+        assert!(findings.sample.starts_with("1a 33 00 01"));
+        // the frame could still be going
+        assert!(!findings.complete);
+
+        received.extend(beast_frame());
+        let findings = inspect(&received).unwrap();
+        assert_eq!(findings.format, ShSourceFormat::Beast);
+        assert!(findings.complete);
+        assert_eq!(findings.sample.split(' ').count(), beast_frame().len());
+
+        let problem = problem(&result(ShSourceKind::Adsb, Some(findings)), false).unwrap();
+        assert!(problem.contains("port 30003"));
+    }
+
+    #[test]
+    fn garbage_is_unknown() {
+        let findings = inspect_text("HELLO 1234 this is not a decoder");
+        assert_eq!(findings.format, ShSourceFormat::Unknown);
+        assert!(!findings.complete);
+
+        let problem = problem(&result(ShSourceKind::Adsb, Some(findings)), false).unwrap();
+        assert!(problem.contains("doesn't recognise"));
+        assert!(problem.contains("have to send SBS"));
+
+        assert!(inspect(&[]).is_none());
+    }
+
+    #[test]
+    fn nothing_received() {
+        let closed = problem(&result(ShSourceKind::AcarsRouter, None), true);
+        assert_eq!(
+            closed.as_deref(),
+            Some("The source closed the connection without sending anything")
+        );
+
+        let silent = problem(&result(ShSourceKind::AcarsRouter, None), false).unwrap();
+        assert!(silent.contains("nothing was received"));
+    }
+
+    /// A source on this machine that sends `data` to the first connection and hangs up
+    async fn source(data: Vec<u8>) -> ShSourceTest {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write_all(&data).await.unwrap();
+        });
+
+        ShSourceTest {
+            kind: ShSourceKind::AcarsRouter,
+            address: "127.0.0.1".to_string(),
+            port: u32::from(port),
+        }
+    }
+
+    #[tokio::test]
+    async fn a_working_source() {
+        let test = source(format!("{DUMPVDL2}\n").into_bytes()).await;
+        let result = test_source(&test).await;
+
+        assert!(result.reachable);
+        assert_eq!(result.format, Some(ShSourceFormat::Json));
+        assert_eq!(result.decoder.as_deref(), Some("dumpvdl2"));
+        assert_eq!(result.bytes_received, DUMPVDL2.len() + 1);
+        assert_eq!(result.error, None);
+        assert!(result.is_usable());
+    }
+
+    #[tokio::test]
+    async fn a_source_that_hangs_up() {
+        let test = source(Vec::new()).await;
+        let result = test_source(&test).await;
+
+        assert!(result.reachable);
+        assert_eq!(result.format, None);
+        assert_eq!(result.bytes_received, 0);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("The source closed the connection without sending anything")
+        );
+        assert!(!result.is_usable());
+    }
+}